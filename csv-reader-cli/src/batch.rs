@@ -0,0 +1,178 @@
+//! Batch processing of independent input files.
+//!
+//! The partners send many small files whose accounts are unrelated, which
+//! would be processed one after the other by as many runs. The `batch`
+//! subcommand processes each file by a pipeline of its own, with its own
+//! accounts storage, several files at a time, and writes the accounts of each
+//! to `<output dir>/<input stem>-accounts.csv`. A file failing does not stop
+//! the others, the report tells the outcome of every file.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail};
+use log::info;
+
+use csv_reader_core::{
+    actor::{channel, spawn_actor},
+    AccountExporter, AccountManager, Accountant, InMemoryAccountStorage, Reader, Result,
+};
+
+/// Settings of a batch.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// The input files, processed independently.
+    pub files: Vec<PathBuf>,
+
+    /// The directory the accounts of the files are written to.
+    pub output_dir: PathBuf,
+
+    /// The number of files processed at a time.
+    pub jobs: usize,
+}
+
+/// Outcome of the processing of a file of a batch.
+#[derive(Debug)]
+pub struct FileOutcome {
+    /// The input file.
+    pub input: PathBuf,
+
+    /// The file the accounts were written to.
+    pub output: PathBuf,
+
+    /// The number of accounts written, or why the file failed.
+    pub result: std::result::Result<usize, String>,
+}
+
+/// Report of a batch, the outcomes being in the order of the input files.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// The outcome of every file.
+    pub files: Vec<FileOutcome>,
+}
+
+impl BatchReport {
+    /// The number of files that failed.
+    pub fn failures(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+            .count()
+    }
+}
+
+impl Display for BatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for outcome in &self.files {
+            match &outcome.result {
+                Ok(accounts) => writeln!(
+                    f,
+                    "{}: {} accounts written to '{}'.",
+                    outcome.input.display(),
+                    accounts,
+                    outcome.output.display()
+                )?,
+                Err(error) => writeln!(f, "{}: failed: {error}", outcome.input.display())?,
+            }
+        }
+        writeln!(
+            f,
+            "{} files processed, {} failed.",
+            self.files.len(),
+            self.failures()
+        )
+    }
+}
+
+/// Process the files of the batch, the given number at a time, and report
+/// their outcome.
+pub fn batch(options: &BatchOptions) -> Result<BatchReport> {
+    let outputs = output_files(&options.files, &options.output_dir)?;
+    std::fs::create_dir_all(&options.output_dir)?;
+    let pending = Mutex::new(options.files.iter().zip(outputs).enumerate());
+    let outcomes = Mutex::new(Vec::with_capacity(options.files.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.jobs.clamp(1, options.files.len().max(1)) {
+            scope.spawn(|| loop {
+                // the lock is released before the file is processed
+                let next = pending.lock().unwrap().next();
+                let Some((index, (input, output))) = next else {
+                    break;
+                };
+                info!("Processing file: '{}'.", input.display());
+                let result = process_file(input, &output).map_err(|e| format!("{e:#}"));
+                outcomes.lock().unwrap().push((
+                    index,
+                    FileOutcome {
+                        input: input.clone(),
+                        output,
+                        result,
+                    },
+                ));
+            });
+        }
+    });
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    Ok(BatchReport {
+        files: outcomes.into_iter().map(|(_, outcome)| outcome).collect(),
+    })
+}
+
+/// Name the accounts file of every input after its stem, two inputs with the
+/// same stem would overwrite each other's accounts.
+fn output_files(files: &[PathBuf], output_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut inputs = HashMap::new();
+    files
+        .iter()
+        .map(|input| {
+            let stem = input
+                .file_stem()
+                .ok_or_else(|| anyhow!("The input '{}' is not a file.", input.display()))?;
+            if let Some(other) = inputs.insert(stem, input) {
+                bail!(
+                    "The inputs '{}' and '{}' would be written to the same accounts file.",
+                    other.display(),
+                    input.display()
+                );
+            }
+            let mut name = stem.to_os_string();
+            name.push("-accounts.csv");
+
+            Ok(output_dir.join(name))
+        })
+        .collect()
+}
+
+/// Process a file by a pipeline of its own, returning the manager of its
+/// accounts.
+pub fn compute_accounts(input: &Path) -> Result<Arc<AccountManager>> {
+    let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    let (order_sender, order_receiver) = channel();
+    let accountant = spawn_actor(Accountant::new(account_manager.clone(), order_receiver))?;
+    let buffer = BufReader::new(File::open(input)?);
+    let read = Reader::new(order_sender, Box::new(buffer)).run();
+    accountant.join().expect("Accountant thread panicked")?;
+    read?;
+
+    Ok(account_manager)
+}
+
+/// Process a file by a pipeline of its own and write its accounts, returning
+/// their number.
+fn process_file(input: &Path, output: &Path) -> Result<usize> {
+    let account_manager = compute_accounts(input)?;
+    let accounts = account_manager.get_accounts().len();
+    let writer = BufWriter::new(File::create(output)?);
+    AccountExporter::new(account_manager, Box::new(writer)).run()?;
+
+    Ok(accounts)
+}
@@ -0,0 +1,395 @@
+//! Configuration file of the command line interface.
+//!
+//! The configuration file is a TOML file holding the settings that are too
+//! detailed for command line arguments, like the row transformers working
+//! around the quirks of a partner's files:
+//!
+//! ```toml
+//! [[transformers]]
+//! type = "trim-bom"
+//!
+//! [[transformers]]
+//! type = "kind-synonyms"
+//! synonyms = { wd = "withdrawal", dep = "deposit" }
+//!
+//! [[transformers]]
+//! type = "scale-amount"
+//! decimals = 2
+//!
+//! [reader]
+//! max_rate = 500
+//!
+//! [timestamps]
+//! format = "dd/mm/yyyy hh:mm"
+//! timezone = "+01:00"
+//!
+//! [export]
+//! header = "HDR;{date};{rows}"
+//! footer = "TRL;{rows};{total}"
+//! command = ["partner-upload", "--queue", "accounts"]
+//! spill_dir = "/mnt/spill"
+//!
+//! [quarantine]
+//! max_amount = 10000
+//! foreign_disputes = true
+//! max_withdrawals = 5
+//! withdrawal_window = 60
+//!
+//! [credit]
+//! limit = 100
+//! clients = { 7 = 2500, 12 = 0 }
+//!
+//! [fraud]
+//! max_withdrawals = 10
+//! withdrawal_window = 60
+//! max_dispute_rate = 0.2
+//! outlier_factor = 50
+//! block = false
+//!
+//! [limits]
+//! max_deposit = 50000
+//! max_withdrawal = 10000
+//! max_daily_total = 100000
+//! ```
+
+use std::{
+    collections::HashMap,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use csv_reader_core::{
+    actor::{QuarantineRules, TimestampFormat},
+    adapter::{KindSynonyms, RowTransformer, ScaleAmount, TrimBom},
+    service::{CreditLimits, FraudRules, OrderLimits},
+    Result,
+};
+
+/// Content of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The transformations applied in turn to the input records.
+    #[serde(default)]
+    pub transformers: Vec<TransformerConfig>,
+
+    /// The settings of the reading of the input.
+    #[serde(default)]
+    pub reader: ReaderConfig,
+
+    /// How the timestamps of the input are written.
+    #[serde(default)]
+    pub timestamps: TimestampConfig,
+
+    /// The settings of the exports.
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// The rules of the orders held back for a review.
+    #[serde(default)]
+    pub quarantine: QuarantineConfig,
+
+    /// The overdraft facilities of the clients.
+    #[serde(default)]
+    pub credit: CreditConfig,
+
+    /// The rules of the fraud detection.
+    #[serde(default)]
+    pub fraud: FraudConfig,
+
+    /// The caps on the amounts of the orders.
+    #[serde(default)]
+    pub limits: LimitsConfig,
+}
+
+/// Configuration of the reading of the input, overridden by the command line
+/// arguments.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReaderConfig {
+    /// The maximum number of records sent per second to the accounts storage.
+    pub max_rate: Option<u32>,
+}
+
+/// Configuration of the timestamps of the input, overridden by the command
+/// line arguments.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimestampConfig {
+    /// The format of the timestamps.
+    pub format: Option<String>,
+
+    /// The timezone of the timestamps without offset.
+    pub timezone: Option<String>,
+}
+
+/// Configuration of the exports: the post-processing of the accounts export,
+/// see [PostExport](crate::post_export::PostExport), and where to write them
+/// when the disk is full.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportConfig {
+    /// The template of the record written before the accounts.
+    pub header: Option<String>,
+
+    /// The template of the record written after the accounts.
+    pub footer: Option<String>,
+
+    /// The command receiving the export on its standard input instead of the
+    /// standard output, the program followed by its arguments.
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// The directory where the export files are written when their disk is
+    /// full, see [write_export](crate::spill::write_export).
+    pub spill_dir: Option<PathBuf>,
+}
+
+/// Configuration of the quarantine of the suspicious orders, see
+/// [Quarantine](csv_reader_core::actor::Quarantine), used when a quarantine
+/// file is given.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuarantineConfig {
+    /// Quarantine the deposits and withdrawals above this amount.
+    pub max_amount: Option<Decimal>,
+
+    /// Quarantine the disputes ordered by another client than the one of the
+    /// deposit.
+    #[serde(default)]
+    pub foreign_disputes: bool,
+
+    /// Quarantine the withdrawals of a client beyond this number within the
+    /// window.
+    pub max_withdrawals: Option<usize>,
+
+    /// The window of the withdrawals in seconds.
+    pub withdrawal_window: Option<u64>,
+}
+
+/// Configuration of the credit limits of the clients, see
+/// [CreditLimits](csv_reader_core::service::CreditLimits).
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreditConfig {
+    /// The credit limit of every client, none by default.
+    pub limit: Option<Decimal>,
+
+    /// The credit limits of the given clients, overriding the default one.
+    #[serde(default)]
+    pub clients: HashMap<String, Decimal>,
+}
+
+/// Configuration of the fraud detection, see
+/// [FraudRules](csv_reader_core::service::FraudRules), disabled when no rule
+/// is configured.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FraudConfig {
+    /// Flag the withdrawals of a client beyond this number within the window.
+    pub max_withdrawals: Option<usize>,
+
+    /// The window of the withdrawals in seconds.
+    pub withdrawal_window: Option<u64>,
+
+    /// Flag the disputes of a client beyond this share of its deposits and
+    /// withdrawals.
+    pub max_dispute_rate: Option<Decimal>,
+
+    /// Flag the amounts above this multiple of the mean amount of the client.
+    pub outlier_factor: Option<Decimal>,
+
+    /// Reject the flagged orders instead of only reporting them.
+    #[serde(default)]
+    pub block: bool,
+}
+
+/// Configuration of the caps on the amounts of the orders, see
+/// [OrderLimits](csv_reader_core::service::OrderLimits).
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// The maximum amount of a deposit.
+    pub max_deposit: Option<Decimal>,
+
+    /// The maximum amount of a withdrawal.
+    pub max_withdrawal: Option<Decimal>,
+
+    /// The maximum amount deposited and withdrawn by a client in a day.
+    pub max_daily_total: Option<Decimal>,
+}
+
+/// Configuration of a row transformer.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TransformerConfig {
+    /// Remove the UTF-8 byte order mark.
+    TrimBom,
+
+    /// Rename the transaction kinds.
+    KindSynonyms {
+        /// The expected kind by synonym.
+        synonyms: HashMap<String, String>,
+    },
+
+    /// Read the amounts as integers in minor units.
+    ScaleAmount {
+        /// The number of decimal places of the amounts.
+        decimals: u32,
+    },
+}
+
+impl Config {
+    /// Load the configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read configuration file '{}': {e}", path.display()))?;
+
+        toml::from_str(&content)
+            .map_err(|e| anyhow!("Invalid configuration file '{}': {e}", path.display()))
+    }
+
+    /// Get the configured format of the timestamps, if any.
+    pub fn timestamp_format(&self) -> Result<Option<TimestampFormat>> {
+        self.timestamps
+            .format
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+    }
+
+    /// Get the configured maximum number of records processed per second, if
+    /// any.
+    pub fn max_rate(&self) -> Result<Option<NonZeroU32>> {
+        self.reader
+            .max_rate
+            .map(|max_rate| {
+                NonZeroU32::new(max_rate)
+                    .ok_or_else(|| anyhow!("The maximum rate of records must be positive."))
+            })
+            .transpose()
+    }
+
+    /// Get the configured rules of the quarantine.
+    pub fn quarantine_rules(&self) -> Result<QuarantineRules> {
+        let quarantine = &self.quarantine;
+        let withdrawal_burst = match (quarantine.max_withdrawals, quarantine.withdrawal_window) {
+            (Some(count), Some(window)) => Some((count, Duration::from_secs(window))),
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!(
+                    "The maximum withdrawals and their window must be configured together."
+                ))
+            }
+        };
+
+        Ok(QuarantineRules {
+            max_amount: quarantine.max_amount,
+            foreign_disputes: quarantine.foreign_disputes,
+            withdrawal_burst,
+        })
+    }
+
+    /// Get the configured credit limits of the clients.
+    pub fn credit_limits(&self) -> Result<CreditLimits> {
+        let credit = &self.credit;
+        let mut limits = credit.limit.iter().chain(credit.clients.values());
+        if limits.any(|limit| *limit < Decimal::ZERO) {
+            return Err(anyhow!("The credit limits must not be negative."));
+        }
+
+        credit.clients.iter().try_fold(
+            CreditLimits::new(credit.limit.unwrap_or_default()),
+            |limits, (client_id, limit)| {
+                let client_id = client_id
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid client '{client_id}' of a credit limit."))?;
+
+                Ok(limits.with_client(client_id, *limit))
+            },
+        )
+    }
+
+    /// Get the configured rules of the fraud detection, if any.
+    pub fn fraud_rules(&self) -> Result<Option<FraudRules>> {
+        let fraud = &self.fraud;
+        let withdrawal_velocity = match (fraud.max_withdrawals, fraud.withdrawal_window) {
+            (Some(count), Some(window)) => Some((count, Duration::from_secs(window))),
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!(
+                    "The maximum withdrawals and their window must be configured together."
+                ))
+            }
+        };
+        let mut ratios = fraud.max_dispute_rate.iter().chain(&fraud.outlier_factor);
+        if ratios.any(|ratio| *ratio <= Decimal::ZERO) {
+            return Err(anyhow!(
+                "The dispute rate and the outlier factor must be strictly positive."
+            ));
+        }
+        if withdrawal_velocity.is_none()
+            && fraud.max_dispute_rate.is_none()
+            && fraud.outlier_factor.is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(FraudRules {
+            withdrawal_velocity,
+            max_dispute_rate: fraud.max_dispute_rate,
+            outlier_factor: fraud.outlier_factor,
+            block: fraud.block,
+        }))
+    }
+
+    /// Get the configured caps on the amounts of the orders.
+    pub fn order_limits(&self) -> Result<OrderLimits> {
+        let limits = &self.limits;
+        let mut caps = limits
+            .max_deposit
+            .iter()
+            .chain(&limits.max_withdrawal)
+            .chain(&limits.max_daily_total);
+        if caps.any(|cap| *cap <= Decimal::ZERO) {
+            return Err(anyhow!(
+                "The limits of the orders must be strictly positive."
+            ));
+        }
+
+        Ok(OrderLimits {
+            max_deposit: limits.max_deposit,
+            max_withdrawal: limits.max_withdrawal,
+            max_daily_total: limits.max_daily_total,
+        })
+    }
+
+    /// Get the configured timezone of the timestamps, if any.
+    pub fn timezone(&self) -> Option<&str> {
+        self.timestamps.timezone.as_deref()
+    }
+
+    /// Create the configured row transformers.
+    pub fn transformers(&self) -> Vec<Arc<dyn RowTransformer>> {
+        self.transformers
+            .iter()
+            .map(|transformer| -> Arc<dyn RowTransformer> {
+                match transformer {
+                    TransformerConfig::TrimBom => Arc::new(TrimBom),
+                    TransformerConfig::KindSynonyms { synonyms } => {
+                        Arc::new(KindSynonyms::new(synonyms.clone()))
+                    }
+                    TransformerConfig::ScaleAmount { decimals } => Arc::new(ScaleAmount {
+                        decimals: *decimals,
+                    }),
+                }
+            })
+            .collect()
+    }
+}
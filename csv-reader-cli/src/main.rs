@@ -0,0 +1,1594 @@
+mod batch;
+mod config;
+mod manifest;
+mod post_export;
+mod priority;
+mod progress_events;
+mod soak;
+mod spill;
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    num::{NonZeroU32, NonZeroUsize},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{debug, error, info, warn};
+
+use csv_reader_core::{
+    actor::{
+        channel, parse_timezone, spawn_actor, AmountFormat, AppliedTransactionSink,
+        ColumnPositions, DeadLetterExporter, DirectoryWatcher, DuplicateFilter, ErrorCollector,
+        ExtraColumns, KindFilter, MetricEvent, MetricsCollector, MissingAmount, Orders, Quarantine,
+        QuarantineRules, Receiver, Sender, SortKey, TimestampFormat, TimestampOrder, TrailerPolicy,
+    },
+    adapter::{FollowReader, InputEncoding, RejectSink},
+    model::MAX_DECIMALS,
+    service::{reconcile, AuditLog, ExcessTransactions, HeldShortfall, Statement},
+    AccountExporter, AccountManager, AccountManagerOptions, Accountant, ClientId,
+    InMemoryAccountStorage, JournalExporter, Reader, ReaderOptions, Result, TransactionOrder, TxId,
+};
+
+use batch::{batch, compute_accounts, BatchOptions};
+use config::Config;
+use manifest::Manifest;
+use post_export::{PostExport, SharedBuffer};
+use priority::IoPriority;
+use progress_events::{EventTarget, ProgressEvents};
+use soak::{soak, SoakOptions};
+use spill::{write_export, write_stdout};
+
+/// Format of the input file.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum InputFormat {
+    /// Comma separated values with a header line.
+    #[default]
+    Csv,
+
+    /// Apache Avro object container file.
+    #[cfg(feature = "avro")]
+    Avro,
+
+    /// Excel workbook, its first sheet holding the CSV columns.
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+
+    /// Stream of protobuf transaction orders, each prefixed by its length.
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+
+    /// Stream of MessagePack maps, one per transaction order.
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+}
+
+/// Command line arguments
+#[derive(Debug, Parser)]
+#[command(subcommand_negates_reqs = true)]
+struct CLIArguments {
+    /// The tool to run instead of processing an input.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The path to the CSV file to read. With the `object-store` feature, it
+    /// can also be an object store URL (e.g. `s3://bucket/tx.csv`). When it is
+    /// a directory, its CSV files are read and the files dropped afterwards
+    /// are read as they come. A glob pattern (e.g. `'data/2024-*.csv'`) reads
+    /// the matching files in path order.
+    #[arg(required = true)]
+    csv_file: Option<PathBuf>,
+
+    /// The format of the input file.
+    #[arg(long, value_enum, default_value_t = InputFormat::Csv)]
+    format: InputFormat,
+
+    /// The CSV field delimiter, a single ASCII character or `tab`. Detected
+    /// from the start of the file when no dialect option is given.
+    #[arg(long, value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+
+    /// The character quoting the CSV fields, `"` or `'`. Detected from the
+    /// start of the file when no dialect option is given.
+    #[arg(long, value_parser = parse_quote)]
+    quote: Option<u8>,
+
+    /// Do not detect the dialect of the CSV file: comma delimited fields,
+    /// double quotes and a header line, unless the `--delimiter`, `--quote` or
+    /// `--columns` options tell otherwise. These options also disable the
+    /// detection.
+    #[arg(long)]
+    no_sniff: bool,
+
+    /// The character encoding of the CSV file: `utf-8`, `latin-1`, `utf-16`
+    /// or `auto` to detect it from the start of the file. Other encodings than
+    /// UTF-8 are transcoded before being parsed. A byte order mark, as written
+    /// by Excel, overrides the encoding and is removed.
+    #[arg(long, default_value = "utf-8")]
+    encoding: InputEncoding,
+
+    /// Read a CSV file without header line. The value gives the positions,
+    /// starting at 0, of the type, client, tx and amount columns (e.g. `0,1,2,3`)
+    /// optionally followed by the ones of the timestamp and currency columns.
+    #[arg(long)]
+    columns: Option<ColumnPositions>,
+
+    /// Rename an input header to one of the expected `type`, `client`, `tx`
+    /// or `amount` names (e.g. `txn_type=type`). Can be repeated.
+    #[arg(long = "rename-header", value_name = "FROM=TO", value_parser = parse_header_mapping)]
+    header_mapping: Vec<(String, String)>,
+
+    /// Verify the control totals of the `trailer,<rows>,<deposits>,<withdrawals>`
+    /// record, the sums being optional: `ignore`, `flag` (log a warning on
+    /// mismatch) or `fail`.
+    #[arg(long, default_value = "ignore")]
+    trailer: TrailerPolicy,
+
+    /// Handling of the rows having more columns than expected: `ignore` or
+    /// `error`.
+    #[arg(long, default_value = "error")]
+    extra_columns: ExtraColumns,
+
+    /// Handling of the rows lacking the trailing amount column: `empty` or
+    /// `error`.
+    #[arg(long, default_value = "empty")]
+    missing_amount: MissingAmount,
+
+    /// Format of the amounts: `decimal` or `minor-units:N` for integer amounts
+    /// having N decimal places (e.g. `minor-units:4` reads `150000` as
+    /// `15.0000`).
+    #[arg(long, default_value = "decimal")]
+    amount_format: AmountFormat,
+
+    /// Reject the rows whose amount has more than this number of decimal
+    /// places instead of rounding them in the exported accounts.
+    #[arg(long, value_name = "N", default_value_t = MAX_DECIMALS)]
+    max_decimals: u32,
+
+    /// Format of the optional `timestamp` column: `iso8601`, `epoch-millis` or
+    /// `dd/mm/yyyy hh:mm`. Defaults to the one of the configuration file, or
+    /// `iso8601`.
+    #[arg(long, value_name = "FORMAT")]
+    timestamp_format: Option<TimestampFormat>,
+
+    /// Timezone of the timestamps without offset: `UTC` or an offset like
+    /// `+01:00`. Defaults to the one of the configuration file, or `UTC`.
+    #[arg(long, value_name = "TZ")]
+    timezone: Option<String>,
+
+    /// Handling of the records older than a previous one: `ignore`, `flag`
+    /// (log a warning) or `fail`. Checking the order reads the input with a
+    /// single worker.
+    #[arg(long, default_value = "ignore")]
+    timestamp_order: TimestampOrder,
+
+    /// Only process the orders of the given comma separated kinds (e.g.
+    /// `deposit,withdrawal`), the rows of the other kinds are skipped and
+    /// counted.
+    #[arg(long, value_name = "KINDS")]
+    only_kinds: Option<KindFilter>,
+
+    /// Drop the rows repeating the transaction and kind of one of the last N
+    /// rows, as delivered twice by an upstream retrying its deliveries. The
+    /// dropped rows are counted.
+    #[arg(long, value_name = "N")]
+    dedup: Option<usize>,
+
+    /// Recover from the rows with unbalanced quotes by skipping them up to the
+    /// next line, reporting the skipped byte range. Quoted fields cannot hold
+    /// line breaks then.
+    #[arg(long)]
+    resync_lines: bool,
+
+    /// Skip this number of data rows at the start of the input.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip: u64,
+
+    /// Stop reading after this number of data rows, not counting the skipped
+    /// ones. Along with `--skip`, it helps bisecting a faulty file.
+    #[arg(long, value_name = "M")]
+    limit: Option<u64>,
+
+    /// Resume the reading of an interrupted run from this byte offset of the
+    /// CSV file, as logged when the run failed. The header line is still read.
+    #[arg(long, value_name = "BYTES", default_value_t = 0)]
+    start_offset: u64,
+
+    /// Fail the run on the first invalid record, reporting its line, instead
+    /// of logging and skipping it. The file is then parsed by a single thread.
+    #[arg(long)]
+    strict: bool,
+
+    /// Write the rejected records to this CSV file, as read followed by an
+    /// `error` column giving the reason of their rejection, so they can be
+    /// fixed and processed again.
+    #[arg(long, value_name = "FILE")]
+    rejects: Option<PathBuf>,
+
+    /// Dispute the deposits listed in this file, one transaction identifier
+    /// per line, before processing the input, so the open cases of the case
+    /// management are reflected when their resolves and chargebacks arrive.
+    #[arg(long, value_name = "FILE")]
+    open_disputes: Option<PathBuf>,
+
+    /// Merge the account of a client assigned two identifiers upstream into
+    /// its other account once the input is processed: the transactions are
+    /// attributed to `INTO` and the funds combined. The merges are recorded
+    /// in the journal. Can be repeated.
+    #[arg(long = "merge-clients", value_name = "FROM:INTO", value_parser = parse_client_merge)]
+    merge_clients: Vec<(ClientId, ClientId)>,
+
+    /// Read the detailed settings, like the row transformers, from the given
+    /// TOML configuration file.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Record the transactions in a double-entry ledger and write the journal
+    /// to the given CSV file.
+    #[arg(long, value_name = "FILE")]
+    journal: Option<PathBuf>,
+
+    /// Write the dispute orders rejected for the transactions of each account
+    /// (not found, not disputable or already disputed) to the given CSV file,
+    /// to tell support why a dispute did not go through.
+    #[arg(long, value_name = "FILE")]
+    dispute_notes: Option<PathBuf>,
+
+    /// Record every processed order, whether it was applied or why it was
+    /// rejected, with the balances of its account before and after, to the
+    /// given CSV file.
+    #[arg(long, value_name = "FILE")]
+    audit_log: Option<PathBuf>,
+
+    /// Write the orders flagged by the fraud detection of the `[fraud]`
+    /// section of the configuration to the given CSV file.
+    #[arg(long, value_name = "FILE")]
+    fraud_flags: Option<PathBuf>,
+
+    /// Write the orders that failed to be processed, with their error, to the
+    /// given CSV file. It can be read again as an input once the cause of the
+    /// failures is fixed, its `error` column being ignored.
+    #[arg(long, value_name = "FILE")]
+    dead_letters: Option<PathBuf>,
+
+    /// Write the number of failed orders by kind of error, with the first
+    /// order failed with each, to the given CSV file.
+    #[arg(long, value_name = "FILE")]
+    error_report: Option<PathBuf>,
+
+    /// Stream the transactions applied to the given CSV file as they are
+    /// applied, the deposits diverted to the suspense account under its
+    /// client. It can be read again as an input to get the same balances.
+    #[arg(long, value_name = "FILE")]
+    applied_journal: Option<PathBuf>,
+
+    /// Write the orders matching the rules of the `[quarantine]` section of
+    /// the configuration file to the given CSV file, with their reason,
+    /// instead of applying them. It can be read again as an input once the
+    /// orders are reviewed, its `reason` column being ignored.
+    #[arg(long, value_name = "FILE")]
+    quarantine: Option<PathBuf>,
+
+    /// Print the counters of the run on the standard error once the input is
+    /// processed: the records read and the orders applied and rejected by
+    /// transaction kind. The records are only counted when reading a CSV
+    /// file.
+    #[arg(long)]
+    metrics: bool,
+
+    /// Credit the deposits made on locked accounts to the given suspense
+    /// account instead of rejecting them.
+    #[arg(long, value_name = "CLIENT_ID")]
+    suspense_account: Option<ClientId>,
+
+    /// Suspend a client for review after this number of consecutive rejected
+    /// orders, its further orders are rejected.
+    #[arg(long, value_name = "K")]
+    suspend_after: Option<NonZeroUsize>,
+
+    /// Report the clients applying more than this number of transactions
+    /// during the run, likely bad data or abuse.
+    #[arg(long, value_name = "N")]
+    max_transactions: Option<NonZeroUsize>,
+
+    /// Handling of the clients exceeding `--max-transactions`: `flag` only
+    /// reports them, `reject` also rejects their orders over the maximum.
+    #[arg(long, default_value = "flag")]
+    excess_transactions: ExcessTransactions,
+
+    /// Handling of the resolutions and chargebacks of a deposit whose amount
+    /// is no longer fully held: `reject` them, `clamp` them to the held funds
+    /// left or settle the `proportional` share of the held funds left among
+    /// the open disputes of the account.
+    #[arg(long, default_value = "reject")]
+    held_shortfall: HeldShortfall,
+
+    /// Apply the `adjustment` orders, the manual corrections of the
+    /// administrators crediting their signed amount to the available funds or
+    /// debiting it. They are rejected without it.
+    #[arg(long)]
+    allow_adjustments: bool,
+
+    /// Keep reading the CSV file once its end is reached, like `tail -f`, so
+    /// the rows appended to it are processed as they are written.
+    #[arg(long)]
+    follow: bool,
+
+    /// When watching a directory or following a file, stop and export the
+    /// accounts once no data has been added for this number of seconds.
+    /// Without it, the input is read until the process is stopped.
+    #[arg(long, value_name = "SECONDS")]
+    idle_timeout: Option<u64>,
+
+    /// On a fatal error, export the accounts processed so far to
+    /// `<input>.partial.csv` and the error report to `<input>.partial.log` in
+    /// the current directory.
+    #[arg(long)]
+    partial_on_error: bool,
+
+    /// Add the `disputed_count` and `disputed_sum` columns to the exported
+    /// accounts, telling how many disputes are open on each account.
+    #[arg(long)]
+    dispute_columns: bool,
+
+    /// Order of the exported accounts: `client`, `total_desc` or `held_desc`
+    /// (largest accounts first). The accounts are in no particular order
+    /// without it.
+    #[arg(long, value_name = "KEY")]
+    sort_by: Option<SortKey>,
+
+    /// Only parse and validate the CSV files without computing the accounts,
+    /// print the validation report and fail if a record is invalid.
+    #[arg(long)]
+    validate_only: bool,
+
+    /// Write the provenance manifest of the exported accounts (version, git
+    /// hash, settings digest, input digests and run id) to this TOML file.
+    /// The other exports get a `<file>.manifest.toml` sidecar manifest.
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Lower the CPU priority of the process to this niceness, from 0 to 19,
+    /// so a batch run does not starve the other workloads of the host.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(i32).range(0..=19))]
+    nice: Option<i32>,
+
+    /// Lower the IO priority of the process: `idle` or `best-effort:N` with N
+    /// from 0 (highest) to 7 (lowest). Only supported on Linux.
+    #[arg(long, value_name = "PRIORITY")]
+    ionice: Option<IoPriority>,
+
+    /// Number of threads parsing a CSV file. The file is split into chunks
+    /// of lines, so its quoted fields must not hold line breaks. Ignored when
+    /// following a file or watching a directory.
+    #[arg(long, default_value = "1")]
+    workers: NonZeroUsize,
+
+    /// Read the files matching a glob pattern concurrently, one thread each,
+    /// instead of one after the other. The orders of a client are applied in
+    /// their order within a file, not across the files.
+    #[arg(long)]
+    concurrent_files: bool,
+
+    /// Bound the channel of the orders sent to the accountant to this number
+    /// of orders, the readers waiting for the accountant once it is full
+    /// instead of queuing the whole input in memory.
+    #[cfg(feature = "crossbeam")]
+    #[arg(long, value_name = "N")]
+    channel_capacity: Option<NonZeroUsize>,
+
+    /// Process at most this number of records per second, so a slow accounts
+    /// storage is not overwhelmed. Overrides the `max_rate` of the `[reader]`
+    /// section of the configuration file. Only applies to the CSV inputs.
+    #[arg(long, value_name = "N")]
+    max_rate: Option<NonZeroU32>,
+
+    /// Write JSON progress events (bytes read, rows processed, rejects, ETA),
+    /// one per line every second, to this target: a file, an inherited file
+    /// descriptor (`fd:N`) or a unix socket (`unix:PATH`). Only the reading of
+    /// a CSV file reports its progress.
+    #[arg(long, value_name = "TARGET")]
+    progress_events: Option<EventTarget>,
+}
+
+/// Tools run instead of processing an input.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Process a CSV file and compare its accounts to an external statement
+    /// of the expected client balances, a CSV file with the columns of the
+    /// accounts export. The discrepancies are printed on the standard output.
+    Reconcile {
+        /// The statement of the expected balances.
+        #[arg(long, value_name = "CSV_FILE")]
+        statement: PathBuf,
+
+        /// The CSV file to process.
+        #[arg(value_name = "CSV_FILE")]
+        csv_file: PathBuf,
+    },
+
+    /// Process an endless stream of synthetic transactions for a set
+    /// duration, verifying the invariants of the accounts periodically, to
+    /// catch the long-run stability regressions before a release. The report
+    /// of the throughput, memory and errors is printed on the standard output.
+    Soak {
+        /// The number of seconds the stream is read. The orders read are all
+        /// processed before the report, which takes longer when the accounts
+        /// fall behind the parsing.
+        #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+        duration: u64,
+
+        /// The number of seconds between two verifications of the invariants.
+        #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+        check_interval: u64,
+
+        /// The number of clients of the stream.
+        #[arg(long, value_name = "N", default_value_t = 1000)]
+        clients: ClientId,
+
+        /// The seed of the stream, the same seed giving the same stream.
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+
+        /// Number of threads parsing the stream.
+        #[arg(long, default_value = "1")]
+        workers: NonZeroUsize,
+    },
+
+    /// Process independent CSV files concurrently, each with accounts of its
+    /// own written to `<output dir>/<input stem>-accounts.csv`. The outcome
+    /// of every file is printed on the standard output.
+    Batch {
+        /// The CSV files to process.
+        #[arg(required = true, value_name = "CSV_FILE")]
+        files: Vec<PathBuf>,
+
+        /// The directory the accounts files are written to, created if
+        /// missing.
+        #[arg(long, value_name = "DIR", default_value = "out")]
+        output_dir: PathBuf,
+
+        /// The number of files processed at a time, the number of CPUs by
+        /// default.
+        #[arg(long, value_name = "N")]
+        jobs: Option<NonZeroUsize>,
+    },
+}
+
+/// Compare the accounts of the given file to a statement and print the
+/// report.
+fn run_reconcile(statement: &Path, csv_file: &Path) -> Result<()> {
+    let file = File::open(statement)
+        .map_err(|e| anyhow!("Cannot read the statement '{}': {e}", statement.display()))?;
+    let statement = Statement::from_reader(BufReader::new(file))?;
+    let accounts = compute_accounts(csv_file)?.get_accounts();
+    let report = reconcile(&accounts, &statement);
+    print!("{report}");
+
+    if !report.is_reconciled() {
+        bail!("The accounts do not match the statement.");
+    }
+
+    Ok(())
+}
+
+/// Run a soak test and print the report.
+fn run_soak(options: &SoakOptions) -> Result<()> {
+    let report = soak(options)?;
+    print!("{report}");
+
+    if !report.is_stable() {
+        bail!("The soak test found the pipeline unstable.");
+    }
+
+    Ok(())
+}
+
+/// Process the files of a batch and print the outcome of each.
+fn run_batch(options: &BatchOptions) -> Result<()> {
+    let report = batch(options)?;
+    print!("{report}");
+
+    let failures = report.failures();
+    if failures > 0 {
+        bail!("{failures} file(s) of the batch failed.");
+    }
+
+    Ok(())
+}
+
+/// Parse a delimiter argument, `tab` and `\t` stand for the tabulation.
+fn parse_delimiter(value: &str) -> std::result::Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!(
+            "delimiter must be a single ASCII character, '{value}' given"
+        )),
+    }
+}
+
+/// Parse a quote argument, a single ASCII character.
+fn parse_quote(value: &str) -> std::result::Result<u8, String> {
+    match value.as_bytes() {
+        [quote] if quote.is_ascii() => Ok(*quote),
+        _ => Err(format!(
+            "quote must be a single ASCII character, '{value}' given"
+        )),
+    }
+}
+
+/// Parse a `FROM=TO` header renaming argument.
+fn parse_header_mapping(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+            Ok((from.trim().to_string(), to.trim().to_string()))
+        }
+        _ => Err(format!("expected FROM=TO, '{value}' given")),
+    }
+}
+
+/// Parse a `FROM:INTO` client merge argument.
+fn parse_client_merge(value: &str) -> std::result::Result<(ClientId, ClientId), String> {
+    value
+        .split_once(':')
+        .and_then(|(from, into)| Some((from.trim().parse().ok()?, into.trim().parse().ok()?)))
+        .ok_or_else(|| format!("expected FROM:INTO client ids, '{value}' given"))
+}
+
+struct Application {
+    csv_file: PathBuf,
+    format: InputFormat,
+    reader_options: ReaderOptions,
+    manager_options: AccountManagerOptions,
+    journal_file: Option<PathBuf>,
+    idle_timeout: Option<Duration>,
+    follow: bool,
+    partial_on_error: bool,
+    dispute_columns: bool,
+    sort_by: Option<SortKey>,
+    validate_only: bool,
+    manifest: Option<(PathBuf, Manifest)>,
+    open_disputes: Vec<TxId>,
+    client_merges: Vec<(ClientId, ClientId)>,
+    post_export: Option<PostExport>,
+    progress_events: Option<Arc<Mutex<ProgressEvents>>>,
+    spill_dir: Option<PathBuf>,
+    dispute_notes_file: Option<PathBuf>,
+    fraud_flags_file: Option<PathBuf>,
+    dead_letters_file: Option<PathBuf>,
+    error_report_file: Option<PathBuf>,
+    applied_journal_file: Option<PathBuf>,
+    quarantine: Option<(PathBuf, QuarantineRules)>,
+    print_metrics: bool,
+    workers: usize,
+    concurrent_files: bool,
+    #[cfg(feature = "crossbeam")]
+    channel_capacity: Option<usize>,
+    max_rate: Option<NonZeroU32>,
+}
+
+impl Application {
+    fn new(
+        csv_file: PathBuf,
+        format: InputFormat,
+        reader_options: ReaderOptions,
+        manager_options: AccountManagerOptions,
+        journal_file: Option<PathBuf>,
+        idle_timeout: Option<Duration>,
+    ) -> Result<Self> {
+        // Object store URLs are checked when the object is opened.
+        if is_glob_pattern(&csv_file) {
+            if !matches!(format, InputFormat::Csv) {
+                bail!("Only CSV files can be read from a glob pattern.");
+            }
+            if reader_options.start_offset > 0 {
+                bail!("A glob pattern cannot be resumed from a byte offset.");
+            }
+            if expand_glob(&csv_file)?.is_empty() {
+                bail!("No file matches the pattern '{}'.", csv_file.display());
+            }
+        } else if !is_object_store_url(&csv_file) {
+            if !csv_file.exists() {
+                bail!("CSV file does not exist: '{:?}'.", csv_file.display());
+            }
+            if csv_file.is_dir() {
+                if reader_options.start_offset > 0 {
+                    bail!("A directory cannot be resumed from a byte offset.");
+                }
+                if !matches!(format, InputFormat::Csv) {
+                    bail!("Only CSV files can be read from a directory.");
+                }
+            } else if !csv_file.is_file() {
+                bail!("CSV file is not a file: '{:?}'.", csv_file.canonicalize());
+            }
+        }
+        let this = Self {
+            csv_file,
+            format,
+            reader_options,
+            manager_options,
+            journal_file,
+            idle_timeout,
+            follow: false,
+            partial_on_error: false,
+            dispute_columns: false,
+            sort_by: None,
+            validate_only: false,
+            manifest: None,
+            open_disputes: Vec::new(),
+            client_merges: Vec::new(),
+            post_export: None,
+            progress_events: None,
+            spill_dir: None,
+            dispute_notes_file: None,
+            fraud_flags_file: None,
+            dead_letters_file: None,
+            error_report_file: None,
+            applied_journal_file: None,
+            quarantine: None,
+            print_metrics: false,
+            workers: 1,
+            concurrent_files: false,
+            #[cfg(feature = "crossbeam")]
+            channel_capacity: None,
+            max_rate: None,
+        };
+
+        Ok(this)
+    }
+
+    /// Keep reading the input file once its end is reached.
+    fn with_follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+
+        self
+    }
+
+    /// Export the accounts processed so far when the run fails.
+    fn with_partial_on_error(mut self, partial_on_error: bool) -> Self {
+        self.partial_on_error = partial_on_error;
+
+        self
+    }
+
+    /// Add the dispute columns to the exported accounts.
+    fn with_dispute_columns(mut self, dispute_columns: bool) -> Self {
+        self.dispute_columns = dispute_columns;
+
+        self
+    }
+
+    /// Export the accounts in the order of the given key, if any.
+    fn with_sort_by(mut self, sort_by: Option<SortKey>) -> Self {
+        self.sort_by = sort_by;
+
+        self
+    }
+
+    /// Dispute the given deposits before processing the input.
+    fn with_open_disputes(mut self, open_disputes: Vec<TxId>) -> Self {
+        self.open_disputes = open_disputes;
+
+        self
+    }
+
+    /// Merge the accounts of the given clients once the input is processed.
+    fn with_client_merges(mut self, client_merges: Vec<(ClientId, ClientId)>) -> Self {
+        self.client_merges = client_merges;
+
+        self
+    }
+
+    /// Post-process the accounts export, see [PostExport].
+    fn with_post_export(mut self, post_export: Option<PostExport>) -> Self {
+        self.post_export = post_export;
+
+        self
+    }
+
+    /// Write the progress events of the reading, see [ProgressEvents].
+    fn with_progress_events(mut self, progress_events: Option<ProgressEvents>) -> Self {
+        self.progress_events = progress_events.map(|events| Arc::new(Mutex::new(events)));
+
+        self
+    }
+
+    /// Write the export files to the given directory when their disk is full.
+    fn with_spill_dir(mut self, spill_dir: Option<PathBuf>) -> Self {
+        self.spill_dir = spill_dir;
+
+        self
+    }
+
+    /// Write the rejected dispute orders of the accounts to the given file.
+    fn with_dispute_notes_file(mut self, dispute_notes_file: Option<PathBuf>) -> Self {
+        self.dispute_notes_file = dispute_notes_file;
+
+        self
+    }
+
+    /// Write the orders flagged by the fraud detection to the given file.
+    fn with_fraud_flags_file(mut self, fraud_flags_file: Option<PathBuf>) -> Self {
+        self.fraud_flags_file = fraud_flags_file;
+
+        self
+    }
+
+    /// Write the orders that failed to be processed to the given file.
+    fn with_dead_letters_file(mut self, dead_letters_file: Option<PathBuf>) -> Self {
+        self.dead_letters_file = dead_letters_file;
+
+        self
+    }
+
+    /// Write the failed orders by kind of error to the given file.
+    fn with_error_report_file(mut self, error_report_file: Option<PathBuf>) -> Self {
+        self.error_report_file = error_report_file;
+
+        self
+    }
+
+    /// Stream the transactions applied to the given file.
+    fn with_applied_journal_file(mut self, applied_journal_file: Option<PathBuf>) -> Self {
+        self.applied_journal_file = applied_journal_file;
+
+        self
+    }
+
+    /// Write the orders matching the given rules to the given file instead of
+    /// applying them.
+    fn with_quarantine(mut self, quarantine_file: Option<PathBuf>, rules: QuarantineRules) -> Self {
+        self.quarantine = quarantine_file.map(|file| (file, rules));
+
+        self
+    }
+
+    /// Print the counters of the run once the input is processed.
+    fn with_print_metrics(mut self, print_metrics: bool) -> Self {
+        self.print_metrics = print_metrics;
+
+        self
+    }
+
+    /// Only validate the input instead of computing the accounts.
+    fn with_validate_only(mut self, validate_only: bool) -> Self {
+        self.validate_only = validate_only;
+
+        self
+    }
+
+    /// Write the given provenance manifest of the exported accounts to the
+    /// given file, and next to the other exports.
+    fn with_manifest(mut self, manifest_file: Option<PathBuf>, manifest: Manifest) -> Self {
+        self.manifest = manifest_file.map(|file| (file, manifest));
+
+        self
+    }
+
+    /// Parse the CSV files with the given number of threads.
+    fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+
+        self
+    }
+
+    /// Read the files matching a glob pattern concurrently.
+    fn with_concurrent_files(mut self, concurrent_files: bool) -> Self {
+        self.concurrent_files = concurrent_files;
+
+        self
+    }
+
+    /// Bound the channel of the orders to the given capacity, if any.
+    #[cfg(feature = "crossbeam")]
+    fn with_channel_capacity(mut self, channel_capacity: Option<usize>) -> Self {
+        self.channel_capacity = channel_capacity;
+
+        self
+    }
+
+    /// Read at most the given number of records per second from the CSV
+    /// files, if any.
+    fn with_max_rate(mut self, max_rate: Option<NonZeroU32>) -> Self {
+        self.max_rate = max_rate;
+
+        self
+    }
+
+    fn run(&self) -> Result<()> {
+        info!("Starting CSV_READER version {}", env!("CARGO_PKG_VERSION"));
+        debug!("Reading CSV file: '{:?}'.", self.csv_file.canonicalize());
+
+        if self.validate_only {
+            return self.validate();
+        }
+
+        let account_manager = Arc::new(AccountManager::with_options(
+            InMemoryAccountStorage::default(),
+            self.manager_options.clone(),
+        ));
+        account_manager.preload_disputes(self.open_disputes.iter().copied());
+        let result = self.process(account_manager.clone());
+        // the audit trail is kept whatever the outcome of the run
+        let audited = self
+            .manager_options
+            .audit_log
+            .as_ref()
+            .map_or(Ok(()), |audit_log| audit_log.flush());
+        let result = result.and(audited);
+
+        if let Err(error) = &result {
+            if self.partial_on_error {
+                if let Err(export_error) = self.export_partial(account_manager, error) {
+                    error!("Partial export failed: {}", export_error);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Validate the CSV files and print the validation report of each.
+    fn validate(&self) -> Result<()> {
+        if !matches!(self.format, InputFormat::Csv) || self.csv_file.is_dir() || self.follow {
+            bail!("Only CSV files and glob patterns can be validated.");
+        }
+        let inputs = if is_glob_pattern(&self.csv_file) {
+            expand_glob(&self.csv_file)?
+        } else {
+            vec![self.csv_file.clone()]
+        };
+        let mut invalid_files = 0;
+
+        for input in inputs {
+            let reader: Box<dyn Read + Sync + Send> = if input == self.csv_file {
+                self.open_input()?
+            } else {
+                Box::new(BufReader::new(File::open(&input)?))
+            };
+            let report = Orders::new(reader, self.reader_options.clone()).validate();
+            print!("{}: {report}", input.display());
+
+            if !report.is_valid() {
+                invalid_files += 1;
+            }
+        }
+        if invalid_files > 0 {
+            bail!("Validation failed for {invalid_files} file(s).");
+        }
+
+        Ok(())
+    }
+
+    /// Create the channel of the orders sent to the accountant, bounded to
+    /// the configured capacity, if any.
+    fn order_channel(&self) -> (Sender<TransactionOrder>, Receiver<TransactionOrder>) {
+        #[cfg(feature = "crossbeam")]
+        if let Some(capacity) = self.channel_capacity {
+            return csv_reader_core::actor::bounded_channel(capacity);
+        }
+
+        channel()
+    }
+
+    /// Process the input with the given account manager and export the
+    /// accounts.
+    fn process(&self, account_manager: Arc<AccountManager>) -> Result<()> {
+        // dependencies
+        // Create a channel to send orders to the accountant actor.
+        let (order_sender, order_receiver) = self.order_channel();
+
+        // Create the accountant actor and start it in a separate thread.
+        // The failed orders are aggregated by the error collector actor.
+        let (dead_letter_sender, dead_letter_receiver) = channel();
+        // The counters of the actors are consolidated by the metrics collector.
+        let (metric_sender, metric_receiver) = channel();
+        let mut accountant_actor = Accountant::new(account_manager.clone(), order_receiver)
+            .with_dead_letter_sender(dead_letter_sender)
+            .with_metric_sender(metric_sender.clone());
+        // The applied transactions are streamed to the journal file by the
+        // sink actor.
+        let sink_handler = match &self.applied_journal_file {
+            Some(path) => {
+                let writer = BufWriter::new(File::create(path)?);
+                let (applied_sender, applied_receiver) = channel();
+                accountant_actor = accountant_actor.with_applied_sender(applied_sender);
+                let sink_actor = AppliedTransactionSink::new(applied_receiver, Box::new(writer));
+                Some(std::thread::spawn(move || sink_actor.run()))
+            }
+            None => None,
+        };
+        let collector_actor = ErrorCollector::new(dead_letter_receiver)
+            .with_dead_letters(self.dead_letters_file.is_some());
+        let collector_handler = spawn_actor(collector_actor)?;
+        let metrics_actor = MetricsCollector::new(metric_receiver);
+        let metrics_handler = spawn_actor(metrics_actor)?;
+        let account_handler = spawn_actor(accountant_actor)?;
+        // The orders read go through the quarantine actor, forwarding the
+        // ones not quarantined to the accountant.
+        let (order_sender, quarantine_handler) = match &self.quarantine {
+            Some((path, rules)) => {
+                let writer = BufWriter::new(File::create(path)?);
+                let (read_sender, read_receiver) = channel();
+                let quarantine_actor =
+                    Quarantine::new(read_receiver, order_sender, Box::new(writer), rules.clone());
+                (read_sender, Some(spawn_actor(quarantine_actor)?))
+            }
+            None => (order_sender, None),
+        };
+
+        // Offset of the CSV file where a failed reading can be resumed.
+        let mut resume_offset = None;
+        let reports_progress = matches!(self.format, InputFormat::Csv)
+            && !self.csv_file.is_dir()
+            && !is_glob_pattern(&self.csv_file);
+        if self.progress_events.is_some() && !reports_progress {
+            warn!("Progress events are only reported when reading a CSV file.");
+        }
+
+        // Create the reader actor and start it in a separate thread.
+        let reader_handler = match self.format {
+            InputFormat::Csv if self.csv_file.is_dir() => {
+                let mut watcher_actor = DirectoryWatcher::new(
+                    order_sender,
+                    &self.csv_file,
+                    self.reader_options.clone(),
+                );
+                if let Some(idle_timeout) = self.idle_timeout {
+                    watcher_actor = watcher_actor.with_idle_timeout(idle_timeout);
+                }
+                std::thread::spawn(move || watcher_actor.run())
+            }
+            InputFormat::Csv if is_glob_pattern(&self.csv_file) => {
+                let csv_files = expand_glob(&self.csv_file)?;
+                let reader_options = self.reader_options.clone();
+                let workers = self.workers;
+                let max_rate = self.max_rate;
+                let concurrent_files = self.concurrent_files;
+                let read_file = move |csv_file: PathBuf, order_sender| -> Result<()> {
+                    info!("Reading CSV file: '{}'.", csv_file.display());
+                    let buffer = BufReader::new(File::open(&csv_file)?);
+                    let mut reader_actor = Reader::with_options(
+                        order_sender,
+                        Box::new(buffer),
+                        reader_options.clone(),
+                    )
+                    .with_workers(workers);
+                    if let Some(max_rate) = max_rate {
+                        reader_actor = reader_actor.with_max_rate(max_rate);
+                    }
+
+                    reader_actor.run()
+                };
+                std::thread::spawn(move || {
+                    if !concurrent_files {
+                        for csv_file in csv_files {
+                            read_file(csv_file, order_sender.clone())?;
+                        }
+
+                        return Ok(());
+                    }
+                    // the readers share the channel of the accountant
+                    let readers = csv_files
+                        .into_iter()
+                        .map(|csv_file| {
+                            let read_file = read_file.clone();
+                            let order_sender = order_sender.clone();
+                            std::thread::spawn(move || read_file(csv_file, order_sender))
+                        })
+                        .collect::<Vec<_>>();
+                    drop(order_sender);
+                    // wait for all the readers before reporting the first error
+                    readers
+                        .into_iter()
+                        .map(|reader| reader.join().expect("Reader thread panicked"))
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .collect()
+                })
+            }
+            InputFormat::Csv => {
+                // Create a buffered reader for the CSV file.
+                let mut buffer = self.open_input()?;
+                let mut workers = self.workers;
+                if self.follow {
+                    let mut follow_reader = FollowReader::new(buffer);
+                    if let Some(idle_timeout) = self.idle_timeout {
+                        follow_reader = follow_reader.with_idle_timeout(idle_timeout);
+                    }
+                    buffer = Box::new(follow_reader);
+                    // chunks would hold the appended rows back
+                    workers = 1;
+                }
+                let progress = Arc::new(AtomicU64::new(self.reader_options.start_offset));
+                resume_offset = Some(progress.clone());
+                let events = self.progress_events.clone();
+                let metric_sender = metric_sender.clone();
+                if let Some(events) = &events {
+                    // a followed file grows, its size tells nothing
+                    let bytes_total = std::fs::metadata(&self.csv_file)
+                        .ok()
+                        .filter(|metadata| metadata.is_file() && !self.follow)
+                        .map(|metadata| metadata.len());
+                    let start_offset = self.reader_options.start_offset;
+                    events.lock().unwrap().start(start_offset, bytes_total);
+                }
+                let mut reader_actor =
+                    Reader::with_options(order_sender, buffer, self.reader_options.clone())
+                        .with_workers(workers)
+                        .with_progress(Duration::from_secs(1), move |p| {
+                            progress.store(p.resume_offset, Ordering::Relaxed);
+                            if let Some(events) = &events {
+                                events.lock().unwrap().progress(p);
+                            }
+                            // The metrics may not be collected anymore.
+                            let _ = metric_sender.send(MetricEvent::Read(p));
+                        });
+                if let Some(max_rate) = self.max_rate {
+                    reader_actor = reader_actor.with_max_rate(max_rate);
+                }
+                spawn_actor(reader_actor)?
+            }
+            #[cfg(feature = "avro")]
+            InputFormat::Avro => {
+                let buffer = self.open_input()?;
+                let reader_actor = csv_reader_core::actor::AvroReader::new(order_sender, buffer);
+                std::thread::spawn(move || reader_actor.run())
+            }
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => {
+                let buffer = self.open_input()?;
+                let reader_actor = csv_reader_core::actor::MsgPackReader::new(order_sender, buffer);
+                std::thread::spawn(move || reader_actor.run())
+            }
+            #[cfg(feature = "protobuf")]
+            InputFormat::Protobuf => {
+                let buffer = self.open_input()?;
+                let reader_actor =
+                    csv_reader_core::actor::ProtobufReader::new(order_sender, buffer);
+                std::thread::spawn(move || reader_actor.run())
+            }
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => {
+                let buffer = self.open_input()?;
+                let reader_actor = csv_reader_core::actor::XlsxReader::with_options(
+                    order_sender,
+                    buffer,
+                    self.reader_options.clone(),
+                );
+                std::thread::spawn(move || reader_actor.run())
+            }
+        };
+
+        let reader_result = reader_handler.join().expect("Reader thread panicked");
+        if let (Some(events), true) = (&self.progress_events, reports_progress) {
+            events.lock().unwrap().done(reader_result.is_ok());
+        }
+        if let (Err(_), Some(offset)) = (&reader_result, &resume_offset) {
+            warn!(
+                "The reading can be resumed with --start-offset {}.",
+                offset.load(Ordering::Relaxed)
+            );
+        }
+        reader_result
+            .and(account_handler.join().expect("Accountant thread panicked"))
+            .map_err(|e| anyhow!("Threads returned an error: {:#?}", e))?; // Join the threads and propagate any error.
+
+        if let (Some(quarantine_handler), Some((path, _))) = (quarantine_handler, &self.quarantine)
+        {
+            let quarantined = quarantine_handler
+                .join()
+                .expect("Quarantine thread panicked")?;
+            info!(
+                "{} orders quarantined to '{}'.",
+                quarantined,
+                path.display()
+            );
+        }
+        // the sink stops once the accountant is gone
+        if let (Some(sink_handler), Some(path)) = (sink_handler, &self.applied_journal_file) {
+            sink_handler
+                .join()
+                .expect("Applied transaction sink thread panicked")?;
+            info!("Applied transactions written to '{}'.", path.display());
+        }
+        // the collector stops once the last sender, of the reader or of the
+        // accountant, is dropped
+        drop(metric_sender);
+        let metrics = metrics_handler
+            .join()
+            .expect("Metrics collector thread panicked")?;
+        if self.print_metrics {
+            eprint!("{metrics}");
+        }
+
+        for (from, into) in &self.client_merges {
+            let count = account_manager.merge_clients(*from, *into)?;
+            info!(
+                "Merged client {} into client {}, {} transactions attributed.",
+                from, into, count
+            );
+        }
+
+        let stats = account_manager.stats();
+        info!(
+            "Storage: {} accounts, {} transactions, {} open disputes, ~{} bytes in memory, {} bytes on disk.",
+            stats.accounts,
+            stats.transactions,
+            stats.open_disputes,
+            stats.memory_bytes,
+            stats.disk_bytes
+        );
+
+        if let Some(filter) = &self.reader_options.only_kinds {
+            for (kind, count) in filter.skipped() {
+                info!("Skipped {} {} rows.", count, kind);
+            }
+        }
+        if let Some(filter) = &self.reader_options.dedup {
+            info!("Dropped {} duplicate rows.", filter.dropped());
+        }
+
+        for client_id in account_manager.get_suspended_clients() {
+            warn!("Client {} was suspended for review.", client_id);
+        }
+
+        for client_id in account_manager.get_excessive_clients() {
+            warn!(
+                "Client {} exceeded the maximum number of transactions.",
+                client_id
+            );
+        }
+
+        for tx_id in account_manager.get_pending_disputes() {
+            warn!("The disputed transaction tx={} was never applied.", tx_id);
+        }
+
+        // Export the double-entry journal if requested.
+        if let Some(journal_file) = &self.journal_file {
+            let journal_file = write_export(journal_file, self.spill_dir.as_deref(), |writer| {
+                JournalExporter::new(account_manager.clone(), writer).run()
+            })?;
+            self.write_manifest(&Manifest::sidecar(&journal_file))?;
+        }
+
+        let error_report = collector_handler
+            .join()
+            .expect("Error collector thread panicked")?;
+        for summary in &error_report.summaries {
+            info!(
+                "{} orders failed with {}, first tx={}: {}",
+                summary.count, summary.kind, summary.first_tx, summary.first_error
+            );
+        }
+
+        // Export the orders that failed to be processed if requested.
+        if let Some(dead_letters_file) = &self.dead_letters_file {
+            let dead_letters = &error_report.dead_letters;
+            let dead_letters_file =
+                write_export(dead_letters_file, self.spill_dir.as_deref(), |writer| {
+                    DeadLetterExporter::new(dead_letters.clone(), writer).run()
+                })?;
+            info!(
+                "{} failed orders written to '{}'.",
+                dead_letters.len(),
+                dead_letters_file.display()
+            );
+            self.write_manifest(&Manifest::sidecar(&dead_letters_file))?;
+        }
+
+        // Export the failed orders by kind of error if requested.
+        if let Some(error_report_file) = &self.error_report_file {
+            let error_report_file =
+                write_export(error_report_file, self.spill_dir.as_deref(), |writer| {
+                    error_report.write(writer)
+                })?;
+            self.write_manifest(&Manifest::sidecar(&error_report_file))?;
+        }
+
+        // Export the rejected dispute orders if requested.
+        if let Some(dispute_notes_file) = &self.dispute_notes_file {
+            let notes = account_manager.get_rejected_disputes();
+            let dispute_notes_file =
+                write_export(dispute_notes_file, self.spill_dir.as_deref(), |writer| {
+                    let mut writer = csv::Writer::from_writer(writer);
+                    for note in &notes {
+                        writer.serialize(note)?;
+                    }
+
+                    Ok(writer.flush()?)
+                })?;
+            info!(
+                "{} rejected dispute orders written to '{}'.",
+                notes.len(),
+                dispute_notes_file.display()
+            );
+            self.write_manifest(&Manifest::sidecar(&dispute_notes_file))?;
+        }
+
+        // Export the orders flagged by the fraud detection if requested.
+        if let Some(fraud_flags_file) = &self.fraud_flags_file {
+            let flags = account_manager.get_fraud_flags();
+            let fraud_flags_file =
+                write_export(fraud_flags_file, self.spill_dir.as_deref(), |writer| {
+                    let mut writer = csv::Writer::from_writer(writer);
+                    for flag in &flags {
+                        writer.serialize(flag)?;
+                    }
+
+                    Ok(writer.flush()?)
+                })?;
+            info!(
+                "{} orders flagged by the fraud detection written to '{}'.",
+                flags.len(),
+                fraud_flags_file.display()
+            );
+            self.write_manifest(&Manifest::sidecar(&fraud_flags_file))?;
+        }
+
+        // Export the accounts to a CSV file.
+        match &self.post_export {
+            Some(post_export) => {
+                let buffer = SharedBuffer::default();
+                let accounts = account_manager.get_accounts();
+                self.account_exporter(account_manager, Box::new(buffer.clone()))
+                    .run()?;
+                post_export.run(&buffer.take(), &accounts)?;
+            }
+            None => write_stdout(|writer| self.account_exporter(account_manager, writer).run())?,
+        }
+
+        match &self.manifest {
+            Some((manifest_file, _)) => self.write_manifest(manifest_file),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the provenance manifest to the given file if requested.
+    fn write_manifest(&self, path: &Path) -> Result<()> {
+        let Some((_, manifest)) = &self.manifest else {
+            return Ok(());
+        };
+        if is_object_store_url(&self.csv_file) {
+            let url = self.csv_file.to_string_lossy().to_string();
+            return manifest.write(path, &[], &[url]);
+        }
+        let files = if is_glob_pattern(&self.csv_file) {
+            expand_glob(&self.csv_file)?
+        } else if self.csv_file.is_dir() {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(&self.csv_file)? {
+                let path = entry?.path();
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if path.is_file() && !hidden {
+                    files.push(path);
+                }
+            }
+            files.sort();
+            files
+        } else {
+            vec![self.csv_file.clone()]
+        };
+        debug!("Writing the manifest '{}'.", path.display());
+
+        manifest.write(path, &files, &[])
+    }
+
+    /// Best-effort export of the accounts processed before the given error
+    /// along with the error report.
+    fn export_partial(
+        &self,
+        account_manager: Arc<AccountManager>,
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        let stem = self
+            .csv_file
+            .file_stem()
+            .map_or("csv_reader".into(), |stem| stem.to_string_lossy());
+        let accounts_file = PathBuf::from(format!("{stem}.partial.csv"));
+        let report_file = PathBuf::from(format!("{stem}.partial.log"));
+        let stats = account_manager.stats();
+
+        let report = format!(
+            "Processing of '{}' failed after {} transactions on {} accounts.\n{:#}\n",
+            self.csv_file.display(),
+            stats.transactions,
+            stats.accounts,
+            error
+        );
+        let report_file = write_export(&report_file, self.spill_dir.as_deref(), |mut writer| {
+            writer.write_all(report.as_bytes())?;
+
+            Ok(writer.flush()?)
+        })?;
+        let accounts_file = write_export(&accounts_file, self.spill_dir.as_deref(), |writer| {
+            self.account_exporter(account_manager.clone(), writer).run()
+        })?;
+        self.write_manifest(&Manifest::sidecar(&accounts_file))?;
+        warn!(
+            "Partial accounts exported to '{}', error report in '{}'.",
+            accounts_file.display(),
+            report_file.display()
+        );
+
+        Ok(())
+    }
+
+    /// Create the exporter of the accounts to the given writer.
+    fn account_exporter(
+        &self,
+        account_manager: Arc<AccountManager>,
+        writer: Box<dyn Write + Sync + Send>,
+    ) -> AccountExporter {
+        let exporter = AccountExporter::new(account_manager, writer)
+            .with_dispute_columns(self.dispute_columns);
+
+        match self.sort_by {
+            Some(sort_by) => exporter.with_sort_by(sort_by),
+            None => exporter,
+        }
+    }
+
+    /// Open the input, either a local file or an object store object.
+    fn open_input(&self) -> Result<Box<dyn Read + Sync + Send>> {
+        #[cfg(feature = "object-store")]
+        if is_object_store_url(&self.csv_file) {
+            let url = self.csv_file.to_string_lossy();
+            let reader = csv_reader_core::adapter::ObjectStoreReader::open(&url)?;
+
+            return Ok(Box::new(BufReader::new(reader)));
+        }
+
+        Ok(Box::new(BufReader::new(File::open(&self.csv_file)?)))
+    }
+}
+
+/// Read the transaction identifiers of an open disputes file, one per line.
+/// Empty lines and a leading `tx` header are skipped.
+fn read_open_disputes(path: &Path) -> Result<Vec<TxId>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut tx_ids = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line.eq_ignore_ascii_case("tx")) {
+            continue;
+        }
+        match line.parse() {
+            Ok(tx_id) => tx_ids.push(tx_id),
+            Err(error) => bail!(
+                "Invalid transaction identifier '{line}' at line {} of '{}': {error}",
+                index + 1,
+                path.display()
+            ),
+        }
+    }
+
+    Ok(tx_ids)
+}
+
+/// Tell if the input is a glob pattern (e.g. `data/2024-*.csv`).
+fn is_glob_pattern(input: &Path) -> bool {
+    input.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// List the files matching a glob pattern sorted by path so the processing
+/// order does not depend on the platform.
+fn expand_glob(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = glob::glob(&pattern.to_string_lossy())?
+        .filter_map(|path| path.ok().filter(|path| path.is_file()))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Tell if the input is an object store URL rather than a local path.
+#[cfg(feature = "object-store")]
+fn is_object_store_url(input: &Path) -> bool {
+    input
+        .to_str()
+        .is_some_and(csv_reader_core::adapter::ObjectStoreReader::is_url)
+}
+
+/// Tell if the input is an object store URL rather than a local path.
+#[cfg(not(feature = "object-store"))]
+fn is_object_store_url(_input: &Path) -> bool {
+    false
+}
+
+fn main() -> Result<()> {
+    let arguments = CLIArguments::parse();
+    match &arguments.command {
+        Some(Command::Reconcile {
+            statement,
+            csv_file,
+        }) => {
+            env_logger::init();
+            return run_reconcile(statement, csv_file);
+        }
+        Some(Command::Soak {
+            duration,
+            check_interval,
+            clients,
+            seed,
+            workers,
+        }) => {
+            env_logger::init();
+            return run_soak(&SoakOptions {
+                duration: Duration::from_secs(*duration),
+                check_interval: Duration::from_secs((*check_interval).max(1)),
+                clients: *clients,
+                seed: *seed,
+                workers: workers.get(),
+            });
+        }
+        Some(Command::Batch {
+            files,
+            output_dir,
+            jobs,
+        }) => {
+            env_logger::init();
+            let jobs = jobs
+                .or_else(|| std::thread::available_parallelism().ok())
+                .map_or(1, NonZeroUsize::get);
+            return run_batch(&BatchOptions {
+                files: files.clone(),
+                output_dir: output_dir.clone(),
+                jobs,
+            });
+        }
+        None => (),
+    }
+    let csv_file = arguments
+        .csv_file
+        .clone()
+        .ok_or_else(|| anyhow!("The CSV file to read is missing."))?;
+    let config = match &arguments.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let config_file = match &arguments.config {
+        Some(path) => std::fs::read(path)?,
+        None => Vec::new(),
+    };
+    let manifest = Manifest::new(&[format!("{arguments:?}").as_bytes(), &config_file]);
+    let manifest_file = arguments.manifest.clone();
+    let rejects = match &arguments.rejects {
+        Some(path) => Some(RejectSink::new(
+            Box::new(BufWriter::new(File::create(path)?)),
+            arguments.delimiter.unwrap_or(b','),
+        )),
+        None => None,
+    };
+    let open_disputes = match &arguments.open_disputes {
+        Some(path) => read_open_disputes(path)?,
+        None => Vec::new(),
+    };
+    let reader_options = ReaderOptions {
+        delimiter: arguments.delimiter.unwrap_or(b','),
+        quote: arguments.quote.unwrap_or(b'"'),
+        sniff: !arguments.no_sniff
+            && arguments.delimiter.is_none()
+            && arguments.quote.is_none()
+            && arguments.columns.is_none(),
+        columns: arguments.columns,
+        header_mapping: arguments.header_mapping.into_iter().collect(),
+        trailer: arguments.trailer,
+        extra_columns: arguments.extra_columns,
+        missing_amount: arguments.missing_amount,
+        amount_format: arguments.amount_format,
+        max_decimals: arguments.max_decimals,
+        timestamp_format: arguments
+            .timestamp_format
+            .or(config.timestamp_format()?)
+            .unwrap_or_default(),
+        timezone: parse_timezone(
+            arguments
+                .timezone
+                .as_deref()
+                .or(config.timezone())
+                .unwrap_or("UTC"),
+        )?,
+        timestamp_order: arguments.timestamp_order,
+        only_kinds: arguments.only_kinds,
+        dedup: arguments.dedup.map(DuplicateFilter::new),
+        resync_lines: arguments.resync_lines,
+        transformers: config.transformers(),
+        skip: arguments.skip,
+        limit: arguments.limit,
+        start_offset: arguments.start_offset,
+        strict: arguments.strict,
+        rejects,
+        encoding: arguments.encoding,
+    };
+    let manager_options = AccountManagerOptions {
+        double_entry: arguments.journal.is_some(),
+        suspense_account: arguments.suspense_account,
+        suspend_after: arguments.suspend_after.map(NonZeroUsize::get),
+        max_transactions: arguments.max_transactions.map(NonZeroUsize::get),
+        excess_transactions: arguments.excess_transactions,
+        held_shortfall: arguments.held_shortfall,
+        adjustments: arguments.allow_adjustments,
+        credit_limits: config.credit_limits()?,
+        fraud_rules: config.fraud_rules()?,
+        order_limits: config.order_limits()?,
+        audit_log: arguments
+            .audit_log
+            .as_deref()
+            .map(AuditLog::create)
+            .transpose()?
+            .map(Arc::new),
+        ..Default::default()
+    };
+    let application = Application::new(
+        csv_file,
+        arguments.format,
+        reader_options,
+        manager_options,
+        arguments.journal,
+        arguments.idle_timeout.map(Duration::from_secs),
+    )?
+    .with_follow(arguments.follow)
+    .with_partial_on_error(arguments.partial_on_error)
+    .with_dispute_columns(arguments.dispute_columns)
+    .with_sort_by(arguments.sort_by)
+    .with_validate_only(arguments.validate_only)
+    .with_manifest(manifest_file, manifest)
+    .with_open_disputes(open_disputes)
+    .with_client_merges(arguments.merge_clients)
+    .with_post_export(PostExport::from_config(&config.export)?)
+    .with_spill_dir(config.export.spill_dir.clone())
+    .with_dispute_notes_file(arguments.dispute_notes.clone())
+    .with_fraud_flags_file(arguments.fraud_flags.clone())
+    .with_dead_letters_file(arguments.dead_letters.clone())
+    .with_error_report_file(arguments.error_report.clone())
+    .with_applied_journal_file(arguments.applied_journal.clone())
+    .with_quarantine(arguments.quarantine.clone(), config.quarantine_rules()?)
+    .with_print_metrics(arguments.metrics)
+    .with_progress_events(
+        arguments
+            .progress_events
+            .as_ref()
+            .map(ProgressEvents::open)
+            .transpose()?,
+    )
+    .with_workers(arguments.workers.get())
+    .with_concurrent_files(arguments.concurrent_files)
+    .with_max_rate(arguments.max_rate.or(config.max_rate()?));
+    #[cfg(feature = "crossbeam")]
+    let application =
+        application.with_channel_capacity(arguments.channel_capacity.map(NonZeroUsize::get));
+    env_logger::init();
+
+    // set before the threads are spawned so they inherit the priorities
+    if let Some(niceness) = arguments.nice {
+        priority::set_niceness(niceness)?;
+    }
+    if let Some(io_priority) = arguments.ionice {
+        priority::set_io_priority(io_priority)?;
+    }
+
+    let result = application.run();
+
+    match &result {
+        Ok(_) => {
+            info!("CSV_READER completed successfully");
+        }
+        Err(error) => {
+            error!("CSV_READER failed with error: {}", error);
+        }
+    };
+
+    result
+}
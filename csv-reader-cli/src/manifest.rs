@@ -0,0 +1,124 @@
+//! Provenance manifest of the exports.
+//!
+//! A manifest is a TOML sidecar file written next to an export telling which
+//! code and settings produced it, so an account file can be traced back to its
+//! run long after it was produced:
+//!
+//! ```toml
+//! version = "0.1.0"
+//! git_hash = "0b8803a..."
+//! run_id = "18f2c3a1b2c3d4e5-1234"
+//! config_digest = "5e884898..."
+//!
+//! [[inputs]]
+//! path = "transactions.csv"
+//! sha256 = "9f86d081..."
+//! ```
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use csv_reader_core::Result;
+
+/// Content of a manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    /// The version of the program.
+    version: &'static str,
+
+    /// The commit the program was built from, `unknown` when built outside of
+    /// a git repository.
+    git_hash: &'static str,
+
+    /// The identifier of the run, shared by the manifests of its exports.
+    run_id: String,
+
+    /// The SHA-256 digest of the settings of the run.
+    config_digest: String,
+
+    /// The inputs of the run.
+    inputs: Vec<InputDigest>,
+}
+
+/// An input of the run.
+#[derive(Debug, Clone, Serialize)]
+struct InputDigest {
+    /// The path or URL of the input.
+    path: String,
+
+    /// The SHA-256 digest of the input content, not computed for object store
+    /// URLs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+impl Manifest {
+    /// Create the manifest of a run from its settings: the command line
+    /// arguments and the content of the configuration file.
+    pub fn new(settings: &[&[u8]]) -> Self {
+        let mut hasher = Sha256::new();
+        for setting in settings {
+            hasher.update(setting);
+        }
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: env!("CSV_READER_GIT_HASH"),
+            run_id: format!("{:x}-{}", started_at.as_nanos(), std::process::id()),
+            config_digest: to_hex(&hasher.finalize()),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Write the manifest to the given file with the digests of the given
+    /// local files and the other inputs (object store URLs) as is.
+    pub fn write(&self, path: &Path, files: &[PathBuf], others: &[String]) -> Result<()> {
+        let mut manifest = self.clone();
+        for file in files {
+            manifest.inputs.push(InputDigest {
+                path: file.display().to_string(),
+                sha256: Some(file_digest(file)?),
+            });
+        }
+        for other in others {
+            manifest.inputs.push(InputDigest {
+                path: other.clone(),
+                sha256: None,
+            });
+        }
+        File::create(path)?.write_all(toml::to_string(&manifest)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// The path of the sidecar manifest of the given export file.
+    pub fn sidecar(export: &Path) -> PathBuf {
+        let mut path = export.as_os_str().to_owned();
+        path.push(".manifest.toml");
+
+        path.into()
+    }
+}
+
+/// Compute the SHA-256 digest of a file.
+fn file_digest(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Write bytes as lowercase hexadecimal.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
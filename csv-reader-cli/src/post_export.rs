@@ -0,0 +1,150 @@
+//! Post-processing of the accounts export.
+//!
+//! Some partners expect the accounts wrapped in their envelope format, with
+//! header and footer records, or handed to their upload tool. The `[export]`
+//! table of the configuration file describes this post-processing so no
+//! wrapper script is needed. The header and footer templates can use the
+//! following placeholders:
+//!
+//! * `{date}`: the date of the export, `YYYYMMDD` in UTC,
+//! * `{rows}`: the number of exported accounts,
+//! * `{total}`: the sum of the total funds of the exported accounts.
+//!
+//! When a command is set, the export is written on its standard input rather
+//! than on the standard output and the run fails if the command fails.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail};
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use csv_reader_core::{model::Account, Result};
+
+use crate::config::ExportConfig;
+
+/// Placeholders of the header and footer templates.
+const PLACEHOLDERS: [&str; 3] = ["date", "rows", "total"];
+
+/// The post-processing of the accounts export.
+#[derive(Debug, Clone)]
+pub struct PostExport {
+    /// The template of the record written before the accounts.
+    header: Option<String>,
+
+    /// The template of the record written after the accounts.
+    footer: Option<String>,
+
+    /// The command receiving the export, with its arguments.
+    command: Vec<String>,
+}
+
+impl PostExport {
+    /// Create the configured post-processing, none if the configuration does
+    /// not set any. Fails if a template uses an unknown placeholder.
+    pub fn from_config(config: &ExportConfig) -> Result<Option<Self>> {
+        if config.header.is_none() && config.footer.is_none() && config.command.is_empty() {
+            return Ok(None);
+        }
+        for template in config.header.iter().chain(&config.footer) {
+            render(template, &PLACEHOLDERS.map(|name| (name, String::new())))?;
+        }
+
+        Ok(Some(Self {
+            header: config.header.clone(),
+            footer: config.footer.clone(),
+            command: config.command.clone(),
+        }))
+    }
+
+    /// Wrap the given export of the given accounts in the header and footer
+    /// records, then write it to the command or the standard output.
+    pub fn run(&self, export: &[u8], accounts: &[Account]) -> Result<()> {
+        let total: Decimal = accounts.iter().map(|account| account.total).sum();
+        let values = [
+            ("date", Utc::now().format("%Y%m%d").to_string()),
+            ("rows", accounts.len().to_string()),
+            ("total", total.round_dp(4).normalize().to_string()),
+        ];
+        let mut output = Vec::with_capacity(export.len());
+        if let Some(header) = &self.header {
+            writeln!(output, "{}", render(header, &values)?)?;
+        }
+        output.extend_from_slice(export);
+        if let Some(footer) = &self.footer {
+            writeln!(output, "{}", render(footer, &values)?)?;
+        }
+
+        let Some((program, arguments)) = self.command.split_first() else {
+            return Ok(io::stdout().write_all(&output)?);
+        };
+        let mut child = Command::new(program)
+            .args(arguments)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Cannot run the export command '{program}': {e}"))?;
+        // the standard input is closed once written so the command ends
+        let written = child
+            .stdin
+            .take()
+            .expect("the standard input is piped")
+            .write_all(&output);
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("The export command '{program}' failed ({status}).");
+        }
+
+        Ok(written?)
+    }
+}
+
+/// Replace the `{name}` placeholders of the template by their value.
+fn render(template: &str, values: &[(&str, String)]) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            bail!("Unclosed placeholder in the export template '{template}'.");
+        };
+        let name = &rest[start + 1..start + end];
+        let Some((_, value)) = values.iter().find(|(placeholder, _)| *placeholder == name) else {
+            bail!(
+                "Unknown placeholder '{{{name}}}' in the export template '{template}' (expected {}).",
+                PLACEHOLDERS.map(|name| format!("{{{name}}}")).join(", ")
+            );
+        };
+        rendered.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// A writer appending to a shared buffer, to keep the export once the
+/// exporter is done with its writer.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Take the content written so far.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
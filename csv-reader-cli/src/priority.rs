@@ -0,0 +1,99 @@
+//! Scheduling priorities of the process.
+//!
+//! Batch runs sharing a host with latency sensitive services can lower their
+//! CPU priority (niceness) and their IO priority so huge files do not starve
+//! the other workloads. The priorities are set at startup, before any thread
+//! is spawned, so every thread inherits them.
+
+use std::str::FromStr;
+
+use anyhow::bail;
+
+use csv_reader_core::Result;
+
+/// IO scheduling priority, as set by `ionice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriority {
+    /// Only get disk time when no other process needs it.
+    Idle,
+
+    /// Best effort scheduling at the given level, from 0 (highest) to 7
+    /// (lowest).
+    BestEffort(u8),
+}
+
+impl FromStr for IoPriority {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let lowercase = value.to_lowercase();
+        let level = lowercase
+            .strip_prefix("best-effort:")
+            .and_then(|level| level.parse().ok())
+            .filter(|level| *level <= 7);
+
+        match (lowercase.as_str(), level) {
+            ("idle", _) => Ok(Self::Idle),
+            (_, Some(level)) => Ok(Self::BestEffort(level)),
+            _ => bail!(
+                "Unknown IO priority '{value}' (expected 'idle' or 'best-effort:N' with N from 0 to 7)."
+            ),
+        }
+    }
+}
+
+/// Lower the CPU priority of the process to the given niceness, from 0 to 19.
+#[cfg(unix)]
+pub fn set_niceness(niceness: i32) -> Result<()> {
+    // SAFETY: setpriority only reads its integer arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, niceness) } != 0 {
+        bail!(
+            "Cannot set the niceness to {niceness}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    Ok(())
+}
+
+/// Lower the CPU priority of the process to the given niceness, from 0 to 19.
+#[cfg(not(unix))]
+pub fn set_niceness(_niceness: i32) -> Result<()> {
+    log::warn!("Setting the niceness is not supported on this platform, ignored.");
+
+    Ok(())
+}
+
+/// Set the IO priority of the process.
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(priority: IoPriority) -> Result<()> {
+    // see linux/ioprio.h
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    const IOPRIO_CLASS_BE: libc::c_long = 2;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+
+    let value = match priority {
+        IoPriority::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        IoPriority::BestEffort(level) => {
+            IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT | libc::c_long::from(level)
+        }
+    };
+    // SAFETY: ioprio_set only reads its integer arguments.
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, value) } != 0 {
+        bail!(
+            "Cannot set the IO priority to {priority:?}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    Ok(())
+}
+
+/// Set the IO priority of the process.
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_priority: IoPriority) -> Result<()> {
+    log::warn!("Setting the IO priority is not supported on this platform, ignored.");
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! Machine readable progress events.
+//!
+//! An orchestration UI follows the progress of a batch run from JSON events,
+//! one per line, written every second on a side channel rather than by
+//! parsing the logs:
+//!
+//! ```text
+//! {"event":"progress","bytes_read":1048576,"bytes_total":4194304,"rows_processed":20480,"rows_rejected":3,"resume_offset":1048550,"elapsed_ms":1002,"eta_ms":3006}
+//! {"event":"done","status":"ok","bytes_read":4194304,"bytes_total":4194304,"rows_processed":81920,"rows_rejected":12,"resume_offset":4194304,"elapsed_ms":4010,"eta_ms":0}
+//! ```
+//!
+//! The `bytes_total` and `eta_ms` fields are `null` when the size of the input
+//! is not known. The channel is a file, an inherited file descriptor or a
+//! unix socket. Failing to write an event does not fail the run, the events
+//! are then no longer written.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail};
+use log::warn;
+
+use csv_reader_core::{actor::ReaderProgress, Result};
+
+/// Where the progress events are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventTarget {
+    /// A file, created or truncated.
+    File(PathBuf),
+
+    /// A file descriptor inherited from the parent process (`fd:N`).
+    Fd(u32),
+
+    /// A listening unix socket (`unix:PATH`).
+    Socket(PathBuf),
+}
+
+impl FromStr for EventTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(fd) = value.strip_prefix("fd:") {
+            return fd
+                .parse()
+                .map(Self::Fd)
+                .map_err(|_| anyhow!("Invalid file descriptor '{fd}' (expected 'fd:N')."));
+        }
+        if let Some(path) = value.strip_prefix("unix:") {
+            return Ok(Self::Socket(PathBuf::from(path)));
+        }
+        if value.is_empty() {
+            bail!("Empty progress events target (expected a file, 'fd:N' or 'unix:PATH').");
+        }
+
+        Ok(Self::File(PathBuf::from(value)))
+    }
+}
+
+/// Writer of the progress events of the reading of an input.
+pub struct ProgressEvents {
+    /// The event channel, none once writing to it failed.
+    output: Option<Box<dyn Write + Send>>,
+
+    /// When the reading started.
+    started: Instant,
+
+    /// The byte offset where the reading started.
+    start_offset: u64,
+
+    /// The size in bytes of the input, if known.
+    bytes_total: Option<u64>,
+
+    /// The last progress reported.
+    last: ReaderProgress,
+}
+
+impl ProgressEvents {
+    /// Open the given event channel.
+    pub fn open(target: &EventTarget) -> Result<Self> {
+        let output: Box<dyn Write + Send> = match target {
+            EventTarget::File(path) => Box::new(
+                File::create(path).map_err(|e| anyhow!("Cannot open '{}': {e}", path.display()))?,
+            ),
+            EventTarget::Fd(fd) => Box::new(
+                OpenOptions::new()
+                    .write(true)
+                    .open(format!("/dev/fd/{fd}"))
+                    .map_err(|e| anyhow!("Cannot open the file descriptor {fd}: {e}"))?,
+            ),
+            #[cfg(unix)]
+            EventTarget::Socket(path) => Box::new(
+                std::os::unix::net::UnixStream::connect(path)
+                    .map_err(|e| anyhow!("Cannot connect to '{}': {e}", path.display()))?,
+            ),
+            #[cfg(not(unix))]
+            EventTarget::Socket(_) => bail!("Unix sockets are only supported on unix."),
+        };
+
+        Ok(Self {
+            output: Some(output),
+            started: Instant::now(),
+            start_offset: 0,
+            bytes_total: None,
+            last: ReaderProgress::default(),
+        })
+    }
+
+    /// Start the reading of an input of the given size from the given byte
+    /// offset, the estimated time left is computed from the bytes read since.
+    pub fn start(&mut self, start_offset: u64, bytes_total: Option<u64>) {
+        self.started = Instant::now();
+        self.start_offset = start_offset;
+        self.bytes_total = bytes_total;
+    }
+
+    /// Write a progress event.
+    pub fn progress(&mut self, progress: ReaderProgress) {
+        self.last = progress;
+        let event = self.render(&progress);
+        self.write(&format!(r#"{{"event":"progress",{event}}}"#));
+    }
+
+    /// Write the final event with the last progress reported, once the
+    /// reading succeeded or failed.
+    pub fn done(&mut self, success: bool) {
+        let status = if success { "ok" } else { "failed" };
+        let event = self.render(&self.last);
+        self.write(&format!(
+            r#"{{"event":"done","status":"{status}",{event}}}"#
+        ));
+    }
+
+    /// Render the fields of an event.
+    fn render(&self, progress: &ReaderProgress) -> String {
+        let elapsed = self.started.elapsed();
+        let bytes_done = progress.bytes_read.saturating_sub(self.start_offset);
+        let eta = self.bytes_total.and_then(|total| {
+            let remaining = total.saturating_sub(progress.bytes_read);
+            match (remaining, bytes_done) {
+                (0, _) => Some(0),
+                (_, 0) => None,
+                _ => Some(elapsed.as_millis() * u128::from(remaining) / u128::from(bytes_done)),
+            }
+        });
+
+        format!(
+            r#""bytes_read":{},"bytes_total":{},"rows_processed":{},"rows_rejected":{},"resume_offset":{},"elapsed_ms":{},"eta_ms":{}"#,
+            progress.bytes_read,
+            json_number(self.bytes_total),
+            progress.records_parsed,
+            progress.records_rejected,
+            progress.resume_offset,
+            elapsed.as_millis(),
+            json_number(eta),
+        )
+    }
+
+    /// Write an event line, giving up on the channel if it fails.
+    fn write(&mut self, event: &str) {
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        if let Err(error) = writeln!(output, "{event}").and_then(|_| output.flush()) {
+            warn!("Cannot write the progress events, they are no longer written: {error}");
+            self.output = None;
+        }
+    }
+}
+
+/// Render an optional number, `null` if none.
+fn json_number(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "null".to_string(), |value| value.to_string())
+}
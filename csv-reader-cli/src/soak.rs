@@ -0,0 +1,352 @@
+//! Soak test of the processing pipeline.
+//!
+//! Some regressions, like a memory leak or a slowdown as the accounts pile up,
+//! only show after hours of processing. The `soak` subcommand feeds the reader
+//! and accountant actors with an endless stream of synthetic transactions, in
+//! process, for a set duration. The invariants of the accounts are verified
+//! periodically and the report tells the throughput, the memory used and the
+//! errors met, to compare with the ones of the previous release. The
+//! throughput is the one of the whole pipeline, the orders read being all
+//! processed before the report.
+//!
+//! The stream is random but reproducible from its seed. It mixes deposits,
+//! withdrawals, disputes, resolutions and a few chargebacks over a fixed set
+//! of clients, with some malformed rows, so the error paths are exercised
+//! along with the nominal ones.
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use csv_reader_core::{
+    actor::{channel, Reader, ReaderOptions, ReaderProgress},
+    model::{ClientId, TxId},
+    service::check_account,
+    AccountManager, Accountant, InMemoryAccountStorage, Result, StorageStats,
+};
+
+/// Number of rows generated at once by the synthetic stream.
+const BATCH_ROWS: usize = 1024;
+
+/// Number of the last transactions the disputes, resolutions and chargebacks
+/// of the synthetic stream refer to.
+const RECENT_TXS: TxId = 1000;
+
+/// Settings of a soak test.
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    /// How long the stream is read.
+    pub duration: Duration,
+
+    /// The interval between two verifications of the invariants.
+    pub check_interval: Duration,
+
+    /// The number of clients of the stream.
+    pub clients: ClientId,
+
+    /// The seed of the stream.
+    pub seed: u64,
+
+    /// The number of threads parsing the stream.
+    pub workers: usize,
+}
+
+/// Report of a soak test.
+#[derive(Debug, Clone, Default)]
+pub struct SoakReport {
+    /// The time the stream was read and its orders processed.
+    pub elapsed: Duration,
+
+    /// The progress of the reading of the stream.
+    pub progress: ReaderProgress,
+
+    /// The number of orders the accountant failed to process.
+    pub failed_orders: u64,
+
+    /// The statistics of the accounts storage at the end.
+    pub stats: StorageStats,
+
+    /// The resident memory in bytes at the start, if known.
+    pub memory_start: Option<u64>,
+
+    /// The resident memory in bytes at the end, if known.
+    pub memory_end: Option<u64>,
+
+    /// The peak resident memory in bytes, if known.
+    pub memory_peak: Option<u64>,
+
+    /// The number of verifications of the invariants.
+    pub checks: usize,
+
+    /// The invariants found broken, with the time they were found at.
+    pub violations: Vec<String>,
+
+    /// The error that stopped the reading, if any.
+    pub error: Option<String>,
+}
+
+impl SoakReport {
+    /// Tell if the pipeline held until the end without breaking an invariant.
+    pub fn is_stable(&self) -> bool {
+        self.error.is_none() && self.violations.is_empty()
+    }
+
+    /// Verify the invariants of the accounts so far.
+    fn check(
+        &mut self,
+        account_manager: &AccountManager,
+        options: &SoakOptions,
+        elapsed: Duration,
+    ) {
+        let accounts = account_manager.get_accounts();
+        let mut violations: Vec<String> = accounts.iter().flat_map(check_account).collect();
+        if accounts.len() as u64 > options.clients {
+            violations.push(format!(
+                "{} accounts for {} clients.",
+                accounts.len(),
+                options.clients
+            ));
+        }
+        self.checks += 1;
+        self.violations.extend(
+            violations
+                .into_iter()
+                .map(|violation| format!("after {}s: {violation}", elapsed.as_secs())),
+        );
+    }
+}
+
+impl Display for SoakReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        writeln!(f, "Duration: {:.1}s", self.elapsed.as_secs_f64())?;
+        writeln!(
+            f,
+            "Throughput: {} rows ({:.0} rows/s), {} bytes ({:.1} MiB/s).",
+            self.progress.records_parsed,
+            self.progress.records_parsed as f64 / seconds,
+            self.progress.bytes_read,
+            self.progress.bytes_read as f64 / seconds / (1024.0 * 1024.0),
+        )?;
+        writeln!(
+            f,
+            "Errors: {} rows rejected, {} orders failed.",
+            self.progress.records_rejected, self.failed_orders
+        )?;
+        writeln!(
+            f,
+            "Storage: {} accounts, {} transactions, {} open disputes.",
+            self.stats.accounts, self.stats.transactions, self.stats.open_disputes
+        )?;
+        match (self.memory_start, self.memory_end, self.memory_peak) {
+            (Some(start), Some(end), Some(peak)) => writeln!(
+                f,
+                "Memory: {} KiB at start, {} KiB at end, {} KiB at peak.",
+                start / 1024,
+                end / 1024,
+                peak / 1024
+            )?,
+            _ => writeln!(f, "Memory: not available on this system")?,
+        }
+        writeln!(
+            f,
+            "Invariants: {} broken in {} checks.",
+            self.violations.len(),
+            self.checks
+        )?;
+        for violation in &self.violations {
+            writeln!(f, "  {violation}")?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Error: {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Process a synthetic stream for the set duration and report how the
+/// pipeline held.
+pub fn soak(options: &SoakOptions) -> Result<SoakReport> {
+    let mut report = SoakReport {
+        memory_start: resident_memory("VmRSS"),
+        ..Default::default()
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let stream = SyntheticStream::new(options.clients, options.seed, stop.clone());
+    let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    let failed_orders = Arc::new(AtomicU64::new(0));
+    let progress = Arc::new(Mutex::new(ReaderProgress::default()));
+    let (order_sender, order_receiver) = channel();
+    let accountant = Accountant::new(account_manager.clone(), order_receiver)
+        .with_error_count(failed_orders.clone());
+    let reader = Reader::with_options(order_sender, Box::new(stream), ReaderOptions::default())
+        .with_workers(options.workers)
+        .with_progress(Duration::from_secs(1), {
+            let progress = progress.clone();
+            move |reported| *progress.lock().unwrap() = reported
+        });
+
+    let started = Instant::now();
+    let accountant = std::thread::spawn(move || accountant.run());
+    let reader = std::thread::spawn(move || reader.run());
+    while !reader.is_finished() {
+        let elapsed = started.elapsed();
+        if elapsed >= options.duration {
+            break;
+        }
+        std::thread::sleep(options.check_interval.min(options.duration - elapsed));
+        let elapsed = started.elapsed();
+        report.check(&account_manager, options, elapsed);
+        let progress = *progress.lock().unwrap();
+        info!(
+            "Soak test: {} rows in {}s, {} rejected, {} failed, {} KiB resident, {} broken invariants.",
+            progress.records_parsed,
+            elapsed.as_secs(),
+            progress.records_rejected,
+            failed_orders.load(Ordering::Relaxed),
+            resident_memory("VmRSS").unwrap_or_default() / 1024,
+            report.violations.len()
+        );
+    }
+    stop.store(true, Ordering::Relaxed);
+    if let Err(error) = reader.join().expect("Reader thread panicked") {
+        report.error = Some(error.to_string());
+    }
+    accountant.join().expect("Accountant thread panicked")?;
+
+    report.elapsed = started.elapsed();
+    report.check(&account_manager, options, report.elapsed);
+    report.progress = *progress.lock().unwrap();
+    report.failed_orders = failed_orders.load(Ordering::Relaxed);
+    report.stats = account_manager.stats();
+    report.memory_end = resident_memory("VmRSS");
+    report.memory_peak = resident_memory("VmHWM");
+
+    Ok(report)
+}
+
+/// Read a memory size of the process from `/proc/self/status`, in bytes.
+fn resident_memory(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))?;
+    let kib: u64 = line.trim().strip_suffix("kB")?.trim().parse().ok()?;
+
+    Some(kib * 1024)
+}
+
+/// An endless CSV stream of random transactions, ending once stopped or once
+/// the transaction identifiers are exhausted. The transaction `tx` belongs to
+/// the client `tx % clients + 1`, so the disputes can refer to the earlier
+/// transactions without keeping them.
+struct SyntheticStream {
+    /// The state of the xorshift random generator.
+    state: u64,
+
+    /// The number of clients.
+    clients: ClientId,
+
+    /// The identifier of the last transaction generated.
+    last_tx: TxId,
+
+    /// The rows generated and not read yet.
+    buffer: io::Cursor<Vec<u8>>,
+
+    /// Set to end the stream.
+    stop: Arc<AtomicBool>,
+}
+
+impl SyntheticStream {
+    /// Create a stream over the given number of clients, starting with the
+    /// header line.
+    fn new(clients: ClientId, seed: u64, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            // the generator state must not be zero
+            state: seed | 1,
+            clients: clients.max(1),
+            last_tx: 0,
+            buffer: io::Cursor::new(b"type,client,tx,amount\n".to_vec()),
+            stop,
+        }
+    }
+
+    /// Draw a random number.
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        self.state
+    }
+
+    /// The client owning the given transaction.
+    fn client_of(&self, tx_id: TxId) -> ClientId {
+        tx_id % self.clients + 1
+    }
+
+    /// Generate the next batch of rows, none once the transaction identifiers
+    /// are exhausted.
+    fn generate(&mut self) -> io::Result<()> {
+        let mut rows = Vec::with_capacity(BATCH_ROWS * 32);
+        for _ in 0..BATCH_ROWS {
+            if self.last_tx == TxId::MAX {
+                break;
+            }
+            let roll = self.next_random() % 1000;
+            let amount = self.next_random() % 10_000_000;
+            let amount = format!("{}.{:04}", amount / 10_000, amount % 10_000);
+            // the disputes refer to one of the recent transactions
+            let disputed = self.last_tx.saturating_sub(self.next_random() % RECENT_TXS);
+            let disputed = disputed.max(1);
+            let (kind, tx_id) = match roll {
+                0..=499 => ("deposit", self.last_tx + 1),
+                500..=749 => ("withdrawal", self.last_tx + 1),
+                750..=899 => ("dispute", disputed),
+                900..=989 => ("resolve", disputed),
+                990 => ("chargeback", disputed),
+                _ => {
+                    writeln!(
+                        rows,
+                        "deposit,{},not-a-tx,{amount}",
+                        self.client_of(disputed)
+                    )?;
+                    continue;
+                }
+            };
+            let client_id = self.client_of(tx_id);
+            match kind {
+                "deposit" | "withdrawal" => {
+                    self.last_tx = tx_id;
+                    writeln!(rows, "{kind},{client_id},{tx_id},{amount}")?;
+                }
+                _ => writeln!(rows, "{kind},{client_id},{tx_id},")?,
+            }
+        }
+        self.buffer = io::Cursor::new(rows);
+
+        Ok(())
+    }
+}
+
+impl Read for SyntheticStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.buffer.position() == self.buffer.get_ref().len() as u64 {
+            if self.stop.load(Ordering::Relaxed) {
+                return Ok(0);
+            }
+            self.generate()?;
+        }
+
+        self.buffer.read(buf)
+    }
+}
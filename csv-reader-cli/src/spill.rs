@@ -0,0 +1,171 @@
+//! Export files on a full disk.
+//!
+//! A run can last hours before its exports are written, a full disk must not
+//! end it with a bare IO error. The exports are written through
+//! [write_export] and [write_stdout]: when the disk is full, the error tells
+//! how many bytes were written and where. An incomplete export file is
+//! removed, so it is not taken for a complete one, and the export is written
+//! again in the spill directory of the configuration if any.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::anyhow;
+use log::{error, warn};
+
+use csv_reader_core::Result;
+
+/// The writer of an export, given to the function writing it.
+pub type ExportWriter = Box<dyn Write + Sync + Send>;
+
+/// Write an export to the given file with the given function, or to the same
+/// file name in the spill directory if the disk of the file is full. Returns
+/// the path of the written file.
+pub fn write_export(
+    path: &Path,
+    spill_dir: Option<&Path>,
+    write: impl Fn(ExportWriter) -> Result<()>,
+) -> Result<PathBuf> {
+    let (error, written) = match write_file(path, &write) {
+        Ok(()) => return Ok(path.to_path_buf()),
+        Err((error, written)) if is_disk_full(&error) => (error, written),
+        Err((error, _)) => return Err(error),
+    };
+    error!(
+        "The disk is full, {written} bytes of the export were written to '{}'.",
+        path.display()
+    );
+    remove_incomplete(path);
+
+    let spill_path = match (spill_dir, path.file_name()) {
+        (Some(spill_dir), Some(file_name)) => spill_dir.join(file_name),
+        _ => {
+            return Err(error.context(format!(
+                "The export to '{}' failed on a full disk after {written} bytes written (no spill directory configured).",
+                path.display()
+            )))
+        }
+    };
+    match write_file(&spill_path, &write) {
+        Ok(()) => {
+            warn!(
+                "The export was written to '{}' instead of '{}'.",
+                spill_path.display(),
+                path.display()
+            );
+
+            Ok(spill_path)
+        }
+        Err((spill_error, spilled)) => {
+            remove_incomplete(&spill_path);
+
+            Err(spill_error.context(format!(
+                "The export to '{}' failed on a full disk after {written} bytes written, and to the spill path '{}' after {spilled} bytes written.",
+                path.display(),
+                spill_path.display()
+            )))
+        }
+    }
+}
+
+/// Write an export to the standard output with the given function, telling
+/// how many bytes were written if the disk it is redirected to is full.
+pub fn write_stdout(write: impl FnOnce(ExportWriter) -> Result<()>) -> Result<()> {
+    let written = Arc::new(AtomicU64::new(0));
+    let writer = CountingWriter {
+        inner: io::stdout(),
+        written: written.clone(),
+    };
+
+    write(Box::new(BufWriter::new(writer))).map_err(|error| {
+        if !is_disk_full(&error) {
+            return error;
+        }
+        let written = written.load(Ordering::Relaxed);
+        error.context(format!(
+            "The export to the standard output failed on a full disk after {written} bytes written."
+        ))
+    })
+}
+
+/// Write the given file with the given function and make sure it reached the
+/// disk. On failure, returns the number of bytes written along the error.
+fn write_file(
+    path: &Path,
+    write: &impl Fn(ExportWriter) -> Result<()>,
+) -> std::result::Result<(), (anyhow::Error, u64)> {
+    let written = Arc::new(AtomicU64::new(0));
+    let result = File::create(path)
+        .map_err(|e| anyhow!("Cannot create '{}': {e}", path.display()))
+        .and_then(|file| {
+            // a full disk may only be reported when the data is synced
+            let synced = file.try_clone()?;
+            write(Box::new(BufWriter::new(CountingWriter {
+                inner: file,
+                written: written.clone(),
+            })))?;
+
+            Ok(synced.sync_all()?)
+        });
+
+    result.map_err(|error| (error, written.load(Ordering::Relaxed)))
+}
+
+/// Remove an incomplete export file, unless it is not a regular file like
+/// a named pipe.
+fn remove_incomplete(path: &Path) {
+    if !path.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return;
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => warn!("The incomplete file '{}' was removed.", path.display()),
+        Err(e) => warn!(
+            "The incomplete file '{}' could not be removed: {e}",
+            path.display()
+        ),
+    }
+}
+
+/// Tell if the error was caused by a full disk.
+fn is_disk_full(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let io_error = match cause.downcast_ref::<csv::Error>() {
+            Some(csv_error) => match csv_error.kind() {
+                csv::ErrorKind::Io(io_error) => Some(io_error),
+                _ => None,
+            },
+            None => cause.downcast_ref::<io::Error>(),
+        };
+
+        io_error.is_some_and(|e| e.kind() == io::ErrorKind::StorageFull)
+    })
+}
+
+/// Writer counting the bytes written to its inner writer.
+struct CountingWriter<W> {
+    /// The written writer.
+    inner: W,
+
+    /// The number of bytes written so far.
+    written: Arc<AtomicU64>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let length = self.inner.write(buf)?;
+        self.written.fetch_add(length as u64, Ordering::Relaxed);
+
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,708 @@
+//! The accountant actor is responsible for managing the transactions and accounts of the clients.
+//! For that purpose, it uses the [AccountManager] service.
+
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use log::{debug, trace};
+
+use super::{
+    reader::kind_position, run_actor, Actor, DeadLetter, Mailbox, MetricEvent, Receiver, Sender,
+};
+use crate::{
+    model::{Account, ClientId, Transaction, TransactionKind, TransactionOrder},
+    service::{AccountManager, TransactionError},
+    Result,
+};
+
+/// Default maximum number of orders applied under a single storage lock.
+const BATCH_SIZE: usize = 1000;
+
+/// What the orders processed by a dry run of the [Accountant] would do, see
+/// [Accountant::run_dry].
+#[derive(Debug, Default, Clone)]
+pub struct DryRunReport {
+    /// The number of orders that would be applied.
+    pub accepted: u64,
+
+    /// The number of orders that would be rejected.
+    pub rejected: u64,
+
+    /// The accounts the orders would update, with their resulting balances,
+    /// by client.
+    pub accounts: Vec<Account>,
+}
+
+/// Hooks called by the [Accountant] as it processes the orders, to attach
+/// side effects (caching, notifications…) to the processing. Every hook does
+/// nothing by default.
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use csv_reader_core::actor::{channel, Accountant, OrderObserver, Reader};
+/// use csv_reader_core::model::{Account, ClientId, TransactionOrder};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// /// Keep the clients whose orders were rejected.
+/// #[derive(Default)]
+/// struct Rejections(Mutex<Vec<ClientId>>);
+///
+/// impl OrderObserver for Rejections {
+///     fn on_rejected(&self, order: &TransactionOrder, _error: &anyhow::Error) {
+///         self.0.lock().unwrap().push(order.client_id);
+///     }
+/// }
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let rejections = Arc::new(Rejections::default());
+/// let (order_sender, order_receiver) = channel();
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.0\nwithdrawal,2,2,5.0\n";
+/// Reader::new(order_sender, Box::new(data.as_bytes())).run().unwrap();
+/// Accountant::new(manager, order_receiver)
+///     .with_observer(rejections.clone())
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(*rejections.0.lock().unwrap(), vec![2]);
+/// ```
+pub trait OrderObserver {
+    /// Called once the given order is applied, resulting in the given
+    /// transaction.
+    fn on_accepted(&self, _order: &TransactionOrder, _transaction: &Transaction) {}
+
+    /// Called once the given order failed to be processed.
+    fn on_rejected(&self, _order: &TransactionOrder, _error: &anyhow::Error) {}
+
+    /// Called once per batch of orders for every account the batch updated,
+    /// with its state after the batch, see [Accountant::with_batch_size].
+    fn on_account_updated(&self, _account: &Account) {}
+}
+
+/// The accountant actor is responsible for managing the transactions and
+/// accounts of the clients.
+pub struct Accountant {
+    /// The account manager service.
+    account_manager: Arc<AccountManager>,
+
+    /// The mailbox receiving the transaction orders.
+    mailbox: Mailbox<TransactionOrder>,
+
+    /// When set, an acknowledgement is sent for every order once it has been
+    /// processed, successfully or not.
+    ack_sender: Option<Sender<()>>,
+
+    /// When set, the number of orders that failed to be processed.
+    error_count: Option<Arc<AtomicU64>>,
+
+    /// When set, the orders that failed to be processed are sent with their
+    /// error on this channel.
+    dead_letter_sender: Option<Sender<DeadLetter>>,
+
+    /// When set, the counts of the orders processed are sent on this channel
+    /// after every batch.
+    metric_sender: Option<Sender<MetricEvent>>,
+
+    /// When set, the transactions successfully applied are sent on this
+    /// channel.
+    applied_sender: Option<Sender<Transaction>>,
+
+    /// The observers of the processing.
+    observers: Vec<Arc<dyn OrderObserver + Sync + Send>>,
+
+    /// The maximum number of orders applied at once.
+    batch_size: usize,
+}
+
+impl Accountant {
+    /// Create a new accountant actor.
+    pub fn new(
+        account_manager: Arc<AccountManager>,
+        order_receiver: Receiver<TransactionOrder>,
+    ) -> Self {
+        Self::with_mailbox(account_manager, Mailbox::new(order_receiver))
+    }
+
+    /// Create a new accountant actor applying the orders of several sources,
+    /// like readers of different files, to the same accounts. The orders of a
+    /// source are applied in their order, so are the ones of each client
+    /// within a source; the orders of the different sources are applied as
+    /// they arrive. See [Mailbox::merge].
+    pub fn with_receivers(
+        account_manager: Arc<AccountManager>,
+        order_receivers: Vec<Receiver<TransactionOrder>>,
+    ) -> Result<Self> {
+        let mailbox = Mailbox::merge(order_receivers)?;
+
+        Ok(Self::with_mailbox(account_manager, mailbox))
+    }
+
+    /// Create a new accountant actor receiving the orders of the given
+    /// mailbox.
+    fn with_mailbox(
+        account_manager: Arc<AccountManager>,
+        mailbox: Mailbox<TransactionOrder>,
+    ) -> Self {
+        Self {
+            account_manager,
+            mailbox,
+            ack_sender: None,
+            error_count: None,
+            dead_letter_sender: None,
+            metric_sender: None,
+            applied_sender: None,
+            observers: Vec::new(),
+            batch_size: BATCH_SIZE,
+        }
+    }
+
+    /// Send an acknowledgement on the given channel every time an order has
+    /// been processed. This lets the order producers know when it is safe to
+    /// consider the orders as handled (e.g. to commit a consumer offset).
+    pub fn with_ack_sender(mut self, ack_sender: Sender<()>) -> Self {
+        self.ack_sender = Some(ack_sender);
+
+        self
+    }
+
+    /// Count the orders that failed to be processed in the given counter,
+    /// their errors being only logged.
+    pub fn with_error_count(mut self, error_count: Arc<AtomicU64>) -> Self {
+        self.error_count = Some(error_count);
+
+        self
+    }
+
+    /// Send the orders that failed to be processed, with their error, to the
+    /// given dead letter channel, see [DeadLetterExporter](super::DeadLetterExporter)
+    /// and [ErrorCollector](super::ErrorCollector). Their errors are then
+    /// logged at the debug level only.
+    pub fn with_dead_letter_sender(mut self, dead_letter_sender: Sender<DeadLetter>) -> Self {
+        self.dead_letter_sender = Some(dead_letter_sender);
+
+        self
+    }
+
+    /// Send the counts of the orders applied and rejected by transaction kind
+    /// to the given channel, see [MetricsCollector](super::MetricsCollector).
+    pub fn with_metric_sender(mut self, metric_sender: Sender<MetricEvent>) -> Self {
+        self.metric_sender = Some(metric_sender);
+
+        self
+    }
+
+    /// Send the transactions successfully applied, in the order they were
+    /// applied, to the given channel, see
+    /// [AppliedTransactionSink](super::AppliedTransactionSink).
+    pub fn with_applied_sender(mut self, applied_sender: Sender<Transaction>) -> Self {
+        self.applied_sender = Some(applied_sender);
+
+        self
+    }
+
+    /// Call the hooks of the given observer as the orders are processed, after
+    /// the ones of the observers already registered.
+    pub fn with_observer(mut self, observer: Arc<dyn OrderObserver + Sync + Send>) -> Self {
+        self.observers.push(observer);
+
+        self
+    }
+
+    /// Apply at most the given number of orders under a single lock of their
+    /// accounts, 1000 by default, see [AccountManager::process_orders]. The
+    /// orders already waiting in the channel are batched, the actor does not
+    /// wait for a batch to fill up.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+
+        self
+    }
+
+    /// Run the accountant actor.
+    /// The actor will process the orders received from the order channel.
+    /// It will NOT stop when the transactions fail but only log the error if any.
+    /// The actor will stop when the order channel is closed which means that no
+    /// more orders will be received.
+    pub fn run(self) -> Result<()> {
+        run_actor(self)
+    }
+
+    /// Run the accountant actor without changing the accounts: the orders are
+    /// processed against a shadow of the account manager, see
+    /// [AccountManager::shadow], and the report tells what would happen. The
+    /// failed orders, the applied transactions and the counters are sent to
+    /// the channels set as for a real run.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::actor::{channel, Accountant, Reader};
+    /// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+    ///
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// let (order_sender, order_receiver) = channel();
+    /// let data = "type,client,tx,amount\ndeposit,1,1,3.0\nwithdrawal,1,2,5.0\nwithdrawal,1,3,1.0\n";
+    /// Reader::new(order_sender, Box::new(data.as_bytes())).run().unwrap();
+    /// let report = Accountant::new(manager.clone(), order_receiver).run_dry().unwrap();
+    ///
+    /// assert_eq!((report.accepted, report.rejected), (2, 1));
+    /// assert_eq!(report.accounts[0].available, Decimal::TWO);
+    /// assert!(manager.get_account(1).is_none());
+    /// ```
+    pub fn run_dry(&self) -> Result<DryRunReport> {
+        debug!("Accountant Actor started (dry run)");
+        let shadow = self.account_manager.shadow();
+        let mut updated = BTreeSet::new();
+        let (accepted, rejected) = self.process_all(&shadow, Some(&mut updated));
+        let accounts = updated
+            .into_iter()
+            .filter_map(|client_id| shadow.get_account(client_id))
+            .collect();
+        debug!("Accountant Actor stopped (dry run)");
+
+        Ok(DryRunReport {
+            accepted,
+            rejected,
+            accounts,
+        })
+    }
+
+    /// Process the orders received with the given manager until the order
+    /// channel is closed and return the numbers of orders applied and
+    /// rejected. The clients of the updated accounts are added to the given
+    /// set if any.
+    fn process_all(
+        &self,
+        manager: &AccountManager,
+        mut updated: Option<&mut BTreeSet<ClientId>>,
+    ) -> (u64, u64) {
+        let mut counts = (0, 0);
+
+        while let Some(batch) = self.mailbox.recv_batch(self.batch_size) {
+            trace!("Accountant Actor: received {} orders", batch.len());
+            let (applied, rejected) = self.process_batch(manager, batch, updated.as_deref_mut());
+            counts.0 += applied;
+            counts.1 += rejected;
+        }
+
+        counts
+    }
+
+    /// Process a batch of orders, reporting the failed ones, and return the
+    /// numbers of orders applied and rejected.
+    fn process_batch(
+        &self,
+        manager: &AccountManager,
+        batch: Vec<TransactionOrder>,
+        all_updated: Option<&mut BTreeSet<ClientId>>,
+    ) -> (u64, u64) {
+        // kept for the dead letter channel and the observers only
+        let orders = (self.dead_letter_sender.is_some() || !self.observers.is_empty())
+            .then(|| batch.clone());
+        let track_updates = !self.observers.is_empty() || all_updated.is_some();
+        let kinds: Vec<usize> = batch
+            .iter()
+            .map(|order| kind_position(&order.kind))
+            .collect();
+        let results = manager.process_orders(batch);
+        let mut applied = [0; 9];
+        let mut rejected = [0; 9];
+        let mut updated = BTreeSet::new();
+
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(transaction) => {
+                    applied[kinds[index]] += 1;
+                    if let Some(orders) = &orders {
+                        for observer in &self.observers {
+                            observer.on_accepted(&orders[index], &transaction);
+                        }
+                    }
+                    if track_updates {
+                        updated.insert(Self::account_of(manager, &transaction));
+                    }
+                    if let Some(applied_sender) = &self.applied_sender {
+                        // The applied transactions may not be streamed anymore.
+                        let _ = applied_sender.send(transaction);
+                    }
+                }
+                Err(error) => {
+                    rejected[kinds[index]] += 1;
+                    if let Some(orders) = &orders {
+                        for observer in &self.observers {
+                            observer.on_rejected(&orders[index], &error);
+                        }
+                    }
+                    if let Some(error_count) = &self.error_count {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                    }
+                    match (&self.dead_letter_sender, &orders) {
+                        (Some(sender), Some(orders)) => {
+                            debug!("Accountant Actor: Error processing order: {}", error);
+                            // The dead letters may not be collected anymore.
+                            let _ = sender.send(DeadLetter {
+                                order: orders[index].clone(),
+                                kind: TransactionError::kind_of(&error),
+                                error: error.to_string(),
+                            });
+                        }
+                        _ => log::info!("Accountant Actor: Error processing order: {}", error),
+                    }
+                }
+            }
+            if let Some(ack_sender) = &self.ack_sender {
+                // The producer may not wait for acknowledgements anymore.
+                let _ = ack_sender.send(());
+            }
+        }
+        if !self.observers.is_empty() {
+            for account in updated
+                .iter()
+                .filter_map(|client_id| manager.get_account(*client_id))
+            {
+                for observer in &self.observers {
+                    observer.on_account_updated(&account);
+                }
+            }
+        }
+        if let Some(all_updated) = all_updated {
+            all_updated.extend(updated);
+        }
+        if let Some(metric_sender) = &self.metric_sender {
+            // The metrics may not be collected anymore.
+            let _ = metric_sender.send(MetricEvent::Processed { applied, rejected });
+        }
+
+        (applied.iter().sum(), rejected.iter().sum())
+    }
+
+    /// Get the client of the account the given transaction updated, the one
+    /// of the disputed transaction for the disputes and their settlements.
+    fn account_of(manager: &AccountManager, transaction: &Transaction) -> ClientId {
+        match transaction.kind {
+            TransactionKind::Deposit(_)
+            | TransactionKind::Withdrawal(_)
+            | TransactionKind::Unlock
+            | TransactionKind::Open
+            | TransactionKind::Close
+            | TransactionKind::Adjustment(_) => transaction.client_id,
+            TransactionKind::Dispute(tx_id)
+            | TransactionKind::Resolve(tx_id)
+            | TransactionKind::ChargeBack(tx_id) => manager
+                .get_transaction(tx_id)
+                .map_or(transaction.client_id, |deposit| deposit.client_id),
+        }
+    }
+}
+
+impl Actor for Accountant {
+    type Message = Vec<TransactionOrder>;
+    type Output = ();
+
+    const NAME: &'static str = "Accountant";
+
+    fn receive(&mut self) -> Option<Self::Message> {
+        let batch = self.mailbox.recv_batch(self.batch_size)?;
+        trace!("Accountant Actor: received {} orders", batch.len());
+
+        Some(batch)
+    }
+
+    fn handle(&mut self, batch: Self::Message) -> Result<()> {
+        self.process_batch(&self.account_manager, batch, None);
+
+        Ok(())
+    }
+
+    fn on_stop(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    use crate::{
+        actor::{channel, Reader},
+        adapter::InMemoryAccountStorage,
+        model::TransactionKind,
+        service::AccountManager,
+    };
+
+    #[test]
+    fn test_run() {
+        let (tx, rx) = channel();
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let errors = Arc::new(AtomicU64::new(0));
+        let accountant =
+            Accountant::new(account_manager.clone(), rx).with_error_count(errors.clone());
+        let handler = std::thread::spawn(move || accountant.run());
+        tx.send(TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        // Dispute a non-existing transaction
+        // This should not fail but log an error
+        tx.send(TransactionOrder {
+            tx_id: 3,
+            client_id: 2,
+            kind: TransactionKind::Dispute(3),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        tx.send(TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Withdrawal(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        // Send twice the same transaction
+        // It must not be taken into account
+        tx.send(TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Withdrawal(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        drop(tx);
+        handler.join().unwrap().unwrap();
+        let account = account_manager.get_account(1).unwrap();
+
+        assert_eq!(account.available, Decimal::ONE_HUNDRED - Decimal::ONE);
+        assert_eq!(errors.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_fan_in() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let sources = [
+            "type,client,tx,amount\ndeposit,1,1,10\nwithdrawal,1,2,4\ndeposit,2,3,1\n",
+            "type,client,tx,amount\ndeposit,3,4,7\ndeposit,2,5,2\nwithdrawal,3,6,7\n",
+        ];
+        let mut receivers = Vec::new();
+        let mut readers = Vec::new();
+        for source in sources {
+            let (tx, rx) = channel();
+            receivers.push(rx);
+            readers.push(std::thread::spawn(move || {
+                Reader::new(tx, Box::new(source.as_bytes())).run()
+            }));
+        }
+        let errors = Arc::new(AtomicU64::new(0));
+        Accountant::with_receivers(account_manager.clone(), receivers)
+            .unwrap()
+            .with_error_count(errors.clone())
+            .run()
+            .unwrap();
+        for reader in readers {
+            reader.join().unwrap().unwrap();
+        }
+
+        // the withdrawals follow the deposits of their source
+        assert_eq!(errors.load(Ordering::Relaxed), 0);
+        assert_eq!(
+            account_manager.get_account(1).unwrap().available,
+            Decimal::from(6)
+        );
+        assert_eq!(
+            account_manager.get_account(2).unwrap().available,
+            Decimal::from(3)
+        );
+        assert_eq!(
+            account_manager.get_account(3).unwrap().available,
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_acknowledgements() {
+        let (tx, rx) = channel();
+        let (ack_tx, ack_rx) = channel();
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let accountant = Accountant::new(account_manager, rx).with_ack_sender(ack_tx);
+        let handler = std::thread::spawn(move || accountant.run());
+        tx.send(TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        ack_rx.recv().unwrap();
+
+        // failing orders are acknowledged as well
+        tx.send(TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED),
+            timestamp: None,
+            currency: None,
+        })
+        .unwrap();
+        ack_rx.recv().unwrap();
+        drop(tx);
+        handler.join().unwrap().unwrap();
+
+        assert!(ack_rx.recv().is_err());
+    }
+
+    /// Record the hooks called.
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl OrderObserver for Recorder {
+        fn on_accepted(&self, order: &TransactionOrder, transaction: &Transaction) {
+            self.0.lock().unwrap().push(format!(
+                "accepted {} {}",
+                order.tx_id, transaction.client_id
+            ));
+        }
+
+        fn on_rejected(&self, order: &TransactionOrder, _error: &anyhow::Error) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("rejected {}", order.tx_id));
+        }
+
+        fn on_account_updated(&self, account: &Account) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("updated {} {}", account.client_id, account.held));
+        }
+    }
+
+    #[test]
+    fn test_observers() {
+        let (tx, rx) = channel();
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let recorder = Arc::new(Recorder::default());
+        for (tx_id, client_id, kind) in [
+            (1, 1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, 1, TransactionKind::Withdrawal(Decimal::ONE_HUNDRED)),
+            // the dispute of another client updates the account of the deposit
+            (3, 2, TransactionKind::Dispute(1)),
+        ] {
+            tx.send(TransactionOrder {
+                tx_id,
+                client_id,
+                kind,
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        }
+        drop(tx);
+        Accountant::new(account_manager, rx)
+            .with_observer(recorder.clone())
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec!["accepted 1 1", "rejected 2", "accepted 3 2", "updated 1 10"]
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        account_manager
+            .process_order(order(1, 1, TransactionKind::Deposit(Decimal::TEN)))
+            .unwrap();
+        let (tx, rx) = channel();
+        for order in [
+            order(2, 2, TransactionKind::Deposit(Decimal::ONE)),
+            order(3, 1, TransactionKind::Dispute(1)),
+            // the funds are held by the dispute
+            order(4, 1, TransactionKind::Withdrawal(Decimal::ONE)),
+            // already stored
+            order(1, 3, TransactionKind::Deposit(Decimal::ONE)),
+        ] {
+            tx.send(order).unwrap();
+        }
+        drop(tx);
+        let report = Accountant::new(account_manager.clone(), rx)
+            .with_batch_size(2)
+            .run_dry()
+            .unwrap();
+
+        assert_eq!((report.accepted, report.rejected), (2, 2));
+        assert_eq!(
+            report
+                .accounts
+                .iter()
+                .map(|account| (account.client_id, account.available, account.held))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, Decimal::ZERO, Decimal::TEN),
+                (2, Decimal::ONE, Decimal::ZERO)
+            ]
+        );
+        // the manager is left untouched
+        assert_eq!(account_manager.get_accounts().len(), 1);
+        assert_eq!(account_manager.get_account(1).unwrap().held, Decimal::ZERO);
+        assert!(account_manager.get_transaction(2).is_none());
+    }
+
+    #[test]
+    fn test_batches() {
+        let (tx, rx) = channel();
+        let (ack_tx, ack_rx) = channel();
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let errors = Arc::new(AtomicU64::new(0));
+        // every third order is a duplicate of the previous one
+        for tx_id in 1..=2500 {
+            tx.send(TransactionOrder {
+                tx_id: tx_id - u64::from(tx_id % 3 == 0),
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        }
+        drop(tx);
+        Accountant::new(account_manager.clone(), rx)
+            .with_ack_sender(ack_tx)
+            .with_error_count(errors.clone())
+            .with_batch_size(1000)
+            .run()
+            .unwrap();
+
+        assert_eq!(ack_rx.iter().count(), 2500);
+        assert_eq!(errors.load(Ordering::Relaxed), 833);
+        assert_eq!(
+            account_manager.get_account(1).unwrap().available,
+            Decimal::from(2500 - 833)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! # Applied Transaction Sink Actor
+//!
+//! This module provides the implementation of the Applied Transaction Sink
+//! Actor that streams the transactions applied by the accountant to a writer,
+//! as they are applied. This journal is the authoritative record of what was
+//! applied, the suspense deposits under the client of the suspense account:
+//! replaying it with the [JournalReplayer](super::JournalReplayer) gives the
+//! same balances.
+
+use std::io::Write;
+
+use log::debug;
+
+use super::{dead_letter_exporter::OrderRecord, Receiver, TryRecvError};
+use crate::{model::Transaction, Result};
+
+/// The applied transaction sink actor.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use csv_reader_core::actor::{channel, Accountant, AppliedTransactionSink, Reader};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let (order_sender, order_receiver) = channel();
+/// let (applied_sender, applied_receiver) = channel();
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.0\nwithdrawal,1,2,5.0\n";
+/// Reader::new(order_sender, Box::new(data.as_bytes())).run().unwrap();
+/// Accountant::new(manager, order_receiver)
+///     .with_applied_sender(applied_sender)
+///     .run()
+///     .unwrap();
+/// AppliedTransactionSink::new(applied_receiver, Box::new(std::io::sink()))
+///     .run()
+///     .unwrap();
+/// ```
+pub struct AppliedTransactionSink {
+    /// The channel receiving the applied transactions.
+    applied_receiver: Receiver<Transaction>,
+
+    /// A Write interface to stream the CSV to
+    writer: Box<dyn Write + Sync + Send>,
+}
+
+impl AppliedTransactionSink {
+    /// Create a new applied transaction sink actor.
+    pub fn new(
+        applied_receiver: Receiver<Transaction>,
+        writer: Box<dyn Write + Sync + Send>,
+    ) -> Self {
+        Self {
+            applied_receiver,
+            writer,
+        }
+    }
+
+    /// Run the applied transaction sink actor.
+    /// The actor writes the transactions as CSV rows read back by the
+    /// [Reader](super::Reader), flushing the writer whenever it waits for the
+    /// next ones, until the channel is closed.
+    pub fn run(self) -> Result<()> {
+        debug!("Applied Transaction Sink Actor started");
+
+        let mut writer = csv::Writer::from_writer(self.writer);
+        let mut next = self.applied_receiver.recv().ok();
+        while let Some(transaction) = next {
+            writer.serialize(OrderRecord::new(
+                transaction.tx_id,
+                transaction.client_id,
+                &transaction.kind,
+                transaction.timestamp,
+                transaction.currency,
+            ))?;
+            next = match self.applied_receiver.try_recv() {
+                Ok(transaction) => Some(transaction),
+                Err(TryRecvError::Empty) => {
+                    writer.flush()?;
+                    self.applied_receiver.recv().ok()
+                }
+                Err(TryRecvError::Disconnected) => None,
+            };
+        }
+
+        writer.flush()?;
+
+        debug!("Applied Transaction Sink Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        actor::{Accountant, Reader},
+        adapter::InMemoryAccountStorage,
+        service::{AccountManager, AccountManagerOptions},
+        test_utils::SharedBuffer,
+    };
+
+    /// Process the given input and return the balances with the journal of
+    /// the applied transactions.
+    fn process(data: String, options: AccountManagerOptions) -> (String, Vec<String>) {
+        let manager = Arc::new(AccountManager::with_options(
+            InMemoryAccountStorage::default(),
+            options,
+        ));
+        let (order_sender, order_receiver) = channel();
+        let (applied_sender, applied_receiver) = channel();
+        let buffer = SharedBuffer::default();
+        let sink = AppliedTransactionSink::new(applied_receiver, Box::new(buffer.clone()));
+        let sink = std::thread::spawn(move || sink.run());
+        Reader::new(order_sender, Box::new(std::io::Cursor::new(data)))
+            .run()
+            .unwrap();
+        Accountant::new(manager.clone(), order_receiver)
+            .with_applied_sender(applied_sender)
+            .run()
+            .unwrap();
+        sink.join().unwrap().unwrap();
+        let journal = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let mut balances: Vec<String> = manager
+            .get_accounts()
+            .iter()
+            .map(|account| {
+                format!(
+                    "{} {} {}",
+                    account.client_id,
+                    account.available.normalize(),
+                    account.held.normalize()
+                )
+            })
+            .collect();
+        balances.sort();
+
+        (journal, balances)
+    }
+
+    #[test]
+    fn test_replay_journal() {
+        let data = "type,client,tx,amount\n\
+            deposit,1,1,10.0\n\
+            withdrawal,1,2,50.0\n\
+            deposit,2,3,5.0\n\
+            dispute,1,1,\n\
+            chargeback,1,1,\n\
+            deposit,1,4,2.0\n\
+            withdrawal,2,5,1.5\n";
+        let options = AccountManagerOptions {
+            suspense_account: Some(99),
+            ..Default::default()
+        };
+        let (journal, balances) = process(data.to_string(), options.clone());
+
+        assert_eq!(
+            journal,
+            "type,client,tx,amount,timestamp,currency\n\
+             deposit,1,1,10,,\n\
+             deposit,2,3,5,,\n\
+             dispute,1,1,,,\n\
+             chargeback,1,1,,,\n\
+             deposit,99,4,2,,\n\
+             withdrawal,2,5,1.5,,\n"
+        );
+
+        // replaying the journal gives the same balances
+        let (replayed, replayed_balances) = process(journal.clone(), options);
+
+        assert_eq!(replayed, journal);
+        assert_eq!(replayed_balances, balances);
+    }
+}
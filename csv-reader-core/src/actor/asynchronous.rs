@@ -0,0 +1,252 @@
+//! Asynchronous actors
+//!
+//! The actors of this module are the [Reader], [Accountant] and
+//! [AccountExporter] actors as tokio tasks communicating through tokio
+//! channels, so the engine can be embedded in an async service without
+//! dedicating an OS thread to every actor. The parsing of the input and the
+//! writing of the export are blocking, they run on the blocking pool of the
+//! runtime.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use csv_reader_core::actor::{AsyncAccountExporter, AsyncAccountant, AsyncReader, ReaderOptions};
+//! use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+//! // a bounded channel, the reader waits for the accountant to catch up
+//! let (sender, receiver) = tokio::sync::mpsc::channel(1024);
+//! let data = "type,client,tx,amount\ndeposit,1,1,2.5\n";
+//! let reader = AsyncReader::new(sender, Box::new(data.as_bytes()), ReaderOptions::default());
+//! let accountant = AsyncAccountant::new(manager.clone(), receiver);
+//! let exporter = AsyncAccountExporter::new(manager.clone(), Box::new(std::io::sink()));
+//!
+//! runtime.block_on(async {
+//!     let accountant = tokio::spawn(accountant.run());
+//!     reader.run().await.unwrap();
+//!     accountant.await.unwrap().unwrap();
+//!     exporter.run().await.unwrap();
+//! });
+//!
+//! assert_eq!(manager.get_account(1).unwrap().total, rust_decimal::Decimal::new(25, 1));
+//! ```
+//!
+//! [Reader]: super::Reader
+//! [Accountant]: super::Accountant
+//! [AccountExporter]: super::AccountExporter
+
+use std::{
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use log::{debug, trace};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use super::{AccountExporter, Orders, ReaderOptions, SortKey};
+use crate::{adapter::InputSource, model::TransactionOrder, service::AccountService, Result};
+
+/// Asynchronous reader actor, sending the orders parsed from a CSV input.
+pub struct AsyncReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The CSV input.
+    reader: Box<dyn Read + Sync + Send>,
+
+    /// The parsing options.
+    options: ReaderOptions,
+}
+
+impl AsyncReader {
+    /// Create a new asynchronous reader actor with the given parsing options.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+        options: ReaderOptions,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader,
+            options,
+        }
+    }
+
+    /// Run the reader actor.
+    /// The input is parsed on the blocking pool of the runtime, waiting for
+    /// room in the order channel when it is full. The actor stops at the end of
+    /// the input, on error or once the accountant is gone.
+    pub async fn run(self) -> Result<()> {
+        debug!("Async Reader Actor started");
+        let Self {
+            order_sender,
+            reader,
+            options,
+        } = self;
+        let result = tokio::task::spawn_blocking(move || {
+            let mut orders = Orders::new(reader, options);
+            while let Some(order) = orders.next_order() {
+                order_sender.blocking_send(order?)?;
+            }
+
+            Ok(())
+        })
+        .await?;
+        debug!("Async Reader Actor stopped");
+
+        result
+    }
+}
+
+/// Asynchronous accountant actor, processing the orders received.
+pub struct AsyncAccountant {
+    /// The account manager service.
+    account_manager: Arc<dyn AccountService>,
+
+    /// The order channel receiver to read transaction orders.
+    order_receiver: Receiver<TransactionOrder>,
+
+    /// When set, the number of orders that failed to be processed.
+    error_count: Option<Arc<AtomicU64>>,
+}
+
+impl AsyncAccountant {
+    /// Create a new asynchronous accountant actor.
+    pub fn new(
+        account_manager: Arc<dyn AccountService>,
+        order_receiver: Receiver<TransactionOrder>,
+    ) -> Self {
+        Self {
+            account_manager,
+            order_receiver,
+            error_count: None,
+        }
+    }
+
+    /// Count the orders that failed to be processed in the given counter,
+    /// their errors being only logged.
+    pub fn with_error_count(mut self, error_count: Arc<AtomicU64>) -> Self {
+        self.error_count = Some(error_count);
+
+        self
+    }
+
+    /// Run the accountant actor.
+    /// Like the [Accountant](super::Accountant), it does not stop when an
+    /// order fails but logs the error, and stops once the order channel is
+    /// closed.
+    pub async fn run(mut self) -> Result<()> {
+        debug!("Async Accountant Actor started");
+
+        while let Some(order) = self.order_receiver.recv().await {
+            trace!("Async Accountant Actor: received order: {:#?}", order);
+
+            if let Err(error) = self.account_manager.process_order(order) {
+                log::info!("Async Accountant Actor: Error processing order: {}", error);
+                if let Some(error_count) = &self.error_count {
+                    error_count.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        debug!("Async Accountant Actor stopped");
+
+        Ok(())
+    }
+}
+
+/// Asynchronous account exporter actor, writing the accounts as CSV.
+pub struct AsyncAccountExporter {
+    /// The exporter run on the blocking pool.
+    exporter: AccountExporter,
+}
+
+impl AsyncAccountExporter {
+    /// Create a new asynchronous account exporter actor.
+    pub fn new(
+        account_manager: Arc<dyn AccountService>,
+        writer: Box<dyn Write + Sync + Send>,
+    ) -> Self {
+        Self {
+            exporter: AccountExporter::new(account_manager, writer),
+        }
+    }
+
+    /// Add the `disputed_count` and `disputed_sum` columns, see
+    /// [AccountExporter::with_dispute_columns].
+    pub fn with_dispute_columns(mut self, dispute_columns: bool) -> Self {
+        self.exporter = self.exporter.with_dispute_columns(dispute_columns);
+
+        self
+    }
+
+    /// Export the accounts in the order of the given key instead of the
+    /// storage order.
+    pub fn with_sort_by(mut self, sort_by: SortKey) -> Self {
+        self.exporter = self.exporter.with_sort_by(sort_by);
+
+        self
+    }
+
+    /// Run the account exporter actor on the blocking pool of the runtime.
+    pub async fn run(self) -> Result<()> {
+        let exporter = self.exporter;
+
+        tokio::task::spawn_blocking(move || exporter.run()).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rust_decimal::Decimal;
+    use tokio::{runtime::Builder, sync::mpsc::channel};
+
+    use crate::{adapter::InMemoryAccountStorage, service::AccountManager};
+
+    #[test]
+    fn test_async_pipeline() {
+        let mut data = String::from("type,client,tx,amount\n");
+        for tx_id in 1..=100 {
+            data.push_str(&format!("deposit,{},{tx_id},1.0\n", tx_id % 3));
+        }
+        // the withdrawal exceeds the funds
+        data.push_str("withdrawal,1,101,100.0\n");
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let errors = Arc::new(AtomicU64::new(0));
+        let (sender, receiver) = channel(1);
+        let reader = AsyncReader::new(
+            sender,
+            Box::new(std::io::Cursor::new(data)),
+            ReaderOptions::default(),
+        );
+        let accountant =
+            AsyncAccountant::new(manager.clone(), receiver).with_error_count(errors.clone());
+
+        runtime.block_on(async {
+            let accountant = tokio::spawn(accountant.run());
+            reader.run().await.unwrap();
+            accountant.await.unwrap().unwrap();
+        });
+
+        assert_eq!(manager.get_accounts().len(), 3);
+        assert_eq!(manager.get_account(1).unwrap().total, Decimal::from(34));
+        assert_eq!(errors.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_async_reader_without_accountant() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let (sender, receiver) = channel(1);
+        drop(receiver);
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\n";
+        let reader = AsyncReader::new(sender, Box::new(data.as_bytes()), ReaderOptions::default());
+
+        assert!(runtime.block_on(reader.run()).is_err());
+    }
+}
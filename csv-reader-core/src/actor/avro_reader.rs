@@ -0,0 +1,157 @@
+//! Avro reader actor
+//!
+//! The Avro reader actor is responsible for reading the transaction data from
+//! an Apache Avro object container file. Each record is mapped on a
+//! [CSVTransactionEntity] using its field names so any writer schema providing
+//! the `type`, `client`, `tx` and `amount` fields can be read. The actor sends
+//! the transaction orders to the accountant actor through a channel.
+
+use std::io::Read;
+
+use log::debug;
+
+use super::Sender;
+use crate::model::{CSVTransactionEntity, TransactionOrder};
+
+/// Reference Avro schema of the transaction records.
+///
+/// The amount may also be declared as a nullable `double` or `string`, both are
+/// converted to decimals.
+pub const TRANSACTION_AVRO_SCHEMA: &str = r#"
+{
+    "type": "record",
+    "name": "Transaction",
+    "fields": [
+        { "name": "type", "type": "string" },
+        { "name": "client", "type": "long" },
+        { "name": "tx", "type": "long" },
+        { "name": "amount", "type": ["null", "string"], "default": null }
+    ]
+}
+"#;
+
+/// Avro reader actor.
+pub struct AvroReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+    reader: Box<dyn Read + Sync + Send>,
+}
+
+impl AvroReader {
+    /// Create a new Avro reader actor.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader,
+        }
+    }
+
+    /// Run the Avro reader actor.
+    /// The actor will read the Avro container record by record and send the
+    /// transaction orders to the accountant actor through the order channel.
+    /// It fails if the container header cannot be read.
+    pub fn run(self) -> crate::Result<()> {
+        debug!("Avro Reader Actor started");
+        let avro_reader = apache_avro::Reader::new(self.reader)?;
+
+        for result in avro_reader {
+            let record: CSVTransactionEntity =
+                match result.and_then(|value| apache_avro::from_value(&value)) {
+                    Err(error) => {
+                        log::info!("Error reading Avro record: {}", error);
+                        continue;
+                    }
+                    Ok(record) => record,
+                };
+            let order = match TransactionOrder::try_from(record) {
+                Err(error) => {
+                    log::info!("Error parsing Avro record: {}", error);
+                    continue;
+                }
+                Ok(order) => order,
+            };
+
+            self.order_sender.send(order)?;
+        }
+        debug!("Avro Reader Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+
+    use apache_avro::{types::Record, Schema, Writer};
+    use rust_decimal_macros::dec;
+
+    use crate::model::TransactionKind;
+
+    fn write_container(rows: &[(&str, i32, i64, Option<&str>)]) -> Vec<u8> {
+        let schema = Schema::parse_str(TRANSACTION_AVRO_SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new()).unwrap();
+
+        for (kind, client, tx, amount) in rows {
+            let mut record = Record::new(writer.schema()).unwrap();
+            record.put("type", *kind);
+            record.put("client", *client);
+            record.put("tx", *tx);
+            record.put("amount", amount.map(|value| value.to_string()));
+            writer.append_value(record).unwrap();
+        }
+
+        writer.into_inner().unwrap()
+    }
+
+    fn run_reader(data: Vec<u8>) -> Vec<TransactionOrder> {
+        let (tx, rx) = channel();
+        let actor = AvroReader::new(tx, Box::new(std::io::Cursor::new(data)));
+        let handler = std::thread::spawn(move || actor.run());
+
+        assert!(handler.join().unwrap().is_ok());
+
+        rx.iter().collect()
+    }
+
+    #[test]
+    fn simple_ok_sample() {
+        let data = write_container(&[
+            ("deposit", 1, 1, Some("1.5")),
+            ("withdrawal", 1, 2, Some("0.5")),
+            ("dispute", 1, 1, None),
+        ]);
+        let orders = run_reader(data);
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(1.5)));
+        assert_eq!(orders[1].kind, TransactionKind::Withdrawal(dec!(0.5)));
+        assert_eq!(orders[2].kind, TransactionKind::Dispute(1));
+    }
+
+    #[test]
+    fn invalid_records_are_skipped() {
+        let data = write_container(&[
+            ("deposit", 1, 1, Some("1.0")),
+            ("whatever", 1, 2, Some("1.0")),
+            ("deposit", 1, 3, None),
+            ("deposit", 1, 4, Some("-1.0")),
+            ("withdrawal", 1, 5, Some("0.5")),
+        ]);
+
+        assert_eq!(run_reader(data).len(), 2);
+    }
+
+    #[test]
+    fn invalid_container() {
+        let (tx, _rx) = channel();
+        let actor = AvroReader::new(tx, Box::new("type, client, tx, amount".as_bytes()));
+
+        assert!(actor.run().is_err());
+    }
+}
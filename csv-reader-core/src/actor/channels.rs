@@ -0,0 +1,20 @@
+//! # Actor Channels
+//!
+//! This module provides the channels the actors communicate through. They are
+//! the channels of `std::sync::mpsc` by default, or the ones of
+//! `crossbeam-channel` with the `crossbeam` feature: those scale better with
+//! many senders, can be bounded so a fast reader waits for the accountant
+//! instead of queuing the whole input, and support `crossbeam_channel::select!`
+//! over several receivers. Both have the same interface, so the actors and
+//! the code creating their channels with [channel] build with either.
+
+#[cfg(not(feature = "crossbeam"))]
+pub use std::sync::mpsc::{
+    channel, Receiver, RecvError, RecvTimeoutError, SendError, Sender, TryRecvError,
+};
+
+#[cfg(feature = "crossbeam")]
+pub use crossbeam_channel::{
+    bounded as bounded_channel, unbounded as channel, Receiver, RecvError, RecvTimeoutError,
+    SendError, Sender, TryRecvError,
+};
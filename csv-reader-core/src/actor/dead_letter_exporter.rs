@@ -0,0 +1,253 @@
+//! # Dead Letter Exporter Actor
+//!
+//! This module provides the implementation of the Dead Letter Exporter Actor
+//! that persists the orders the accountant failed to process, with their
+//! error, for a later replay. The dead letters are written as CSV rows read
+//! back by the [Reader](super::Reader), the `error` column being ignored.
+
+use std::io::Write;
+
+use log::debug;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::reader::{kind_position, KIND_NAMES};
+use crate::{
+    model::{ClientId, Currency, Timestamp, TransactionKind, TransactionOrder, TxId},
+    Result,
+};
+
+/// An order the accountant failed to process, sent to the dead letter channel
+/// of the [Accountant](super::Accountant).
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// The failed order.
+    pub order: TransactionOrder,
+
+    /// The kind of the error, see [TransactionError::kind_of].
+    ///
+    /// [TransactionError::kind_of]: crate::service::TransactionError::kind_of
+    pub kind: &'static str,
+
+    /// Why the order failed.
+    pub error: String,
+}
+
+impl Serialize for DeadLetter {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let order = &self.order;
+        let record = OrderRecord {
+            error: Some(&self.error),
+            ..OrderRecord::new(
+                order.tx_id,
+                order.client_id,
+                &order.kind,
+                order.timestamp,
+                order.currency,
+            )
+        };
+
+        record.serialize(serializer)
+    }
+}
+
+/// The CSV record of an order or of a transaction, as read by the
+/// [Reader](super::Reader).
+#[derive(Serialize)]
+pub(crate) struct OrderRecord<'a> {
+    /// The transaction kind.
+    r#type: &'static str,
+
+    /// The client identifier.
+    client: ClientId,
+
+    /// The transaction identifier.
+    tx: TxId,
+
+    /// The amount of the deposits and withdrawals.
+    amount: Option<Decimal>,
+
+    /// The time of the transaction, in RFC 3339 format.
+    timestamp: Option<String>,
+
+    /// The currency of the transaction.
+    currency: Option<String>,
+
+    /// Why the order failed, for the dead letters only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+
+    /// Why the order was quarantined, for the quarantined orders only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+impl<'a> OrderRecord<'a> {
+    /// Create the record of the given transaction fields.
+    pub(crate) fn new(
+        tx_id: TxId,
+        client_id: ClientId,
+        kind: &TransactionKind,
+        timestamp: Option<Timestamp>,
+        currency: Option<Currency>,
+    ) -> Self {
+        let amount = match kind {
+            TransactionKind::Deposit(amount)
+            | TransactionKind::Withdrawal(amount)
+            | TransactionKind::Adjustment(amount) => Some(amount.normalize()),
+            _ => None,
+        };
+
+        Self {
+            r#type: KIND_NAMES[kind_position(kind)],
+            client: client_id,
+            tx: tx_id,
+            amount,
+            timestamp: timestamp.map(|timestamp| timestamp.to_rfc3339()),
+            currency: currency.map(|currency| currency.to_string()),
+            error: None,
+            reason: None,
+        }
+    }
+
+    /// Add the reason why the order was quarantined.
+    pub(crate) fn with_reason(mut self, reason: &'a str) -> Self {
+        self.reason = Some(reason);
+
+        self
+    }
+}
+
+/// The dead letter exporter actor.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::actor::{channel, Accountant, DeadLetterExporter};
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let (order_sender, order_receiver) = channel();
+/// let (dead_letter_sender, dead_letter_receiver) = channel();
+/// let kind = TransactionKind::Withdrawal(Decimal::ONE);
+/// order_sender.send(TransactionOrder { tx_id: 1, client_id: 1, kind, timestamp: None, currency: None }).unwrap();
+/// drop(order_sender);
+/// Accountant::new(manager, order_receiver)
+///     .with_dead_letter_sender(dead_letter_sender)
+///     .run()
+///     .unwrap();
+/// let dead_letters: Vec<_> = dead_letter_receiver.iter().collect();
+///
+/// assert_eq!(dead_letters.len(), 1);
+/// DeadLetterExporter::new(dead_letters, Box::new(std::io::sink()))
+///     .run()
+///     .unwrap();
+/// ```
+pub struct DeadLetterExporter {
+    /// The dead letters to export.
+    dead_letters: Vec<DeadLetter>,
+
+    /// A Write interface to export the CSV to
+    writer: Box<dyn Write + Sync + Send>,
+}
+
+impl DeadLetterExporter {
+    /// Create a new dead letter exporter actor with the dead letters collected
+    /// from the channel of the accountant once it stopped.
+    pub fn new(dead_letters: Vec<DeadLetter>, writer: Box<dyn Write + Sync + Send>) -> Self {
+        Self {
+            dead_letters,
+            writer,
+        }
+    }
+
+    /// Run the dead letter exporter actor.
+    /// The actor will export the dead letters to a CSV file.
+    pub fn run(self) -> Result<()> {
+        debug!("Dead Letter Exporter Actor started");
+
+        let mut writer = csv::Writer::from_writer(self.writer);
+        for dead_letter in &self.dead_letters {
+            writer.serialize(dead_letter)?;
+        }
+
+        writer.flush()?;
+
+        debug!("Dead Letter Exporter Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{
+        actor::{Accountant, Reader},
+        adapter::InMemoryAccountStorage,
+        service::AccountManager,
+        test_utils::SharedBuffer,
+    };
+
+    #[test]
+    fn test_dead_letters_replay() {
+        let data = "type,client,tx,amount,timestamp\n\
+            deposit,1,1,2.0,\n\
+            withdrawal,1,2,5.0,2024-03-01T12:30:00Z\n\
+            dispute,1,3,,\n\
+            deposit,1,1,1.0,\n";
+        let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let (order_sender, order_receiver) = channel();
+        let (dead_letter_sender, dead_letter_receiver) = channel();
+        Reader::new(order_sender, Box::new(data.as_bytes()))
+            .run()
+            .unwrap();
+        Accountant::new(manager, order_receiver)
+            .with_dead_letter_sender(dead_letter_sender)
+            .run()
+            .unwrap();
+        let dead_letters: Vec<DeadLetter> = dead_letter_receiver.iter().collect();
+        let buffer = SharedBuffer::default();
+        DeadLetterExporter::new(dead_letters, Box::new(buffer.clone()))
+            .run()
+            .unwrap();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let rows: Vec<&str> = output.lines().collect();
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], "type,client,tx,amount,timestamp,currency,error");
+        assert!(rows[1].starts_with("withdrawal,1,2,5,2024-03-01T12:30:00+00:00,,"));
+        assert!(rows[2].starts_with("dispute,1,3,,,,"));
+        assert!(rows[3].starts_with("deposit,1,1,1,,,"));
+
+        // the dead letters are read back as they were sent
+        let (order_sender, order_receiver) = channel();
+        Reader::new(order_sender, Box::new(std::io::Cursor::new(output)))
+            .run()
+            .unwrap();
+        let orders: Vec<TransactionOrder> = order_receiver.iter().collect();
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(
+            orders[0].kind,
+            TransactionKind::Withdrawal(Decimal::from(5))
+        );
+        assert_eq!(
+            orders[0].timestamp,
+            Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap())
+        );
+        assert_eq!(orders[1].kind, TransactionKind::Dispute(3));
+    }
+}
@@ -0,0 +1,207 @@
+//! Directory watcher actor
+//!
+//! The directory watcher actor implements the "drop folder" integration: it
+//! reads the CSV files already present in a directory and then polls the
+//! directory for newly dropped files. Every file is parsed with a [Reader]
+//! actor and the transaction orders are sent to the accountant actor through
+//! the same channel so they apply against the same account state.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+
+use super::{Reader, ReaderOptions, Sender};
+use crate::{model::TransactionOrder, Result};
+
+/// Directory watcher actor.
+pub struct DirectoryWatcher {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The watched directory.
+    directory: PathBuf,
+
+    /// The options used to parse the files.
+    options: ReaderOptions,
+
+    /// The delay between two scans of the directory.
+    poll_interval: Duration,
+
+    /// Stop watching when no file has been dropped for this duration.
+    idle_timeout: Option<Duration>,
+}
+
+impl DirectoryWatcher {
+    /// Create a new directory watcher actor polling the directory every
+    /// second.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        directory: impl Into<PathBuf>,
+        options: ReaderOptions,
+    ) -> Self {
+        Self {
+            order_sender,
+            directory: directory.into(),
+            options,
+            poll_interval: Duration::from_secs(1),
+            idle_timeout: None,
+        }
+    }
+
+    /// Set the delay between two scans of the directory.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+
+        self
+    }
+
+    /// Stop watching once no file has been dropped for the given duration.
+    /// Without it, the directory is watched forever.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+
+        self
+    }
+
+    /// Run the directory watcher actor.
+    /// The files present at start are read in name order. A newly dropped file
+    /// is read once its size did not change between two scans so files still
+    /// being written are not read partially. Hidden files are ignored. A file
+    /// that cannot be read is logged and skipped.
+    pub fn run(self) -> Result<()> {
+        debug!("Directory Watcher Actor started on {:?}", self.directory);
+        let mut processed: HashSet<PathBuf> = HashSet::new();
+        let mut pending: HashMap<PathBuf, u64> = HashMap::new();
+
+        for path in self.list_files()? {
+            self.process(&path);
+            processed.insert(path);
+        }
+        let mut last_activity = Instant::now();
+
+        while self
+            .idle_timeout
+            .is_none_or(|timeout| last_activity.elapsed() < timeout)
+        {
+            std::thread::sleep(self.poll_interval);
+
+            for path in self.list_files()? {
+                if processed.contains(&path) {
+                    continue;
+                }
+                // the file may have been removed since the scan
+                let Ok(metadata) = path.metadata() else {
+                    continue;
+                };
+                let size = metadata.len();
+                last_activity = Instant::now();
+
+                if pending.insert(path.clone(), size) == Some(size) {
+                    pending.remove(&path);
+                    self.process(&path);
+                    processed.insert(path);
+                }
+            }
+        }
+        debug!("Directory Watcher Actor stopped");
+
+        Ok(())
+    }
+
+    /// List the visible files of the directory sorted by name.
+    fn list_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        for entry in std::fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+            if path.is_file() && !hidden {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Read the given file and send its transaction orders.
+    fn process(&self, path: &Path) {
+        info!("Directory Watcher Actor: reading {:?}", path);
+        let result = File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                Reader::with_options(
+                    self.order_sender.clone(),
+                    Box::new(BufReader::new(file)),
+                    self.options.clone(),
+                )
+                .run()
+            });
+
+        if let Err(error) = result {
+            info!(
+                "Directory Watcher Actor: error reading {:?}: {}",
+                path, error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+
+    use super::*;
+
+    #[test]
+    fn test_drop_folder() {
+        let directory = std::env::temp_dir().join(format!(
+            "csv_reader_directory_watcher_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("1.csv"),
+            "type,client,tx,amount\ndeposit,1,1,1.0\n",
+        )
+        .unwrap();
+        std::fs::write(directory.join(".hidden.csv"), "type,client,tx,amount\n").unwrap();
+
+        let (tx, rx) = channel();
+        let watcher = DirectoryWatcher::new(tx, &directory, ReaderOptions::default())
+            .with_poll_interval(Duration::from_millis(10))
+            .with_idle_timeout(Duration::from_millis(200));
+        let handler = std::thread::spawn(move || watcher.run());
+
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::write(
+            directory.join("2.csv"),
+            "type,client,tx,amount\ndeposit,2,2,1.0\nwithdrawal,2,3,0.5\n",
+        )
+        .unwrap();
+        handler.join().unwrap().unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let orders: Vec<TransactionOrder> = rx.iter().collect();
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].client_id, 1);
+        assert_eq!(orders[2].tx_id, 3);
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        let (tx, _rx) = channel();
+        let watcher = DirectoryWatcher::new(tx, "/does/not/exist", ReaderOptions::default());
+
+        assert!(watcher.run().is_err());
+    }
+}
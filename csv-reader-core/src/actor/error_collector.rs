@@ -0,0 +1,241 @@
+//! # Error Collector Actor
+//!
+//! This module provides the implementation of the Error Collector Actor that
+//! aggregates the orders the accountant failed to process by kind of error,
+//! for a report at the end of the run instead of a log line per order.
+
+use std::{collections::BTreeMap, fmt::Display, io::Write};
+
+use serde::Serialize;
+
+use super::{run_actor, Actor, DeadLetter, Mailbox, Receiver};
+use crate::{model::TxId, Result};
+
+/// The failed orders of a kind of error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorSummary {
+    /// The kind of error, see [TransactionError::kind_of].
+    ///
+    /// [TransactionError::kind_of]: crate::service::TransactionError::kind_of
+    pub kind: &'static str,
+
+    /// The number of orders failed with this kind of error.
+    pub count: u64,
+
+    /// The transaction of the first order failed with this kind of error.
+    pub first_tx: TxId,
+
+    /// The error of the first order failed with this kind of error.
+    pub first_error: String,
+}
+
+/// The errors of a run, aggregated by the [ErrorCollector].
+#[derive(Debug, Default, Clone)]
+pub struct ErrorReport {
+    /// The failed orders by kind of error, the most frequent first.
+    pub summaries: Vec<ErrorSummary>,
+
+    /// The failed orders, when kept.
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+impl ErrorReport {
+    /// Get the number of failed orders.
+    pub fn total(&self) -> u64 {
+        self.summaries.iter().map(|summary| summary.count).sum()
+    }
+
+    /// Write the summaries as CSV, one row per kind of error.
+    pub fn write(&self, writer: Box<dyn Write + Sync + Send>) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for summary in &self.summaries {
+            writer.serialize(summary)?;
+        }
+
+        Ok(writer.flush()?)
+    }
+}
+
+impl Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} failed orders.", self.total())?;
+        for summary in &self.summaries {
+            writeln!(
+                f,
+                "  {}: {} (first tx={}: {})",
+                summary.kind, summary.count, summary.first_tx, summary.first_error
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The error collector actor, receiving the failed orders from the dead
+/// letter channel of the [Accountant](super::Accountant).
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::actor::{channel, Accountant, ErrorCollector};
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let (order_sender, order_receiver) = channel();
+/// let (error_sender, error_receiver) = channel();
+/// for tx_id in 1..=2 {
+///     let kind = TransactionKind::Withdrawal(Decimal::ONE);
+///     order_sender.send(TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None }).unwrap();
+/// }
+/// drop(order_sender);
+/// let collector = std::thread::spawn(move || ErrorCollector::new(error_receiver).run());
+/// Accountant::new(manager, order_receiver)
+///     .with_dead_letter_sender(error_sender)
+///     .run()
+///     .unwrap();
+/// let report = collector.join().unwrap().unwrap();
+///
+/// assert_eq!(report.total(), 2);
+/// assert_eq!(report.summaries[0].kind, "insufficient_available_funds");
+/// ```
+pub struct ErrorCollector {
+    /// The mailbox receiving the failed orders.
+    mailbox: Mailbox<DeadLetter>,
+
+    /// Keep the failed orders in the report.
+    keep_dead_letters: bool,
+
+    /// The failed orders by kind of error so far.
+    summaries: BTreeMap<&'static str, ErrorSummary>,
+
+    /// The failed orders kept so far.
+    dead_letters: Vec<DeadLetter>,
+}
+
+impl ErrorCollector {
+    /// Create a new error collector actor.
+    pub fn new(dead_letter_receiver: Receiver<DeadLetter>) -> Self {
+        Self {
+            mailbox: Mailbox::new(dead_letter_receiver),
+            keep_dead_letters: false,
+            summaries: BTreeMap::new(),
+            dead_letters: Vec::new(),
+        }
+    }
+
+    /// Keep the failed orders in the report, for a
+    /// [DeadLetterExporter](super::DeadLetterExporter).
+    pub fn with_dead_letters(mut self, keep_dead_letters: bool) -> Self {
+        self.keep_dead_letters = keep_dead_letters;
+
+        self
+    }
+
+    /// Run the error collector actor.
+    /// The actor aggregates the failed orders until the channel is closed,
+    /// once the accountant stopped, and returns the report.
+    pub fn run(self) -> Result<ErrorReport> {
+        run_actor(self)
+    }
+}
+
+impl Actor for ErrorCollector {
+    type Message = DeadLetter;
+    type Output = ErrorReport;
+
+    const NAME: &'static str = "Error Collector";
+
+    fn receive(&mut self) -> Option<DeadLetter> {
+        self.mailbox.recv()
+    }
+
+    fn handle(&mut self, dead_letter: DeadLetter) -> Result<()> {
+        self.summaries
+            .entry(dead_letter.kind)
+            .and_modify(|summary| summary.count += 1)
+            .or_insert_with(|| ErrorSummary {
+                kind: dead_letter.kind,
+                count: 1,
+                first_tx: dead_letter.order.tx_id,
+                first_error: dead_letter.error.clone(),
+            });
+        if self.keep_dead_letters {
+            self.dead_letters.push(dead_letter);
+        }
+
+        Ok(())
+    }
+
+    fn on_stop(self) -> Result<ErrorReport> {
+        let mut summaries: Vec<ErrorSummary> = self.summaries.into_values().collect();
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.count));
+
+        Ok(ErrorReport {
+            summaries,
+            dead_letters: self.dead_letters,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::model::{TransactionKind, TransactionOrder};
+
+    fn dead_letter(tx_id: TxId, kind: &'static str) -> DeadLetter {
+        DeadLetter {
+            order: TransactionOrder {
+                tx_id,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            },
+            kind,
+            error: format!("{kind} {tx_id}"),
+        }
+    }
+
+    #[test]
+    fn test_error_report() {
+        let (tx, rx) = channel();
+        for (tx_id, kind) in [
+            (1, "account_locked"),
+            (2, "duplicate_transaction_id"),
+            (3, "account_locked"),
+            (4, "other"),
+            (5, "account_locked"),
+            (6, "duplicate_transaction_id"),
+        ] {
+            tx.send(dead_letter(tx_id, kind)).unwrap();
+        }
+        drop(tx);
+        let report = ErrorCollector::new(rx)
+            .with_dead_letters(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(report.total(), 6);
+        assert_eq!(report.dead_letters.len(), 6);
+        assert_eq!(
+            report.to_string(),
+            "6 failed orders.\n  account_locked: 3 (first tx=1: account_locked 1)\n  duplicate_transaction_id: 2 (first tx=2: duplicate_transaction_id 2)\n  other: 1 (first tx=4: other 4)\n"
+        );
+        let mut output = Vec::new();
+        let mut writer = csv::Writer::from_writer(&mut output);
+        writer.serialize(&report.summaries[2]).unwrap();
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "kind,count,first_tx,first_error\nother,1,4,other 4\n"
+        );
+    }
+}
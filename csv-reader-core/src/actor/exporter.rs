@@ -0,0 +1,545 @@
+//! # Account Exporter Actor
+//!
+//! This module provides the implementation of the Account Exporter Actor.
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Seek, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, bail};
+use log::debug;
+use rust_decimal::Decimal;
+use serde::{ser::SerializeStruct, Serialize};
+
+use super::{run_actor, Actor};
+use crate::{
+    model::{Account, DisputeSummary},
+    service::AccountService,
+    Result,
+};
+
+/// Default maximum number of accounts sorted in memory at once.
+const SORT_RUN_SIZE: usize = 1_000_000;
+
+/// Number of sorted runs spilled so far, to name their files uniquely.
+static SORT_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Order of the exported accounts. Accounts with equal keys are ordered by
+/// client identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// By increasing client identifier.
+    Client,
+
+    /// By decreasing total funds, the largest accounts first.
+    TotalDesc,
+
+    /// By decreasing held funds.
+    HeldDesc,
+}
+
+impl SortKey {
+    /// Compare two accounts according to this key.
+    fn compare(&self, left: &Account, right: &Account) -> Ordering {
+        let ordering = match self {
+            Self::Client => Ordering::Equal,
+            Self::TotalDesc => right.total.cmp(&left.total),
+            Self::HeldDesc => right.held.cmp(&left.held),
+        };
+
+        ordering.then(left.client_id.cmp(&right.client_id))
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "client" => Ok(Self::Client),
+            "total_desc" => Ok(Self::TotalDesc),
+            "held_desc" => Ok(Self::HeldDesc),
+            _ => bail!(
+                "Unknown sort key '{value}' (expected 'client', 'total_desc' or 'held_desc')."
+            ),
+        }
+    }
+}
+
+/// An exported account with its dispute columns.
+struct AccountWithDisputes<'a>(&'a Account, DisputeSummary);
+
+impl Serialize for AccountWithDisputes<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let Self(account, disputes) = self;
+        let mut state = serializer.serialize_struct("Account", 7)?;
+        state.serialize_field("client", &account.client_id)?;
+        state.serialize_field("available", &account.available.round_dp(4).normalize())?;
+        state.serialize_field("held", &account.held.round_dp(4).normalize())?;
+        state.serialize_field("total", &account.total.round_dp(4).normalize())?;
+        state.serialize_field("locked", &account.locked)?;
+        state.serialize_field("disputed_count", &disputes.count)?;
+        state.serialize_field("disputed_sum", &disputes.sum.round_dp(4).normalize())?;
+
+        state.end()
+    }
+}
+
+/// The account exporter actor.
+pub struct AccountExporter {
+    /// The account manager service.
+    account_manager: Arc<dyn AccountService>,
+
+    /// A Write interface to export the CSV to
+    writer: Box<dyn Write + Sync + Send>,
+
+    /// Add the `disputed_count` and `disputed_sum` columns.
+    dispute_columns: bool,
+
+    /// The order of the accounts, the storage order if not set.
+    sort_by: Option<SortKey>,
+
+    /// The maximum number of accounts sorted in memory at once.
+    sort_run_size: usize,
+}
+
+impl AccountExporter {
+    /// Create a new account exporter actor.
+    pub fn new(
+        account_manager: Arc<dyn AccountService>,
+        writer: Box<dyn Write + Sync + Send>,
+    ) -> Self {
+        Self {
+            account_manager,
+            writer,
+            dispute_columns: false,
+            sort_by: None,
+            sort_run_size: SORT_RUN_SIZE,
+        }
+    }
+
+    /// Add the `disputed_count` and `disputed_sum` columns telling how many
+    /// disputes are open on each account and the amount they sum up to.
+    pub fn with_dispute_columns(mut self, dispute_columns: bool) -> Self {
+        self.dispute_columns = dispute_columns;
+
+        self
+    }
+
+    /// Export the accounts in the order of the given key instead of the
+    /// storage order.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::actor::{AccountExporter, SortKey};
+    /// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    ///
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// for (client_id, amount) in [(1, Decimal::ONE), (2, Decimal::TEN)] {
+    ///     let kind = TransactionKind::Deposit(amount);
+    ///     manager.process_order(TransactionOrder { tx_id: client_id.into(), client_id, kind, timestamp: None, currency: None }).unwrap();
+    /// }
+    /// AccountExporter::new(manager, Box::new(std::io::sink()))
+    ///     .with_sort_by("total_desc".parse::<SortKey>().unwrap())
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn with_sort_by(mut self, sort_by: SortKey) -> Self {
+        self.sort_by = Some(sort_by);
+
+        self
+    }
+
+    /// Set the maximum number of accounts sorted in memory at once, one
+    /// million by default. Beyond it, sorted runs of accounts are written to
+    /// temporary files and merged while exporting.
+    pub fn with_sort_run_size(mut self, sort_run_size: usize) -> Self {
+        self.sort_run_size = sort_run_size.max(1);
+
+        self
+    }
+
+    /// Run the account exporter actor.
+    /// The actor will export the accounts to a CSV file.
+    pub fn run(self) -> Result<()> {
+        run_actor(self)
+    }
+
+    /// Write the accounts. To be sorted, they are read by chunks of the size
+    /// of the sorted runs.
+    fn export(self) -> Result<()> {
+        let accounts: Box<dyn Iterator<Item = Result<Account>>> = match self.sort_by {
+            None => Box::new(self.account_manager.get_accounts().into_iter().map(Ok)),
+            Some(key) => sort_accounts(
+                read_chunks(self.account_manager.as_ref(), self.sort_run_size),
+                key,
+                self.sort_run_size,
+            )?,
+        };
+
+        let mut writer = csv::Writer::from_writer(self.writer);
+        if self.dispute_columns {
+            let summaries = self.account_manager.get_dispute_summaries();
+            for account in accounts {
+                let account = account?;
+                let disputes = summaries
+                    .get(&account.client_id)
+                    .copied()
+                    .unwrap_or_default();
+                writer.serialize(AccountWithDisputes(&account, disputes))?;
+            }
+        } else {
+            for account in accounts {
+                writer.serialize(account?)?;
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Actor for AccountExporter {
+    type Message = ();
+    type Output = ();
+
+    const NAME: &'static str = "Account Exporter";
+
+    fn on_stop(self) -> Result<()> {
+        self.export()
+    }
+}
+
+/// Read the accounts of the given service by chunks of the given size, by
+/// increasing client identifier.
+fn read_chunks(
+    account_manager: &dyn AccountService,
+    chunk_size: usize,
+) -> impl Iterator<Item = Account> + '_ {
+    let mut after = None;
+
+    std::iter::from_fn(move || {
+        let chunk = account_manager.get_accounts_after(after, chunk_size);
+        after = Some(chunk.last()?.client_id);
+
+        Some(chunk)
+    })
+    .flatten()
+}
+
+/// Sort the accounts by the given key. When there are more than `run_size`
+/// accounts, they are sorted by runs written to temporary files and the runs
+/// are merged while iterating (external merge sort).
+fn sort_accounts(
+    accounts: impl IntoIterator<Item = Account>,
+    key: SortKey,
+    run_size: usize,
+) -> Result<Box<dyn Iterator<Item = Result<Account>>>> {
+    let mut accounts = accounts.into_iter();
+    let mut runs = Vec::new();
+
+    loop {
+        let mut run: Vec<Account> = accounts.by_ref().take(run_size).collect();
+        run.sort_by(|left, right| key.compare(left, right));
+
+        if runs.is_empty() && run.len() < run_size {
+            return Ok(Box::new(run.into_iter().map(Ok)));
+        }
+        if run.is_empty() {
+            break;
+        }
+        runs.push(SortRun::write(&run)?);
+    }
+    debug!("Merging {} sorted runs of accounts", runs.len());
+
+    RunMerger::new(runs, key).map(|merger| Box::new(merger) as Box<_>)
+}
+
+/// A sorted run of accounts written to a temporary file, removed on drop.
+struct SortRun {
+    /// The path of the file.
+    path: PathBuf,
+
+    /// The records of the file.
+    records: csv::StringRecordsIntoIter<BufReader<File>>,
+}
+
+impl SortRun {
+    /// Write the given sorted accounts to a new temporary file. The amounts
+    /// are written unrounded. The file is created by this run only, the
+    /// names already taken in the temporary directory are skipped, and read
+    /// back through the same handle.
+    fn write(accounts: &[Account]) -> Result<Self> {
+        let (path, mut file) = loop {
+            let path = std::env::temp_dir().join(format!(
+                "csv_reader_sort_{}_{}.csv",
+                std::process::id(),
+                SORT_RUNS.fetch_add(1, AtomicOrdering::Relaxed)
+            ));
+            match OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => break (path, file),
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error.into()),
+            }
+        };
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(BufWriter::new(file.try_clone()?));
+        for account in accounts {
+            writer.write_record([
+                account.client_id.to_string(),
+                account.available.to_string(),
+                account.held.to_string(),
+                account.total.to_string(),
+                account.locked.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        drop(writer);
+        file.rewind()?;
+        let records = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(BufReader::new(file))
+            .into_records();
+
+        Ok(Self { path, records })
+    }
+
+    /// Read the next account of the run.
+    fn next_account(&mut self) -> Result<Option<Account>> {
+        let Some(record) = self.records.next().transpose()? else {
+            return Ok(None);
+        };
+        let field = |position: usize| {
+            record
+                .get(position)
+                .ok_or_else(|| anyhow!("Truncated sorted run record: {record:?}"))
+        };
+        let decimal = |position| -> Result<Decimal> { Ok(field(position)?.parse()?) };
+
+        Ok(Some(Account {
+            client_id: field(0)?.parse()?,
+            available: decimal(1)?,
+            held: decimal(2)?,
+            total: decimal(3)?,
+            locked: field(4)?.parse()?,
+            ..Default::default()
+        }))
+    }
+}
+
+impl Drop for SortRun {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The next account of a sorted run, ordered for the merge heap.
+struct HeapEntry {
+    /// The account.
+    account: Account,
+
+    /// The index of its run.
+    run: usize,
+
+    /// The sort key.
+    key: SortKey,
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed as the heap pops the greatest entry first
+        self.key.compare(&other.account, &self.account)
+    }
+}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+/// Iterator merging sorted runs of accounts.
+struct RunMerger {
+    /// The sorted runs.
+    runs: Vec<SortRun>,
+
+    /// The next account of every run not exhausted.
+    heap: BinaryHeap<HeapEntry>,
+
+    /// The sort key.
+    key: SortKey,
+}
+
+impl RunMerger {
+    /// Merge the given runs sorted by the given key.
+    fn new(runs: Vec<SortRun>, key: SortKey) -> Result<Self> {
+        let mut merger = Self {
+            runs,
+            heap: BinaryHeap::new(),
+            key,
+        };
+        for run in 0..merger.runs.len() {
+            merger.refill(run)?;
+        }
+
+        Ok(merger)
+    }
+
+    /// Push the next account of the given run in the heap.
+    fn refill(&mut self, run: usize) -> Result<()> {
+        if let Some(account) = self.runs[run].next_account()? {
+            self.heap.push(HeapEntry {
+                account,
+                run,
+                key: self.key,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for RunMerger {
+    type Item = Result<Account>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let HeapEntry { account, run, .. } = self.heap.pop()?;
+
+        Some(self.refill(run).map(|_| account))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{
+        adapter::InMemoryAccountStorage,
+        model::{TransactionKind, TransactionOrder},
+        service::AccountManager,
+        test_utils::SharedBuffer,
+    };
+
+    #[test]
+    fn test_account_exporter_actor() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        account_manager
+            .process_order(TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        let writer = Cursor::new(Vec::new());
+        let account_exporter = AccountExporter::new(account_manager, Box::new(writer));
+
+        account_exporter.run().unwrap();
+    }
+
+    #[test]
+    fn test_dispute_columns() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        for (tx_id, kind) in [
+            (1, TransactionKind::Deposit(Decimal::ONE_HUNDRED)),
+            (2, TransactionKind::Deposit(Decimal::TEN)),
+            (3, TransactionKind::Dispute(1)),
+        ] {
+            account_manager
+                .process_order(TransactionOrder {
+                    tx_id,
+                    client_id: 1,
+                    kind,
+                    timestamp: None,
+                    currency: None,
+                })
+                .unwrap();
+        }
+        let buffer = SharedBuffer::default();
+        AccountExporter::new(account_manager, Box::new(buffer.clone()))
+            .with_dispute_columns(true)
+            .run()
+            .unwrap();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert_eq!(
+            output,
+            "client,available,held,total,locked,disputed_count,disputed_sum\n1,10,100,110,false,1,100\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let amounts = [(1, 5), (2, 50), (3, 5), (4, 7), (5, 1)];
+        for (client_id, amount) in amounts {
+            account_manager
+                .process_order(TransactionOrder {
+                    tx_id: client_id,
+                    client_id,
+                    kind: TransactionKind::Deposit(Decimal::new(amount, 1)),
+                    timestamp: None,
+                    currency: None,
+                })
+                .unwrap();
+        }
+        let export = |sort_by: &str, run_size| {
+            let buffer = SharedBuffer::default();
+            AccountExporter::new(account_manager.clone(), Box::new(buffer.clone()))
+                .with_sort_by(sort_by.parse().unwrap())
+                .with_sort_run_size(run_size)
+                .run()
+                .unwrap();
+            let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+            output
+                .lines()
+                .skip(1)
+                .map(|line| line.split(',').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // sorted in memory or by merging runs spilled to files
+        for run_size in [10, 2, 1] {
+            assert_eq!(export("client", run_size), ["1", "2", "3", "4", "5"]);
+            assert_eq!(export("total_desc", run_size), ["2", "4", "1", "3", "5"]);
+            assert_eq!(export("held_desc", run_size), ["1", "2", "3", "4", "5"]);
+        }
+        assert!("total".parse::<SortKey>().is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! HTTP server actor
+//!
+//! The HTTP server actor exposes a `POST /orders` endpoint accepting one
+//! transaction record or an array of records as JSON. Each valid record is
+//! enqueued for the accountant actor and the response tells, record by record,
+//! whether it was accepted or rejected. Accepted orders are processed
+//! asynchronously by the accountant and may still fail there.
+//!
+//! Given the account manager, it also serves its storage statistics as JSON
+//! on `GET /metrics`.
+
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use log::debug;
+use serde::Serialize;
+use serde_json::Value;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::Sender;
+use crate::model::{CSVTransactionEntity, TransactionOrder};
+use crate::service::AccountManager;
+
+/// Outcome of a record posted to the orders endpoint.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum OrderStatus {
+    /// The order has been enqueued for the accountant.
+    Accepted {
+        /// Position of the record in the request.
+        index: usize,
+    },
+
+    /// The record could not be turned into an order.
+    Rejected {
+        /// Position of the record in the request.
+        index: usize,
+
+        /// Why the record was rejected.
+        error: String,
+    },
+}
+
+/// HTTP server actor.
+pub struct HttpServer {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The bound HTTP server.
+    server: Server,
+
+    /// Stop serving after this number of requests.
+    max_requests: Option<usize>,
+
+    /// The account manager whose statistics are served, if any.
+    account_manager: Option<Arc<AccountManager>>,
+}
+
+impl HttpServer {
+    /// Create a new HTTP server actor bound to the given address.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        order_sender: Sender<TransactionOrder>,
+    ) -> crate::Result<Self> {
+        Ok(Self {
+            order_sender,
+            server: Server::http(address).map_err(|e| anyhow!(e))?,
+            max_requests: None,
+            account_manager: None,
+        })
+    }
+
+    /// Serve the storage statistics of the given account manager on
+    /// `GET /metrics`, see [AccountManager::stats].
+    pub fn with_account_manager(mut self, account_manager: Arc<AccountManager>) -> Self {
+        self.account_manager = Some(account_manager);
+
+        self
+    }
+
+    /// Stop serving once the given number of requests has been handled.
+    pub fn with_max_requests(mut self, max_requests: usize) -> Self {
+        self.max_requests = Some(max_requests);
+
+        self
+    }
+
+    /// Get the address the server is bound to.
+    pub fn local_addr(&self) -> crate::Result<SocketAddr> {
+        self.server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| anyhow!("The HTTP server is not bound to an IP address."))
+    }
+
+    /// Run the HTTP server actor.
+    /// The actor stops with an error if the accountant is not running anymore.
+    pub fn run(self) -> crate::Result<()> {
+        debug!("HTTP Server Actor started on {}", self.local_addr()?);
+        let requests = self
+            .server
+            .incoming_requests()
+            .take(self.max_requests.unwrap_or(usize::MAX));
+
+        for request in requests {
+            self.handle(request)?;
+        }
+        debug!("HTTP Server Actor stopped");
+
+        Ok(())
+    }
+
+    fn handle(&self, mut request: Request) -> crate::Result<()> {
+        debug!("HTTP Server Actor: {} {}", request.method(), request.url());
+        let (status_code, body) = match (request.method(), request.url()) {
+            (Method::Post, "/orders") => {
+                let mut payload = Vec::new();
+                request.as_reader().read_to_end(&mut payload)?;
+
+                match self.enqueue(&payload) {
+                    Ok(statuses) => (200, serde_json::to_string(&statuses)?),
+                    Err(error) => (400, error_body(&error.to_string())),
+                }
+            }
+            (_, "/orders") => (405, error_body("Method not allowed.")),
+            (method, "/metrics") if self.account_manager.is_some() => match method {
+                Method::Get => (200, self.metrics()?),
+                _ => (405, error_body("Method not allowed.")),
+            },
+            _ => (404, error_body("Not found.")),
+        };
+        let content_type = Header::from_bytes("Content-Type", "application/json")
+            .expect("Static header is valid.");
+        let response = Response::from_string(body)
+            .with_status_code(status_code)
+            .with_header(content_type);
+
+        if let Err(error) = request.respond(response) {
+            log::info!("Error sending HTTP response: {}", error);
+        }
+
+        Ok(())
+    }
+
+    /// Get the storage statistics of the account manager as JSON.
+    fn metrics(&self) -> crate::Result<String> {
+        let stats = self
+            .account_manager
+            .as_ref()
+            .map(|account_manager| account_manager.stats())
+            .unwrap_or_default();
+
+        Ok(serde_json::to_string(&stats)?)
+    }
+
+    /// Parse the posted records and send the valid orders to the accountant.
+    /// Fails if the payload is not a JSON object or array, or if the
+    /// accountant is not running anymore.
+    fn enqueue(&self, payload: &[u8]) -> crate::Result<Vec<OrderStatus>> {
+        let records = match serde_json::from_slice(payload)? {
+            Value::Array(records) => records,
+            record @ Value::Object(_) => vec![record],
+            _ => return Err(anyhow!("Expected a JSON object or array.")),
+        };
+        let mut statuses = Vec::with_capacity(records.len());
+
+        for (index, record) in records.into_iter().enumerate() {
+            let order = serde_json::from_value::<CSVTransactionEntity>(record)
+                .map_err(anyhow::Error::from)
+                .and_then(|record| Ok(TransactionOrder::try_from(record)?));
+            let status = match order {
+                Err(error) => OrderStatus::Rejected {
+                    index,
+                    error: error.to_string(),
+                },
+                Ok(order) => {
+                    self.order_sender.send(order)?;
+                    OrderStatus::Accepted { index }
+                }
+            };
+            statuses.push(status);
+        }
+
+        Ok(statuses)
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::{
+        actor::{channel, Receiver},
+        adapter::InMemoryAccountStorage,
+        model::TransactionKind,
+    };
+
+    fn server() -> (HttpServer, Receiver<TransactionOrder>) {
+        let (tx, rx) = channel();
+
+        (HttpServer::bind("127.0.0.1:0", tx).unwrap(), rx)
+    }
+
+    fn post(address: SocketAddr, path: &str, body: &str) -> String {
+        request(address, "POST", path, body)
+    }
+
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        response
+    }
+
+    #[test]
+    fn test_enqueue() {
+        let (server, orders) = server();
+        let statuses = server
+            .enqueue(
+                br#"[
+                    {"type": "deposit", "client": 1, "tx": 1, "amount": "2.5"},
+                    {"type": "whatever", "client": 1, "tx": 2, "amount": "1.0"},
+                    {"type": "dispute", "client": 1, "tx": 1}
+                ]"#,
+            )
+            .unwrap();
+        drop(server);
+        let orders: Vec<TransactionOrder> = orders.iter().collect();
+
+        assert!(matches!(statuses[0], OrderStatus::Accepted { index: 0 }));
+        assert!(matches!(
+            statuses[1],
+            OrderStatus::Rejected { index: 1, .. }
+        ));
+        assert!(matches!(statuses[2], OrderStatus::Accepted { index: 2 }));
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(2.5)));
+    }
+
+    #[test]
+    fn test_enqueue_single_record() {
+        let (server, _orders) = server();
+        let statuses = server
+            .enqueue(br#"{"type": "withdrawal", "client": 1, "tx": 1, "amount": 1}"#)
+            .unwrap();
+
+        assert_eq!(statuses, vec![OrderStatus::Accepted { index: 0 }]);
+        assert!(server.enqueue(b"deposit, 1, 1, 1.0").is_err());
+        assert!(server.enqueue(b"42").is_err());
+    }
+
+    #[test]
+    fn test_requests() {
+        let (server, orders) = server();
+        let server = server.with_max_requests(3);
+        let address = server.local_addr().unwrap();
+        let handler = std::thread::spawn(move || server.run());
+
+        let response = post(
+            address,
+            "/orders",
+            r#"[{"type": "deposit", "client": 1, "tx": 1, "amount": "1.0"}, {"type": "deposit"}]"#,
+        );
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(
+            r#"[{"status":"accepted","index":0},{"status":"rejected","index":1,"error":"missing field `client`"}]"#
+        ));
+        assert!(post(address, "/orders", "not json").starts_with("HTTP/1.1 400"));
+        assert!(post(address, "/accounts", "").starts_with("HTTP/1.1 404"));
+
+        handler.join().unwrap().unwrap();
+        assert_eq!(orders.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let account_manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        account_manager
+            .process_order(TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(dec!(1)),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        let (without_manager, _orders) = server();
+        let address = without_manager.local_addr().unwrap();
+        let handler = std::thread::spawn(move || without_manager.with_max_requests(1).run());
+        assert!(request(address, "GET", "/metrics", "").starts_with("HTTP/1.1 404"));
+        handler.join().unwrap().unwrap();
+
+        let (with_manager, _orders) = server();
+        let server = with_manager
+            .with_account_manager(account_manager)
+            .with_max_requests(2);
+        let address = server.local_addr().unwrap();
+        let handler = std::thread::spawn(move || server.run());
+
+        let response = request(address, "GET", "/metrics", "");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let metrics: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(metrics["accounts"], 1);
+        assert_eq!(metrics["transactions"], 1);
+        assert_eq!(metrics["open_disputes"], 0);
+        assert!(post(address, "/metrics", "").starts_with("HTTP/1.1 405"));
+
+        handler.join().unwrap().unwrap();
+    }
+}
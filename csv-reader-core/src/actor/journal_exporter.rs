@@ -0,0 +1,90 @@
+//! # Journal Exporter Actor
+//!
+//! This module provides the implementation of the Journal Exporter Actor that
+//! dumps the double-entry journal of the applied transactions.
+
+use std::{io::Write, sync::Arc};
+
+use log::debug;
+
+use crate::{service::AccountManager, Result};
+
+/// The journal exporter actor.
+pub struct JournalExporter {
+    /// The account manager service.
+    account_manager: Arc<AccountManager>,
+
+    /// A Write interface to export the CSV to
+    writer: Box<dyn Write + Sync + Send>,
+}
+
+impl JournalExporter {
+    /// Create a new journal exporter actor.
+    pub fn new(account_manager: Arc<AccountManager>, writer: Box<dyn Write + Sync + Send>) -> Self {
+        Self {
+            account_manager,
+            writer,
+        }
+    }
+
+    /// Run the journal exporter actor.
+    /// The actor will export the journal entries to a CSV file.
+    pub fn run(self) -> Result<()> {
+        debug!("Journal Exporter Actor started");
+
+        let mut writer = csv::Writer::from_writer(self.writer);
+        for entry in self.account_manager.get_journal() {
+            writer.serialize(entry)?;
+        }
+
+        writer.flush()?;
+
+        debug!("Journal Exporter Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{
+        adapter::InMemoryAccountStorage,
+        model::{TransactionKind, TransactionOrder},
+        service::AccountManagerOptions,
+        test_utils::SharedBuffer,
+    };
+
+    #[test]
+    fn test_journal_exporter_actor() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            ..Default::default()
+        };
+        let account_manager = Arc::new(AccountManager::with_options(
+            InMemoryAccountStorage::default(),
+            options,
+        ));
+        account_manager
+            .process_order(TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        let buffer = SharedBuffer::default();
+        let journal_exporter = JournalExporter::new(account_manager, Box::new(buffer.clone()));
+
+        journal_exporter.run().unwrap();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert_eq!(
+            output,
+            "tx,debit,credit,amount\n1,omnibus,client:1:available,100\n"
+        );
+    }
+}
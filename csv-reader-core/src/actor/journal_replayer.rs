@@ -0,0 +1,117 @@
+//! # Journal Replayer Actor
+//!
+//! This module provides the implementation of the Journal Replayer Actor that
+//! rebuilds the state of the accounts from a journal of applied transactions,
+//! as streamed by the [AppliedTransactionSink](super::AppliedTransactionSink).
+//! Every transaction of the journal was applied once, so all of them must be
+//! applied again: the replay stops at the first one rejected, the journal not
+//! matching the state it is replayed onto.
+
+use std::{io::Read, sync::Arc};
+
+use anyhow::Context;
+use log::debug;
+
+use super::{Orders, ReaderOptions};
+use crate::{service::AccountService, Result};
+
+/// The journal replayer actor.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::actor::JournalReplayer;
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let journal = "type,client,tx,amount,timestamp,currency\ndeposit,1,1,10,,\nwithdrawal,1,2,4,,\n";
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let replayed = JournalReplayer::new(manager.clone(), Box::new(journal.as_bytes()))
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(replayed, 2);
+/// assert_eq!(manager.get_account(1).unwrap().available, Decimal::from(6));
+/// ```
+pub struct JournalReplayer {
+    /// The account manager service the journal is replayed onto, usually a
+    /// fresh one with the options of the run that produced the journal.
+    account_manager: Arc<dyn AccountService>,
+
+    /// The journal to replay.
+    reader: Box<dyn Read + Sync + Send>,
+}
+
+impl JournalReplayer {
+    /// Create a new journal replayer actor.
+    pub fn new(
+        account_manager: Arc<dyn AccountService>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self {
+            account_manager,
+            reader,
+        }
+    }
+
+    /// Run the journal replayer actor.
+    /// The actor applies the transactions of the journal in turn and returns
+    /// their number. It fails on the first record that cannot be read or
+    /// transaction that cannot be applied, the ones before staying applied.
+    pub fn run(self) -> Result<u64> {
+        debug!("Journal Replayer Actor started");
+        let options = ReaderOptions {
+            strict: true,
+            ..ReaderOptions::default()
+        };
+        let mut replayed = 0;
+
+        for order in Orders::new(self.reader, options) {
+            let order = order.context("The journal cannot be read")?;
+            let tx_id = order.tx_id;
+            self.account_manager.process_order(order).with_context(|| {
+                format!("The journal transaction tx={tx_id} cannot be replayed")
+            })?;
+            replayed += 1;
+        }
+        debug!("Journal Replayer Actor stopped");
+
+        Ok(replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{adapter::InMemoryAccountStorage, service::AccountManager};
+
+    #[test]
+    fn test_replay_failures() {
+        let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let journal = "type,client,tx,amount\n\
+            deposit,1,1,10\n\
+            deposit,1,2,5\n\
+            withdrawal,1,3,20\n\
+            deposit,1,4,1\n";
+        let error = JournalReplayer::new(manager.clone(), Box::new(journal.as_bytes()))
+            .run()
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The journal transaction tx=3 cannot be replayed"
+        );
+        // the transactions before the failure stay applied
+        assert_eq!(manager.get_account(1).unwrap().available, Decimal::from(15));
+
+        let journal = "type,client,tx,amount\ndeposit,1,5,10\nwhatever,1,6,1\n";
+        let error = JournalReplayer::new(manager, Box::new(journal.as_bytes()))
+            .run()
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "The journal cannot be read");
+    }
+}
@@ -0,0 +1,272 @@
+//! Kafka reader actor
+//!
+//! The Kafka reader actor consumes transaction records from a Kafka topic and
+//! sends the transaction orders to the accountant actor through a channel. The
+//! consumed offsets are committed only once the accountant acknowledged every
+//! order of the consumed message sets (see [crate::actor::Accountant::with_ack_sender]).
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use csv::{ReaderBuilder, StringRecord};
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use log::debug;
+use serde::Deserialize;
+
+use super::{reader::FIELD_NAMES, Receiver, Sender};
+use crate::model::{CSVTransactionEntity, TransactionOrder};
+
+/// Format of the Kafka message payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// One or several CSV lines without header, the columns being `type`,
+    /// `client`, `tx` and `amount`.
+    #[default]
+    Csv,
+
+    /// A JSON object with the `type`, `client`, `tx` and `amount` fields or an
+    /// array of such objects.
+    Json,
+}
+
+impl FromStr for PayloadFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => bail!("Unknown payload format '{value}' (expected 'csv' or 'json')."),
+        }
+    }
+}
+
+/// A JSON payload holds one or many records.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPayload {
+    One(CSVTransactionEntity),
+    Many(Vec<CSVTransactionEntity>),
+}
+
+impl PayloadFormat {
+    /// Decode the records of a message payload.
+    ///
+    /// ```
+    /// use csv_reader_core::actor::PayloadFormat;
+    ///
+    /// let records = PayloadFormat::Csv
+    ///     .decode(b"deposit, 1, 1, 1.0\ndispute, 1, 1")
+    ///     .unwrap();
+    /// assert_eq!(records.len(), 2);
+    ///
+    /// let records = PayloadFormat::Json
+    ///     .decode(br#"{"type": "deposit", "client": 1, "tx": 1, "amount": "1.0"}"#)
+    ///     .unwrap();
+    /// assert_eq!(records.len(), 1);
+    /// ```
+    pub fn decode(&self, payload: &[u8]) -> crate::Result<Vec<CSVTransactionEntity>> {
+        match self {
+            Self::Csv => {
+                let headers = StringRecord::from(FIELD_NAMES.to_vec());
+                let mut csv_reader = ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .trim(csv::Trim::All)
+                    .from_reader(payload);
+
+                csv_reader
+                    .records()
+                    .map(|record| Ok(record?.deserialize(Some(&headers))?))
+                    .collect()
+            }
+            Self::Json => match serde_json::from_slice(payload)? {
+                JsonPayload::One(record) => Ok(vec![record]),
+                JsonPayload::Many(records) => Ok(records),
+            },
+        }
+    }
+}
+
+/// Options of the Kafka consumer.
+#[derive(Debug, Clone)]
+pub struct KafkaReaderOptions {
+    /// The bootstrap brokers (e.g. `localhost:9092`).
+    pub hosts: Vec<String>,
+
+    /// The topic to consume.
+    pub topic: String,
+
+    /// The consumer group the offsets are committed for.
+    pub group: String,
+
+    /// The format of the message payloads.
+    pub payload_format: PayloadFormat,
+}
+
+/// Kafka reader actor.
+pub struct KafkaReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The acknowledgement channel receiver, one acknowledgement is expected
+    /// per order sent.
+    ack_receiver: Receiver<()>,
+
+    /// The consumer options.
+    options: KafkaReaderOptions,
+}
+
+impl KafkaReader {
+    /// Create a new Kafka reader actor. The `ack_receiver` must be the
+    /// counterpart of the channel given to the accountant with
+    /// [crate::actor::Accountant::with_ack_sender].
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        ack_receiver: Receiver<()>,
+        options: KafkaReaderOptions,
+    ) -> Self {
+        Self {
+            order_sender,
+            ack_receiver,
+            options,
+        }
+    }
+
+    /// Run the Kafka reader actor.
+    /// The actor polls the topic continuously and stops with an error if the
+    /// broker cannot be reached or if the accountant is not running anymore.
+    pub fn run(self) -> crate::Result<()> {
+        debug!("Kafka Reader Actor started");
+        let mut consumer = Consumer::from_hosts(self.options.hosts.clone())
+            .with_topic(self.options.topic.clone())
+            .with_group(self.options.group.clone())
+            .with_fallback_offset(FetchOffset::Earliest)
+            .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+            .create()?;
+
+        loop {
+            let message_sets = consumer.poll()?;
+            let mut sent_orders = 0;
+
+            for message_set in message_sets.iter() {
+                for message in message_set.messages() {
+                    sent_orders += self.send_payload(message.value)?;
+                }
+                consumer.consume_messageset(message_set)?;
+            }
+
+            // Wait for the accountant to process the orders before committing.
+            for _ in 0..sent_orders {
+                self.ack_receiver
+                    .recv()
+                    .map_err(|_| anyhow!("The accountant stopped acknowledging orders."))?;
+            }
+            consumer.commit_consumed()?;
+        }
+    }
+
+    /// Decode a message payload and send its orders, return the number of
+    /// orders sent. Invalid records are logged and skipped.
+    fn send_payload(&self, payload: &[u8]) -> crate::Result<usize> {
+        let records = match self.options.payload_format.decode(payload) {
+            Err(error) => {
+                log::info!("Error reading Kafka message: {}", error);
+                return Ok(0);
+            }
+            Ok(records) => records,
+        };
+        let mut sent_orders = 0;
+
+        for record in records {
+            let order = match TransactionOrder::try_from(record) {
+                Err(error) => {
+                    log::info!("Error parsing Kafka record: {}", error);
+                    continue;
+                }
+                Ok(order) => order,
+            };
+            self.order_sender.send(order)?;
+            sent_orders += 1;
+        }
+
+        Ok(sent_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::model::TransactionKind;
+
+    fn reader(payload_format: PayloadFormat) -> (KafkaReader, Receiver<TransactionOrder>) {
+        let (order_sender, order_receiver) = channel();
+        let (_ack_sender, ack_receiver) = channel();
+        let options = KafkaReaderOptions {
+            hosts: vec!["localhost:9092".to_string()],
+            topic: "transactions".to_string(),
+            group: "csv_reader".to_string(),
+            payload_format,
+        };
+
+        (
+            KafkaReader::new(order_sender, ack_receiver, options),
+            order_receiver,
+        )
+    }
+
+    #[test]
+    fn test_csv_payload() {
+        let (reader, orders) = reader(PayloadFormat::Csv);
+        let sent = reader
+            .send_payload(b"deposit, 1, 1, 1.5\nwhatever, 1, 2, 1.0\ndispute, 1, 1,")
+            .unwrap();
+        drop(reader);
+        let orders: Vec<TransactionOrder> = orders.iter().collect();
+
+        assert_eq!(sent, 2);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(1.5)));
+        assert_eq!(orders[1].kind, TransactionKind::Dispute(1));
+    }
+
+    #[test]
+    fn test_json_payload() {
+        let (reader, orders) = reader(PayloadFormat::Json);
+        let sent = reader
+            .send_payload(
+                br#"[
+                    {"type": "deposit", "client": 1, "tx": 1, "amount": "2.5"},
+                    {"type": "withdrawal", "client": 1, "tx": 2, "amount": 1.25},
+                    {"type": "resolve", "client": 1, "tx": 1}
+                ]"#,
+            )
+            .unwrap();
+        drop(reader);
+        let orders: Vec<TransactionOrder> = orders.iter().collect();
+
+        assert_eq!(sent, 3);
+        assert_eq!(orders[1].kind, TransactionKind::Withdrawal(dec!(1.25)));
+        assert_eq!(orders[2].kind, TransactionKind::Resolve(1));
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let (reader, _orders) = reader(PayloadFormat::Json);
+
+        assert_eq!(reader.send_payload(b"deposit, 1, 1, 1.0").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_payload_format_from_str() {
+        assert_eq!("CSV".parse::<PayloadFormat>().unwrap(), PayloadFormat::Csv);
+        assert_eq!(
+            "json".parse::<PayloadFormat>().unwrap(),
+            PayloadFormat::Json
+        );
+        assert!("avro".parse::<PayloadFormat>().is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! # Actor Lifecycle
+//!
+//! This module provides the [Actor] trait shared by the actors and the
+//! functions running them, so the actors are spawned and stopped uniformly:
+//! an actor is started, handles the messages of its [Mailbox] until it is
+//! closed, then stopped, returning its output.
+
+use std::thread::JoinHandle;
+
+use log::debug;
+
+use super::{channel, Receiver};
+use crate::Result;
+
+/// An actor of the pipeline.
+///
+/// ```
+///
+/// use csv_reader_core::actor::{channel, spawn_actor, Actor, Mailbox};
+///
+/// /// Sum the numbers received.
+/// struct Adder {
+///     mailbox: Mailbox<u64>,
+///     sum: u64,
+/// }
+///
+/// impl Actor for Adder {
+///     type Message = u64;
+///     type Output = u64;
+///
+///     const NAME: &'static str = "Adder";
+///
+///     fn receive(&mut self) -> Option<u64> {
+///         self.mailbox.recv()
+///     }
+///
+///     fn handle(&mut self, number: u64) -> csv_reader_core::Result<()> {
+///         self.sum += number;
+///
+///         Ok(())
+///     }
+///
+///     fn on_stop(self) -> csv_reader_core::Result<u64> {
+///         Ok(self.sum)
+///     }
+/// }
+///
+/// let (sender, receiver) = channel();
+/// let adder = spawn_actor(Adder { mailbox: Mailbox::new(receiver), sum: 0 }).unwrap();
+/// for number in 1..=10 {
+///     sender.send(number).unwrap();
+/// }
+/// drop(sender);
+///
+/// assert_eq!(adder.join().unwrap().unwrap(), 55);
+/// ```
+pub trait Actor: Send + Sized + 'static {
+    /// The messages handled by the actor.
+    type Message;
+
+    /// What the actor returns once stopped.
+    type Output: Send + 'static;
+
+    /// The name of the actor, for its thread and its logs.
+    const NAME: &'static str;
+
+    /// Wait for the next message, `None` once the mailbox is closed. The
+    /// actors without mailbox, producing their own input, have no message.
+    fn receive(&mut self) -> Option<Self::Message> {
+        None
+    }
+
+    /// Called once before the first message.
+    fn on_start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Handle a message, an error stops the actor.
+    fn handle(&mut self, _message: Self::Message) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once the mailbox is closed, returning the output of the actor.
+    /// The actors without mailbox do their whole work here.
+    fn on_stop(self) -> Result<Self::Output>;
+}
+
+/// The mailbox of an [Actor], receiving its messages from the other actors.
+pub struct Mailbox<M> {
+    /// The channel of the messages.
+    receiver: Receiver<M>,
+}
+
+impl<M> Mailbox<M> {
+    /// Create a mailbox receiving the messages of the given channel.
+    pub fn new(receiver: Receiver<M>) -> Self {
+        Self { receiver }
+    }
+
+    /// Create a mailbox receiving the messages of all the given channels, in
+    /// the order they arrive. Each channel is forwarded by a thread of its
+    /// own, so the messages of a channel keep their order but the ones of
+    /// different channels are interleaved. The mailbox is closed once all the
+    /// channels are.
+    pub fn merge(receivers: Vec<Receiver<M>>) -> Result<Self>
+    where
+        M: Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        for (index, source) in receivers.into_iter().enumerate() {
+            let sender = sender.clone();
+            std::thread::Builder::new()
+                .name(format!("mailbox-fan-in-{index}"))
+                .spawn(move || {
+                    for message in source {
+                        // the mailbox owner is gone, nothing left to forward
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                })?;
+        }
+
+        Ok(Self::new(receiver))
+    }
+
+    /// Wait for the next message, `None` once all the senders are dropped.
+    pub fn recv(&self) -> Option<M> {
+        self.receiver.recv().ok()
+    }
+
+    /// Wait for the next message and take along the ones already waiting, up
+    /// to the given number of messages in total.
+    pub fn recv_batch(&self, max: usize) -> Option<Vec<M>> {
+        let mut batch = vec![self.receiver.recv().ok()?];
+        batch.extend(self.receiver.try_iter().take(max.saturating_sub(1)));
+
+        Some(batch)
+    }
+}
+
+/// Run the given actor in the current thread until it stops and return its
+/// output.
+pub fn run_actor<A: Actor>(mut actor: A) -> Result<A::Output> {
+    debug!("{} Actor started", A::NAME);
+    actor.on_start()?;
+    while let Some(message) = actor.receive() {
+        actor.handle(message)?;
+    }
+    let output = actor.on_stop();
+    debug!("{} Actor stopped", A::NAME);
+
+    output
+}
+
+/// Run the given actor in a thread of its own, named after it.
+pub fn spawn_actor<A: Actor>(actor: A) -> Result<JoinHandle<Result<A::Output>>> {
+    let handle = std::thread::Builder::new()
+        .name(A::NAME.to_lowercase().replace(' ', "-"))
+        .spawn(move || run_actor(actor))?;
+
+    Ok(handle)
+}
@@ -0,0 +1,244 @@
+//! # Metrics Collector Actor
+//!
+//! This module provides the implementation of the Metrics Collector Actor that
+//! consolidates the counters sent by the other actors during a run: the
+//! progress of the reader and the orders applied or rejected by the
+//! accountant, per transaction kind.
+
+use std::fmt::Display;
+
+use super::{reader::KIND_NAMES, run_actor, Actor, Mailbox, ReaderProgress, Receiver};
+use crate::Result;
+
+/// A counter event sent to the [MetricsCollector].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricEvent {
+    /// The progress of the reading so far, replacing the previous one.
+    Read(ReaderProgress),
+
+    /// Orders processed by the accountant since its previous event, by
+    /// transaction kind in the `deposit`, `withdrawal`, `dispute`, `resolve`,
+    /// `chargeback`, `unlock`, `open`, `close` and `adjustment` order.
+    Processed {
+        /// The number of orders applied.
+        applied: [u64; 9],
+
+        /// The number of orders rejected.
+        rejected: [u64; 9],
+    },
+}
+
+/// The orders of a transaction kind processed during a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KindMetrics {
+    /// The transaction kind.
+    pub kind: &'static str,
+
+    /// The number of orders applied.
+    pub applied: u64,
+
+    /// The number of orders rejected.
+    pub rejected: u64,
+}
+
+/// The consolidated counters of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunMetrics {
+    /// The last progress of the reading reported, zero when the reader does
+    /// not report it.
+    pub read: ReaderProgress,
+
+    /// The orders processed by transaction kind.
+    pub kinds: [KindMetrics; 9],
+}
+
+impl Default for RunMetrics {
+    fn default() -> Self {
+        Self {
+            read: ReaderProgress::default(),
+            kinds: KIND_NAMES.map(|kind| KindMetrics {
+                kind,
+                applied: 0,
+                rejected: 0,
+            }),
+        }
+    }
+}
+
+impl RunMetrics {
+    /// Get the number of orders applied.
+    pub fn applied(&self) -> u64 {
+        self.kinds.iter().map(|kind| kind.applied).sum()
+    }
+
+    /// Get the number of orders rejected.
+    pub fn rejected(&self) -> u64 {
+        self.kinds.iter().map(|kind| kind.rejected).sum()
+    }
+
+    /// Account for a counter event.
+    fn record(&mut self, event: MetricEvent) {
+        match event {
+            MetricEvent::Read(progress) => self.read = progress,
+            MetricEvent::Processed { applied, rejected } => {
+                for (index, kind) in self.kinds.iter_mut().enumerate() {
+                    kind.applied += applied[index];
+                    kind.rejected += rejected[index];
+                }
+            }
+        }
+    }
+}
+
+impl Display for RunMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Read: {} bytes, {} records parsed, {} records rejected.",
+            self.read.bytes_read, self.read.records_parsed, self.read.records_rejected
+        )?;
+        writeln!(
+            f,
+            "Orders: {} applied, {} rejected.",
+            self.applied(),
+            self.rejected()
+        )?;
+        for kind in &self.kinds {
+            writeln!(
+                f,
+                "  {}: {} applied, {} rejected.",
+                kind.kind, kind.applied, kind.rejected
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The metrics collector actor.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use csv_reader_core::actor::{channel, Accountant, MetricEvent, MetricsCollector, Reader};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let (metric_sender, metric_receiver) = channel();
+/// let (order_sender, order_receiver) = channel();
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.0\nwithdrawal,1,2,5.0\nwhatever,1,3,1\n";
+/// let collector = std::thread::spawn(move || MetricsCollector::new(metric_receiver).run());
+/// Reader::new(order_sender, Box::new(data.as_bytes()))
+///     .with_progress(Duration::from_secs(1), {
+///         let metric_sender = metric_sender.clone();
+///         move |progress| metric_sender.send(MetricEvent::Read(progress)).unwrap()
+///     })
+///     .run()
+///     .unwrap();
+/// Accountant::new(manager, order_receiver)
+///     .with_metric_sender(metric_sender)
+///     .run()
+///     .unwrap();
+/// let metrics = collector.join().unwrap().unwrap();
+///
+/// assert_eq!(metrics.read.records_rejected, 1);
+/// assert_eq!((metrics.applied(), metrics.rejected()), (1, 1));
+/// assert_eq!(metrics.kinds[1].rejected, 1);
+/// ```
+pub struct MetricsCollector {
+    /// The mailbox receiving the counter events.
+    mailbox: Mailbox<MetricEvent>,
+
+    /// The metrics consolidated so far.
+    metrics: RunMetrics,
+}
+
+impl MetricsCollector {
+    /// Create a new metrics collector actor.
+    pub fn new(metric_receiver: Receiver<MetricEvent>) -> Self {
+        Self {
+            mailbox: Mailbox::new(metric_receiver),
+            metrics: RunMetrics::default(),
+        }
+    }
+
+    /// Run the metrics collector actor.
+    /// The actor consolidates the counter events until the channel is closed,
+    /// once all the actors sending them stopped, and returns the metrics.
+    pub fn run(self) -> Result<RunMetrics> {
+        run_actor(self)
+    }
+}
+
+impl Actor for MetricsCollector {
+    type Message = MetricEvent;
+    type Output = RunMetrics;
+
+    const NAME: &'static str = "Metrics Collector";
+
+    fn receive(&mut self) -> Option<MetricEvent> {
+        self.mailbox.recv()
+    }
+
+    fn handle(&mut self, event: MetricEvent) -> Result<()> {
+        self.metrics.record(event);
+
+        Ok(())
+    }
+
+    fn on_stop(self) -> Result<RunMetrics> {
+        Ok(self.metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+
+    use super::*;
+
+    #[test]
+    fn test_run_metrics() {
+        let (tx, rx) = channel();
+        for records_parsed in [10, 25] {
+            tx.send(MetricEvent::Read(ReaderProgress {
+                bytes_read: records_parsed * 10,
+                records_parsed,
+                records_rejected: 1,
+                resume_offset: 0,
+            }))
+            .unwrap();
+        }
+        tx.send(MetricEvent::Processed {
+            applied: [10, 5, 0, 0, 0, 0, 0, 0, 0],
+            rejected: [0, 2, 1, 0, 0, 0, 0, 0, 0],
+        })
+        .unwrap();
+        tx.send(MetricEvent::Processed {
+            applied: [5, 0, 1, 0, 1, 0, 0, 0, 0],
+            rejected: [0, 0, 0, 0, 0, 0, 0, 0, 0],
+        })
+        .unwrap();
+        drop(tx);
+        let metrics = MetricsCollector::new(rx).run().unwrap();
+
+        assert_eq!(metrics.read.records_parsed, 25);
+        assert_eq!(metrics.applied(), 22);
+        assert_eq!(metrics.rejected(), 3);
+        assert_eq!(
+            metrics.to_string(),
+            "Read: 250 bytes, 25 records parsed, 1 records rejected.\n\
+             Orders: 22 applied, 3 rejected.\n  \
+             deposit: 15 applied, 0 rejected.\n  \
+             withdrawal: 5 applied, 2 rejected.\n  \
+             dispute: 1 applied, 1 rejected.\n  \
+             resolve: 0 applied, 0 rejected.\n  \
+             chargeback: 1 applied, 0 rejected.\n  \
+             unlock: 0 applied, 0 rejected.\n  \
+             open: 0 applied, 0 rejected.\n  \
+             close: 0 applied, 0 rejected.\n  \
+             adjustment: 0 applied, 0 rejected.\n"
+        );
+    }
+}
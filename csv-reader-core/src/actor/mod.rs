@@ -0,0 +1,66 @@
+//! Actor module contains the controlers of the application.
+//!
+//! The actors are controlers. They use services to perform their tasks.
+//! They communicate with other actors through messages.
+
+mod accountant;
+mod applied_transaction_sink;
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "avro")]
+mod avro_reader;
+mod channels;
+mod dead_letter_exporter;
+mod directory_watcher;
+mod error_collector;
+mod exporter;
+#[cfg(feature = "http")]
+mod http_server;
+mod journal_exporter;
+mod journal_replayer;
+#[cfg(feature = "kafka")]
+mod kafka_reader;
+mod lifecycle;
+mod metrics_collector;
+#[cfg(feature = "msgpack")]
+mod msgpack_reader;
+#[cfg(feature = "protobuf")]
+mod protobuf_reader;
+mod quarantine;
+mod reader;
+mod socket_listener;
+#[cfg(feature = "unstable")]
+mod source_reader;
+#[cfg(feature = "xlsx")]
+mod xlsx_reader;
+
+pub use accountant::*;
+pub use applied_transaction_sink::*;
+#[cfg(feature = "async")]
+pub use asynchronous::*;
+#[cfg(feature = "avro")]
+pub use avro_reader::*;
+pub use channels::*;
+pub use dead_letter_exporter::*;
+pub use directory_watcher::*;
+pub use error_collector::*;
+pub use exporter::*;
+#[cfg(feature = "http")]
+pub use http_server::*;
+pub use journal_exporter::*;
+pub use journal_replayer::*;
+#[cfg(feature = "kafka")]
+pub use kafka_reader::*;
+pub use lifecycle::*;
+pub use metrics_collector::*;
+#[cfg(feature = "msgpack")]
+pub use msgpack_reader::*;
+#[cfg(feature = "protobuf")]
+pub use protobuf_reader::*;
+pub use quarantine::*;
+pub use reader::*;
+pub use socket_listener::*;
+#[cfg(feature = "unstable")]
+pub use source_reader::*;
+#[cfg(feature = "xlsx")]
+pub use xlsx_reader::*;
@@ -0,0 +1,153 @@
+//! MessagePack reader actor
+//!
+//! The MessagePack reader actor is responsible for reading the transaction
+//! orders from a stream of MessagePack values, one map per order, as written
+//! by the other services over a pipe. Each map is mapped on a
+//! [CSVTransactionEntity] using its keys, the `type`, `client`, `tx` fields
+//! and the optional `amount`, `timestamp` and `currency` ones. The actor sends
+//! the transaction orders to the accountant actor through a channel.
+
+use std::io::{BufRead, BufReader, Read};
+
+use log::debug;
+
+use super::Sender;
+use crate::model::{CSVTransactionEntity, TransactionOrder};
+
+/// MessagePack reader actor.
+pub struct MsgPackReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+    reader: BufReader<Box<dyn Read + Sync + Send>>,
+}
+
+impl MsgPackReader {
+    /// Create a new MessagePack reader actor.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader: BufReader::new(reader),
+        }
+    }
+
+    /// Run the MessagePack reader actor.
+    /// The actor will read the stream value by value and send the transaction
+    /// orders to the accountant actor through the order channel. A value that
+    /// is not a valid order is logged and skipped, the reading fails if the
+    /// stream is not valid MessagePack.
+    pub fn run(mut self) -> crate::Result<()> {
+        debug!("MessagePack Reader Actor started");
+
+        while !self.reader.fill_buf()?.is_empty() {
+            // the whole value is read so the stream stays aligned on the next
+            // one whatever its content
+            let value = rmpv::decode::read_value(&mut self.reader)?;
+            let record: CSVTransactionEntity = match rmpv::ext::from_value(value) {
+                Err(error) => {
+                    log::info!("Error reading MessagePack value: {}", error);
+                    continue;
+                }
+                Ok(record) => record,
+            };
+            let order = match TransactionOrder::try_from(record) {
+                Err(error) => {
+                    log::info!("Error parsing MessagePack value: {}", error);
+                    continue;
+                }
+                Ok(order) => order,
+            };
+
+            self.order_sender.send(order)?;
+        }
+        debug!("MessagePack Reader Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+
+    use rmpv::Value;
+    use rust_decimal_macros::dec;
+
+    use crate::model::TransactionKind;
+
+    fn order(kind: &str, tx: u64, amount: Option<Value>) -> Value {
+        let mut fields = vec![
+            (Value::from("type"), Value::from(kind)),
+            (Value::from("client"), Value::from(1)),
+            (Value::from("tx"), Value::from(tx)),
+        ];
+        if let Some(amount) = amount {
+            fields.push((Value::from("amount"), amount));
+        }
+
+        Value::Map(fields)
+    }
+
+    fn write_stream(values: &[Value]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for value in values {
+            rmpv::encode::write_value(&mut buffer, value).unwrap();
+        }
+
+        buffer
+    }
+
+    fn run_reader(data: Vec<u8>) -> crate::Result<Vec<TransactionOrder>> {
+        let (tx, rx) = channel();
+        let actor = MsgPackReader::new(tx, Box::new(std::io::Cursor::new(data)));
+        let handler = std::thread::spawn(move || actor.run());
+
+        handler.join().unwrap().map(|_| rx.iter().collect())
+    }
+
+    #[test]
+    fn simple_ok_sample() {
+        let mut deposit = order("deposit", 1, Some(Value::from("1.5")));
+        if let Value::Map(fields) = &mut deposit {
+            fields.push((Value::from("currency"), Value::from("eur")));
+        }
+        let data = write_stream(&[
+            deposit,
+            order("withdrawal", 2, Some(Value::from(0.25))),
+            order("dispute", 1, None),
+        ]);
+        let orders = run_reader(data).unwrap();
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(1.5)));
+        assert_eq!(orders[0].currency.unwrap().to_string(), "EUR");
+        assert_eq!(orders[1].kind, TransactionKind::Withdrawal(dec!(0.25)));
+        assert_eq!(orders[2].kind, TransactionKind::Dispute(1));
+    }
+
+    #[test]
+    fn invalid_values_are_skipped() {
+        let data = write_stream(&[
+            order("deposit", 1, Some(Value::from("1.0"))),
+            order("whatever", 2, Some(Value::from("1.0"))),
+            order("deposit", 3, None),
+            Value::from("deposit,1,4,1.0"),
+            order("deposit", 5, Some(Value::from(true))),
+            order("withdrawal", 6, Some(Value::from("0.5"))),
+        ]);
+
+        assert_eq!(run_reader(data).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let mut data = write_stream(&[order("deposit", 1, Some(Value::from("1.0")))]);
+        data.pop();
+
+        assert!(run_reader(data).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Protobuf reader actor
+//!
+//! The protobuf reader actor is responsible for reading the transaction
+//! orders from a stream of protobuf messages, each one prefixed by its length
+//! encoded as a varint like the delimited messages of the protobuf libraries.
+//! The messages are mapped on a [CSVTransactionEntity] so they are checked as
+//! the CSV records. The actor sends the transaction orders to the accountant
+//! actor through a channel.
+
+use std::io::{self, BufReader, Read};
+
+use anyhow::anyhow;
+use chrono::DateTime;
+use log::debug;
+use prost::Message;
+
+use super::Sender;
+use crate::model::{CSVTransactionEntity, TransactionOrder};
+
+/// Protobuf schema of the transaction order messages.
+///
+/// The amount is a decimal string so it is not rounded as a floating point
+/// number would be.
+pub const TRANSACTION_PROTO_SCHEMA: &str = r#"
+syntax = "proto3";
+
+package csv_reader;
+
+message TransactionOrder {
+    enum Kind {
+        DEPOSIT = 0;
+        WITHDRAWAL = 1;
+        DISPUTE = 2;
+        RESOLVE = 3;
+        CHARGEBACK = 4;
+        UNLOCK = 5;
+        OPEN = 6;
+        CLOSE = 7;
+        ADJUSTMENT = 8;
+    }
+
+    Kind kind = 1;
+    uint64 client = 2;
+    uint64 tx = 3;
+    optional string amount = 4;
+    optional int64 timestamp_millis = 5;
+    optional string currency = 6;
+}
+"#;
+
+/// Largest size of a message, a longer length prefix is a framing error.
+const MAX_MESSAGE_SIZE: u64 = 64 * 1024;
+
+/// Transaction order message, see [TRANSACTION_PROTO_SCHEMA].
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoTransactionOrder {
+    /// The transaction kind, a [ProtoTransactionKind].
+    #[prost(enumeration = "ProtoTransactionKind", tag = "1")]
+    pub kind: i32,
+
+    /// The client identifier that made the transaction.
+    #[prost(uint64, tag = "2")]
+    pub client: u64,
+
+    /// The unique identifier of the transaction.
+    #[prost(uint64, tag = "3")]
+    pub tx: u64,
+
+    /// The decimal amount of the transaction.
+    #[prost(string, optional, tag = "4")]
+    pub amount: Option<String>,
+
+    /// The time of the transaction in milliseconds since the Unix epoch.
+    #[prost(int64, optional, tag = "5")]
+    pub timestamp_millis: Option<i64>,
+
+    /// The ISO 4217 code of the currency of the transaction.
+    #[prost(string, optional, tag = "6")]
+    pub currency: Option<String>,
+}
+
+/// Transaction kind of the [ProtoTransactionOrder] messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ProtoTransactionKind {
+    /// Deposit of the amount.
+    Deposit = 0,
+
+    /// Withdrawal of the amount.
+    Withdrawal = 1,
+
+    /// Dispute of the deposit or withdrawal `tx`.
+    Dispute = 2,
+
+    /// Resolve of the disputed transaction `tx`.
+    Resolve = 3,
+
+    /// Chargeback of the disputed transaction `tx`.
+    ChargeBack = 4,
+
+    /// Unlock of the account of the client.
+    Unlock = 5,
+
+    /// Opening of the account of the client.
+    Open = 6,
+
+    /// Closing of the account of the client.
+    Close = 7,
+
+    /// Adjustment of the account of the client by the signed amount.
+    Adjustment = 8,
+}
+
+impl TryFrom<ProtoTransactionOrder> for CSVTransactionEntity {
+    type Error = anyhow::Error;
+
+    fn try_from(message: ProtoTransactionOrder) -> Result<Self, Self::Error> {
+        let kind = match ProtoTransactionKind::try_from(message.kind) {
+            Ok(ProtoTransactionKind::Deposit) => "deposit",
+            Ok(ProtoTransactionKind::Withdrawal) => "withdrawal",
+            Ok(ProtoTransactionKind::Dispute) => "dispute",
+            Ok(ProtoTransactionKind::Resolve) => "resolve",
+            Ok(ProtoTransactionKind::ChargeBack) => "chargeback",
+            Ok(ProtoTransactionKind::Unlock) => "unlock",
+            Ok(ProtoTransactionKind::Open) => "open",
+            Ok(ProtoTransactionKind::Close) => "close",
+            Ok(ProtoTransactionKind::Adjustment) => "adjustment",
+            Err(_) => return Err(anyhow!("Unknown transaction kind {}", message.kind)),
+        };
+        let timestamp = message
+            .timestamp_millis
+            .map(|millis| {
+                DateTime::from_timestamp_millis(millis)
+                    .ok_or_else(|| anyhow!("Invalid timestamp {millis}"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            r#type: kind.to_string(),
+            client: message.client,
+            tx: message.tx,
+            amount: message.amount.map(|amount| amount.parse()).transpose()?,
+            timestamp,
+            currency: message
+                .currency
+                .map(|currency| currency.parse())
+                .transpose()?,
+        })
+    }
+}
+
+/// Protobuf reader actor.
+pub struct ProtobufReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+    reader: BufReader<Box<dyn Read + Sync + Send>>,
+}
+
+impl ProtobufReader {
+    /// Create a new protobuf reader actor.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader: BufReader::new(reader),
+        }
+    }
+
+    /// Run the protobuf reader actor.
+    /// The actor will read the stream message by message and send the
+    /// transaction orders to the accountant actor through the order channel.
+    /// An invalid message is logged and skipped, the reading fails if the
+    /// stream ends in the middle of a message or its length prefix is invalid.
+    pub fn run(mut self) -> crate::Result<()> {
+        debug!("Protobuf Reader Actor started");
+
+        while let Some(buffer) = self.read_message()? {
+            let record = match ProtoTransactionOrder::decode(buffer.as_slice())
+                .map_err(anyhow::Error::from)
+                .and_then(CSVTransactionEntity::try_from)
+            {
+                Err(error) => {
+                    log::info!("Error reading protobuf message: {}", error);
+                    continue;
+                }
+                Ok(record) => record,
+            };
+            let order = match TransactionOrder::try_from(record) {
+                Err(error) => {
+                    log::info!("Error parsing protobuf message: {}", error);
+                    continue;
+                }
+                Ok(order) => order,
+            };
+
+            self.order_sender.send(order)?;
+        }
+        debug!("Protobuf Reader Actor stopped");
+
+        Ok(())
+    }
+
+    /// Read the next length-prefixed message, none at the end of the stream.
+    fn read_message(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        let mut length: u64 = 0;
+        let mut byte = [0u8];
+
+        for index in 0..10 {
+            if self.reader.read(&mut byte)? == 0 {
+                if index == 0 {
+                    return Ok(None);
+                }
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            length |= u64::from(byte[0] & 0x7f) << (7 * index);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        if length > MAX_MESSAGE_SIZE {
+            return Err(anyhow!(
+                "Invalid protobuf message length {length} (at most {MAX_MESSAGE_SIZE})."
+            ));
+        }
+        let mut buffer = vec![0; length as usize];
+        self.reader.read_exact(&mut buffer)?;
+
+        Ok(Some(buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+
+    use rust_decimal_macros::dec;
+
+    use crate::model::TransactionKind;
+
+    fn message(kind: ProtoTransactionKind, tx: u64, amount: Option<&str>) -> ProtoTransactionOrder {
+        ProtoTransactionOrder {
+            kind: kind as i32,
+            client: 1,
+            tx,
+            amount: amount.map(str::to_string),
+            timestamp_millis: None,
+            currency: None,
+        }
+    }
+
+    fn write_stream(messages: &[ProtoTransactionOrder]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for message in messages {
+            message.encode_length_delimited(&mut buffer).unwrap();
+        }
+
+        buffer
+    }
+
+    fn run_reader(data: Vec<u8>) -> crate::Result<Vec<TransactionOrder>> {
+        let (tx, rx) = channel();
+        let actor = ProtobufReader::new(tx, Box::new(std::io::Cursor::new(data)));
+        let handler = std::thread::spawn(move || actor.run());
+
+        handler.join().unwrap().map(|_| rx.iter().collect())
+    }
+
+    #[test]
+    fn simple_ok_sample() {
+        let mut deposit = message(ProtoTransactionKind::Deposit, 1, Some("1.5"));
+        deposit.timestamp_millis = Some(1_709_292_600_000);
+        deposit.currency = Some("eur".to_string());
+        let data = write_stream(&[
+            deposit,
+            message(ProtoTransactionKind::Withdrawal, 2, Some("0.5")),
+            message(ProtoTransactionKind::Dispute, 1, None),
+        ]);
+        let orders = run_reader(data).unwrap();
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(1.5)));
+        assert_eq!(
+            orders[0].timestamp.unwrap().to_rfc3339(),
+            "2024-03-01T11:30:00+00:00"
+        );
+        assert_eq!(orders[0].currency.unwrap().to_string(), "EUR");
+        assert_eq!(orders[1].kind, TransactionKind::Withdrawal(dec!(0.5)));
+        assert_eq!(orders[2].kind, TransactionKind::Dispute(1));
+    }
+
+    #[test]
+    fn invalid_messages_are_skipped() {
+        // the clients are not limited to 16 bits
+        let mut wide_client = message(ProtoTransactionKind::Deposit, 3, Some("1.0"));
+        wide_client.client = 70_000;
+        let mut unknown_kind = message(ProtoTransactionKind::Deposit, 4, Some("1.0"));
+        unknown_kind.kind = 9;
+        let data = write_stream(&[
+            message(ProtoTransactionKind::Deposit, 1, Some("1.0")),
+            message(ProtoTransactionKind::Deposit, 2, None),
+            wide_client,
+            unknown_kind,
+            message(ProtoTransactionKind::Deposit, 5, Some("1,0")),
+            message(ProtoTransactionKind::Withdrawal, 6, Some("0.5")),
+        ]);
+
+        assert_eq!(run_reader(data).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let mut data = write_stream(&[message(ProtoTransactionKind::Deposit, 1, Some("1.0"))]);
+        data.pop();
+
+        assert!(run_reader(data).is_err());
+        assert!(run_reader(vec![0xff; 4]).is_err());
+    }
+}
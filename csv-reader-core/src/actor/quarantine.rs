@@ -0,0 +1,271 @@
+//! # Quarantine Actor
+//!
+//! This module provides the implementation of the Quarantine Actor, a stage
+//! between the reader and the accountant holding back the suspicious orders:
+//! the orders matching one of the [QuarantineRules] are written to the
+//! quarantine output for a review instead of being applied. The quarantined
+//! orders are written as CSV rows read back by the [Reader](super::Reader),
+//! the `reason` column being ignored, so the reviewed ones can be applied
+//! later.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, warn};
+use rust_decimal::Decimal;
+
+use super::{dead_letter_exporter::OrderRecord, run_actor, Actor, Mailbox, Receiver, Sender};
+use crate::{
+    model::{ClientId, Timestamp, TransactionKind, TransactionOrder, TxId},
+    Result,
+};
+
+/// The rules of the orders held back by the [Quarantine], every rule is
+/// disabled by default.
+#[derive(Debug, Default, Clone)]
+pub struct QuarantineRules {
+    /// Quarantine the deposits and withdrawals above this amount.
+    pub max_amount: Option<Decimal>,
+
+    /// Quarantine the disputes, resolutions and chargebacks ordered by
+    /// another client than the one of the deposit or withdrawal. Only the
+    /// transactions passed through the quarantine are known.
+    pub foreign_disputes: bool,
+
+    /// Quarantine the withdrawals of a client beyond the given number within
+    /// the given duration, measured on the timestamps of the orders or on
+    /// their arrival when they have none.
+    pub withdrawal_burst: Option<(usize, Duration)>,
+}
+
+/// The quarantine actor.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::actor::{channel, Accountant, Quarantine, QuarantineRules, Reader};
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+/// let (read_sender, read_receiver) = channel();
+/// let (order_sender, order_receiver) = channel();
+/// let data = "type,client,tx,amount\ndeposit,1,1,100.0\ndeposit,1,2,50000.0\n";
+/// Reader::new(read_sender, Box::new(data.as_bytes())).run().unwrap();
+/// let rules = QuarantineRules {
+///     max_amount: Some(Decimal::from(10_000)),
+///     ..Default::default()
+/// };
+/// let quarantined = Quarantine::new(read_receiver, order_sender, Box::new(std::io::sink()), rules)
+///     .run()
+///     .unwrap();
+/// Accountant::new(manager.clone(), order_receiver).run().unwrap();
+///
+/// assert_eq!(quarantined, 1);
+/// assert_eq!(manager.get_account(1).unwrap().total, Decimal::ONE_HUNDRED);
+/// ```
+pub struct Quarantine {
+    /// The mailbox receiving the orders read.
+    mailbox: Mailbox<TransactionOrder>,
+
+    /// The order channel sender to send the orders to apply.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The CSV of the quarantined orders.
+    writer: csv::Writer<Box<dyn Write + Sync + Send>>,
+
+    /// The rules of the quarantined orders.
+    rules: QuarantineRules,
+
+    /// The client of the deposits and withdrawals passed through, for the
+    /// foreign disputes.
+    transactions: HashMap<TxId, ClientId>,
+
+    /// The time of the latest withdrawals of the clients, for the bursts.
+    withdrawals: HashMap<ClientId, VecDeque<Timestamp>>,
+
+    /// The number of orders quarantined.
+    quarantined: u64,
+}
+
+impl Quarantine {
+    /// Create a new quarantine actor receiving the orders read and sending
+    /// the ones not quarantined to the accountant.
+    pub fn new(
+        order_receiver: Receiver<TransactionOrder>,
+        order_sender: Sender<TransactionOrder>,
+        writer: Box<dyn Write + Sync + Send>,
+        rules: QuarantineRules,
+    ) -> Self {
+        Self {
+            mailbox: Mailbox::new(order_receiver),
+            order_sender,
+            writer: csv::Writer::from_writer(writer),
+            rules,
+            transactions: HashMap::new(),
+            withdrawals: HashMap::new(),
+            quarantined: 0,
+        }
+    }
+
+    /// Run the quarantine actor.
+    /// The actor forwards or quarantines the orders until the channel is
+    /// closed and returns the number of orders quarantined.
+    pub fn run(self) -> Result<u64> {
+        run_actor(self)
+    }
+
+    /// Tell why the given order must be quarantined, if it must.
+    fn check(&mut self, order: &TransactionOrder) -> Option<&'static str> {
+        match order.kind {
+            TransactionKind::Deposit(amount) | TransactionKind::Withdrawal(amount)
+                if self.rules.max_amount.is_some_and(|max| amount > max) =>
+            {
+                Some("amount_above_threshold")
+            }
+            TransactionKind::Withdrawal(_) => self.check_burst(order),
+            TransactionKind::Dispute(tx_id)
+            | TransactionKind::Resolve(tx_id)
+            | TransactionKind::ChargeBack(tx_id)
+                if self.rules.foreign_disputes =>
+            {
+                self.transactions
+                    .get(&tx_id)
+                    .filter(|client_id| **client_id != order.client_id)
+                    .map(|_| "foreign_dispute")
+            }
+            _ => None,
+        }
+    }
+
+    /// Record a withdrawal of the client and tell if it exceeds the burst.
+    fn check_burst(&mut self, order: &TransactionOrder) -> Option<&'static str> {
+        let (count, window) = self.rules.withdrawal_burst?;
+        let now = order
+            .timestamp
+            .unwrap_or_else(|| Timestamp::from(SystemTime::now()));
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+        let times = self.withdrawals.entry(order.client_id).or_default();
+        while times.front().is_some_and(|time| now - *time >= window) {
+            times.pop_front();
+        }
+        times.push_back(now);
+        // the times beyond the limit are not needed
+        if times.len() > count + 1 {
+            times.pop_front();
+        }
+
+        (times.len() > count).then_some("withdrawal_burst")
+    }
+}
+
+impl Actor for Quarantine {
+    type Message = TransactionOrder;
+    type Output = u64;
+
+    const NAME: &'static str = "Quarantine";
+
+    fn receive(&mut self) -> Option<TransactionOrder> {
+        self.mailbox.recv()
+    }
+
+    fn handle(&mut self, order: TransactionOrder) -> Result<()> {
+        let Some(reason) = self.check(&order) else {
+            if let (TransactionKind::Deposit(_) | TransactionKind::Withdrawal(_), true) =
+                (&order.kind, self.rules.foreign_disputes)
+            {
+                self.transactions.insert(order.tx_id, order.client_id);
+            }
+            self.order_sender.send(order)?;
+
+            return Ok(());
+        };
+        warn!(
+            "Order tx={} of client {} quarantined: {}.",
+            order.tx_id, order.client_id, reason
+        );
+        debug!("Quarantine Actor: quarantined order: {:#?}", order);
+        let record = OrderRecord::new(
+            order.tx_id,
+            order.client_id,
+            &order.kind,
+            order.timestamp,
+            order.currency,
+        );
+        self.writer.serialize(record.with_reason(reason))?;
+        self.quarantined += 1;
+
+        Ok(())
+    }
+
+    fn on_stop(mut self) -> Result<u64> {
+        self.writer.flush()?;
+
+        Ok(self.quarantined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::channel;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::test_utils::SharedBuffer;
+
+    #[test]
+    fn test_quarantine_rules() {
+        let (read_sender, read_receiver) = channel();
+        let (order_sender, order_receiver) = channel();
+        let minute = |minute| Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, minute, 0).unwrap());
+        for (tx_id, client_id, kind, timestamp) in [
+            (1, 1, TransactionKind::Deposit(Decimal::from(500)), None),
+            (2, 2, TransactionKind::Deposit(Decimal::from(5000)), None),
+            (3, 2, TransactionKind::Dispute(1), None),
+            (4, 1, TransactionKind::Dispute(1), None),
+            (5, 1, TransactionKind::Withdrawal(Decimal::ONE), minute(0)),
+            (6, 1, TransactionKind::Withdrawal(Decimal::ONE), minute(1)),
+            (7, 1, TransactionKind::Withdrawal(Decimal::ONE), minute(2)),
+            // the first withdrawal left the window, not the next two
+            (8, 1, TransactionKind::Withdrawal(Decimal::ONE), minute(5)),
+        ] {
+            read_sender
+                .send(TransactionOrder {
+                    tx_id,
+                    client_id,
+                    kind,
+                    timestamp,
+                    currency: None,
+                })
+                .unwrap();
+        }
+        drop(read_sender);
+        let rules = QuarantineRules {
+            max_amount: Some(Decimal::from(1000)),
+            foreign_disputes: true,
+            withdrawal_burst: Some((2, Duration::from_secs(300))),
+        };
+        let buffer = SharedBuffer::default();
+        let quarantined =
+            Quarantine::new(read_receiver, order_sender, Box::new(buffer.clone()), rules)
+                .run()
+                .unwrap();
+        let forwarded: Vec<TxId> = order_receiver.iter().map(|order| order.tx_id).collect();
+
+        assert_eq!(quarantined, 4);
+        assert_eq!(forwarded, vec![1, 4, 5, 6]);
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "type,client,tx,amount,timestamp,currency,reason\n\
+             deposit,2,2,5000,,,amount_above_threshold\n\
+             dispute,2,3,,,,foreign_dispute\n\
+             withdrawal,1,7,1,2024-03-01T12:02:00+00:00,,withdrawal_burst\n\
+             withdrawal,1,8,1,2024-03-01T12:05:00+00:00,,withdrawal_burst\n"
+        );
+    }
+}
@@ -0,0 +1,3455 @@
+//! Reader actor
+//!
+//! The reader actor is responsible for reading the transaction data from a CSV
+//! file.  The actor reads the file line by line and send the transaction orders
+//! to the accountant actor through a channel.
+//!
+//! With several workers, the input is split into chunks of lines parsed in
+//! parallel and the orders are sent back in the input order.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    io::{self, BufRead, BufReader, Cursor, Read},
+    num::NonZeroU32,
+    ops::{AddAssign, Range},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, sync_channel, SyncSender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Utc};
+use csv::{ByteRecord, ByteRecordsIntoIter, ReaderBuilder, StringRecord};
+use log::debug;
+use rust_decimal::Decimal;
+
+use super::{run_actor, Actor, Sender};
+use crate::{
+    adapter::{InputEncoding, InputSource, RawRecord, RejectSink, RowTransformer},
+    model::{
+        Timestamp, TransactionKind, TransactionKindError, TransactionOrder, TxId, MAX_DECIMALS,
+    },
+};
+
+/// Default size in bytes of the chunks parsed by the workers.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Number of bytes of already parsed records kept by a [Recording] before they
+/// are dropped.
+const RECORDING_SLACK: usize = 64 * 1024;
+
+/// Names of the fields of a transaction record.
+pub(crate) const FIELD_NAMES: [&str; 6] =
+    ["type", "client", "tx", "amount", "timestamp", "currency"];
+
+/// Positions (starting at 0) of the fields in a CSV file without header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnPositions {
+    /// Position of the transaction kind column.
+    pub kind: usize,
+
+    /// Position of the client identifier column.
+    pub client: usize,
+
+    /// Position of the transaction identifier column.
+    pub tx: usize,
+
+    /// Position of the amount column. When it is the last position, rows
+    /// lacking it are handled according to [MissingAmount].
+    pub amount: usize,
+
+    /// Position of the optional timestamp column.
+    pub timestamp: Option<usize>,
+
+    /// Position of the optional currency column.
+    pub currency: Option<usize>,
+}
+
+impl FromStr for ColumnPositions {
+    type Err = anyhow::Error;
+
+    /// Parse the comma separated positions of the type, client, tx and amount
+    /// columns, optionally followed by the positions of the timestamp and
+    /// currency columns.
+    ///
+    /// ```
+    /// use csv_reader_core::actor::ColumnPositions;
+    ///
+    /// let positions: ColumnPositions = "3, 0, 1, 2".parse().unwrap();
+    /// assert_eq!(positions, ColumnPositions { kind: 3, client: 0, tx: 1, amount: 2, timestamp: None, currency: None });
+    ///
+    /// let positions: ColumnPositions = "0,1,2,3,4".parse().unwrap();
+    /// assert_eq!(positions.timestamp, Some(4));
+    ///
+    /// let positions: ColumnPositions = "0,1,2,3,5,4".parse().unwrap();
+    /// assert_eq!(positions.currency, Some(4));
+    ///
+    /// assert!("0,1,2".parse::<ColumnPositions>().is_err());
+    /// assert!("0,1,2,type".parse::<ColumnPositions>().is_err());
+    /// ```
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let positions = value
+            .split(',')
+            .map(|position| {
+                position
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| anyhow!("Invalid column position '{position}': {e}"))
+            })
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        match positions[..] {
+            [kind, client, tx, amount] => Ok(Self {
+                kind,
+                client,
+                tx,
+                amount,
+                timestamp: None,
+                currency: None,
+            }),
+            [kind, client, tx, amount, timestamp] => Ok(Self {
+                kind,
+                client,
+                tx,
+                amount,
+                timestamp: Some(timestamp),
+                currency: None,
+            }),
+            [kind, client, tx, amount, timestamp, currency] => Ok(Self {
+                kind,
+                client,
+                tx,
+                amount,
+                timestamp: Some(timestamp),
+                currency: Some(currency),
+            }),
+            _ => bail!(
+                "Expected 4 to 6 column positions (type, client, tx, amount[, timestamp[, currency]]), {} given.",
+                positions.len()
+            ),
+        }
+    }
+}
+
+impl ColumnPositions {
+    /// Number of columns of a record holding every field.
+    fn width(&self) -> usize {
+        [self.kind, self.client, self.tx, self.amount]
+            .into_iter()
+            .chain(self.timestamp)
+            .chain(self.currency)
+            .max()
+            .unwrap_or_default()
+            + 1
+    }
+}
+
+/// Size of the start of the input read to detect its dialect.
+const SNIFF_SIZE: u64 = 8 * 1024;
+
+/// The delimiters tried when detecting the dialect, the first ones winning
+/// ties.
+const SNIFFED_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// The dialect of a CSV input: how its fields are delimited and quoted, and
+/// whether it starts with a header line.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use csv_reader_core::actor::Dialect;
+///
+/// let dialect = Dialect::sniff(b"deposit;1;1;'1.0'\nwithdrawal;1;2;'0.5'\n", &HashMap::new());
+///
+/// assert_eq!(dialect.delimiter, b';');
+/// assert_eq!(dialect.quote, b'\'');
+/// assert_eq!(dialect.columns.map(|columns| columns.amount), Some(3));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// The field delimiter.
+    pub delimiter: u8,
+
+    /// The quote character.
+    pub quote: u8,
+
+    /// The positions of the fields when the input has no header line, in the
+    /// `type`, `client`, `tx`, `amount`, `timestamp`, `currency` order.
+    pub columns: Option<ColumnPositions>,
+}
+
+impl Dialect {
+    /// Detect the dialect from the start of an input. The quote is the one
+    /// opening the most fields, `"` by default. The delimiter is the one
+    /// splitting the most lines in the same number of fields, `,` if none
+    /// splits them. The input has a header line unless its first record is a
+    /// transaction rather than field names, renamed by the given mapping.
+    pub fn sniff(sample: &[u8], header_mapping: &HashMap<String, String>) -> Self {
+        // the last line of the sample may be cut
+        let sample = match sample.iter().rposition(|byte| *byte == b'\n') {
+            Some(end) => &sample[..=end],
+            None => sample,
+        };
+        let opened = |quote: u8| {
+            sample
+                .iter()
+                .enumerate()
+                .filter(|(index, byte)| {
+                    **byte == quote
+                        && (*index == 0
+                            || matches!(sample[index - 1], b'\n' | b' ')
+                            || SNIFFED_DELIMITERS.contains(&sample[index - 1]))
+                })
+                .count()
+        };
+        let quote = match opened(b'\'') > opened(b'"') {
+            true => b'\'',
+            false => b'"',
+        };
+        let records = |delimiter: u8| {
+            ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .delimiter(delimiter)
+                .quote(quote)
+                .trim(csv::Trim::All)
+                .from_reader(sample)
+                .into_byte_records()
+                .map_while(Result::ok)
+        };
+        // the most frequent width of the records, with its frequency
+        let width = |delimiter: u8| {
+            let mut widths = BTreeMap::<usize, usize>::new();
+            records(delimiter).for_each(|record| *widths.entry(record.len()).or_default() += 1);
+
+            widths
+                .into_iter()
+                .map(|(width, count)| (count, width))
+                .max()
+                .filter(|(_, width)| *width > 1)
+        };
+        let (delimiter, width) = SNIFFED_DELIMITERS
+            .into_iter()
+            .rev()
+            .filter_map(|delimiter| width(delimiter).map(|score| (delimiter, score)))
+            .max_by_key(|(_, score)| *score)
+            .map_or((b',', 0), |(delimiter, (_, width))| (delimiter, width));
+
+        let first = records(delimiter).next().unwrap_or_default();
+        let is_name = |field: &[u8]| {
+            let field = String::from_utf8_lossy(field);
+            let name = header_mapping
+                .get(field.as_ref())
+                .map_or(&*field, String::as_str);
+
+            FIELD_NAMES.contains(&name)
+        };
+        let is_kind = |field: &[u8]| {
+            KIND_NAMES
+                .iter()
+                .any(|name| name.as_bytes().eq_ignore_ascii_case(field))
+        };
+        let has_header = first.iter().any(is_name) || !first.get(0).is_some_and(is_kind);
+        let columns = (!has_header).then(|| ColumnPositions {
+            kind: 0,
+            client: 1,
+            tx: 2,
+            amount: 3,
+            timestamp: (width > 4).then_some(4),
+            currency: (width > 5).then_some(5),
+        });
+
+        Self {
+            delimiter,
+            quote,
+            columns,
+        }
+    }
+
+    /// Read the start of the input to detect its dialect and set it in the
+    /// given options. Returns the input, its start read again.
+    fn sniff_input(
+        mut reader: Box<dyn Read + Sync + Send>,
+        options: &mut ReaderOptions,
+    ) -> io::Result<Box<dyn Read + Sync + Send>> {
+        let mut sample = Vec::new();
+        (&mut reader).take(SNIFF_SIZE).read_to_end(&mut sample)?;
+        let dialect = Self::sniff(&sample, &options.header_mapping);
+        debug!("Detected the CSV dialect {dialect:?}");
+        options.delimiter = dialect.delimiter;
+        options.quote = dialect.quote;
+        options.columns = dialect.columns;
+        options.sniff = false;
+
+        Ok(Box::new(Cursor::new(sample).chain(reader)))
+    }
+}
+
+/// Positions of the [FIELD_NAMES] fields in the records, `None` for the fields
+/// missing from the input.
+#[derive(Debug, Clone, Copy)]
+struct FieldPositions([Option<usize>; 6]);
+
+impl FieldPositions {
+    /// Find the fields by name in the header.
+    fn from_headers(headers: &StringRecord) -> Self {
+        Self(FIELD_NAMES.map(|name| headers.iter().position(|header| header == name)))
+    }
+
+    /// Get the bytes of the given field of a record. They are trimmed again as
+    /// the CSV reader does not trim the first record of a headerless input.
+    fn get<'r>(&self, record: &'r ByteRecord, field: usize) -> crate::Result<&'r [u8]> {
+        self.0[field]
+            .and_then(|position| record.get(position))
+            .map(<[u8]>::trim_ascii)
+            .ok_or_else(|| anyhow!("missing field `{}`", FIELD_NAMES[field]))
+    }
+
+    /// Tell if a record is a trailer record.
+    fn is_trailer(&self, record: &ByteRecord) -> bool {
+        self.get(record, 0)
+            .is_ok_and(|kind| kind.eq_ignore_ascii_case(b"trailer"))
+    }
+
+    /// Read the control totals of a trailer record, the sums being optional.
+    fn parse_trailer(&self, record: &ByteRecord, format: AmountFormat) -> crate::Result<Trailer> {
+        let sum = |field| match self.get(record, field) {
+            Err(_) | Ok(b"") => Ok(None),
+            Ok(amount) => parse_amount(amount, format).map(Some),
+        };
+        let totals = self.get(record, 1).and_then(|rows| {
+            Ok(Trailer {
+                rows: parse_number(rows, "rows")?,
+                deposits: sum(2)?,
+                withdrawals: sum(3)?,
+            })
+        });
+
+        totals.map_err(|e| anyhow!("Invalid trailer record: {e}"))
+    }
+
+    /// Read a transaction order from the record fields without allocating
+    /// them.
+    fn parse_order(
+        &self,
+        record: &ByteRecord,
+        options: &ReaderOptions,
+    ) -> crate::Result<TransactionOrder> {
+        let kind = self.get(record, 0)?;
+        let client_id = parse_number(self.get(record, 1)?, "client")?;
+        let tx_id = parse_number(self.get(record, 2)?, "tx")?;
+        // the amount column is optional
+        let amount = match self.get(record, 3) {
+            Err(_) | Ok(b"") => None,
+            Ok(amount) => Some(TransactionKind::check_precision(
+                parse_amount(amount, options.amount_format)?,
+                options.max_decimals,
+            )?),
+        };
+        // so is the timestamp column
+        let timestamp = match self.get(record, 4) {
+            Err(_) | Ok(b"") => None,
+            Ok(timestamp) => Some(
+                options
+                    .timestamp_format
+                    .parse(std::str::from_utf8(timestamp)?, options.timezone)?,
+            ),
+        };
+        // and the currency column
+        let currency = match self.get(record, 5) {
+            Err(_) | Ok(b"") => None,
+            Ok(currency) => Some(std::str::from_utf8(currency)?.parse()?),
+        };
+        let position = KIND_NAMES
+            .iter()
+            .position(|name| name.as_bytes().eq_ignore_ascii_case(kind));
+        let amount = || amount.ok_or(TransactionKindError::MissingAmount);
+        let kind = match position {
+            Some(0) => TransactionKind::deposit(amount()?)?,
+            Some(1) => TransactionKind::withdrawal(amount()?)?,
+            Some(2) => TransactionKind::dispute(tx_id),
+            Some(3) => TransactionKind::resolve(tx_id),
+            Some(4) => TransactionKind::chargeback(tx_id),
+            Some(5) => TransactionKind::Unlock,
+            Some(6) => TransactionKind::Open,
+            Some(7) => TransactionKind::Close,
+            Some(8) => TransactionKind::adjustment(amount()?)?,
+            _ => {
+                let kind = String::from_utf8_lossy(kind).to_lowercase();
+                return Err(TransactionKindError::UnknownKind(kind).into());
+            }
+        };
+
+        Ok(TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp,
+            currency,
+        })
+    }
+}
+
+impl From<&ColumnPositions> for FieldPositions {
+    fn from(columns: &ColumnPositions) -> Self {
+        let [kind, client, tx, amount] =
+            [columns.kind, columns.client, columns.tx, columns.amount].map(Some);
+
+        Self([
+            kind,
+            client,
+            tx,
+            amount,
+            columns.timestamp,
+            columns.currency,
+        ])
+    }
+}
+
+/// Parse an integer field.
+fn parse_number<T: FromStr>(field: &[u8], name: &str) -> crate::Result<T>
+where
+    T::Err: Display,
+{
+    let text = std::str::from_utf8(field)?;
+
+    text.parse()
+        .map_err(|e| anyhow!("Invalid {name} '{text}': {e}"))
+}
+
+/// Parse an amount field in the given format.
+fn parse_amount(field: &[u8], format: AmountFormat) -> crate::Result<Decimal> {
+    format.parse(std::str::from_utf8(field)?)
+}
+
+/// Control totals of an input: the number of transaction rows and the sums of
+/// the deposit and withdrawal amounts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ControlTotals {
+    /// Number of transaction rows, valid or not.
+    pub rows: u64,
+
+    /// Sum of the deposit amounts.
+    pub deposits: Decimal,
+
+    /// Sum of the withdrawal amounts.
+    pub withdrawals: Decimal,
+}
+
+impl ControlTotals {
+    /// Account for a transaction order in the totals.
+    fn record(&mut self, kind: &TransactionKind) {
+        match kind {
+            TransactionKind::Deposit(amount) => self.deposits += amount,
+            TransactionKind::Withdrawal(amount) => self.withdrawals += amount,
+            _ => {}
+        }
+    }
+}
+
+impl AddAssign for ControlTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.rows += other.rows;
+        self.deposits += other.deposits;
+        self.withdrawals += other.withdrawals;
+    }
+}
+
+impl Display for ControlTotals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rows, deposits {}, withdrawals {}",
+            self.rows,
+            self.deposits.normalize(),
+            self.withdrawals.normalize()
+        )
+    }
+}
+
+/// Control totals declared by a trailer record. The sums of the amounts are
+/// only verified when the trailer holds them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Trailer {
+    /// Number of transaction rows.
+    rows: u64,
+
+    /// Sum of the deposit amounts, if declared.
+    deposits: Option<Decimal>,
+
+    /// Sum of the withdrawal amounts, if declared.
+    withdrawals: Option<Decimal>,
+}
+
+impl Trailer {
+    /// Tell if the processed totals match the declared ones.
+    fn matches(&self, totals: &ControlTotals) -> bool {
+        self.rows == totals.rows
+            && self
+                .deposits
+                .is_none_or(|deposits| deposits == totals.deposits)
+            && self
+                .withdrawals
+                .is_none_or(|withdrawals| withdrawals == totals.withdrawals)
+    }
+}
+
+impl Display for Trailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} rows", self.rows)?;
+        if let Some(deposits) = self.deposits {
+            write!(f, ", deposits {}", deposits.normalize())?;
+        }
+        if let Some(withdrawals) = self.withdrawals {
+            write!(f, ", withdrawals {}", withdrawals.normalize())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What to do with the trailer record carrying the control totals of the
+/// input. The trailer is a `trailer,<rows>,<deposits>,<withdrawals>` record
+/// using the `type`, `client`, `tx` and `amount` columns, the sums being
+/// optional: `trailer,<rows>,,` only verifies the number of rows, so a
+/// truncated file is detected. A malformed trailer fails the run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrailerPolicy {
+    /// No trailer is expected, a trailer record is an invalid transaction.
+    #[default]
+    Ignore,
+
+    /// Verify the control totals and log a warning on mismatch.
+    Flag,
+
+    /// Verify the control totals and fail the run on mismatch.
+    Fail,
+}
+
+impl TrailerPolicy {
+    /// Compare the control totals of the trailer to the processed ones.
+    fn verify(&self, trailer: Option<Trailer>, totals: ControlTotals) -> crate::Result<()> {
+        let message = match trailer {
+            None => "No trailer record found.".to_string(),
+            Some(expected) if expected.matches(&totals) => return Ok(()),
+            Some(expected) => {
+                format!("Control totals mismatch: trailer {expected}, processed {totals}.")
+            }
+        };
+
+        match self {
+            Self::Ignore => Ok(()),
+            Self::Flag => {
+                log::warn!("{}", message);
+                Ok(())
+            }
+            Self::Fail => bail!(message),
+        }
+    }
+}
+
+impl FromStr for TrailerPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "flag" => Ok(Self::Flag),
+            "fail" => Ok(Self::Fail),
+            _ => bail!("Unknown trailer policy '{value}' (expected 'ignore', 'flag' or 'fail')."),
+        }
+    }
+}
+
+/// What to do with the records whose timestamp is older than the one of a
+/// previous record. Checking the order reads the input sequentially.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampOrder {
+    /// The order of the records is not checked.
+    #[default]
+    Ignore,
+
+    /// Log a warning for every record out of order.
+    Flag,
+
+    /// Fail the run on the first record out of order.
+    Fail,
+}
+
+impl FromStr for TimestampOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "flag" => Ok(Self::Flag),
+            "fail" => Ok(Self::Fail),
+            _ => bail!(
+                "Unknown timestamp order policy '{value}' (expected 'ignore', 'flag' or 'fail')."
+            ),
+        }
+    }
+}
+
+/// What to do with the rows having more columns than expected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraColumns {
+    /// The extra columns are dropped.
+    Ignore,
+
+    /// The row is rejected.
+    #[default]
+    Error,
+}
+
+impl FromStr for ExtraColumns {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "error" => Ok(Self::Error),
+            _ => bail!("Unknown extra columns handling '{value}' (expected 'ignore' or 'error')."),
+        }
+    }
+}
+
+/// What to do with the rows lacking the amount column when it is the last
+/// column, as some exports write dispute rows without it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissingAmount {
+    /// The row is read with an empty amount.
+    #[default]
+    Empty,
+
+    /// The row is rejected.
+    Error,
+}
+
+impl FromStr for MissingAmount {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "empty" => Ok(Self::Empty),
+            "error" => Ok(Self::Error),
+            _ => bail!("Unknown missing amount handling '{value}' (expected 'empty' or 'error')."),
+        }
+    }
+}
+
+/// Largest number of decimal places of a [Decimal].
+const MAX_EXPONENT: u32 = 28;
+
+/// How the amounts of the input are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AmountFormat {
+    /// Decimal amounts (e.g. `15.0000`), scientific notation is accepted.
+    #[default]
+    Decimal,
+
+    /// Integer amounts in minor units having the given number of decimal
+    /// places (e.g. `150000` is `15.0000` with 4 decimal places).
+    MinorUnits {
+        /// The number of decimal places, at most 28.
+        exponent: u32,
+    },
+}
+
+impl AmountFormat {
+    /// Parse an amount in this format. Amounts in minor units are converted
+    /// exactly, without going through a decimal representation.
+    ///
+    /// ```
+    /// use csv_reader_core::actor::AmountFormat;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let format: AmountFormat = "minor-units:4".parse().unwrap();
+    ///
+    /// assert_eq!(format.parse("150000").unwrap(), Decimal::new(15, 0));
+    /// assert!(format.parse("15.0").is_err());
+    /// ```
+    pub fn parse(&self, text: &str) -> crate::Result<Decimal> {
+        match *self {
+            Self::Decimal => Decimal::from_str(text)
+                .or_else(|_| Decimal::from_scientific(text))
+                .map_err(|e| anyhow!("Invalid amount '{text}': {e}")),
+            Self::MinorUnits { exponent } => text
+                .parse::<i128>()
+                .map_err(anyhow::Error::from)
+                .and_then(|units| Ok(Decimal::try_from_i128_with_scale(units, exponent)?))
+                .map_err(|e| anyhow!("Invalid amount in minor units '{text}': {e}")),
+        }
+    }
+}
+
+impl FromStr for AmountFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let lowercase = value.to_lowercase();
+        let exponent = lowercase
+            .strip_prefix("minor-units:")
+            .and_then(|exponent| exponent.parse().ok())
+            .filter(|exponent| *exponent <= MAX_EXPONENT);
+
+        match (lowercase.as_str(), exponent) {
+            ("decimal", _) => Ok(Self::Decimal),
+            (_, Some(exponent)) => Ok(Self::MinorUnits { exponent }),
+            _ => bail!(
+                "Unknown amount format '{value}' (expected 'decimal' or 'minor-units:N' with N up to {}).",
+                MAX_EXPONENT
+            ),
+        }
+    }
+}
+
+/// Layouts of the ISO 8601 timestamps without timezone, read in the timezone
+/// of the input.
+const ISO_8601_LOCAL: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// How the timestamps of the rows are written. Partners format their dates
+/// differently, a timestamp not matching the format is an error rather than
+/// a guess.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// ISO 8601 date and time (e.g. `2024-03-01T12:30:00+01:00`), the
+    /// timestamps without offset are in the timezone of the input.
+    #[default]
+    Iso8601,
+
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+
+    /// Day first date and time to the minute (e.g. `01/03/2024 12:30`) in the
+    /// timezone of the input.
+    DayMonthYear,
+}
+
+impl TimestampFormat {
+    /// Parse a timestamp in this format, the timestamps without offset being
+    /// in the given timezone.
+    ///
+    /// ```
+    /// use chrono::{FixedOffset, TimeZone, Utc};
+    /// use csv_reader_core::actor::TimestampFormat;
+    ///
+    /// let paris = FixedOffset::east_opt(3600).unwrap();
+    /// let expected = Utc.with_ymd_and_hms(2024, 3, 1, 11, 30, 0).unwrap();
+    ///
+    /// assert_eq!(TimestampFormat::DayMonthYear.parse("01/03/2024 12:30", paris).unwrap(), expected);
+    /// assert_eq!(TimestampFormat::Iso8601.parse("2024-03-01T11:30:00Z", paris).unwrap(), expected);
+    /// assert!(TimestampFormat::DayMonthYear.parse("2024-03-01 12:30", paris).is_err());
+    /// ```
+    pub fn parse(&self, text: &str, timezone: FixedOffset) -> crate::Result<DateTime<Utc>> {
+        let timestamp = match self {
+            Self::Iso8601 => DateTime::parse_from_rfc3339(text)
+                .map(|timestamp| timestamp.to_utc())
+                .ok()
+                .or_else(|| {
+                    ISO_8601_LOCAL
+                        .iter()
+                        .find_map(|layout| NaiveDateTime::parse_from_str(text, layout).ok())
+                        .and_then(|local| local.and_local_timezone(timezone).single())
+                        .map(|timestamp| timestamp.to_utc())
+                }),
+            Self::EpochMillis => text.parse().ok().and_then(DateTime::from_timestamp_millis),
+            Self::DayMonthYear => NaiveDateTime::parse_from_str(text, "%d/%m/%Y %H:%M")
+                .ok()
+                .and_then(|local| local.and_local_timezone(timezone).single())
+                .map(|timestamp| timestamp.to_utc()),
+        };
+
+        timestamp.ok_or_else(|| anyhow!("Invalid timestamp '{text}' (expected {self})."))
+    }
+}
+
+impl Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Iso8601 => write!(f, "iso8601"),
+            Self::EpochMillis => write!(f, "epoch-millis"),
+            Self::DayMonthYear => write!(f, "dd/mm/yyyy hh:mm"),
+        }
+    }
+}
+
+impl FromStr for TimestampFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "iso8601" => Ok(Self::Iso8601),
+            "epoch-millis" => Ok(Self::EpochMillis),
+            "dd/mm/yyyy hh:mm" => Ok(Self::DayMonthYear),
+            _ => bail!(
+                "Unknown timestamp format '{value}' (expected 'iso8601', 'epoch-millis' or 'dd/mm/yyyy hh:mm')."
+            ),
+        }
+    }
+}
+
+/// Parse the timezone of the timestamps of an input: `UTC` or an offset from
+/// UTC like `+01:00`. Daylight saving time changes are not followed, an input
+/// spanning them must use offsets in its timestamps.
+///
+/// ```
+/// use csv_reader_core::actor::parse_timezone;
+///
+/// assert_eq!(parse_timezone("UTC").unwrap().local_minus_utc(), 0);
+/// assert_eq!(parse_timezone("-05:30").unwrap().local_minus_utc(), -19800);
+/// assert!(parse_timezone("Europe/Paris").is_err());
+/// ```
+pub fn parse_timezone(value: &str) -> crate::Result<FixedOffset> {
+    if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Ok(Utc.fix());
+    }
+
+    value.parse().map_err(|_| {
+        anyhow!("Unknown timezone '{value}' (expected 'UTC' or an offset like '+01:00').")
+    })
+}
+
+/// Names of the transaction kinds, in the [TransactionKind] order.
+pub(crate) const KIND_NAMES: [&str; 9] = [
+    "deposit",
+    "withdrawal",
+    "dispute",
+    "resolve",
+    "chargeback",
+    "unlock",
+    "open",
+    "close",
+    "adjustment",
+];
+
+/// Transaction kinds kept when reading the input, the orders of the other kinds
+/// are skipped before reaching the accountant. Skipped rows still count in the
+/// control totals. The clones of a filter share the counts of skipped rows.
+#[derive(Debug, Clone, Default)]
+pub struct KindFilter {
+    /// The kept kinds, in the [KIND_NAMES] order.
+    kept: [bool; 9],
+
+    /// The number of skipped rows per kind, in the [KIND_NAMES] order.
+    skipped: Arc<[AtomicU64; 9]>,
+}
+
+impl KindFilter {
+    /// Tell if the orders of the given kind are kept, counting the skipped
+    /// ones.
+    fn keep(&self, kind: &TransactionKind) -> bool {
+        let position = kind_position(kind);
+        if !self.kept[position] {
+            self.skipped[position].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.kept[position]
+    }
+
+    /// Get the number of rows skipped so far for each kind having skipped
+    /// rows.
+    ///
+    /// ```
+    /// use csv_reader_core::actor::{KindFilter, Orders, ReaderOptions};
+    ///
+    /// let filter: KindFilter = "deposit".parse().unwrap();
+    /// let data = "type,client,tx,amount\ndeposit,1,1,2\ndispute,1,1,\n";
+    /// let options = ReaderOptions {
+    ///     only_kinds: Some(filter.clone()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(Orders::new(Box::new(data.as_bytes()), options).count(), 1);
+    /// assert_eq!(filter.skipped(), vec![("dispute", 1)]);
+    /// ```
+    pub fn skipped(&self) -> Vec<(&'static str, u64)> {
+        KIND_NAMES
+            .into_iter()
+            .zip(self.skipped.iter())
+            .map(|(name, count)| (name, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+impl FromStr for KindFilter {
+    type Err = anyhow::Error;
+
+    /// Parse the comma separated names of the kept kinds.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::default();
+
+        for name in value.split(',').map(str::trim) {
+            let Some(position) = KIND_NAMES
+                .iter()
+                .position(|kind| kind.eq_ignore_ascii_case(name))
+            else {
+                bail!(
+                    "Unknown transaction kind '{name}' (expected {}).",
+                    KIND_NAMES.join(", ")
+                );
+            };
+            filter.kept[position] = true;
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Position of a transaction kind in [KIND_NAMES].
+pub(crate) fn kind_position(kind: &TransactionKind) -> usize {
+    match kind {
+        TransactionKind::Deposit(_) => 0,
+        TransactionKind::Withdrawal(_) => 1,
+        TransactionKind::Dispute(_) => 2,
+        TransactionKind::Resolve(_) => 3,
+        TransactionKind::ChargeBack(_) => 4,
+        TransactionKind::Unlock => 5,
+        TransactionKind::Open => 6,
+        TransactionKind::Close => 7,
+        TransactionKind::Adjustment(_) => 8,
+    }
+}
+
+/// Filter of the duplicate rows delivered by an upstream retrying its
+/// deliveries. A row is a duplicate when one of the recent rows has the same
+/// transaction and kind, whatever its other fields. Only the keys of the last
+/// rows are kept, so memory stays bounded on endless inputs. Dropped rows
+/// still count in the control totals. The clones of a filter share its recent
+/// keys and the count of dropped rows.
+#[derive(Debug, Clone)]
+pub struct DuplicateFilter {
+    /// The keys of the recent rows.
+    recent: Arc<Mutex<RecentKeys>>,
+
+    /// The number of dropped rows.
+    dropped: Arc<AtomicU64>,
+}
+
+/// The transaction and kind keys of the last rows.
+#[derive(Debug, Default)]
+struct RecentKeys {
+    /// The keys, for lookups.
+    keys: HashSet<(TxId, usize)>,
+
+    /// The keys from the oldest to the newest, to forget the oldest.
+    order: VecDeque<(TxId, usize)>,
+
+    /// The maximum number of keys kept.
+    capacity: usize,
+}
+
+impl DuplicateFilter {
+    /// Create a filter remembering the keys of the given number of last
+    /// rows, at least one.
+    ///
+    /// ```
+    /// use csv_reader_core::actor::{DuplicateFilter, Orders, ReaderOptions};
+    ///
+    /// let filter = DuplicateFilter::new(1000);
+    /// let data = "type,client,tx,amount\ndeposit,1,1,2\ndeposit,1,1,2\ndispute,1,1,\n";
+    /// let options = ReaderOptions {
+    ///     dedup: Some(filter.clone()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(Orders::new(Box::new(data.as_bytes()), options).count(), 2);
+    /// assert_eq!(filter.dropped(), 1);
+    /// ```
+    pub fn new(capacity: usize) -> Self {
+        let recent = RecentKeys {
+            capacity: capacity.max(1),
+            ..Default::default()
+        };
+
+        Self {
+            recent: Arc::new(Mutex::new(recent)),
+            dropped: Arc::default(),
+        }
+    }
+
+    /// Tell if the given order is not a duplicate, counting the dropped ones.
+    fn keep(&self, order: &TransactionOrder) -> bool {
+        let key = (order.tx_id, kind_position(&order.kind));
+        let mut recent = self.recent.lock().unwrap();
+        if !recent.keys.insert(key) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        recent.order.push_back(key);
+        if recent.order.len() > recent.capacity {
+            if let Some(oldest) = recent.order.pop_front() {
+                recent.keys.remove(&oldest);
+            }
+        }
+
+        true
+    }
+
+    /// Get the number of duplicate rows dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Options driving how the reader parses the CSV input.
+#[derive(Debug, Clone)]
+pub struct ReaderOptions {
+    /// The field delimiter, `b','` by default. Use `b'\t'` for TSV files or
+    /// `b';'` for semicolon separated files.
+    pub delimiter: u8,
+
+    /// The character quoting the fields, `b'"'` by default.
+    pub quote: u8,
+
+    /// Detect the dialect of the input from its first kilobytes, see
+    /// [Dialect::sniff]. The detected delimiter, quote and header presence
+    /// replace the `delimiter`, `quote` and `columns` options.
+    pub sniff: bool,
+
+    /// When set, the input has no header line and the fields are found at the
+    /// given positions.
+    pub columns: Option<ColumnPositions>,
+
+    /// Renaming of the input header names to the expected `type`, `client`,
+    /// `tx` and `amount` field names (e.g. `txn_type` → `type`). Headers that
+    /// are not in the mapping are kept as is.
+    pub header_mapping: HashMap<String, String>,
+
+    /// How the control totals of the trailer record are verified.
+    pub trailer: TrailerPolicy,
+
+    /// How rows with more columns than the header, or than the last column
+    /// position, are handled.
+    pub extra_columns: ExtraColumns,
+
+    /// How rows lacking the trailing amount column are handled. Rows missing
+    /// any other column are rejected.
+    pub missing_amount: MissingAmount,
+
+    /// How the amounts of the rows and of the trailer record are written.
+    pub amount_format: AmountFormat,
+
+    /// How the timestamps of the `timestamp` column are written.
+    pub timestamp_format: TimestampFormat,
+
+    /// The timezone of the timestamps without offset, UTC by default.
+    pub timezone: FixedOffset,
+
+    /// What to do with the records older than a previous one.
+    pub timestamp_order: TimestampOrder,
+
+    /// The maximum number of decimal places of the amounts, [MAX_DECIMALS] by
+    /// default. The rows with more precise amounts are rejected rather than
+    /// rounded.
+    pub max_decimals: u32,
+
+    /// When set, only the orders of these kinds are sent.
+    pub only_kinds: Option<KindFilter>,
+
+    /// When set, the duplicate rows are dropped before reaching the
+    /// accountant.
+    pub dedup: Option<DuplicateFilter>,
+
+    /// Recover from the rows with unbalanced quotes: a record spanning several
+    /// lines is considered malformed, its first line is skipped and reported
+    /// with its byte range, and the reading resumes on the next line. Quoted
+    /// fields cannot hold line breaks then.
+    pub resync_lines: bool,
+
+    /// The transformations applied in turn to the records before they are
+    /// parsed.
+    pub transformers: Vec<Arc<dyn RowTransformer>>,
+
+    /// Number of data rows skipped at the start of the input. The control
+    /// totals only cover the rows read.
+    pub skip: u64,
+
+    /// When set, the reading stops after this number of data rows, not
+    /// counting the skipped ones.
+    pub limit: Option<u64>,
+
+    /// Byte offset of the input where the reading starts, to resume an
+    /// interrupted reading from its [ReaderProgress::resume_offset]. The header
+    /// line is still read, the bytes between it and the offset are discarded
+    /// without being parsed.
+    pub start_offset: u64,
+
+    /// Fail the reading on the first invalid record, with its line, instead
+    /// of logging and skipping it.
+    pub strict: bool,
+
+    /// When set, the rejected records are written to this sink along with the
+    /// reason of their rejection.
+    pub rejects: Option<RejectSink>,
+
+    /// The character encoding of the input, UTF-8 by default. Other inputs are
+    /// transcoded to UTF-8 before being parsed, the byte offsets and the bytes
+    /// read are then counted on the transcoded input. A byte order mark at the
+    /// start of the input overrides the encoding, it is removed and not
+    /// counted.
+    pub encoding: InputEncoding,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            sniff: false,
+            columns: None,
+            header_mapping: HashMap::new(),
+            trailer: TrailerPolicy::default(),
+            extra_columns: ExtraColumns::default(),
+            missing_amount: MissingAmount::default(),
+            amount_format: AmountFormat::default(),
+            timestamp_format: TimestampFormat::default(),
+            timezone: Utc.fix(),
+            timestamp_order: TimestampOrder::default(),
+            max_decimals: MAX_DECIMALS,
+            only_kinds: None,
+            dedup: None,
+            resync_lines: false,
+            transformers: Vec::new(),
+            skip: 0,
+            limit: None,
+            start_offset: 0,
+            strict: false,
+            rejects: None,
+            encoding: InputEncoding::default(),
+        }
+    }
+}
+
+/// Progress of the reading of an input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReaderProgress {
+    /// Number of bytes read from the input.
+    pub bytes_read: u64,
+
+    /// Number of records parsed into transaction orders.
+    pub records_parsed: u64,
+
+    /// Number of invalid records.
+    pub records_rejected: u64,
+
+    /// Byte offset of the input following the last record parsed into a
+    /// transaction order, where an interrupted reading can be resumed with
+    /// [ReaderOptions::start_offset].
+    pub resume_offset: u64,
+}
+
+/// Calls a progress callback at a given interval.
+pub(crate) struct ProgressReporter {
+    /// The minimum delay between two calls.
+    interval: Duration,
+
+    /// The progress callback.
+    callback: Box<dyn FnMut(ReaderProgress) + Send>,
+
+    /// When the callback was last called.
+    last_report: Instant,
+}
+
+impl ProgressReporter {
+    /// Create a reporter calling the given callback at most once per
+    /// interval.
+    pub(crate) fn new(
+        interval: Duration,
+        callback: impl FnMut(ReaderProgress) + Send + 'static,
+    ) -> Self {
+        Self {
+            interval,
+            callback: Box::new(callback),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Report the progress if the interval elapsed since the last report, or
+    /// if forced to.
+    pub(crate) fn report(&mut self, progress: ReaderProgress, force: bool) {
+        if force || self.last_report.elapsed() >= self.interval {
+            (self.callback)(progress);
+            self.last_report = Instant::now();
+        }
+    }
+}
+
+/// Limits the rate at which the orders are sent.
+pub(crate) struct Throttle {
+    /// The maximum number of orders sent per second.
+    rate: NonZeroU32,
+
+    /// When the first order was sent.
+    started: Option<Instant>,
+
+    /// The number of orders sent so far.
+    sent: u64,
+}
+
+impl Throttle {
+    /// Create a throttle sending at most the given number of orders per
+    /// second.
+    pub(crate) fn new(rate: NonZeroU32) -> Self {
+        Self {
+            rate,
+            started: None,
+            sent: 0,
+        }
+    }
+
+    /// Wait until the next order can be sent. The rate is averaged since the
+    /// first order, so a slow consumer is caught up with a burst.
+    pub(crate) fn wait(&mut self) {
+        let delay = self.delay(Instant::now());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Get how long to wait at the given time before sending the next order,
+    /// which is counted as sent.
+    fn delay(&mut self, now: Instant) -> Duration {
+        let started = *self.started.get_or_insert(now);
+        let rate = self.rate.get();
+        // the remainder is below the rate so it fits its type
+        let due = Duration::from_secs(self.sent / u64::from(rate))
+            + Duration::from_secs(1) * (self.sent % u64::from(rate)) as u32 / rate;
+        self.sent += 1;
+
+        due.saturating_sub(now.saturating_duration_since(started))
+    }
+}
+
+/// Reader actor.
+pub struct Reader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+    reader: Box<dyn Read + Sync + Send>,
+
+    /// The parsing options.
+    options: ReaderOptions,
+
+    /// The number of threads parsing the input.
+    workers: usize,
+
+    /// The size in bytes of the chunks parsed by the workers.
+    chunk_size: usize,
+
+    /// The progress reporting, if any.
+    progress: Option<ProgressReporter>,
+
+    /// The maximum number of orders sent per second, if any.
+    max_rate: Option<NonZeroU32>,
+}
+
+impl Reader {
+    /// Create a new reader actor with the default options.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self::with_options(order_sender, reader, ReaderOptions::default())
+    }
+
+    /// Create a new reader actor with the given parsing options.
+    ///
+    /// ```
+    ///
+    /// use csv_reader_core::actor::{channel, Reader, ReaderOptions};
+    ///
+    /// let (sender, receiver) = channel();
+    /// let data = "type;client;tx;amount\ndeposit;1;1;1.5\n";
+    /// let options = ReaderOptions {
+    ///     delimiter: b';',
+    ///     ..Default::default()
+    /// };
+    /// Reader::with_options(sender, Box::new(data.as_bytes()), options)
+    ///     .run()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(receiver.iter().count(), 1);
+    /// ```
+    pub fn with_options(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+        options: ReaderOptions,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader,
+            options,
+            workers: 1,
+            chunk_size: CHUNK_SIZE,
+            progress: None,
+            max_rate: None,
+        }
+    }
+
+    /// Call the given callback with the progress of the reading at most once
+    /// per interval while orders are sent, and once when the reading is over,
+    /// even if it failed. With several workers, the progress is reported once
+    /// per chunk at most.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// use csv_reader_core::actor::{channel, Reader, ReaderProgress};
+    ///
+    /// let (sender, _receiver) = channel();
+    /// let data = "type,client,tx,amount\ndeposit,1,1,1.5\nwhatever,1,2,1\n";
+    /// let last = Arc::new(Mutex::new(ReaderProgress::default()));
+    /// let progress = last.clone();
+    /// Reader::new(sender, Box::new(data.as_bytes()))
+    ///     .with_progress(Duration::from_secs(1), move |p| *progress.lock().unwrap() = p)
+    ///     .run()
+    ///     .unwrap();
+    ///
+    /// let last = *last.lock().unwrap();
+    /// assert_eq!((last.bytes_read, last.resume_offset), (53, 38));
+    /// assert_eq!((last.records_parsed, last.records_rejected), (1, 1));
+    /// ```
+    pub fn with_progress(
+        mut self,
+        interval: Duration,
+        callback: impl FnMut(ReaderProgress) + Send + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressReporter::new(interval, callback));
+
+        self
+    }
+
+    /// Parse the input with the given number of threads. The input is split
+    /// into chunks ending on a line boundary, so records must not hold line
+    /// breaks in quoted fields. The orders are still sent in the input order.
+    /// The iterator returned by [Reader::into_iter] always parses the input
+    /// sequentially, as well as the reader skipping or limiting the rows or
+    /// reading in strict mode.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+
+        self
+    }
+
+    /// Set the size in bytes of the chunks parsed by the workers, 4MiB by
+    /// default. A chunk is extended up to the end of its last line.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+
+        self
+    }
+
+    /// Send at most the given number of orders per second, so a storage
+    /// slower than the parsing, like a database, is not flooded. The rate is
+    /// averaged since the first order sent.
+    ///
+    /// ```
+    /// use std::num::NonZeroU32;
+    ///
+    /// use csv_reader_core::actor::{channel, Reader};
+    ///
+    /// let (sender, receiver) = channel();
+    /// let data = "type,client,tx,amount\ndeposit,1,1,1\ndeposit,1,2,1\ndeposit,1,3,1\n";
+    /// Reader::new(sender, Box::new(data.as_bytes()))
+    ///     .with_max_rate(NonZeroU32::new(100).unwrap())
+    ///     .run()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(receiver.iter().count(), 3);
+    /// ```
+    pub fn with_max_rate(mut self, records_per_second: NonZeroU32) -> Self {
+        self.max_rate = Some(records_per_second);
+
+        self
+    }
+
+    /// Run the reader actor.
+    /// The actor will read the CSV file line by line and send the transaction
+    /// orders to the accountant actor through the order channel. Depending on
+    /// the [TrailerPolicy], the control totals of the trailer are verified once
+    /// the whole input is read.
+    pub fn run(self) -> crate::Result<()> {
+        run_actor(self)
+    }
+
+    /// Read the whole input, sending the orders.
+    fn read(self) -> crate::Result<()> {
+        if self.workers > 1
+            && self.options.skip == 0
+            && self.options.limit.is_none()
+            && !self.options.strict
+            && self.options.timestamp_order == TimestampOrder::Ignore
+        {
+            return self.run_parallel();
+        }
+        let mut orders = Orders::new(self.reader, self.options);
+        let mut progress = self.progress;
+        let mut throttle = self.max_rate.map(Throttle::new);
+        let result = send_orders(
+            &mut orders,
+            &self.order_sender,
+            progress.as_mut(),
+            throttle.as_mut(),
+        );
+
+        if let Some(progress) = &mut progress {
+            progress.report(orders.progress(), true);
+        }
+
+        result
+    }
+}
+
+impl Actor for Reader {
+    type Message = ();
+    type Output = ();
+
+    const NAME: &'static str = "Reader";
+
+    fn on_stop(self) -> crate::Result<()> {
+        self.read()
+    }
+}
+
+/// Send the orders of a source to the accountant, reporting the progress on
+/// the way and waiting for the throttle, if any, before each order.
+pub(crate) fn send_orders(
+    source: &mut dyn InputSource,
+    order_sender: &Sender<TransactionOrder>,
+    mut progress: Option<&mut ProgressReporter>,
+    mut throttle: Option<&mut Throttle>,
+) -> crate::Result<()> {
+    while let Some(order) = source.next_order() {
+        let order = order?;
+        if let Some(throttle) = throttle.as_mut() {
+            throttle.wait();
+        }
+        order_sender.send(order)?;
+
+        if let (Some(progress), Some(read)) = (progress.as_mut(), source.progress()) {
+            progress.report(read, false);
+        }
+    }
+
+    Ok(())
+}
+
+impl Reader {
+    /// Split the input into chunks parsed by the workers and send the orders
+    /// of every chunk once all the previous chunks have been sent.
+    fn run_parallel(mut self) -> crate::Result<()> {
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        // the chunks are parsed from the transcoded input
+        let encoding = std::mem::take(&mut self.options.encoding);
+        let mut input = encoding.decode(self.reader)?;
+        if self.options.sniff {
+            input = Dialect::sniff_input(input, &mut self.options)?;
+        }
+        let mut input = BufReader::new(ByteCounter {
+            input,
+            count: bytes_read.clone(),
+        });
+        let mut header = Vec::new();
+        if self.options.columns.is_none() {
+            input.read_until(b'\n', &mut header)?;
+        }
+        let skipped = self
+            .options
+            .start_offset
+            .saturating_sub(header.len() as u64);
+        io::copy(&mut (&mut input).take(skipped), &mut io::sink())?;
+        let offset = header.len() as u64 + skipped;
+        let mut template = Orders::new(Box::new(Cursor::new(header)), self.options);
+        if let Some(error) = template.error.take() {
+            return Err(error);
+        }
+        let (chunk_sender, chunk_receiver) = sync_channel(self.workers);
+        // dropped with the last worker so the splitter stops if they stop early
+        let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+        let (parsed_sender, parsed_receiver) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            let splitter =
+                scope.spawn(|| split_chunks(input, offset, self.chunk_size, chunk_sender));
+            for _ in 0..self.workers {
+                let parsed_sender = parsed_sender.clone();
+                let chunk_receiver = chunk_receiver.clone();
+                let template = &template;
+                scope.spawn(move || loop {
+                    let Ok((index, offset, data)) = chunk_receiver.lock().unwrap().recv() else {
+                        break;
+                    };
+                    if parsed_sender
+                        .send((index, template.parse_chunk(offset, data)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                });
+            }
+            drop((parsed_sender, chunk_receiver));
+
+            let mut progress = self.progress;
+            let merger = Merger {
+                order_sender: &self.order_sender,
+                trailer_policy: &template.options.trailer,
+                dedup: template.options.dedup.as_ref(),
+                bytes_read: &bytes_read,
+                start_offset: offset,
+                progress: progress.as_mut(),
+                throttle: self.max_rate.map(Throttle::new),
+            };
+            merger.merge(parsed_receiver)?;
+            splitter.join().expect("Chunk splitter thread panicked")
+        })
+    }
+}
+
+/// The outcome of the parsing of a chunk of the input.
+struct ParsedChunk {
+    /// The valid orders of the chunk.
+    orders: Vec<TransactionOrder>,
+
+    /// The control totals of the trailer, if in the chunk.
+    trailer: Option<Trailer>,
+
+    /// The control totals of the records of the chunk.
+    totals: ControlTotals,
+
+    /// The error that stopped the parsing of the chunk.
+    error: Option<anyhow::Error>,
+
+    /// The number of parsed and rejected records of the chunk.
+    progress: ReaderProgress,
+}
+
+/// Read the input by chunks of whole lines and number them in the input
+/// order along with their offset in the input. Stops when the workers are
+/// gone.
+fn split_chunks(
+    mut input: impl Read,
+    mut offset: u64,
+    chunk_size: usize,
+    chunk_sender: SyncSender<(usize, u64, Vec<u8>)>,
+) -> crate::Result<()> {
+    let mut index = 0;
+    let mut chunk = Vec::new();
+
+    loop {
+        let length = chunk.len();
+        (&mut input)
+            .take(chunk_size as u64)
+            .read_to_end(&mut chunk)?;
+        let end_of_input = chunk.len() < length + chunk_size;
+        let rest = match chunk.iter().rposition(|byte| *byte == b'\n') {
+            _ if end_of_input => Vec::new(),
+            // a line longer than a chunk, keep reading it
+            None => continue,
+            Some(position) => chunk.split_off(position + 1),
+        };
+        let length = chunk.len() as u64;
+        if length > 0 && chunk_sender.send((index, offset, chunk)).is_err() {
+            return Ok(());
+        }
+        if end_of_input {
+            return Ok(());
+        }
+        index += 1;
+        offset += length;
+        chunk = rest;
+    }
+}
+
+/// Merger of the parsed chunks.
+struct Merger<'a> {
+    /// The order channel sender to send transaction orders.
+    order_sender: &'a Sender<TransactionOrder>,
+
+    /// How the control totals of the whole input are verified.
+    trailer_policy: &'a TrailerPolicy,
+
+    /// The filter of the duplicate rows, if any.
+    dedup: Option<&'a DuplicateFilter>,
+
+    /// The number of bytes read from the input.
+    bytes_read: &'a AtomicU64,
+
+    /// The offset of the first chunk in the input.
+    start_offset: u64,
+
+    /// The progress reporting, if any.
+    progress: Option<&'a mut ProgressReporter>,
+
+    /// The limit of the rate of the orders sent, if any.
+    throttle: Option<Throttle>,
+}
+
+impl Merger<'_> {
+    /// Send the orders of the parsed chunks in the input order, then verify
+    /// the control totals of the whole input.
+    fn merge(mut self, parsed_receiver: mpsc::Receiver<(usize, ParsedChunk)>) -> crate::Result<()> {
+        let mut records = ReaderProgress {
+            resume_offset: self.start_offset,
+            ..Default::default()
+        };
+        let result = self.send_chunks(parsed_receiver, &mut records);
+
+        self.report(records, true);
+
+        result
+    }
+
+    fn send_chunks(
+        &mut self,
+        parsed_receiver: mpsc::Receiver<(usize, ParsedChunk)>,
+        records: &mut ReaderProgress,
+    ) -> crate::Result<()> {
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+        let mut trailer = None;
+        let mut totals = ControlTotals::default();
+
+        for (index, chunk) in parsed_receiver {
+            pending.insert(index, chunk);
+
+            while let Some(chunk) = pending.remove(&next_index) {
+                for order in chunk.orders {
+                    if self.dedup.is_some_and(|filter| !filter.keep(&order)) {
+                        continue;
+                    }
+                    if let Some(throttle) = self.throttle.as_mut() {
+                        throttle.wait();
+                    }
+                    self.order_sender.send(order)?;
+                }
+                records.records_parsed += chunk.progress.records_parsed;
+                records.records_rejected += chunk.progress.records_rejected;
+                records.resume_offset = records.resume_offset.max(chunk.progress.resume_offset);
+                if let Some(error) = chunk.error {
+                    return Err(error);
+                }
+                trailer = chunk.trailer.or(trailer);
+                totals += chunk.totals;
+                next_index += 1;
+                self.report(*records, false);
+            }
+        }
+
+        self.trailer_policy.verify(trailer, totals)
+    }
+
+    /// Report the progress with the given record counts.
+    fn report(&mut self, records: ReaderProgress, force: bool) {
+        if let Some(progress) = self.progress.as_mut() {
+            let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+            progress.report(
+                ReaderProgress {
+                    bytes_read,
+                    ..records
+                },
+                force,
+            );
+        }
+    }
+}
+
+impl IntoIterator for Reader {
+    type Item = crate::Result<TransactionOrder>;
+    type IntoIter = Orders;
+
+    /// Parse the input without sending the orders, the order channel is not
+    /// used.
+    fn into_iter(self) -> Self::IntoIter {
+        Orders::new(self.reader, self.options)
+    }
+}
+
+/// An invalid record found when validating an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRecord {
+    /// The line of the record, starting at 1, in the parsed part of the input.
+    pub line: u64,
+
+    /// Why the record is invalid.
+    pub error: String,
+}
+
+/// The outcome of the validation of an input by [Orders::validate].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Number of records parsed into transaction orders.
+    pub valid_records: u64,
+
+    /// The invalid records in the input order.
+    pub invalid_records: Vec<InvalidRecord>,
+
+    /// The error that stopped the validation (unreadable header, malformed
+    /// trailer, control totals mismatch), if any.
+    pub error: Option<String>,
+}
+
+impl ValidationReport {
+    /// Tell if the input has no invalid record and could be fully read.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_records.is_empty() && self.error.is_none()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} valid records, {} invalid records.",
+            self.valid_records,
+            self.invalid_records.len()
+        )?;
+        for record in &self.invalid_records {
+            writeln!(f, "line {}: {}", record.line, record.error)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Validation stopped: {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the transaction orders of a CSV input, for single threaded
+/// uses where no channel is needed. Invalid records are logged and skipped,
+/// errors are only yielded for failures that stop the parsing (unreadable
+/// header, malformed trailer, control totals mismatch) and end the iteration.
+///
+/// ```
+/// use csv_reader_core::actor::{Orders, ReaderOptions};
+///
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.5\nwhatever,1,2,1\n";
+/// let orders = Orders::new(Box::new(data.as_bytes()), ReaderOptions::default())
+///     .collect::<csv_reader_core::Result<Vec<_>>>()
+///     .unwrap();
+///
+/// assert_eq!(orders.len(), 1);
+/// ```
+pub struct Orders {
+    /// The records of the input.
+    records: ByteRecordsIntoIter<Box<dyn Read + Sync + Send>>,
+
+    /// The positions of the fields in the records.
+    fields: FieldPositions,
+
+    /// The expected number of columns of the records.
+    width: usize,
+
+    /// The amount is the last column of the records.
+    amount_is_last: bool,
+
+    /// The parsing options.
+    options: ReaderOptions,
+
+    /// The control totals of the trailer, if read.
+    trailer: Option<Trailer>,
+
+    /// The control totals of the records read so far.
+    totals: ControlTotals,
+
+    /// An error to yield before ending the iteration.
+    error: Option<anyhow::Error>,
+
+    /// The iteration is over.
+    done: bool,
+
+    /// The raw input, recorded to recover from malformed rows.
+    recording: Option<Arc<Mutex<Recording>>>,
+
+    /// The records read again after a malformed row, along with their line
+    /// and the offset of their end in the parsed bytes.
+    replayed: VecDeque<(u64, u64, crate::Result<ByteRecord>)>,
+
+    /// The offset of the parsed bytes in the input.
+    offset: u64,
+
+    /// The offset of the end of the last record read in the parsed bytes.
+    record_end: u64,
+
+    /// The line of the last record read in the parsed bytes.
+    record_line: u64,
+
+    /// The invalid records, collected when validating the input.
+    invalid: Option<Vec<InvalidRecord>>,
+
+    /// The number of bytes read from the input.
+    bytes_read: Arc<AtomicU64>,
+
+    /// The number of parsed and rejected records.
+    counts: ReaderProgress,
+
+    /// The number of data rows read, skipped ones included.
+    rows_read: u64,
+
+    /// The latest timestamp of the records read so far.
+    last_timestamp: Option<Timestamp>,
+}
+
+impl Orders {
+    /// Parse and validate the whole input without sending the orders
+    /// anywhere, so no account state changes, and report the invalid records.
+    /// The trailer control totals are verified according to the
+    /// [TrailerPolicy].
+    ///
+    /// ```
+    /// use csv_reader_core::actor::{Orders, ReaderOptions};
+    ///
+    /// let data = "type,client,tx,amount\ndeposit,1,1,1.5\nwhatever,1,2,1\ndeposit,1,3,-1\n";
+    /// let report = Orders::new(Box::new(data.as_bytes()), ReaderOptions::default()).validate();
+    ///
+    /// assert!(!report.is_valid());
+    /// assert_eq!(report.valid_records, 1);
+    /// assert_eq!(report.invalid_records[0].line, 3);
+    /// assert_eq!(report.invalid_records[1].line, 4);
+    /// ```
+    pub fn validate(mut self) -> ValidationReport {
+        self.invalid = Some(Vec::new());
+        // all the invalid records are reported, even in strict mode
+        self.options.strict = false;
+        let error = self.by_ref().find_map(Result::err);
+
+        ValidationReport {
+            valid_records: self.counts.records_parsed,
+            invalid_records: self.invalid.take().unwrap_or_default(),
+            error: error.map(|error| error.to_string()),
+        }
+    }
+
+    /// Get the progress of the reading so far.
+    pub fn progress(&self) -> ReaderProgress {
+        ReaderProgress {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            ..self.counts
+        }
+    }
+
+    /// Create an iterator over the transaction orders of the given input.
+    pub fn new(reader: Box<dyn Read + Sync + Send>, mut options: ReaderOptions) -> Self {
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let decoded = options
+            .encoding
+            .decode(reader)
+            .and_then(|reader| match options.sniff {
+                true => Dialect::sniff_input(reader, &mut options),
+                false => Ok(reader),
+            });
+        let (reader, decode_error) = match decoded {
+            Ok(reader) => (reader, None),
+            Err(error) => (Box::new(io::empty()) as Box<_>, Some(error.into())),
+        };
+        let reader = Box::new(ByteCounter {
+            input: reader,
+            count: bytes_read.clone(),
+        });
+        let (reader, offset, skip_error) =
+            match skip_to(reader, options.start_offset, options.columns.is_none()) {
+                Ok((reader, offset)) => (reader, offset, None),
+                Err(error) => (Box::new(io::empty()) as Box<_>, 0, Some(error.into())),
+            };
+        let (reader, recording) = Recording::wrap(reader, options.resync_lines);
+        let mut csv_reader = csv_reader_builder(&options)
+            .has_headers(options.columns.is_none())
+            .from_reader(reader);
+        let headers: crate::Result<StringRecord> = match options.columns {
+            Some(_) => Ok(StringRecord::new()),
+            None => csv_reader
+                .headers()
+                .map_err(anyhow::Error::from)
+                .and_then(|headers| {
+                    if let Some(rejects) = &options.rejects {
+                        rejects.write_header(headers)?;
+                    }
+
+                    Ok(headers
+                        .iter()
+                        .map(|name| {
+                            options
+                                .header_mapping
+                                .get(name)
+                                .map_or(name, String::as_str)
+                        })
+                        .collect())
+                }),
+        };
+        let (headers, error) = match headers {
+            Ok(headers) => (headers, None),
+            Err(error) => (StringRecord::new(), Some(error)),
+        };
+        let (fields, width, amount_is_last) = match &options.columns {
+            Some(columns) => (
+                columns.into(),
+                columns.width(),
+                columns.amount + 1 == columns.width(),
+            ),
+            None => (
+                FieldPositions::from_headers(&headers),
+                headers.len(),
+                headers.iter().next_back() == Some("amount"),
+            ),
+        };
+
+        let counts = ReaderProgress {
+            resume_offset: options.start_offset,
+            ..Default::default()
+        };
+
+        Self {
+            records: csv_reader.into_byte_records(),
+            fields,
+            width,
+            amount_is_last,
+            options,
+            trailer: None,
+            totals: ControlTotals::default(),
+            error: decode_error.or(skip_error).or(error),
+            done: false,
+            recording,
+            replayed: VecDeque::new(),
+            offset,
+            record_end: 0,
+            record_line: 0,
+            invalid: None,
+            bytes_read,
+            counts,
+            rows_read: 0,
+            last_timestamp: None,
+        }
+    }
+
+    /// Parse the next record, `None` if it is the trailer or if it is invalid.
+    fn parse(
+        &mut self,
+        result: crate::Result<ByteRecord>,
+    ) -> crate::Result<Option<TransactionOrder>> {
+        let raw = match (&self.options.rejects, &result) {
+            (Some(_), Ok(record)) => Some(record.clone()),
+            _ => None,
+        };
+        let record = match result
+            .and_then(|record| self.fit(record))
+            .and_then(|record| self.transform(record))
+        {
+            Err(error) => {
+                log::info!("Error reading CSV record: {}", error);
+                self.reject(error, raw.as_ref())?;
+                None
+            }
+            Ok(record) => Some(record),
+        };
+        if self.options.trailer != TrailerPolicy::Ignore {
+            if let Some(record) = &record {
+                if self.fields.is_trailer(record) {
+                    self.trailer = Some(
+                        self.fields
+                            .parse_trailer(record, self.options.amount_format)?,
+                    );
+                    return Ok(None);
+                }
+            }
+            self.totals.rows += 1;
+        }
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        let order = match self.fields.parse_order(&record, &self.options) {
+            Err(error) => {
+                log::info!("Error parsing CSV record: {}", error);
+                self.reject(error, raw.as_ref())?;
+                return Ok(None);
+            }
+            Ok(order) => order,
+        };
+        self.check_order(order.timestamp)?;
+        self.counts.records_parsed += 1;
+        self.counts.resume_offset = self.offset + self.record_end;
+        self.totals.record(&order.kind);
+
+        match (&self.options.only_kinds, &self.options.dedup) {
+            (Some(filter), _) if !filter.keep(&order.kind) => Ok(None),
+            (_, Some(filter)) if !filter.keep(&order) => Ok(None),
+            _ => Ok(Some(order)),
+        }
+    }
+}
+
+impl Orders {
+    /// Parse a chunk of headerless lines of the input the same way as this
+    /// iterator.
+    fn parse_chunk(&self, offset: u64, data: Vec<u8>) -> ParsedChunk {
+        let (reader, recording) =
+            Recording::wrap(Box::new(Cursor::new(data)), self.options.resync_lines);
+        let mut orders = Self {
+            records: csv_reader_builder(&self.options)
+                .has_headers(false)
+                .from_reader(reader)
+                .into_byte_records(),
+            fields: self.fields,
+            width: self.width,
+            amount_is_last: self.amount_is_last,
+            // the duplicates are dropped in the input order by the merger
+            options: ReaderOptions {
+                dedup: None,
+                ..self.options.clone()
+            },
+            trailer: None,
+            totals: ControlTotals::default(),
+            error: None,
+            done: false,
+            recording,
+            replayed: VecDeque::new(),
+            offset,
+            record_end: 0,
+            record_line: 0,
+            invalid: None,
+            bytes_read: Arc::default(),
+            counts: ReaderProgress::default(),
+            rows_read: 0,
+            last_timestamp: None,
+        };
+        let mut parsed = Vec::new();
+
+        while let Some(result) = orders.next_record() {
+            match orders.parse(result) {
+                Ok(Some(order)) => parsed.push(order),
+                Ok(None) => continue,
+                Err(error) => {
+                    orders.error = Some(error);
+                    break;
+                }
+            }
+        }
+
+        ParsedChunk {
+            orders: parsed,
+            trailer: orders.trailer,
+            totals: orders.totals,
+            error: orders.error,
+            progress: orders.counts,
+        }
+    }
+
+    /// Read the next record. When recovering from malformed rows, a record
+    /// spanning several lines is split into lines: the first one is rejected
+    /// and the following ones are read again one by one.
+    fn next_record(&mut self) -> Option<crate::Result<ByteRecord>> {
+        if let Some((line, end, result)) = self.replayed.pop_front() {
+            self.record_line = line;
+            self.record_end = end;
+            return Some(result);
+        }
+        let record = match self.records.next()? {
+            Err(error) => {
+                self.record_line = self.records.reader().position().line();
+                return Some(Err(error.into()));
+            }
+            Ok(record) => record,
+        };
+        let end = self.records.reader().position().byte();
+        self.record_end = end;
+        self.record_line = record.position().map_or(0, csv::Position::line);
+        let Some(recording) = &self.recording else {
+            return Some(Ok(record));
+        };
+        let start = record.position().map_or(0, csv::Position::byte);
+        let mut recording = recording.lock().unwrap();
+
+        if !spans_lines(&record) {
+            recording.forget(start);
+            return Some(Ok(record));
+        }
+        let raw = recording.get(start..end).to_vec();
+        recording.forget(end);
+        drop(recording);
+
+        Some(self.resync(start, &raw))
+    }
+
+    /// Split the raw bytes of a record spanning several lines, starting at
+    /// the given position. The first line, holding the unbalanced quote, is
+    /// rejected, the following lines are queued to be read again.
+    fn resync(&mut self, start: u64, raw: &[u8]) -> crate::Result<ByteRecord> {
+        let mut lines = raw.split_inclusive(|byte| *byte == b'\n');
+        let malformed = lines.next().unwrap_or_default();
+        let mut line_start = start + malformed.len() as u64;
+        let mut line_number = self.record_line;
+        self.record_end = line_start;
+
+        for line in lines {
+            line_number += 1;
+            let mut record = ByteRecord::new();
+            let result = csv_reader_builder(&self.options)
+                .has_headers(false)
+                .from_reader(line)
+                .read_byte_record(&mut record);
+            let line_end = line_start + line.len() as u64;
+            match result {
+                Ok(false) => {}
+                Ok(true) if spans_lines(&record) => {
+                    let error = self.malformed(line_start..line_end);
+                    self.replayed.push_back((line_number, line_end, Err(error)));
+                }
+                Ok(true) => self.replayed.push_back((line_number, line_end, Ok(record))),
+                Err(error) => {
+                    let error = error.into();
+                    self.replayed.push_back((line_number, line_end, Err(error)));
+                }
+            }
+            line_start = line_end;
+        }
+
+        Err(self.malformed(start..start + malformed.len() as u64))
+    }
+
+    /// Check the timestamps of the records are in chronological order,
+    /// according to the [TimestampOrder] policy.
+    fn check_order(&mut self, timestamp: Option<Timestamp>) -> crate::Result<()> {
+        let Some(timestamp) = timestamp else {
+            return Ok(());
+        };
+        match self.last_timestamp {
+            Some(last) if timestamp < last => {
+                let message = format!(
+                    "Record at line {} is older than a previous one ({timestamp} < {last}).",
+                    self.record_line
+                );
+                match self.options.timestamp_order {
+                    TimestampOrder::Ignore => {}
+                    TimestampOrder::Flag => log::warn!("{}", message),
+                    TimestampOrder::Fail => bail!(message),
+                }
+            }
+            _ => self.last_timestamp = Some(timestamp),
+        }
+
+        Ok(())
+    }
+
+    /// Count an invalid record given as read, if it could be read, write it to
+    /// the rejects sink and collect it when validating the input. In strict
+    /// mode, the error stopping the reading is returned instead.
+    fn reject(&mut self, error: anyhow::Error, raw: Option<&ByteRecord>) -> crate::Result<()> {
+        self.counts.records_rejected += 1;
+
+        if let Some(rejects) = &self.options.rejects {
+            rejects.write(raw, self.width, &error)?;
+        }
+
+        if self.options.strict {
+            bail!("Invalid record at line {}: {error}", self.record_line);
+        }
+        if let Some(invalid) = &mut self.invalid {
+            invalid.push(InvalidRecord {
+                line: self.record_line,
+                error: error.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The error of a malformed row at the given range of the parsed bytes.
+    fn malformed(&self, range: Range<u64>) -> anyhow::Error {
+        anyhow!(
+            "Malformed row skipped at bytes {}..{} of the input.",
+            self.offset + range.start,
+            self.offset + range.end
+        )
+    }
+
+    /// Apply the [ReaderOptions::transformers] to a record.
+    fn transform(&self, mut record: ByteRecord) -> crate::Result<ByteRecord> {
+        for transformer in &self.options.transformers {
+            transformer.transform(&mut RawRecord::new(&mut record, self.fields.0))?;
+        }
+
+        Ok(record)
+    }
+
+    /// Fit a raw record to the expected number of columns according to the
+    /// [ExtraColumns] and [MissingAmount] options.
+    fn fit(&self, mut record: ByteRecord) -> crate::Result<ByteRecord> {
+        let found = record.len();
+
+        if found > self.width && self.options.extra_columns == ExtraColumns::Ignore {
+            record.truncate(self.width);
+        } else if found + 1 == self.width
+            && self.amount_is_last
+            && self.options.missing_amount == MissingAmount::Empty
+        {
+            record.push_field(b"");
+        } else if found != self.width {
+            bail!("Expected {} columns, {found} found.", self.width);
+        }
+
+        Ok(record)
+    }
+}
+
+impl InputSource for Orders {
+    fn next_order(&mut self) -> Option<crate::Result<TransactionOrder>> {
+        self.next()
+    }
+
+    fn progress(&self) -> Option<ReaderProgress> {
+        Some(Orders::progress(self))
+    }
+}
+
+impl Iterator for Orders {
+    type Item = crate::Result<TransactionOrder>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Some(error) = self.error.take() {
+            self.done = true;
+            return Some(Err(error));
+        }
+
+        let last_row = self.options.limit.map(|limit| self.options.skip + limit);
+
+        while last_row.is_none_or(|last_row| self.rows_read < last_row) {
+            let Some(result) = self.next_record() else {
+                break;
+            };
+            self.rows_read += 1;
+            if self.rows_read <= self.options.skip {
+                continue;
+            }
+            match self.parse(result) {
+                Ok(Some(order)) => return Some(Ok(order)),
+                Ok(None) => continue,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.done = true;
+
+        self.options
+            .trailer
+            .verify(self.trailer, self.totals)
+            .err()
+            .map(Err)
+    }
+}
+
+/// Tell if a record holds a line break, so it spans several lines.
+fn spans_lines(record: &ByteRecord) -> bool {
+    record.iter().any(|field| field.contains(&b'\n'))
+}
+
+/// The bytes read from an input past a given offset, so the raw bytes of a
+/// malformed record can be read again.
+#[derive(Debug, Default)]
+struct Recording {
+    /// The offset in the input of the first recorded byte.
+    offset: u64,
+
+    /// The recorded bytes.
+    bytes: Vec<u8>,
+}
+
+impl Recording {
+    /// Record the given input if requested.
+    fn wrap(
+        input: Box<dyn Read + Sync + Send>,
+        record: bool,
+    ) -> (Box<dyn Read + Sync + Send>, Option<Arc<Mutex<Self>>>) {
+        if !record {
+            return (input, None);
+        }
+        let recording = Arc::new(Mutex::new(Self::default()));
+        let recorder = Recorder {
+            input,
+            recording: recording.clone(),
+        };
+
+        (Box::new(recorder), Some(recording))
+    }
+
+    /// Get the recorded bytes of the given range of the input.
+    fn get(&self, range: Range<u64>) -> &[u8] {
+        let start = (range.start - self.offset) as usize;
+        let end = (range.end - self.offset) as usize;
+
+        &self.bytes[start..end.min(self.bytes.len())]
+    }
+
+    /// Allow dropping the bytes before the given offset of the input.
+    fn forget(&mut self, offset: u64) {
+        let length = (offset - self.offset) as usize;
+
+        if length >= RECORDING_SLACK {
+            self.bytes.drain(..length);
+            self.offset = offset;
+        }
+    }
+}
+
+/// Discard the input up to the given offset, keeping the header line if the
+/// input has one. Return the input to parse and the number of discarded bytes.
+fn skip_to(
+    input: Box<dyn Read + Sync + Send>,
+    offset: u64,
+    has_header: bool,
+) -> io::Result<(Box<dyn Read + Sync + Send>, u64)> {
+    if offset == 0 {
+        return Ok((input, 0));
+    }
+    let mut input = BufReader::new(input);
+    let mut header = Vec::new();
+    if has_header {
+        input.read_until(b'\n', &mut header)?;
+    }
+    let skipped = offset.saturating_sub(header.len() as u64);
+    io::copy(&mut (&mut input).take(skipped), &mut io::sink())?;
+
+    Ok((Box::new(Cursor::new(header).chain(input)), skipped))
+}
+
+/// Input counting the bytes read.
+struct ByteCounter {
+    /// The counted input.
+    input: Box<dyn Read + Sync + Send>,
+
+    /// The number of bytes read so far.
+    count: Arc<AtomicU64>,
+}
+
+impl Read for ByteCounter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.input.read(buf)?;
+        self.count.fetch_add(length as u64, Ordering::Relaxed);
+
+        Ok(length)
+    }
+}
+
+/// Input recording the bytes read.
+struct Recorder {
+    /// The recorded input.
+    input: Box<dyn Read + Sync + Send>,
+
+    /// The bytes read so far.
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl Read for Recorder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.input.read(buf)?;
+        self.recording
+            .lock()
+            .unwrap()
+            .bytes
+            .extend_from_slice(&buf[..length]);
+
+        Ok(length)
+    }
+}
+
+/// CSV reader configuration shared by the sequential and parallel parsings.
+fn csv_reader_builder(options: &ReaderOptions) -> ReaderBuilder {
+    let mut builder = ReaderBuilder::new();
+    builder
+        .flexible(true)
+        .delimiter(options.delimiter)
+        .quote(options.quote)
+        .trim(csv::Trim::All);
+
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+
+    use crate::model::TxId;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn assert_run_ok(data: &'static str, ok_lines: usize) {
+        assert_run_ok_with_options(data, ok_lines, ReaderOptions::default());
+    }
+
+    fn assert_run_ok_with_options(data: &'static str, ok_lines: usize, options: ReaderOptions) {
+        let (tx, rx) = channel();
+        let actor = Reader::with_options(tx, Box::new(data.as_bytes()), options);
+        let handler = std::thread::spawn(move || actor.run());
+
+        assert!(handler.join().unwrap().is_ok());
+        let orders: Vec<TransactionOrder> = rx.iter().collect();
+        assert_eq!(orders.len(), ok_lines);
+    }
+
+    #[test]
+    fn simple_ok_sample() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 2.0
+deposit, 1, 3, 2
+withdrawal, 1, 4, 1.500
+withdrawal, 2, 5, 3.0
+dispute, 3, 3,"#;
+        assert_run_ok(data, 6);
+    }
+
+    #[test]
+    fn test_mixed_case() {
+        let data = r#"type, client, tx, amount
+Deposit,1,1,1.0
+dEpOsiT, 2, 2, 2.0  
+DEPOSIT, 1, 3, 2
+Withdrawal, 1, 4, 1.500"#;
+        assert_run_ok(data, 4);
+    }
+
+    #[test]
+    fn test_empty_lines() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1 , 1.0
+deposit, 2, 2, 2.0  
+
+deposit, 1, 3, 2
+withdrawal, 1, 4, 1.500
+
+withdrawal, 2, 5, 3.0
+
+"#;
+        assert_run_ok(data, 5);
+    }
+
+    #[test]
+    fn test_extra_spaces() {
+        let data = r#"type, client, tx, amount
+   deposit, 1, 1 , 1.0
+deposit, 2, 2, 2.0    
+deposit    , 1, 3, 2
+withdrawal, 1,   4, 1.500
+withdrawal, 2, 5, 3.0     "#;
+        assert_run_ok(data, 5);
+    }
+
+    #[test]
+    fn test_invalid_transaction_kind() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+whatever, 1, 2, 2.0
+withdrawal, 1,   4, 1.500
+dispute, 2, 5,"#;
+        assert_run_ok(data, 3);
+    }
+
+    #[test]
+    fn test_tab_delimiter() {
+        let data = "type\tclient\ttx\tamount
+deposit\t1\t1\t1.0
+withdrawal\t1\t2\t0.5
+dispute\t1\t1\t";
+        let options = ReaderOptions {
+            delimiter: b'\t',
+            ..Default::default()
+        };
+        assert_run_ok_with_options(data, 3, options);
+    }
+
+    #[test]
+    fn test_semicolon_delimiter() {
+        let data = r#"type; client; tx; amount
+deposit; 1; 1; 1.0
+withdrawal; 1; 2; 0.5"#;
+        let options = ReaderOptions {
+            delimiter: b';',
+            ..Default::default()
+        };
+        assert_run_ok_with_options(data, 2, options);
+
+        // the default delimiter does not parse semicolon separated files
+        assert_run_ok(data, 0);
+    }
+
+    #[test]
+    fn test_headerless_positional_columns() {
+        let data = r#"1, 1.0, deposit, 1
+2, 2.0, deposit, 1
+3, , dispute, 1
+4, 0.5, withdrawal, 2"#;
+        let options = ReaderOptions {
+            columns: Some(ColumnPositions {
+                kind: 2,
+                client: 3,
+                tx: 0,
+                amount: 1,
+                timestamp: None,
+                currency: None,
+            }),
+            ..Default::default()
+        };
+        assert_run_ok_with_options(data, 4, options.clone());
+
+        let (tx, rx) = channel();
+        Reader::with_options(tx, Box::new(data.as_bytes()), options)
+            .run()
+            .unwrap();
+        let orders: Vec<TransactionOrder> = rx.iter().collect();
+
+        assert_eq!(orders[0].client_id, 1);
+        assert_eq!(orders[0].tx_id, 1);
+        assert_eq!(orders[2].kind, crate::model::TransactionKind::Dispute(3));
+    }
+
+    #[test]
+    fn test_headerless_missing_amount_column() {
+        let data = r#"deposit, 1, 1, 1.0
+dispute, 1, 1
+resolve, 1, 1"#;
+        let options = ReaderOptions {
+            columns: Some(ColumnPositions {
+                kind: 0,
+                client: 1,
+                tx: 2,
+                amount: 3,
+                timestamp: None,
+                currency: None,
+            }),
+            ..Default::default()
+        };
+        assert_run_ok_with_options(data, 3, options);
+
+        // with headers, the first row is eaten
+        assert_run_ok(data, 0);
+    }
+
+    #[test]
+    fn test_header_mapping() {
+        let data = r#"txn_type, customer_id, txn_id, value
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 0.5"#;
+        let header_mapping = [
+            ("txn_type", "type"),
+            ("customer_id", "client"),
+            ("txn_id", "tx"),
+            ("value", "amount"),
+        ]
+        .into_iter()
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .collect();
+        let options = ReaderOptions {
+            header_mapping,
+            ..Default::default()
+        };
+        assert_run_ok_with_options(data, 2, options);
+
+        // without mapping, no record can be deserialized
+        assert_run_ok(data, 0);
+    }
+
+    fn run_with_trailer(data: &'static str, trailer: TrailerPolicy) -> crate::Result<()> {
+        let (tx, _rx) = channel();
+        let options = ReaderOptions {
+            trailer,
+            ..Default::default()
+        };
+
+        Reader::with_options(tx, Box::new(data.as_bytes()), options).run()
+    }
+
+    #[test]
+    fn test_trailer_control_totals() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 2.5
+whatever, 1, 3, 2.0
+withdrawal, 1, 4, 0.5
+dispute, 2, 2,
+TRAILER, 5, 3.50, 0.5"#;
+        assert_run_ok_with_options(
+            data,
+            4,
+            ReaderOptions {
+                trailer: TrailerPolicy::Fail,
+                ..Default::default()
+            },
+        );
+        assert!(run_with_trailer(data, TrailerPolicy::Flag).is_ok());
+
+        // without trailer policy, the trailer is an invalid record
+        assert_run_ok(data, 4);
+    }
+
+    #[test]
+    fn test_trailer_mismatch() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 0.5
+trailer, 3, 1.0, 0.5"#;
+        let error = run_with_trailer(data, TrailerPolicy::Fail).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Control totals mismatch: trailer 3 rows, deposits 1, withdrawals 0.5, processed 2 rows, deposits 1, withdrawals 0.5."
+        );
+        assert!(run_with_trailer(data, TrailerPolicy::Flag).is_ok());
+        assert!(run_with_trailer(data, TrailerPolicy::Ignore).is_ok());
+    }
+
+    #[test]
+    fn test_trailer_row_count() {
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,2.0\ntrailer,2,,\n";
+        assert!(run_with_trailer(data, TrailerPolicy::Fail).is_ok());
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,2.0\ntrailer,2,3.0,\n";
+        assert!(run_with_trailer(data, TrailerPolicy::Fail).is_ok());
+
+        // a truncated file
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\ntrailer,2,,\n";
+        let error = run_with_trailer(data, TrailerPolicy::Fail).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Control totals mismatch: trailer 2 rows, processed 1 rows, deposits 1, withdrawals 0."
+        );
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,2.0\ntrailer,2,2.0,\n";
+        assert!(run_with_trailer(data, TrailerPolicy::Fail).is_err());
+    }
+
+    #[test]
+    fn test_extra_columns() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0, extra
+deposit, 1, 2, 1.0"#;
+        assert_run_ok(data, 1);
+        assert_run_ok_with_options(
+            data,
+            2,
+            ReaderOptions {
+                extra_columns: ExtraColumns::Ignore,
+                ..Default::default()
+            },
+        );
+
+        // in positional mode, the columns after the last position are extra
+        let data = "deposit, 1, 1, 1.0, extra\ndeposit, 1, 2, 1.0";
+        let columns = Some("0,1,2,3".parse().unwrap());
+        assert_run_ok_with_options(
+            data,
+            1,
+            ReaderOptions {
+                columns,
+                ..Default::default()
+            },
+        );
+        assert_run_ok_with_options(
+            data,
+            2,
+            ReaderOptions {
+                columns,
+                extra_columns: ExtraColumns::Ignore,
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn test_missing_amount() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+dispute, 1, 1
+resolve, 1"#;
+        assert_run_ok(data, 2);
+        assert_run_ok_with_options(
+            data,
+            1,
+            ReaderOptions {
+                missing_amount: MissingAmount::Error,
+                ..Default::default()
+            },
+        );
+
+        // only a trailing amount column can be missing
+        let data = r#"amount, type, client, tx
+1.0, deposit, 1, 1
+dispute, 1, 1"#;
+        assert_run_ok(data, 1);
+    }
+
+    #[test]
+    fn test_minor_units() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 150000
+withdrawal, 1, 2, 5
+deposit, 1, 3, 1.5
+dispute, 1, 1,
+trailer, 4, 150000, 5"#;
+        let options = ReaderOptions {
+            amount_format: "minor-units:4".parse().unwrap(),
+            trailer: TrailerPolicy::Fail,
+            ..Default::default()
+        };
+        let orders: Vec<TransactionOrder> = Orders::new(Box::new(data.as_bytes()), options)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+
+        assert_eq!(orders.len(), 3);
+        assert!(matches!(orders[0].kind, TransactionKind::Deposit(amount) if amount == dec!(15)));
+        assert!(
+            matches!(orders[1].kind, TransactionKind::Withdrawal(amount) if amount == dec!(0.0005))
+        );
+        assert!("minor-units:29".parse::<AmountFormat>().is_err());
+        assert!("cents".parse::<AmountFormat>().is_err());
+    }
+
+    #[test]
+    fn test_wide_identifiers() {
+        let data = r#"type, client, tx, amount
+deposit, 70000, 5000000000, 1
+dispute, 70000, 5000000000,
+deposit, 18446744073709551616, 1, 1"#;
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(data.as_bytes()), ReaderOptions::default())
+                .collect::<crate::Result<_>>()
+                .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(
+            (orders[0].client_id, orders[0].tx_id),
+            (70_000, 5_000_000_000)
+        );
+        assert_eq!(orders[1].kind, TransactionKind::Dispute(5_000_000_000));
+    }
+
+    #[test]
+    fn test_signed_adjustments() {
+        let data = r#"type, client, tx, amount
+adjustment, 1, 1, -2.5
+adjustment, 1, 2, 1
+adjustment, 1, 3, 0
+adjustment, 1, 4,
+deposit, 1, 5, -1"#;
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(data.as_bytes()), ReaderOptions::default())
+                .collect::<crate::Result<_>>()
+                .unwrap();
+
+        assert_eq!(
+            orders.iter().map(|order| &order.kind).collect::<Vec<_>>(),
+            [
+                &TransactionKind::Adjustment(dec!(-2.5)),
+                &TransactionKind::Adjustment(dec!(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let utc = parse_timezone("utc").unwrap();
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+
+        for (format, text) in [
+            ("iso8601", "2024-03-01T12:30:00Z"),
+            ("iso8601", "2024-03-01T14:30:00+02:00"),
+            ("iso8601", "2024-03-01 12:30:00"),
+            ("ISO8601", "2024-03-01T12:30:00.000"),
+            ("epoch-millis", "1709296200000"),
+            ("dd/mm/yyyy hh:mm", "01/03/2024 12:30"),
+        ] {
+            let format: TimestampFormat = format.parse().unwrap();
+            assert_eq!(format.parse(text, utc).unwrap(), expected, "{text}");
+        }
+
+        // the local timestamps are in the timezone of the input
+        let tokyo = parse_timezone("+09:00").unwrap();
+        let timestamp = TimestampFormat::Iso8601
+            .parse("2024-03-01T21:30:00", tokyo)
+            .unwrap();
+        assert_eq!(timestamp, expected);
+
+        // a month first date is not silently swapped
+        assert!(TimestampFormat::DayMonthYear
+            .parse("03/13/2024 12:30", utc)
+            .is_err());
+        assert!(TimestampFormat::EpochMillis
+            .parse("2024-03-01", utc)
+            .is_err());
+        assert!("mm/dd/yyyy".parse::<TimestampFormat>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp_column() {
+        let data = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 1.0, 01/03/2024 12:30
+deposit, 1, 2, 1.0,
+withdrawal, 1, 3, 0.5, 01/03/2024 12:00
+dispute, 1, 1, , 02/03/2024 09:00
+deposit, 1, 4, 1.0, 2024-03-02"#;
+        let options = ReaderOptions {
+            timestamp_format: TimestampFormat::DayMonthYear,
+            timezone: parse_timezone("+01:00").unwrap(),
+            timestamp_order: TimestampOrder::Flag,
+            ..Default::default()
+        };
+        let orders: Vec<TransactionOrder> = Orders::new(Box::new(data.as_bytes()), options)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+
+        assert_eq!(orders.len(), 4);
+        assert_eq!(
+            orders[0].timestamp,
+            Some(Utc.with_ymd_and_hms(2024, 3, 1, 11, 30, 0).unwrap())
+        );
+        assert_eq!(orders[1].timestamp, None);
+        assert!(orders[2].timestamp < orders[0].timestamp);
+        assert!(matches!(orders[3].kind, TransactionKind::Dispute(1)));
+
+        let options = ReaderOptions {
+            timestamp_format: TimestampFormat::DayMonthYear,
+            timestamp_order: TimestampOrder::Fail,
+            ..Default::default()
+        };
+        let error = Orders::new(Box::new(data.as_bytes()), options)
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap_err();
+
+        assert!(error.to_string().contains("line 4"), "{error}");
+    }
+
+    #[test]
+    fn test_currency_column() {
+        let data = r#"type, client, tx, amount, currency
+deposit, 1, 1, 1.0, eur
+deposit, 1, 2, 1.0,
+deposit, 1, 3, 1.0, euro"#;
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(data.as_bytes()), ReaderOptions::default())
+                .collect::<crate::Result<_>>()
+                .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].currency, Some("EUR".parse().unwrap()));
+        assert_eq!(orders[1].currency, None);
+    }
+
+    #[test]
+    fn test_max_decimals() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0001
+deposit, 1, 2, 1.0000000001
+withdrawal, 1, 3, 0.50000000"#;
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(data.as_bytes()), ReaderOptions::default())
+                .collect::<crate::Result<_>>()
+                .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[1].tx_id, 3);
+
+        let options = ReaderOptions {
+            max_decimals: 10,
+            ..Default::default()
+        };
+        let orders = Orders::new(Box::new(data.as_bytes()), options).count();
+
+        assert_eq!(orders, 3);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+whatever, 1, 2, 2.0
+withdrawal, 1, 3, 0.5"#;
+        let (tx, _rx) = channel();
+        let orders: Vec<TransactionOrder> = Reader::new(tx, Box::new(data.as_bytes()))
+            .into_iter()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[1].tx_id, 3);
+    }
+
+    #[test]
+    fn test_orders_end_with_fatal_error() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+trailer, 2, 1.0, 0"#;
+        let options = ReaderOptions {
+            trailer: TrailerPolicy::Fail,
+            ..Default::default()
+        };
+        let mut orders = Orders::new(Box::new(data.as_bytes()), options);
+
+        assert!(orders.next().unwrap().is_ok());
+        assert!(orders.next().unwrap().is_err());
+        assert!(orders.next().is_none());
+
+        // the header is not valid UTF-8
+        let mut orders = Orders::new(
+            Box::new(&b"\xfftype,client\n"[..]),
+            ReaderOptions::default(),
+        );
+        assert!(orders.next().unwrap().is_err());
+        assert!(orders.next().is_none());
+    }
+
+    #[test]
+    fn test_missing_trailer() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0"#;
+
+        assert!(run_with_trailer(data, TrailerPolicy::Fail).is_err());
+        assert!(run_with_trailer(data, TrailerPolicy::Flag).is_ok());
+    }
+
+    #[test]
+    fn test_headerless_trailer() {
+        let data = r#"1, 1.0, deposit, 1
+1.0, 0, trailer, 1"#;
+        let options = ReaderOptions {
+            columns: Some(ColumnPositions {
+                kind: 2,
+                client: 3,
+                tx: 0,
+                amount: 1,
+                timestamp: None,
+                currency: None,
+            }),
+            trailer: TrailerPolicy::Fail,
+            ..Default::default()
+        };
+
+        // the trailer columns are mapped as the transaction columns
+        assert_run_ok_with_options(data, 1, options);
+    }
+
+    fn run_parallel(data: String, options: ReaderOptions) -> crate::Result<Vec<TransactionOrder>> {
+        let (tx, rx) = channel();
+        Reader::with_options(tx, Box::new(Cursor::new(data)), options)
+            .with_workers(4)
+            .with_chunk_size(64)
+            .run()?;
+
+        Ok(rx.iter().collect())
+    }
+
+    #[test]
+    fn test_parallel_chunks_in_input_order() {
+        let mut data = String::from("type, client, tx, amount\n");
+        for tx_id in 1..=1000 {
+            data.push_str(&format!("deposit, {}, {tx_id}, 1.5\n", tx_id % 7));
+            if tx_id % 100 == 0 {
+                data.push_str("whatever, 1, 0, 1\n");
+            }
+        }
+        let orders = run_parallel(data.clone(), ReaderOptions::default()).unwrap();
+        let (tx, _rx) = channel();
+        let expected: Vec<TransactionOrder> = Reader::new(tx, Box::new(Cursor::new(data)))
+            .into_iter()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        let ids = |orders: &[TransactionOrder]| -> Vec<(u64, u64)> {
+            orders.iter().map(|o| (o.client_id, o.tx_id)).collect()
+        };
+
+        assert_eq!(orders.len(), 1000);
+        assert_eq!(ids(&orders), ids(&expected));
+    }
+
+    #[test]
+    fn test_parallel_trailer() {
+        let mut data = String::from("type, client, tx, amount\n");
+        for tx_id in 1..=100 {
+            data.push_str(&format!("deposit, 1, {tx_id}, 1\n"));
+        }
+        data.push_str("withdrawal, 1, 101, 0.5\n");
+        let options = ReaderOptions {
+            trailer: TrailerPolicy::Fail,
+            ..Default::default()
+        };
+
+        let valid = format!("{data}trailer, 101, 100, 0.5\n");
+        assert_eq!(run_parallel(valid, options.clone()).unwrap().len(), 101);
+        let invalid = format!("{data}trailer, 101, 100, 1.5\n");
+        assert!(run_parallel(invalid, options.clone()).is_err());
+        assert!(run_parallel(data, options).is_err());
+    }
+
+    #[test]
+    fn test_parallel_headerless() {
+        let data = "1, 1.0, deposit, 1\n2, 2.0, deposit, 1\n3, 0.5, withdrawal, 1\n".repeat(10);
+        let options = ReaderOptions {
+            columns: Some(ColumnPositions {
+                kind: 2,
+                client: 3,
+                tx: 0,
+                amount: 1,
+                timestamp: None,
+                currency: None,
+            }),
+            ..Default::default()
+        };
+        let orders = run_parallel(data, options).unwrap();
+
+        assert_eq!(orders.len(), 30);
+        assert_eq!(
+            orders[2].kind,
+            TransactionKind::Withdrawal(Decimal::new(5, 1))
+        );
+    }
+
+    #[test]
+    fn test_only_kinds() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 2.0
+withdrawal, 1, 2, 1.0
+dispute, 1, 1,
+resolve, 1, 1,
+dispute, 1, 1,
+trailer, 5, 2.0, 1.0"#;
+        let filter: KindFilter = "Deposit, withdrawal".parse().unwrap();
+        let options = ReaderOptions {
+            trailer: TrailerPolicy::Fail,
+            only_kinds: Some(filter.clone()),
+            ..Default::default()
+        };
+        let orders: Vec<TransactionOrder> = Orders::new(Box::new(data.as_bytes()), options)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(filter.skipped(), vec![("dispute", 2), ("resolve", 1)]);
+        assert!("deposit,refund".parse::<KindFilter>().is_err());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut data = String::from("type,client,tx,amount\n");
+        for tx_id in 1..=100 {
+            data.push_str(&format!("deposit,1,{tx_id},1.0\n"));
+            // retried right away, then later in the filter window
+            data.push_str(&format!("deposit,1,{tx_id},1.0\ndispute,1,{tx_id},\n"));
+            if tx_id > 10 {
+                data.push_str(&format!("deposit,1,{},1.0\n", tx_id - 10));
+            }
+            // and once out of it
+            if tx_id == 100 {
+                data.push_str("deposit,1,1,1.0\n");
+            }
+        }
+        let filter = DuplicateFilter::new(25);
+        let options = ReaderOptions {
+            dedup: Some(filter.clone()),
+            ..Default::default()
+        };
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(Cursor::new(data.clone())), options)
+                .collect::<crate::Result<_>>()
+                .unwrap();
+        assert_eq!(orders.len(), 201);
+        assert_eq!(filter.dropped(), 190);
+
+        let filter = DuplicateFilter::new(25);
+        let options = ReaderOptions {
+            dedup: Some(filter.clone()),
+            ..Default::default()
+        };
+        let parallel = run_parallel(data, options).unwrap();
+        assert_eq!(
+            parallel.iter().map(|order| order.tx_id).collect::<Vec<_>>(),
+            orders.iter().map(|order| order.tx_id).collect::<Vec<_>>()
+        );
+        assert_eq!(filter.dropped(), 190);
+    }
+
+    #[test]
+    fn test_max_rate() {
+        let mut data = String::from("type,client,tx,amount\n");
+        for tx_id in 1..=11 {
+            data.push_str(&format!("deposit,1,{tx_id},1.0\n"));
+        }
+        for workers in [1, 4] {
+            let (tx, rx) = channel();
+            Reader::new(tx, Box::new(Cursor::new(data.clone())))
+                .with_workers(workers)
+                .with_chunk_size(64)
+                .with_max_rate(NonZeroU32::new(500).unwrap())
+                .run()
+                .unwrap();
+
+            assert_eq!(rx.iter().count(), 11);
+        }
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let mut throttle = Throttle::new(NonZeroU32::new(50).unwrap());
+        let started = Instant::now();
+        let at = |millis| started + Duration::from_millis(millis);
+
+        // the first order is sent at once, the next ones every 20ms
+        assert_eq!(throttle.delay(at(0)), Duration::ZERO);
+        assert_eq!(throttle.delay(at(5)), Duration::from_millis(15));
+        assert_eq!(throttle.delay(at(20)), Duration::from_millis(20));
+        // a slow consumer is caught up with a burst
+        assert_eq!(throttle.delay(at(100)), Duration::ZERO);
+        assert_eq!(throttle.delay(at(100)), Duration::ZERO);
+        assert_eq!(throttle.delay(at(100)), Duration::ZERO);
+        assert_eq!(throttle.delay(at(100)), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_resync_lines() {
+        let data = r#"type,client,tx,amount
+deposit,1,1,1.0
+deposit,1,"2,2.0
+deposit,1,3,3.0
+deposit,1,4,4.0
+deposit,"1",5,5.0
+deposit,1,6,6.0
+"#;
+        let options = ReaderOptions {
+            resync_lines: true,
+            ..Default::default()
+        };
+        let mut orders = Orders::new(Box::new(data.as_bytes()), options.clone());
+        let mut errors = Vec::new();
+        let mut tx_ids = Vec::new();
+        while let Some(result) = orders.next_record() {
+            match orders.parse(result.inspect_err(|e| errors.push(e.to_string()))) {
+                Ok(Some(order)) => tx_ids.push(order.tx_id),
+                _ => continue,
+            }
+        }
+
+        assert_eq!(tx_ids, vec![1, 3, 4, 5, 6]);
+        assert_eq!(
+            errors,
+            vec!["Malformed row skipped at bytes 38..55 of the input."]
+        );
+        // without recovery, the quote swallows the rows up to the next one
+        assert_run_ok(data, 2);
+
+        // the chunks are recovered independently
+        let rows = data.replace("type,client,tx,amount\n", "").repeat(20);
+        let options = ReaderOptions {
+            columns: Some("0, 1, 2, 3".parse().unwrap()),
+            ..options
+        };
+        assert_eq!(run_parallel(rows, options).unwrap().len(), 100);
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut data = String::from("type, client, tx, amount\n");
+        for tx_id in 1..=100 {
+            data.push_str(&format!(
+                "deposit, 1, {tx_id}, 1\nwhatever, 1, {tx_id}, 1\n"
+            ));
+        }
+        let expected = ReaderProgress {
+            bytes_read: data.len() as u64,
+            records_parsed: 100,
+            records_rejected: 100,
+            resume_offset: (data.len() - "whatever, 1, 100, 1\n".len()) as u64,
+        };
+
+        for workers in [1, 4] {
+            let (tx, _rx) = channel();
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let progress = reports.clone();
+            Reader::new(tx, Box::new(Cursor::new(data.clone())))
+                .with_workers(workers)
+                .with_chunk_size(256)
+                .with_progress(Duration::ZERO, move |p| progress.lock().unwrap().push(p))
+                .run()
+                .unwrap();
+            let reports = reports.lock().unwrap();
+
+            assert!(reports.len() > 2);
+            assert!(reports.is_sorted_by_key(|p| p.records_parsed));
+            assert_eq!(reports.last(), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_skip_and_limit() {
+        let mut data = String::from("type, client, tx, amount\n");
+        for tx_id in 1..=10 {
+            data.push_str(&format!("deposit, 1, {tx_id}, 1\n"));
+        }
+        let read = |skip, limit| -> Vec<TxId> {
+            let options = ReaderOptions {
+                skip,
+                limit,
+                ..Default::default()
+            };
+            let (tx, rx) = channel();
+            Reader::with_options(tx, Box::new(Cursor::new(data.clone())), options)
+                .with_workers(4)
+                .run()
+                .unwrap();
+
+            rx.iter().map(|order| order.tx_id).collect()
+        };
+
+        assert_eq!(read(3, Some(2)), vec![4, 5]);
+        assert_eq!(read(8, None), vec![9, 10]);
+        assert_eq!(read(0, Some(1)), vec![1]);
+        assert_eq!(read(20, Some(1)), Vec::<TxId>::new());
+    }
+
+    #[test]
+    fn test_input_encoding() {
+        let data = "type, client, tx, amount\ndeposit, 1, 1, 1.5\nwithdrawal, 2, 2, 0.5\n";
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend(data.encode_utf16().flat_map(u16::to_le_bytes));
+        for encoding in [InputEncoding::Utf16, InputEncoding::Auto] {
+            let options = ReaderOptions {
+                encoding,
+                ..Default::default()
+            };
+            let orders: Vec<TransactionOrder> =
+                Orders::new(Box::new(Cursor::new(utf16.clone())), options.clone())
+                    .collect::<crate::Result<_>>()
+                    .unwrap();
+            assert_eq!(orders.len(), 2);
+            assert_eq!(orders[1].client_id, 2);
+
+            let (tx, rx) = channel();
+            Reader::with_options(tx, Box::new(Cursor::new(utf16.clone())), options)
+                .with_workers(2)
+                .with_chunk_size(16)
+                .run()
+                .unwrap();
+            assert_eq!(
+                rx.iter().map(|order| order.tx_id).collect::<Vec<_>>(),
+                vec![1, 2]
+            );
+        }
+
+        // the byte order mark tells the encoding
+        let orders = Orders::new(
+            Box::new(Cursor::new(utf16.clone())),
+            ReaderOptions::default(),
+        );
+        assert_eq!(orders.filter(Result::is_ok).count(), 2);
+        // without it, the UTF-16 input read as UTF-8 has no valid header
+        let orders = Orders::new(
+            Box::new(Cursor::new(utf16.split_off(2))),
+            ReaderOptions::default(),
+        );
+        assert_eq!(orders.filter(Result::is_ok).count(), 0);
+    }
+
+    /// Input returning a single byte per read, like a slow stream.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let length = buf.len().min(1);
+            self.0.read(&mut buf[..length])
+        }
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let data = "\u{feff}type, client, tx, amount\ndeposit, 1, 1, 1.5\n";
+        let headerless = "\u{feff}deposit, 1, 1, 1.5\ndeposit, 1, 2, 1\n";
+        let read = |data: &str, options: ReaderOptions| {
+            let input = Trickle(Cursor::new(data.as_bytes().to_vec()));
+            Orders::new(Box::new(input), options)
+                .collect::<crate::Result<Vec<_>>>()
+                .map(|orders| orders.len())
+        };
+
+        assert_eq!(read(data, ReaderOptions::default()).unwrap(), 1);
+        let options = ReaderOptions {
+            columns: Some("0,1,2,3".parse().unwrap()),
+            strict: true,
+            ..Default::default()
+        };
+        assert_eq!(read(headerless, options).unwrap(), 2);
+
+        let (tx, rx) = channel();
+        let input = Trickle(Cursor::new(data.as_bytes().to_vec()));
+        Reader::new(tx, Box::new(input))
+            .with_workers(2)
+            .run()
+            .unwrap();
+        assert_eq!(rx.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_sniff_dialect() {
+        let no_mapping = HashMap::new();
+        let dialect = Dialect::sniff(b"type;client;tx;amount\ndeposit;1;1;\"1,5\"\n", &no_mapping);
+        assert_eq!((dialect.delimiter, dialect.quote), (b';', b'"'));
+        assert_eq!(dialect.columns, None);
+
+        let dialect = Dialect::sniff(b"deposit|1|1|'1.5'|2024-01-01\n", &no_mapping);
+        assert_eq!((dialect.delimiter, dialect.quote), (b'|', b'\''));
+        assert_eq!(dialect.columns, Some("0,1,2,3,4".parse().unwrap()));
+
+        // a mapped header is still a header, whatever the cut last line
+        let mapping = HashMap::from([("kind".to_string(), "type".to_string())]);
+        let dialect = Dialect::sniff(
+            b"kind\tclient\ttx\tamount\ndeposit\t1\t1\t1.5\ndepo",
+            &mapping,
+        );
+        assert_eq!(dialect.delimiter, b'\t');
+        assert_eq!(dialect.columns, None);
+
+        // an unknown first record is taken for a header
+        let dialect = Dialect::sniff(b"txn_type,client\n", &no_mapping);
+        assert_eq!(dialect.delimiter, b',');
+        assert_eq!(dialect.columns, None);
+    }
+
+    #[test]
+    fn test_sniffed_reading() {
+        let data = "deposit;1;1;'1.5'\nwithdrawal;2;2;'0.5'\n";
+        let options = ReaderOptions {
+            sniff: true,
+            ..Default::default()
+        };
+        let orders: Vec<TransactionOrder> =
+            Orders::new(Box::new(Cursor::new(data)), options.clone())
+                .collect::<crate::Result<_>>()
+                .unwrap();
+        assert_eq!(
+            orders.iter().map(|order| order.tx_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let orders = run_parallel(data.to_string(), options).unwrap();
+        assert_eq!(
+            orders.iter().map(|order| order.tx_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_start_offset() {
+        let mut data = String::from("type, client, tx, amount\n");
+        for tx_id in 1..=10 {
+            data.push_str(&format!("deposit, 1, {tx_id}, 1\n"));
+        }
+        data.push_str("whatever, 1, 11, 1\n");
+        let options = ReaderOptions {
+            limit: Some(4),
+            ..Default::default()
+        };
+        let mut orders = Orders::new(Box::new(Cursor::new(data.clone())), options);
+        assert_eq!(orders.by_ref().count(), 4);
+        let resume_offset = orders.progress().resume_offset;
+        assert_eq!(&data[resume_offset as usize..][..13], "deposit, 1, 5");
+
+        for workers in [1, 4] {
+            let options = ReaderOptions {
+                start_offset: resume_offset,
+                ..Default::default()
+            };
+            let (tx, rx) = channel();
+            let last = Arc::new(Mutex::new(ReaderProgress::default()));
+            let progress = last.clone();
+            Reader::with_options(tx, Box::new(Cursor::new(data.clone())), options)
+                .with_workers(workers)
+                .with_chunk_size(32)
+                .with_progress(Duration::ZERO, move |p| *progress.lock().unwrap() = p)
+                .run()
+                .unwrap();
+            let tx_ids: Vec<TxId> = rx.iter().map(|order| order.tx_id).collect();
+
+            assert_eq!(tx_ids, (5..=10).collect::<Vec<_>>());
+            assert_eq!(
+                last.lock().unwrap().resume_offset,
+                (data.len() - "whatever, 1, 11, 1\n".len()) as u64
+            );
+        }
+
+        // headerless inputs are resumed from the offset as well
+        let headerless = data.split_once('\n').unwrap().1;
+        let options = ReaderOptions {
+            columns: Some("0,1,2,3".parse().unwrap()),
+            start_offset: (resume_offset as usize + headerless.len() - data.len()) as u64,
+            ..Default::default()
+        };
+        let orders = Orders::new(Box::new(Cursor::new(headerless.to_string())), options)
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(orders[0].tx_id, 5);
+    }
+
+    #[test]
+    fn test_validate() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, "2, 1.0
+dispute, 1, 1
+withdrawal, 1, 3
+deposit, 1, 4, 0
+trailer, 5, 2.0, 0"#;
+        let options = ReaderOptions {
+            resync_lines: true,
+            trailer: TrailerPolicy::Fail,
+            ..Default::default()
+        };
+        let report = Orders::new(Box::new(data.as_bytes()), options).validate();
+        let lines: Vec<u64> = report.invalid_records.iter().map(|r| r.line).collect();
+
+        assert_eq!(report.valid_records, 2);
+        assert_eq!(lines, vec![3, 5, 6]);
+        assert!(report.error.is_some());
+        assert!(report
+            .to_string()
+            .starts_with("2 valid records, 3 invalid records.\n"));
+
+        let data = "type, client, tx, amount\ndeposit, 1, 1, 1.0\n";
+        let report = Orders::new(Box::new(data.as_bytes()), ReaderOptions::default()).validate();
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_strict() {
+        let data = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, -1.0
+deposit, 1, 3, 1.0"#;
+        let options = ReaderOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let (tx, rx) = channel();
+        let error = Reader::with_options(tx, Box::new(data.as_bytes()), options.clone())
+            .with_workers(4)
+            .run()
+            .unwrap_err();
+
+        assert!(error.to_string().starts_with("Invalid record at line 3: "));
+        assert_eq!(rx.iter().count(), 1);
+
+        let data = "type, client, tx, amount\ndeposit, 1\n";
+        let error = Orders::new(Box::new(data.as_bytes()), options)
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid record at line 2: Expected 4 columns, 2 found."
+        );
+    }
+}
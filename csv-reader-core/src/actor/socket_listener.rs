@@ -0,0 +1,150 @@
+//! Socket listener actor
+//!
+//! The socket listener actor accepts TCP connections and parses the
+//! transaction records pushed on each connection with a [Reader] actor. The
+//! transaction orders of every connection are sent to the accountant actor
+//! through the same channel. This lets upstream systems push transactions live.
+
+use std::{
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    thread::JoinHandle,
+};
+
+use log::{debug, info};
+
+use super::{Reader, ReaderOptions, Sender};
+use crate::{model::TransactionOrder, Result};
+
+/// Socket listener actor.
+pub struct SocketListener {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The bound TCP listener.
+    listener: TcpListener,
+
+    /// The options used to parse the records of each connection.
+    options: ReaderOptions,
+
+    /// Stop accepting connections after this number of connections.
+    max_connections: Option<usize>,
+}
+
+impl SocketListener {
+    /// Create a new socket listener actor bound to the given address. Each
+    /// connection is parsed as a CSV stream with the given options, use
+    /// [ReaderOptions::columns] for line-delimited records without header.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        order_sender: Sender<TransactionOrder>,
+        options: ReaderOptions,
+    ) -> Result<Self> {
+        Ok(Self {
+            order_sender,
+            listener: TcpListener::bind(address)?,
+            options,
+            max_connections: None,
+        })
+    }
+
+    /// Stop accepting connections once the given number of connections has
+    /// been accepted. The actor then waits for these connections to close.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+
+        self
+    }
+
+    /// Get the address the listener is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Run the socket listener actor.
+    /// Every accepted connection is read in its own thread. Connection errors
+    /// are logged and do not stop the listener.
+    pub fn run(self) -> Result<()> {
+        debug!("Socket Listener Actor started on {}", self.local_addr()?);
+        let mut handlers: Vec<JoinHandle<()>> = Vec::new();
+        let connections = self
+            .listener
+            .incoming()
+            .take(self.max_connections.unwrap_or(usize::MAX));
+
+        for stream in connections {
+            let stream = match stream {
+                Err(error) => {
+                    info!("Socket Listener Actor: connection failed: {}", error);
+                    continue;
+                }
+                Ok(stream) => stream,
+            };
+            let peer = stream.peer_addr()?;
+            debug!("Socket Listener Actor: connection from {}", peer);
+            let reader = Reader::with_options(
+                self.order_sender.clone(),
+                Box::new(stream),
+                self.options.clone(),
+            );
+            handlers.push(std::thread::spawn(move || {
+                if let Err(error) = reader.run() {
+                    info!(
+                        "Socket Listener Actor: connection {} failed: {}",
+                        peer, error
+                    );
+                }
+                debug!("Socket Listener Actor: connection {} closed", peer);
+            }));
+        }
+
+        for handler in handlers {
+            handler.join().expect("Connection thread panicked");
+        }
+        debug!("Socket Listener Actor stopped");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, net::TcpStream};
+
+    use super::*;
+    use crate::actor::{channel, ColumnPositions};
+
+    #[test]
+    fn test_connections() {
+        let (tx, rx) = channel();
+        let options = ReaderOptions {
+            columns: Some(ColumnPositions {
+                kind: 0,
+                client: 1,
+                tx: 2,
+                amount: 3,
+                timestamp: None,
+                currency: None,
+            }),
+            ..Default::default()
+        };
+        let listener = SocketListener::bind("127.0.0.1:0", tx, options)
+            .unwrap()
+            .with_max_connections(2);
+        let address = listener.local_addr().unwrap();
+        let handler = std::thread::spawn(move || listener.run());
+
+        let mut first = TcpStream::connect(address).unwrap();
+        let mut second = TcpStream::connect(address).unwrap();
+        first.write_all(b"deposit, 1, 1, 1.0\n").unwrap();
+        second
+            .write_all(b"deposit, 2, 2, 2.0\nwhatever, 2, 3, 1.0\n")
+            .unwrap();
+        first.write_all(b"withdrawal, 1, 4, 0.5\n").unwrap();
+        drop(first);
+        drop(second);
+        handler.join().unwrap().unwrap();
+        let orders: Vec<TransactionOrder> = rx.iter().collect();
+
+        assert_eq!(orders.len(), 3);
+    }
+}
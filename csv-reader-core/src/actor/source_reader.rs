@@ -0,0 +1,115 @@
+//! Source reader actor
+//!
+//! The source reader actor reads the transaction orders of any
+//! [InputSource] and sends them to the accountant actor through a channel,
+//! reporting the progress of the sources tracking it.
+
+use std::time::Duration;
+
+use log::debug;
+
+use super::reader::{send_orders, ProgressReporter};
+use super::{ReaderProgress, Sender};
+use crate::{adapter::InputSource, model::TransactionOrder};
+
+/// Source reader actor.
+///
+/// ```
+///
+/// use csv_reader_core::actor::{channel, Orders, ReaderOptions, SourceReader};
+///
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.0\n";
+/// let orders = Orders::new(Box::new(data.as_bytes()), ReaderOptions::default());
+/// let (sender, receiver) = channel();
+/// SourceReader::new(sender, Box::new(orders)).run().unwrap();
+///
+/// assert_eq!(receiver.iter().count(), 1);
+/// ```
+pub struct SourceReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+
+    /// The source of the orders.
+    source: Box<dyn InputSource>,
+
+    /// The progress reporting, if any.
+    progress: Option<ProgressReporter>,
+}
+
+impl SourceReader {
+    /// Create a new source reader actor.
+    pub fn new(order_sender: Sender<TransactionOrder>, source: Box<dyn InputSource>) -> Self {
+        Self {
+            order_sender,
+            source,
+            progress: None,
+        }
+    }
+
+    /// Call the given callback with the progress of the source at most once
+    /// per interval, and once the reading is over. Nothing is reported for the
+    /// sources not tracking their progress.
+    pub fn with_progress(
+        mut self,
+        interval: Duration,
+        callback: impl FnMut(ReaderProgress) + Send + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressReporter::new(interval, callback));
+
+        self
+    }
+
+    /// Run the source reader actor.
+    /// The actor sends the orders of the source until it is exhausted or
+    /// fails.
+    pub fn run(mut self) -> crate::Result<()> {
+        debug!("Source Reader Actor started");
+        let result = send_orders(
+            self.source.as_mut(),
+            &self.order_sender,
+            self.progress.as_mut(),
+            None,
+        );
+
+        if let (Some(progress), Some(read)) = (&mut self.progress, self.source.progress()) {
+            progress.report(read, true);
+        }
+        debug!("Source Reader Actor stopped");
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+    use std::sync::{Arc, Mutex};
+
+    use crate::actor::{Orders, ReaderOptions};
+
+    #[test]
+    fn test_csv_source() {
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\nrefund,1,2,1.0\ndeposit,1,3,1.0\n";
+        let orders = Orders::new(Box::new(data.as_bytes()), ReaderOptions::default());
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = channel();
+        SourceReader::new(tx, Box::new(orders))
+            .with_progress(Duration::from_secs(60), {
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress)
+            })
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            rx.iter().map(|order| order.tx_id).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].records_parsed, 2);
+        assert_eq!(reports[0].records_rejected, 1);
+    }
+}
@@ -0,0 +1,235 @@
+//! XLSX reader actor
+//!
+//! The XLSX reader actor is responsible for reading the transaction data from
+//! the first sheet of an Excel workbook. The sheet holds the same columns as
+//! the CSV files, its rows are written as CSV records and parsed by a
+//! [Reader] with the same options so the orders go through the same pipeline.
+//!
+//! The numeric and date cells are written from their values, not from the
+//! format displayed by Excel, so the locale of the workbook does not change the
+//! amounts or the timestamps read.
+
+use std::io::{Cursor, Read};
+
+use anyhow::anyhow;
+use calamine::{Data, Reader as _, Xlsx};
+use chrono::{NaiveDateTime, Utc};
+use log::debug;
+
+use crate::model::TransactionOrder;
+
+use super::{Reader, ReaderOptions, Sender, TimestampFormat};
+
+/// XLSX reader actor.
+pub struct XlsxReader {
+    /// The order channel sender to send transaction orders.
+    order_sender: Sender<TransactionOrder>,
+    reader: Box<dyn Read + Sync + Send>,
+
+    /// The parsing options of the rows.
+    options: ReaderOptions,
+}
+
+impl XlsxReader {
+    /// Create a new XLSX reader actor.
+    pub fn new(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+    ) -> Self {
+        Self::with_options(order_sender, reader, ReaderOptions::default())
+    }
+
+    /// Create a new XLSX reader actor with the given parsing options. The
+    /// dialect and the start offset options are not used.
+    pub fn with_options(
+        order_sender: Sender<TransactionOrder>,
+        reader: Box<dyn Read + Sync + Send>,
+        options: ReaderOptions,
+    ) -> Self {
+        Self {
+            order_sender,
+            reader,
+            options,
+        }
+    }
+
+    /// Run the XLSX reader actor.
+    /// The actor will load the workbook, convert its first sheet to CSV and
+    /// read it as a CSV input. It fails if the input is not a workbook or has
+    /// no sheet.
+    pub fn run(mut self) -> crate::Result<()> {
+        debug!("XLSX Reader Actor started");
+        // the workbook is a zip archive, it cannot be read as a stream
+        let mut workbook = Vec::new();
+        self.reader.read_to_end(&mut workbook)?;
+        let mut workbook = Xlsx::new(Cursor::new(workbook))?;
+        let sheet = workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| anyhow!("The workbook has no sheet."))??;
+
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(Vec::new());
+        for row in sheet.rows() {
+            if row.iter().all(|cell| *cell == Data::Empty) {
+                continue;
+            }
+            writer.write_record(row.iter().map(|cell| self.cell_to_string(cell)))?;
+        }
+        let csv = writer.into_inner().map_err(|e| anyhow!("{e}"))?;
+
+        let options = ReaderOptions {
+            delimiter: b',',
+            quote: b'"',
+            sniff: false,
+            start_offset: 0,
+            ..self.options
+        };
+        Reader::with_options(self.order_sender, Box::new(Cursor::new(csv)), options).run()?;
+        debug!("XLSX Reader Actor stopped");
+
+        Ok(())
+    }
+
+    /// Write a cell as a CSV field. The date cells are written in the
+    /// timestamp format of the options, in the timezone of the input.
+    fn cell_to_string(&self, cell: &Data) -> String {
+        match cell {
+            Data::DateTime(date) => match date.as_datetime() {
+                Some(date) => self.format_timestamp(date),
+                None => date.to_string(),
+            },
+            // the shortest representation of the float, never in scientific
+            // notation nor with a locale decimal separator
+            Data::Float(value) => value.to_string(),
+            cell => cell.to_string().trim().to_string(),
+        }
+    }
+
+    /// Write a date and time without timezone in the timestamp format of the
+    /// options.
+    fn format_timestamp(&self, date: NaiveDateTime) -> String {
+        match self.options.timestamp_format {
+            TimestampFormat::Iso8601 => date.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+            TimestampFormat::DayMonthYear => date.format("%d/%m/%Y %H:%M").to_string(),
+            TimestampFormat::EpochMillis => date
+                .and_local_timezone(self.options.timezone)
+                .single()
+                .map(|date| date.with_timezone(&Utc).timestamp_millis().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::actor::channel;
+
+    use chrono::{FixedOffset, TimeZone};
+    use rust_decimal_macros::dec;
+    use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+
+    use crate::model::TransactionKind;
+
+    /// Write a workbook whose first sheet holds the given rows below a header.
+    fn write_workbook(header: &[&str], rows: &[(&str, f64, f64, Option<f64>)]) -> Vec<u8> {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+
+        for (column, name) in header.iter().enumerate() {
+            sheet.write_string(0, column as u16, *name).unwrap();
+        }
+        for (row, (kind, client, tx, amount)) in rows.iter().enumerate() {
+            let row = row as u32 + 1;
+            sheet.write_string(row, 0, *kind).unwrap();
+            sheet.write_number(row, 1, *client).unwrap();
+            sheet.write_number(row, 2, *tx).unwrap();
+            if let Some(amount) = amount {
+                sheet.write_number(row, 3, *amount).unwrap();
+            }
+        }
+
+        workbook.save_to_buffer().unwrap()
+    }
+
+    fn run_reader(
+        actor: XlsxReader,
+        rx: crate::actor::Receiver<TransactionOrder>,
+    ) -> Vec<TransactionOrder> {
+        let handler = std::thread::spawn(move || actor.run());
+
+        assert!(handler.join().unwrap().is_ok());
+
+        rx.iter().collect()
+    }
+
+    #[test]
+    fn simple_ok_sample() {
+        let data = write_workbook(
+            &["type", "client", "tx", "amount"],
+            &[
+                ("deposit", 1.0, 1.0, Some(1.5)),
+                ("withdrawal", 1.0, 2.0, Some(0.1)),
+                ("dispute", 1.0, 1.0, None),
+                ("whatever", 1.0, 3.0, Some(1.0)),
+                ("deposit", 1.0, 4.0, Some(1.23456)),
+            ],
+        );
+        let (tx, rx) = channel();
+        let orders = run_reader(XlsxReader::new(tx, Box::new(Cursor::new(data))), rx);
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(1.5)));
+        assert_eq!(orders[1].kind, TransactionKind::Withdrawal(dec!(0.1)));
+        assert_eq!(orders[2].kind, TransactionKind::Dispute(1));
+    }
+
+    #[test]
+    fn date_cells() {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let format = Format::new().set_num_format("dd/mm/yyyy hh:mm");
+        let date = ExcelDateTime::parse_from_str("2024-03-01 12:30:00").unwrap();
+        sheet
+            .write_row(0, 0, ["type", "client", "tx", "amount", "timestamp"])
+            .unwrap();
+        sheet.write_row(1, 0, ["deposit"]).unwrap();
+        sheet.write_row(1, 1, [1, 1]).unwrap();
+        sheet.write_string(1, 3, "2.5").unwrap();
+        sheet
+            .write_datetime_with_format(1, 4, &date, &format)
+            .unwrap();
+        let data = workbook.save_to_buffer().unwrap();
+        let paris = FixedOffset::east_opt(3600).unwrap();
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 11, 30, 0).unwrap();
+
+        for timestamp_format in [
+            TimestampFormat::Iso8601,
+            TimestampFormat::EpochMillis,
+            TimestampFormat::DayMonthYear,
+        ] {
+            let options = ReaderOptions {
+                timestamp_format,
+                timezone: paris,
+                ..ReaderOptions::default()
+            };
+            let (tx, rx) = channel();
+            let actor = XlsxReader::with_options(tx, Box::new(Cursor::new(data.clone())), options);
+            let orders = run_reader(actor, rx);
+
+            assert_eq!(orders.len(), 1);
+            assert_eq!(orders[0].kind, TransactionKind::Deposit(dec!(2.5)));
+            assert_eq!(orders[0].timestamp, Some(expected));
+        }
+    }
+
+    #[test]
+    fn invalid_workbook() {
+        let (tx, _rx) = channel();
+        let actor = XlsxReader::new(tx, Box::new("type, client, tx, amount".as_bytes()));
+
+        assert!(actor.run().is_err());
+    }
+}
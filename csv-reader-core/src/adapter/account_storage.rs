@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::model::{Account, ClientId, LogEntry, Transaction, TxId};
+use crate::Result;
+
+/// Storage statistics, mainly used for capacity planning.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct StorageStats {
+    /// The number of stored accounts.
+    pub accounts: usize,
+
+    /// The number of stored transactions.
+    pub transactions: usize,
+
+    /// The number of transactions currently under dispute.
+    pub open_disputes: usize,
+
+    /// Approximate memory used by the storage in bytes.
+    pub memory_bytes: usize,
+
+    /// Approximate disk space used by the storage in bytes.
+    pub disk_bytes: u64,
+}
+
+/// Account storage trait.
+///
+/// This trait defines the operations that can be performed on an account
+/// storage.  It must raise an error only if the operation leads to a non
+/// consistent state or if there are IO errors.
+pub trait AccountStorage {
+    /// Get an account by its client id.
+    fn get_account(&self, client_id: &ClientId) -> Option<Account>;
+
+    /// Export the accounts
+    fn get_accounts(&self) -> Vec<Account>;
+
+    /// Get at most `limit` accounts of client identifier greater than the
+    /// given one, all of them if none, by increasing client identifier. The
+    /// accounts are read by chunks this way without loading all of them. By
+    /// default, all the accounts are loaded to pick the chunk.
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        accounts_after(self.get_accounts(), after, limit)
+    }
+
+    /// Get a transaction by its identifier.
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction>;
+
+    /// Get the transactions of a client, by identifier.
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction>;
+
+    /// Check if a transaction is disputed.
+    fn is_disputed(&self, tx_id: &TxId) -> bool;
+
+    /// Get the transactions currently under dispute.
+    fn get_disputed_transactions(&self) -> Vec<Transaction>;
+
+    /// Get the storage statistics.
+    fn stats(&self) -> StorageStats;
+
+    /// Add or update an account.
+    fn store_account(&mut self, account: Account) -> Result<Account>;
+
+    /// Store a new transaction.
+    /// Fails if the transaction already exists.
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction>;
+
+    /// Set a transaction as disputed or not.
+    /// Fails if the transaction does not exist.
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()>;
+
+    /// Attribute a stored transaction to another client.
+    /// Fails if the transaction does not exist.
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()>;
+
+    /// Remove an account and return it, if it exists. Its transactions are
+    /// kept.
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>>;
+
+    /// Append an applied transaction to the log of the storage, the disputes
+    /// and their settlements included, or a merge of accounts or a preloaded
+    /// dispute, so the accounts can be rebuilt from it. Nothing is logged by
+    /// the storages without log.
+    fn append_log(&mut self, _entry: LogEntry) -> Result<()> {
+        Ok(())
+    }
+
+    /// Get the log entries in the order they were appended, `None` if the
+    /// storage keeps no log.
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        None
+    }
+
+    /// Check the schema of the storage is the expected one. Nothing is
+    /// checked by the storages without schema.
+    fn check_schema(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make the changes of the session permanent. Nothing is done by the
+    /// storages writing their changes at once.
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Drop the changes of the session not committed yet. The storages
+    /// writing their changes at once cannot drop them and do nothing.
+    fn discard(&mut self) {}
+}
+
+/// Keep at most `limit` of the given accounts of client identifier greater
+/// than `after`, the lowest ones, by increasing client identifier. Only the
+/// accounts kept are held in memory.
+pub(crate) fn accounts_after(
+    accounts: impl IntoIterator<Item = Account>,
+    after: Option<ClientId>,
+    limit: usize,
+) -> Vec<Account> {
+    let mut chunk = BTreeMap::new();
+    for account in accounts {
+        if after.is_some_and(|after| account.client_id <= after) {
+            continue;
+        }
+        chunk.insert(account.client_id, account);
+        if chunk.len() > limit {
+            chunk.pop_last();
+        }
+    }
+
+    chunk.into_values().collect()
+}
+
+/// Open the storage backend of the given data source name. Only the
+/// in-memory storage, `memory`, is available.
+///
+/// ```
+/// use csv_reader_core::adapter::open_storage;
+///
+/// assert_eq!(open_storage("memory:").unwrap().stats().accounts, 0);
+/// assert!(open_storage("postgres://localhost/accounts").is_err());
+/// ```
+pub fn open_storage(dsn: &str) -> Result<Box<dyn AccountStorage + Send + Sync>> {
+    match dsn.trim_end_matches(':').to_lowercase().as_str() {
+        "memory" => Ok(Box::new(InMemoryAccountStorage::default())),
+        _ => Err(anyhow!("Unsupported storage '{dsn}' (expected 'memory').")),
+    }
+}
+
+/// A simple in-memory account storage.
+#[derive(Debug, Default)]
+pub struct InMemoryAccountStorage {
+    accounts: HashMap<ClientId, Account>,
+    transactions: HashMap<TxId, Transaction>,
+    disputed: HashSet<TxId>,
+    log: Vec<LogEntry>,
+}
+
+impl AccountStorage for InMemoryAccountStorage {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        self.accounts.get(client_id).cloned()
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        self.accounts.values().cloned().collect()
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        accounts_after(self.accounts.values().cloned(), after, limit)
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        self.transactions.get(tx_id).cloned()
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        let mut transactions: Vec<Transaction> = self
+            .transactions
+            .values()
+            .filter(|transaction| transaction.client_id == *client_id)
+            .cloned()
+            .collect();
+        transactions.sort_unstable_by_key(|transaction| transaction.tx_id);
+
+        transactions
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        self.disputed.contains(tx_id)
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        self.disputed
+            .iter()
+            .filter_map(|tx_id| self.transactions.get(tx_id).cloned())
+            .collect()
+    }
+
+    fn stats(&self) -> StorageStats {
+        // Only the allocated buckets are accounted for, hashing overhead is
+        // ignored hence the result is an approximation.
+        let memory_bytes = size_of::<Self>()
+            + self.accounts.capacity() * size_of::<(ClientId, Account)>()
+            + self.transactions.capacity() * size_of::<(TxId, Transaction)>()
+            + self.disputed.capacity() * size_of::<TxId>()
+            + self.log.capacity() * size_of::<LogEntry>();
+
+        StorageStats {
+            accounts: self.accounts.len(),
+            transactions: self.transactions.len(),
+            open_disputes: self.disputed.len(),
+            memory_bytes,
+            disk_bytes: 0,
+        }
+    }
+
+    fn store_account(&mut self, account: Account) -> Result<Account> {
+        self.accounts.insert(account.client_id, account.clone());
+
+        Ok(account)
+    }
+
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        if self.transactions.contains_key(&transaction.tx_id) {
+            return Err(anyhow!("Transaction {} already exists", transaction.tx_id));
+        }
+        self.transactions
+            .insert(transaction.tx_id, transaction.clone());
+
+        Ok(transaction)
+    }
+
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()> {
+        let _ = self
+            .transactions
+            .get(&tx_id)
+            .ok_or_else(|| anyhow!("Transaction {} does not exist", tx_id))?;
+
+        if disputed {
+            self.disputed.insert(tx_id);
+        } else {
+            self.disputed.remove(&tx_id);
+        }
+
+        Ok(())
+    }
+
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()> {
+        let transaction = self
+            .transactions
+            .get_mut(&tx_id)
+            .ok_or_else(|| anyhow!("Transaction {} does not exist", tx_id))?;
+        transaction.client_id = client_id;
+
+        Ok(())
+    }
+
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>> {
+        Ok(self.accounts.remove(client_id))
+    }
+
+    fn append_log(&mut self, entry: LogEntry) -> Result<()> {
+        self.log.push(entry);
+
+        Ok(())
+    }
+
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        Some(self.log.clone())
+    }
+}
+
+#[cfg(test)]
+mod in_memory_storage_tests {
+    use rust_decimal_macros::dec;
+
+    use crate::model::{TransactionKind, TransactionOrder};
+
+    use super::*;
+
+    #[test]
+    fn test_get_account_exists() {
+        let mut storage = InMemoryAccountStorage::default();
+        let account = Account::new(1);
+        storage.accounts.insert(1, account.clone());
+
+        assert_eq!(storage.get_account(&1), Some(account));
+    }
+
+    #[test]
+    fn test_get_account_not_exists() {
+        let storage = InMemoryAccountStorage::default();
+
+        assert_eq!(storage.get_account(&1), None);
+    }
+
+    #[test]
+    fn test_get_transaction_exists() {
+        let mut storage = InMemoryAccountStorage::default();
+        let transaction: Transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(1)),
+            timestamp: None,
+            currency: None,
+        }
+        .into();
+        storage.transactions.insert(1, transaction.clone());
+
+        assert_eq!(storage.get_transaction(&1), Some(transaction));
+    }
+
+    #[test]
+    fn test_get_transaction_not_exists() {
+        let storage = InMemoryAccountStorage::default();
+
+        assert_eq!(storage.get_transaction(&1), None);
+    }
+
+    #[test]
+    fn test_set_disputed() {
+        let mut storage = InMemoryAccountStorage::default();
+
+        assert!(!storage.is_disputed(&1));
+
+        let transaction: Transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(1)),
+            timestamp: None,
+            currency: None,
+        }
+        .into();
+        storage.transactions.insert(1, transaction.clone());
+
+        // By default, transactions are not disputed
+        assert!(!storage.is_disputed(&1));
+
+        storage.set_disputed(1, true).unwrap();
+
+        // Transaction is now disputed
+        assert!(storage.is_disputed(&1));
+
+        storage.set_disputed(1, true).unwrap();
+
+        // Transaction is still disputed
+        assert!(storage.is_disputed(&1));
+
+        storage.set_disputed(1, false).unwrap();
+
+        // Transaction is not disputed anymore
+        assert!(!storage.is_disputed(&1));
+    }
+
+    #[test]
+    fn test_set_disputed_non_existing_transaction() {
+        let mut storage = InMemoryAccountStorage::default();
+        let error = storage.set_disputed(1, true).unwrap_err();
+
+        assert_eq!(error.to_string(), "Transaction 1 does not exist");
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut storage = InMemoryAccountStorage::default();
+        let empty_stats = storage.stats();
+
+        assert_eq!(empty_stats.accounts, 0);
+        assert_eq!(empty_stats.transactions, 0);
+        assert_eq!(empty_stats.open_disputes, 0);
+
+        storage.store_account(Account::new(1)).unwrap();
+        for tx_id in 1..=2 {
+            let transaction: Transaction = TransactionOrder {
+                tx_id,
+                client_id: 1,
+                kind: TransactionKind::Deposit(dec!(1)),
+                timestamp: None,
+                currency: None,
+            }
+            .into();
+            storage.store_transaction(transaction).unwrap();
+        }
+        storage.set_disputed(2, true).unwrap();
+        let stats = storage.stats();
+        let disputed = storage.get_disputed_transactions();
+
+        assert_eq!(disputed.len(), 1);
+        assert_eq!(disputed[0].tx_id, 2);
+
+        assert_eq!(stats.accounts, 1);
+        assert_eq!(stats.transactions, 2);
+        assert_eq!(stats.open_disputes, 1);
+        assert!(stats.memory_bytes > empty_stats.memory_bytes);
+        assert_eq!(stats.disk_bytes, 0);
+    }
+
+    #[test]
+    fn test_store_account() {
+        let mut storage = InMemoryAccountStorage::default();
+        let account = Account::new(1);
+        let account = storage.store_account(account).unwrap();
+
+        assert_eq!(storage.accounts.get(&1), Some(&account));
+    }
+
+    #[test]
+    fn test_store_transaction() {
+        let mut storage = InMemoryAccountStorage::default();
+        let transaction: Transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(1)),
+            timestamp: None,
+            currency: None,
+        }
+        .into();
+        let transaction = storage.store_transaction(transaction).unwrap();
+
+        assert_eq!(storage.transactions.get(&1), Some(&transaction));
+    }
+
+    #[test]
+    fn test_store_transaction_already_exists() {
+        let mut storage = InMemoryAccountStorage::default();
+        let transaction: Transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(1)),
+            timestamp: None,
+            currency: None,
+        }
+        .into();
+        let _ = storage.store_transaction(transaction.clone()).unwrap();
+        let error = storage.store_transaction(transaction).unwrap_err();
+
+        assert_eq!(error.to_string(), "Transaction 1 already exists");
+    }
+
+    #[test]
+    fn test_reassign_transaction() {
+        let mut storage = InMemoryAccountStorage::default();
+        for tx_id in 1..=2 {
+            let transaction: Transaction = TransactionOrder {
+                tx_id,
+                client_id: 2,
+                kind: TransactionKind::Deposit(dec!(1)),
+                timestamp: None,
+                currency: None,
+            }
+            .into();
+            storage.store_transaction(transaction).unwrap();
+        }
+        storage.store_account(Account::new(2)).unwrap();
+        storage.reassign_transaction(2, 1).unwrap();
+
+        assert_eq!(storage.get_client_transactions(&2).len(), 1);
+        assert_eq!(storage.get_client_transactions(&1)[0].tx_id, 2);
+        assert!(storage.reassign_transaction(3, 1).is_err());
+        assert_eq!(storage.remove_account(&2).unwrap(), Some(Account::new(2)));
+        assert_eq!(storage.remove_account(&2).unwrap(), None);
+    }
+}
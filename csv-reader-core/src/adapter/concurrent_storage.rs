@@ -0,0 +1,276 @@
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use dashmap::{DashMap, DashSet};
+
+use super::{accounts_after, AccountStorage, StorageStats};
+use crate::model::{Account, ClientId, LogEntry, Transaction, TxId};
+use crate::Result;
+
+/// An in-memory account storage written concurrently: its maps are sharded
+/// and locked per shard, so it is written through a shared reference, see
+/// the implementation of [AccountStorage] for `&ConcurrentAccountStorage`.
+/// Given to [AccountManager::with_concurrent_storage], the orders of
+/// different accounts are applied in parallel without locking the whole
+/// storage for each operation.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::ConcurrentAccountStorage;
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{AccountManager, AccountManagerOptions};
+///
+/// let manager = Arc::new(AccountManager::with_concurrent_storage(
+///     ConcurrentAccountStorage::default(),
+///     AccountManagerOptions::default(),
+/// ));
+/// let threads: Vec<_> = (1..=4)
+///     .map(|client_id| {
+///         let manager = manager.clone();
+///         std::thread::spawn(move || {
+///             let kind = TransactionKind::Deposit(Decimal::TEN);
+///             let order = TransactionOrder { tx_id: client_id.into(), client_id, kind, timestamp: None, currency: None };
+///             manager.process_order(order).unwrap();
+///         })
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+///
+/// assert_eq!(manager.get_accounts().len(), 4);
+/// assert_eq!(manager.stats().transactions, 4);
+/// ```
+///
+/// [AccountManager::with_concurrent_storage]: crate::service::AccountManager::with_concurrent_storage
+#[derive(Debug, Default)]
+pub struct ConcurrentAccountStorage {
+    accounts: DashMap<ClientId, Account>,
+    transactions: DashMap<TxId, Transaction>,
+    disputed: DashSet<TxId>,
+    log: Mutex<Vec<LogEntry>>,
+}
+
+impl AccountStorage for &ConcurrentAccountStorage {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        self.accounts
+            .get(client_id)
+            .map(|account| account.value().clone())
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        self.accounts
+            .iter()
+            .map(|account| account.value().clone())
+            .collect()
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        accounts_after(
+            self.accounts.iter().map(|account| account.value().clone()),
+            after,
+            limit,
+        )
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        self.transactions
+            .get(tx_id)
+            .map(|transaction| transaction.value().clone())
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        let mut transactions: Vec<Transaction> = self
+            .transactions
+            .iter()
+            .filter(|transaction| transaction.client_id == *client_id)
+            .map(|transaction| transaction.value().clone())
+            .collect();
+        transactions.sort_unstable_by_key(|transaction| transaction.tx_id);
+
+        transactions
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        self.disputed.contains(tx_id)
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        self.disputed
+            .iter()
+            .filter_map(|tx_id| self.get_transaction(&tx_id))
+            .collect()
+    }
+
+    fn stats(&self) -> StorageStats {
+        // Like the in-memory storage, only the allocated buckets are
+        // accounted for.
+        let memory_bytes = size_of::<ConcurrentAccountStorage>()
+            + self.accounts.capacity() * size_of::<(ClientId, Account)>()
+            + self.transactions.capacity() * size_of::<(TxId, Transaction)>()
+            + self.disputed.capacity() * size_of::<TxId>()
+            + self.log.lock().unwrap().capacity() * size_of::<LogEntry>();
+
+        StorageStats {
+            accounts: self.accounts.len(),
+            transactions: self.transactions.len(),
+            open_disputes: self.disputed.len(),
+            memory_bytes,
+            disk_bytes: 0,
+        }
+    }
+
+    fn store_account(&mut self, account: Account) -> Result<Account> {
+        self.accounts.insert(account.client_id, account.clone());
+
+        Ok(account)
+    }
+
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        match self.transactions.entry(transaction.tx_id) {
+            dashmap::Entry::Occupied(_) => {
+                Err(anyhow!("Transaction {} already exists", transaction.tx_id))
+            }
+            dashmap::Entry::Vacant(entry) => {
+                entry.insert(transaction.clone());
+
+                Ok(transaction)
+            }
+        }
+    }
+
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()> {
+        if !self.transactions.contains_key(&tx_id) {
+            return Err(anyhow!("Transaction {} does not exist", tx_id));
+        }
+
+        if disputed {
+            self.disputed.insert(tx_id);
+        } else {
+            self.disputed.remove(&tx_id);
+        }
+
+        Ok(())
+    }
+
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()> {
+        let mut transaction = self
+            .transactions
+            .get_mut(&tx_id)
+            .ok_or_else(|| anyhow!("Transaction {} does not exist", tx_id))?;
+        transaction.client_id = client_id;
+
+        Ok(())
+    }
+
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>> {
+        Ok(self.accounts.remove(client_id).map(|(_, account)| account))
+    }
+
+    fn append_log(&mut self, entry: LogEntry) -> Result<()> {
+        self.log.lock().unwrap().push(entry);
+
+        Ok(())
+    }
+
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        Some(self.log.lock().unwrap().clone())
+    }
+}
+
+impl AccountStorage for ConcurrentAccountStorage {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        (&self).get_account(client_id)
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        (&self).get_accounts()
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        (&self).get_accounts_after(after, limit)
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        (&self).get_transaction(tx_id)
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        (&self).get_client_transactions(client_id)
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        (&self).is_disputed(tx_id)
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        (&self).get_disputed_transactions()
+    }
+
+    fn stats(&self) -> StorageStats {
+        (&self).stats()
+    }
+
+    fn store_account(&mut self, account: Account) -> Result<Account> {
+        (&*self).store_account(account)
+    }
+
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        (&*self).store_transaction(transaction)
+    }
+
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()> {
+        (&*self).set_disputed(tx_id, disputed)
+    }
+
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()> {
+        (&*self).reassign_transaction(tx_id, client_id)
+    }
+
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>> {
+        (&*self).remove_account(client_id)
+    }
+
+    fn append_log(&mut self, entry: LogEntry) -> Result<()> {
+        (&*self).append_log(entry)
+    }
+
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        (&self).get_log()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use crate::model::{TransactionKind, TransactionOrder};
+
+    use super::*;
+
+    #[test]
+    fn transactions_are_stored_once() {
+        let storage = ConcurrentAccountStorage::default();
+        let transaction: Transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(1)),
+            timestamp: None,
+            currency: None,
+        }
+        .into();
+        let mut shared = &storage;
+        shared.store_transaction(transaction.clone()).unwrap();
+
+        assert!(shared.store_transaction(transaction).is_err());
+        assert!(shared.set_disputed(2, true).is_err());
+        shared.set_disputed(1, true).unwrap();
+        shared.reassign_transaction(1, 2).unwrap();
+        assert_eq!(storage.get_disputed_transactions()[0].client_id, 2);
+        assert_eq!(storage.get_client_transactions(&2).len(), 1);
+        assert_eq!(storage.stats().open_disputes, 1);
+    }
+}
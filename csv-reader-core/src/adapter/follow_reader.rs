@@ -0,0 +1,120 @@
+use std::{
+    io::{self, Read},
+    time::{Duration, Instant},
+};
+
+/// Reader following a growing input like `tail -f`: when the end of the input
+/// is reached, it waits for data to be appended instead of ending the input.
+/// The input only ends once no data has been appended for the idle timeout,
+/// if any.
+///
+/// ```
+/// use std::{io::Read, time::Duration};
+///
+/// use csv_reader_core::adapter::FollowReader;
+///
+/// let mut reader = FollowReader::new("deposit,1,1,1.0\n".as_bytes())
+///     .with_idle_timeout(Duration::from_millis(10));
+/// let mut content = String::new();
+/// reader.read_to_string(&mut content).unwrap();
+///
+/// assert_eq!(content, "deposit,1,1,1.0\n");
+/// ```
+pub struct FollowReader<R> {
+    /// The followed input.
+    inner: R,
+
+    /// The delay before reading again at the end of the input.
+    poll_interval: Duration,
+
+    /// End the input when no data has been appended for this duration.
+    idle_timeout: Option<Duration>,
+}
+
+impl<R: Read> FollowReader<R> {
+    /// Follow the given input, checking for appended data every 100ms.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            poll_interval: Duration::from_millis(100),
+            idle_timeout: None,
+        }
+    }
+
+    /// Set the delay before reading again at the end of the input.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+
+        self
+    }
+
+    /// End the input once no data has been appended for the given duration.
+    /// Without it, the input is followed forever.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+
+        self
+    }
+}
+
+impl<R: Read> Read for FollowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let idle_since = Instant::now();
+
+        loop {
+            let length = self.inner.read(buf)?;
+
+            if length > 0 || buf.is_empty() {
+                return Ok(length);
+            }
+            if self
+                .idle_timeout
+                .is_some_and(|timeout| idle_since.elapsed() >= timeout)
+            {
+                return Ok(0);
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::OpenOptions, io::Write};
+
+    use super::*;
+    use crate::{
+        actor::{channel, Reader},
+        model::TransactionOrder,
+    };
+
+    #[test]
+    fn test_follow_appended_rows() {
+        let path = std::env::temp_dir().join(format!(
+            "csv_reader_follow_reader_{}.csv",
+            std::process::id()
+        ));
+        std::fs::write(&path, "type,client,tx,amount\ndeposit,1,1,1.0\n").unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = FollowReader::new(file)
+            .with_poll_interval(Duration::from_millis(10))
+            .with_idle_timeout(Duration::from_millis(200));
+        let (tx, rx) = channel();
+        let actor = Reader::new(tx, Box::new(reader));
+        let handler = std::thread::spawn(move || actor.run());
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        // a row written in two steps is read once complete
+        file.write_all(b"deposit,2,").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        file.write_all(b"2,2.0\nwithdrawal,1,3,0.5\n").unwrap();
+        handler.join().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let orders: Vec<TransactionOrder> = rx.iter().collect();
+
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[1].client_id, 2);
+        assert_eq!(orders[2].tx_id, 3);
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fmt::Display,
+    io::{self, Cursor, Read},
+    str::FromStr,
+};
+
+use anyhow::bail;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+/// Size of the start of the input read to detect its encoding.
+const SAMPLE_SIZE: u64 = 4096;
+
+/// The UTF-8 byte order mark, written by Excel at the start of its exports.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Character encoding of an input. The inputs not in UTF-8, like the files
+/// exported from older Windows systems, are transcoded to UTF-8 before being
+/// parsed, the byte offsets of the reading are then the ones of the
+/// transcoded input.
+///
+/// Whatever the encoding, a byte order mark at the start of the input tells
+/// its actual encoding and is removed, so it does not stick to the first
+/// header name. The byte offsets do not count it.
+///
+/// ```
+/// use std::io::Read;
+///
+/// use csv_reader_core::adapter::InputEncoding;
+///
+/// // "type,client" in UTF-16 without byte order mark
+/// let data: Vec<u8> = "type,client".encode_utf16().flat_map(u16::to_le_bytes).collect();
+/// let mut content = String::new();
+/// InputEncoding::Auto
+///     .decode(Box::new(std::io::Cursor::new(data)))
+///     .unwrap()
+///     .read_to_string(&mut content)
+///     .unwrap();
+///
+/// assert_eq!(content, "type,client");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// UTF-8, read as is.
+    #[default]
+    Utf8,
+
+    /// ISO 8859-1, read as its Windows-1252 superset.
+    Latin1,
+
+    /// UTF-16, little endian unless the byte order mark tells otherwise.
+    Utf16,
+
+    /// Detected from the start of the input: its byte order mark, the zero
+    /// bytes of the ASCII characters in UTF-16, Latin-1 if it is not valid
+    /// UTF-8.
+    Auto,
+}
+
+impl InputEncoding {
+    /// Wrap the given input in a reader transcoding it to UTF-8 and removing
+    /// its byte order mark. The start of the input is read to find the byte
+    /// order mark or detect the encoding, hence the IO error.
+    pub fn decode(
+        self,
+        mut reader: Box<dyn Read + Sync + Send>,
+    ) -> io::Result<Box<dyn Read + Sync + Send>> {
+        let encoding = match self {
+            Self::Latin1 => Some(WINDOWS_1252),
+            Self::Utf16 => Some(UTF_16LE),
+            Self::Utf8 | Self::Auto => {
+                let size = match self {
+                    Self::Auto => SAMPLE_SIZE,
+                    _ => UTF8_BOM.len() as u64,
+                };
+                let mut sample = Vec::new();
+                (&mut reader).take(size).read_to_end(&mut sample)?;
+                let encoding = match self {
+                    Self::Auto => detect(&sample),
+                    _ => Encoding::for_bom(&sample).map(|(encoding, _)| encoding),
+                };
+                // a UTF-8 input is not transcoded, only its mark is removed
+                if encoding == Some(UTF_8) {
+                    sample.drain(..UTF8_BOM.len());
+                }
+                reader = Box::new(Cursor::new(sample).chain(reader));
+
+                encoding.filter(|encoding| *encoding != UTF_8)
+            }
+        };
+        let Some(encoding) = encoding else {
+            return Ok(reader);
+        };
+
+        Ok(Box::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(Some(encoding))
+                .bom_override(true)
+                .strip_bom(true)
+                .build(reader),
+        ))
+    }
+}
+
+/// Detect the encoding of the given start of an input, none for UTF-8.
+fn detect(sample: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return Some(encoding);
+    }
+    // the ASCII characters have a zero byte in UTF-16
+    let zeros = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|byte| **byte == 0)
+            .count()
+    };
+    if zeros(1) > sample.len() / 4 {
+        return Some(UTF_16LE);
+    }
+    if zeros(0) > sample.len() / 4 {
+        return Some(UTF_16BE);
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => None,
+        // a full sample may end in the middle of a character
+        Err(error) if error.error_len().is_none() && sample.len() as u64 == SAMPLE_SIZE => None,
+        Err(_) => Some(WINDOWS_1252),
+    }
+}
+
+impl Display for InputEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "utf-8"),
+            Self::Latin1 => write!(f, "latin-1"),
+            Self::Utf16 => write!(f, "utf-16"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl FromStr for InputEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "latin-1" | "latin1" | "iso-8859-1" | "windows-1252" | "cp1252" => Ok(Self::Latin1),
+            "utf-16" | "utf16" => Ok(Self::Utf16),
+            "auto" => Ok(Self::Auto),
+            _ => bail!(
+                "Unknown encoding '{value}' (expected 'utf-8', 'latin-1', 'utf-16' or 'auto')."
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoding: InputEncoding, data: Vec<u8>) -> String {
+        let mut content = String::new();
+        encoding
+            .decode(Box::new(Cursor::new(data)))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+
+        content
+    }
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_explicit_encodings() {
+        let latin1 = b"type,client\ndeposit,1 \xe9\n".to_vec();
+        let mut utf16_be = vec![0xfe, 0xff];
+        utf16_be.extend(utf16("type,client\n", true));
+
+        assert_eq!(
+            decode(InputEncoding::Latin1, latin1),
+            "type,client\ndeposit,1 é\n"
+        );
+        assert_eq!(decode(InputEncoding::Utf16, utf16_be), "type,client\n");
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let mut utf16_le = vec![0xff, 0xfe];
+        utf16_le.extend(utf16("type", false));
+        let utf8 = b"\xef\xbb\xbftype".to_vec();
+
+        assert_eq!(decode(InputEncoding::Utf8, utf8.clone()), "type");
+        assert_eq!(decode(InputEncoding::Auto, utf8.clone()), "type");
+        assert_eq!(decode(InputEncoding::Latin1, utf8), "type");
+        assert_eq!(decode(InputEncoding::Utf8, utf16_le), "type");
+        assert_eq!(decode(InputEncoding::Utf8, b"ty".to_vec()), "ty");
+    }
+
+    #[test]
+    fn test_detection() {
+        let mut utf16_le = vec![0xff, 0xfe];
+        utf16_le.extend(utf16("type,client\n", false));
+
+        assert_eq!(decode(InputEncoding::Auto, utf16_le), "type,client\n");
+        assert_eq!(decode(InputEncoding::Auto, utf16("tx,é", true)), "tx,é");
+        assert_eq!(decode(InputEncoding::Auto, b"tx,\xe9".to_vec()), "tx,é");
+        assert_eq!(decode(InputEncoding::Auto, "tx,é".into()), "tx,é");
+        assert_eq!(decode(InputEncoding::Auto, Vec::new()), "");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "Latin1".parse::<InputEncoding>().unwrap(),
+            InputEncoding::Latin1
+        );
+        assert_eq!(
+            "UTF-16".parse::<InputEncoding>().unwrap(),
+            InputEncoding::Utf16
+        );
+        assert!("ebcdic".parse::<InputEncoding>().is_err());
+    }
+}
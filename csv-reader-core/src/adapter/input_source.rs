@@ -0,0 +1,51 @@
+use crate::{actor::ReaderProgress, model::TransactionOrder};
+
+/// A source of transaction orders. The CSV parsing of [Orders] is one;
+/// embedders implement this trait to read the orders from their own systems,
+/// like a database or a queue. The source reader actor of the `unstable`
+/// feature sends the orders of any source to the accountant, so the reader
+/// actor is not reimplemented for each.
+///
+/// A source skips and logs its invalid records itself, an error ends the
+/// reading.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::InputSource;
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder, TxId};
+///
+/// /// Deposits of one unit, as read from a queue.
+/// struct Queue(Vec<TxId>);
+///
+/// impl InputSource for Queue {
+///     fn next_order(&mut self) -> Option<csv_reader_core::Result<TransactionOrder>> {
+///         let tx_id = self.0.pop()?;
+///
+///         Some(Ok(TransactionOrder {
+///             tx_id,
+///             client_id: 1,
+///             kind: TransactionKind::Deposit(Decimal::ONE),
+///             timestamp: None,
+///             currency: None,
+///         }))
+///     }
+/// }
+///
+/// let mut queue = Queue(vec![2, 1]);
+///
+/// assert_eq!(queue.next_order().unwrap().unwrap().tx_id, 1);
+/// assert!(queue.progress().is_none());
+/// ```
+///
+/// [Orders]: crate::actor::Orders
+pub trait InputSource: Send {
+    /// Get the next transaction order, `None` once the source is exhausted.
+    fn next_order(&mut self) -> Option<crate::Result<TransactionOrder>>;
+
+    /// Get the progress of the reading so far, none if the source does not
+    /// track it.
+    fn progress(&self) -> Option<ReaderProgress> {
+        None
+    }
+}
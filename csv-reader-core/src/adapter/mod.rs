@@ -0,0 +1,29 @@
+//! Adapters are tools mainly to perform IO operations.
+//!
+//! The adapter module holds the implementation of tools required by the services.
+//! These different adapters perform operation that involve IOs like reading or
+//! writing to files or databases. (more geneally, the outside world)
+
+mod account_storage;
+#[cfg(feature = "concurrent-storage")]
+mod concurrent_storage;
+mod follow_reader;
+mod input_encoding;
+mod input_source;
+#[cfg(feature = "object-store")]
+mod object_store_reader;
+mod overlay_storage;
+mod reject_sink;
+mod row_transformer;
+
+pub use account_storage::*;
+#[cfg(feature = "concurrent-storage")]
+pub use concurrent_storage::*;
+pub use follow_reader::*;
+pub use input_encoding::*;
+pub use input_source::*;
+#[cfg(feature = "object-store")]
+pub use object_store_reader::*;
+pub use overlay_storage::*;
+pub use reject_sink::*;
+pub use row_transformer::*;
@@ -0,0 +1,119 @@
+use std::{
+    io::{self, Read},
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use object_store::{path::Path, ObjectStore, ObjectStoreExt};
+use tokio::runtime::{Builder, Runtime};
+use url::Url;
+
+use crate::Result;
+
+/// Blocking reader streaming an object from an object store (S3, GCS, Azure,
+/// HTTP or local file system) so it can be given to the reader actors. The
+/// object is fetched chunk by chunk, it is never loaded in memory as a whole.
+pub struct ObjectStoreReader {
+    /// The runtime driving the object store client.
+    runtime: Runtime,
+
+    /// The chunks of the object, the mutex only makes the stream `Sync`.
+    stream: Mutex<BoxStream<'static, object_store::Result<Bytes>>>,
+
+    /// The remaining bytes of the current chunk.
+    chunk: Bytes,
+}
+
+impl ObjectStoreReader {
+    /// Open the object at the given URL (e.g. `s3://bucket/tx.csv`). The
+    /// object store is configured from the environment variables (e.g.
+    /// `AWS_ACCESS_KEY_ID`, `AWS_REGION`).
+    pub fn open(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        let (store, path) = object_store::parse_url_opts(&url, std::env::vars())?;
+
+        Self::new(store.as_ref(), &path)
+    }
+
+    /// Open the object at the given path of the given object store.
+    pub fn new(store: &dyn ObjectStore, path: &Path) -> Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let stream = runtime
+            .block_on(store.get(path))
+            .map_err(|e| anyhow!("Cannot open object '{path}': {e}"))?
+            .into_stream();
+
+        Ok(Self {
+            runtime,
+            stream: Mutex::new(stream),
+            chunk: Bytes::new(),
+        })
+    }
+
+    /// Tell if the given input designates an object store URL rather than a
+    /// local path.
+    ///
+    /// ```
+    /// use csv_reader_core::adapter::ObjectStoreReader;
+    ///
+    /// assert!(ObjectStoreReader::is_url("s3://bucket/tx.csv"));
+    /// assert!(!ObjectStoreReader::is_url("tx.csv"));
+    /// ```
+    pub fn is_url(input: &str) -> bool {
+        input.contains("://") && Url::parse(input).is_ok()
+    }
+}
+
+impl Read for ObjectStoreReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let stream = self.stream.get_mut().unwrap();
+
+        while self.chunk.is_empty() {
+            match self.runtime.block_on(stream.next()) {
+                None => return Ok(0),
+                Some(chunk) => self.chunk = chunk.map_err(io::Error::other)?,
+            }
+        }
+        let length = buf.len().min(self.chunk.len());
+        buf[..length].copy_from_slice(&self.chunk.split_to(length));
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[test]
+    fn test_read_object() {
+        let store = InMemory::new();
+        let path = Path::from("input/tx.csv");
+        let data = "type,client,tx,amount\ndeposit,1,1,1.0\n".repeat(1000);
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(store.put(&path, data.clone().into()))
+            .unwrap();
+        let mut content = String::new();
+        ObjectStoreReader::new(&store, &path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+
+        assert_eq!(content, data);
+    }
+
+    #[test]
+    fn test_missing_object() {
+        let store = InMemory::new();
+
+        assert!(ObjectStoreReader::new(&store, &Path::from("missing.csv")).is_err());
+        assert!(ObjectStoreReader::open("memory:///missing.csv").is_err());
+        assert!(ObjectStoreReader::open("not a url").is_err());
+    }
+}
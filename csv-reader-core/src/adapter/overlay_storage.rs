@@ -0,0 +1,474 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::anyhow;
+
+use crate::model::{Account, ClientId, LogEntry, Transaction, TxId};
+use crate::Result;
+
+use super::{accounts_after, AccountStorage, StorageStats};
+
+/// Storage session over a base storage, usually a persistent one. The base is
+/// read through while the changes are kept in memory until they are committed
+/// to the base or discarded. This allows a dry run against the production
+/// state, or staging a run before committing it, without copying the base.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::{AccountStorage, InMemoryAccountStorage, OverlayStorage};
+/// use csv_reader_core::model::Account;
+///
+/// let mut base = InMemoryAccountStorage::default();
+/// base.store_account(Account::new(1)).unwrap();
+/// let mut overlay = OverlayStorage::new(base);
+///
+/// let mut account = overlay.get_account(&1).unwrap();
+/// account.deposit(Decimal::ONE).unwrap();
+/// overlay.store_account(account).unwrap();
+///
+/// assert_eq!(overlay.get_account(&1).unwrap().total, Decimal::ONE);
+/// assert_eq!(overlay.base().get_account(&1).unwrap().total, Decimal::ZERO);
+///
+/// overlay.commit().unwrap();
+/// assert_eq!(overlay.base().get_account(&1).unwrap().total, Decimal::ONE);
+/// ```
+#[derive(Debug, Default)]
+pub struct OverlayStorage<B> {
+    /// The storage read through and receiving the committed changes.
+    base: B,
+
+    /// The accounts stored during the session.
+    accounts: HashMap<ClientId, Account>,
+
+    /// The transactions stored during the session.
+    transactions: HashMap<TxId, Transaction>,
+
+    /// The dispute status set during the session, by transaction.
+    disputed: HashMap<TxId, bool>,
+
+    /// The client of the base transactions reassigned during the session.
+    reassigned: HashMap<TxId, ClientId>,
+
+    /// The base accounts removed during the session.
+    removed: HashSet<ClientId>,
+
+    /// The entries logged during the session, in order.
+    log: Vec<LogEntry>,
+}
+
+impl<B: AccountStorage> OverlayStorage<B> {
+    /// Start a session over the given base storage.
+    pub fn new(base: B) -> Self {
+        Self {
+            base,
+            accounts: HashMap::new(),
+            transactions: HashMap::new(),
+            disputed: HashMap::new(),
+            reassigned: HashMap::new(),
+            removed: HashSet::new(),
+            log: Vec::new(),
+        }
+    }
+
+    /// Get the base storage, without the changes of the session not
+    /// committed yet.
+    pub fn base(&self) -> &B {
+        &self.base
+    }
+
+    /// Tell if the session has changes not committed yet.
+    pub fn has_changes(&self) -> bool {
+        !(self.accounts.is_empty()
+            && self.transactions.is_empty()
+            && self.disputed.is_empty()
+            && self.reassigned.is_empty()
+            && self.removed.is_empty()
+            && self.log.is_empty())
+    }
+}
+
+impl<B: AccountStorage> AccountStorage for OverlayStorage<B> {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        if self.removed.contains(client_id) {
+            return None;
+        }
+        self.accounts
+            .get(client_id)
+            .cloned()
+            .or_else(|| self.base.get_account(client_id))
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        let mut accounts: Vec<Account> = self
+            .base
+            .get_accounts()
+            .into_iter()
+            .filter(|account| {
+                !(self.accounts.contains_key(&account.client_id)
+                    || self.removed.contains(&account.client_id))
+            })
+            .collect();
+        accounts.extend(self.accounts.values().cloned());
+
+        accounts
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        let mut chunk = accounts_after(self.accounts.values().cloned(), after, limit);
+        // the chunks of the base are read until they go past the accounts
+        // kept, some of their accounts being replaced or removed
+        let mut base_after = after;
+        loop {
+            let base_chunk = self.base.get_accounts_after(base_after, limit);
+            let exhausted = base_chunk.len() < limit;
+            base_after = base_chunk.last().map(|account| account.client_id);
+            let base_chunk = base_chunk.into_iter().filter(|account| {
+                !(self.accounts.contains_key(&account.client_id)
+                    || self.removed.contains(&account.client_id))
+            });
+            chunk = accounts_after(chunk.into_iter().chain(base_chunk), after, limit);
+            let complete =
+                chunk.len() == limit && chunk.last().map(|account| account.client_id) <= base_after;
+            if exhausted || complete {
+                return chunk;
+            }
+        }
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        self.transactions.get(tx_id).cloned().or_else(|| {
+            let mut transaction = self.base.get_transaction(tx_id)?;
+            if let Some(client_id) = self.reassigned.get(tx_id) {
+                transaction.client_id = *client_id;
+            }
+
+            Some(transaction)
+        })
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        let mut transactions: Vec<Transaction> = self
+            .base
+            .get_client_transactions(client_id)
+            .into_iter()
+            .filter(|transaction| !self.reassigned.contains_key(&transaction.tx_id))
+            .collect();
+        transactions.extend(
+            self.reassigned
+                .iter()
+                .filter(|(_, reassigned_to)| *reassigned_to == client_id)
+                .filter_map(|(tx_id, _)| self.get_transaction(tx_id)),
+        );
+        transactions.extend(
+            self.transactions
+                .values()
+                .filter(|transaction| transaction.client_id == *client_id)
+                .cloned(),
+        );
+        transactions.sort_unstable_by_key(|transaction| transaction.tx_id);
+
+        transactions
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        self.disputed
+            .get(tx_id)
+            .copied()
+            .unwrap_or_else(|| self.base.is_disputed(tx_id))
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        let mut transactions: Vec<Transaction> = self
+            .base
+            .get_disputed_transactions()
+            .into_iter()
+            .filter(|transaction| !self.disputed.contains_key(&transaction.tx_id))
+            .collect();
+        transactions.extend(
+            self.disputed
+                .iter()
+                .filter(|(_, disputed)| **disputed)
+                .filter_map(|(tx_id, _)| self.get_transaction(tx_id)),
+        );
+
+        transactions
+    }
+
+    fn stats(&self) -> StorageStats {
+        let base = self.base.stats();
+        let new_accounts = self
+            .accounts
+            .keys()
+            .filter(|client_id| self.base.get_account(client_id).is_none())
+            .count();
+        let removed_accounts = self
+            .removed
+            .iter()
+            .filter(|client_id| self.base.get_account(client_id).is_some())
+            .count();
+        // as for the in-memory storage, hashing overhead is ignored
+        let memory_bytes = size_of::<Self>()
+            + self.accounts.capacity() * size_of::<(ClientId, Account)>()
+            + self.transactions.capacity() * size_of::<(TxId, Transaction)>()
+            + self.disputed.capacity() * size_of::<(TxId, bool)>()
+            + self.reassigned.capacity() * size_of::<(TxId, ClientId)>()
+            + self.removed.capacity() * size_of::<ClientId>()
+            + self.log.capacity() * size_of::<LogEntry>();
+
+        StorageStats {
+            accounts: base.accounts + new_accounts - removed_accounts,
+            // the base cannot hold the transactions of the session
+            transactions: base.transactions + self.transactions.len(),
+            open_disputes: self.get_disputed_transactions().len(),
+            memory_bytes: base.memory_bytes + memory_bytes,
+            disk_bytes: base.disk_bytes,
+        }
+    }
+
+    fn store_account(&mut self, account: Account) -> Result<Account> {
+        self.removed.remove(&account.client_id);
+        self.accounts.insert(account.client_id, account.clone());
+
+        Ok(account)
+    }
+
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        if self.get_transaction(&transaction.tx_id).is_some() {
+            return Err(anyhow!("Transaction {} already exists", transaction.tx_id));
+        }
+        self.transactions
+            .insert(transaction.tx_id, transaction.clone());
+
+        Ok(transaction)
+    }
+
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()> {
+        let _ = self
+            .get_transaction(&tx_id)
+            .ok_or_else(|| anyhow!("Transaction {} does not exist", tx_id))?;
+        self.disputed.insert(tx_id, disputed);
+
+        Ok(())
+    }
+
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()> {
+        if let Some(transaction) = self.transactions.get_mut(&tx_id) {
+            transaction.client_id = client_id;
+        } else if self.base.get_transaction(&tx_id).is_some() {
+            self.reassigned.insert(tx_id, client_id);
+        } else {
+            return Err(anyhow!("Transaction {} does not exist", tx_id));
+        }
+
+        Ok(())
+    }
+
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>> {
+        let account = self.get_account(client_id);
+        self.accounts.remove(client_id);
+        if self.base.get_account(client_id).is_some() {
+            self.removed.insert(*client_id);
+        }
+
+        Ok(account)
+    }
+
+    fn append_log(&mut self, entry: LogEntry) -> Result<()> {
+        self.log.push(entry);
+
+        Ok(())
+    }
+
+    /// The log of the base followed by the entries logged during the
+    /// session, `None` if the base keeps no log.
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        let mut log = self.base.get_log()?;
+        log.extend(self.log.iter().cloned());
+
+        Some(log)
+    }
+
+    /// Write the changes of the session to the base: the transactions first,
+    /// then their log, their client and dispute status, the accounts and the
+    /// removed accounts. The changes are not atomic, on error the ones not
+    /// written yet are kept in the session.
+    fn commit(&mut self) -> Result<()> {
+        let mut transactions: Vec<TxId> = self.transactions.keys().copied().collect();
+        transactions.sort_unstable();
+        for tx_id in transactions {
+            if let Some(transaction) = self.transactions.remove(&tx_id) {
+                self.base.store_transaction(transaction)?;
+            }
+        }
+        let log: Vec<LogEntry> = self.log.drain(..).collect();
+        for (index, entry) in log.iter().enumerate() {
+            if let Err(error) = self.base.append_log(entry.clone()) {
+                self.log.extend(log[index..].iter().cloned());
+                return Err(error);
+            }
+        }
+        let reassigned: Vec<(TxId, ClientId)> = self.reassigned.drain().collect();
+        for (index, (tx_id, client_id)) in reassigned.iter().enumerate() {
+            if let Err(error) = self.base.reassign_transaction(*tx_id, *client_id) {
+                self.reassigned.extend(reassigned[index..].iter().copied());
+                return Err(error);
+            }
+        }
+        let disputed: Vec<(TxId, bool)> = self.disputed.drain().collect();
+        for (index, (tx_id, disputed_status)) in disputed.iter().enumerate() {
+            if let Err(error) = self.base.set_disputed(*tx_id, *disputed_status) {
+                self.disputed.extend(disputed[index..].iter().copied());
+                return Err(error);
+            }
+        }
+        let accounts: Vec<Account> = self.accounts.drain().map(|(_, account)| account).collect();
+        for (index, account) in accounts.iter().enumerate() {
+            if let Err(error) = self.base.store_account(account.clone()) {
+                self.accounts.extend(
+                    accounts[index..]
+                        .iter()
+                        .map(|account| (account.client_id, account.clone())),
+                );
+                return Err(error);
+            }
+        }
+        let removed: Vec<ClientId> = self.removed.drain().collect();
+        for (index, client_id) in removed.iter().enumerate() {
+            if let Err(error) = self.base.remove_account(client_id) {
+                self.removed.extend(removed[index..].iter().copied());
+                return Err(error);
+            }
+        }
+
+        self.base.commit()
+    }
+
+    fn discard(&mut self) {
+        self.accounts.clear();
+        self.transactions.clear();
+        self.disputed.clear();
+        self.reassigned.clear();
+        self.removed.clear();
+        self.log.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::adapter::InMemoryAccountStorage;
+    use crate::model::{TransactionKind, TransactionOrder};
+
+    /// A base storage holding the account 1 with a deposit tx=1.
+    fn base() -> InMemoryAccountStorage {
+        let mut base = InMemoryAccountStorage::default();
+        let mut account = Account::new(1);
+        account.deposit(dec!(10)).unwrap();
+        base.store_account(account).unwrap();
+        base.store_transaction(deposit(1, dec!(10))).unwrap();
+
+        base
+    }
+
+    /// A deposit transaction of the account 1.
+    fn deposit(tx_id: TxId, amount: rust_decimal::Decimal) -> Transaction {
+        TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind: TransactionKind::Deposit(amount),
+            timestamp: None,
+            currency: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_read_through() {
+        let mut overlay = OverlayStorage::new(base());
+        overlay.store_account(Account::new(2)).unwrap();
+        overlay.store_transaction(deposit(2, dec!(1))).unwrap();
+        overlay.set_disputed(1, true).unwrap();
+
+        assert!(overlay.store_transaction(deposit(1, dec!(1))).is_err());
+        assert!(overlay.set_disputed(3, true).is_err());
+        assert_eq!(overlay.get_accounts().len(), 2);
+        assert!(overlay.is_disputed(&1));
+        assert!(!overlay.base().is_disputed(&1));
+
+        let stats = overlay.stats();
+        assert_eq!(stats.accounts, 2);
+        assert_eq!(stats.transactions, 2);
+        assert_eq!(stats.open_disputes, 1);
+    }
+
+    #[test]
+    fn test_commit_and_discard() {
+        let mut overlay = OverlayStorage::new(base());
+        overlay.store_transaction(deposit(2, dec!(1))).unwrap();
+        overlay.set_disputed(2, true).unwrap();
+        overlay.discard();
+
+        assert!(!overlay.has_changes());
+        assert!(overlay.get_transaction(&2).is_none());
+
+        overlay.store_transaction(deposit(2, dec!(1))).unwrap();
+        overlay.set_disputed(2, true).unwrap();
+        overlay.set_disputed(1, true).unwrap();
+        overlay.set_disputed(1, false).unwrap();
+        overlay.commit().unwrap();
+
+        assert!(!overlay.has_changes());
+        assert!(overlay.base().get_transaction(&2).is_some());
+        assert_eq!(overlay.base().get_disputed_transactions().len(), 1);
+        assert!(!overlay.base().is_disputed(&1));
+    }
+
+    #[test]
+    fn test_reassign_and_remove() {
+        let mut overlay = OverlayStorage::new(base());
+        overlay.store_transaction(deposit(2, dec!(1))).unwrap();
+        overlay.reassign_transaction(1, 2).unwrap();
+        overlay.reassign_transaction(2, 2).unwrap();
+        let account = overlay.remove_account(&1).unwrap();
+
+        assert_eq!(account.unwrap().total, dec!(10));
+        assert!(overlay.get_account(&1).is_none());
+        assert!(overlay.get_client_transactions(&1).is_empty());
+        assert_eq!(overlay.get_client_transactions(&2).len(), 2);
+        assert_eq!(overlay.base().get_transaction(&1).unwrap().client_id, 1);
+        assert_eq!(overlay.stats().accounts, 0);
+
+        overlay.commit().unwrap();
+
+        assert!(overlay.base().get_account(&1).is_none());
+        assert_eq!(overlay.base().get_client_transactions(&2).len(), 2);
+    }
+
+    #[test]
+    fn test_accounts_by_chunks() {
+        let mut base = InMemoryAccountStorage::default();
+        for client_id in 1..=6 {
+            base.store_account(Account::new(client_id)).unwrap();
+        }
+        let mut overlay = OverlayStorage::new(base);
+        overlay.remove_account(&2).unwrap();
+        overlay.remove_account(&3).unwrap();
+        let mut account = Account::new(4);
+        account.deposit(dec!(1)).unwrap();
+        overlay.store_account(account).unwrap();
+        overlay.store_account(Account::new(7)).unwrap();
+
+        let client_ids = |accounts: Vec<Account>| {
+            accounts
+                .into_iter()
+                .map(|account| account.client_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(client_ids(overlay.get_accounts_after(None, 2)), [1, 4]);
+        assert_eq!(client_ids(overlay.get_accounts_after(Some(4), 2)), [5, 6]);
+        assert_eq!(client_ids(overlay.get_accounts_after(Some(6), 2)), [7]);
+        assert_eq!(overlay.get_accounts_after(Some(1), 1)[0].total, dec!(1));
+    }
+}
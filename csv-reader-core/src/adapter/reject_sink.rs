@@ -0,0 +1,169 @@
+use std::{
+    fmt::Debug,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use csv::{ByteRecord, StringRecord, Writer, WriterBuilder};
+
+use crate::Result;
+
+/// Destination of the records rejected by the readers, so they can be fixed
+/// and processed again. The rejected rows are written as they were read
+/// followed by an `error` column telling why they were rejected. The clones
+/// of a sink share the same writer.
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use csv_reader_core::actor::{Orders, ReaderOptions};
+/// use csv_reader_core::adapter::RejectSink;
+///
+/// # #[derive(Clone, Default)]
+/// # struct Buffer(Arc<Mutex<Vec<u8>>>);
+/// # impl std::io::Write for Buffer {
+/// #     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+/// #         self.0.lock().unwrap().write(buf)
+/// #     }
+/// #     fn flush(&mut self) -> std::io::Result<()> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// let buffer = Buffer::default();
+/// let data = "type,client,tx,amount\ndeposit,1,1,1.5\nwhatever,1,2,1\n";
+/// let options = ReaderOptions {
+///     rejects: Some(RejectSink::new(Box::new(buffer.clone()), b',')),
+///     ..Default::default()
+/// };
+/// let orders = Orders::new(Box::new(data.as_bytes()), options).count();
+///
+/// assert_eq!(orders, 1);
+/// assert_eq!(
+///     String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+///     "type,client,tx,amount,error\nwhatever,1,2,1,Unknown transaction kind: 'whatever'\n"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct RejectSink {
+    /// The writer of the rejected records.
+    writer: Arc<Mutex<RejectWriter>>,
+}
+
+/// Writer of the rejected records.
+struct RejectWriter {
+    /// The CSV writer.
+    writer: Writer<Box<dyn Write + Send>>,
+
+    /// The header line has been written.
+    header_written: bool,
+}
+
+impl RejectSink {
+    /// Write the rejected records to the given writer with the given field
+    /// delimiter, usually the one of the input.
+    pub fn new(writer: Box<dyn Write + Send>, delimiter: u8) -> Self {
+        let writer = WriterBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_writer(writer);
+
+        Self {
+            writer: Arc::new(Mutex::new(RejectWriter {
+                writer,
+                header_written: false,
+            })),
+        }
+    }
+
+    /// Write the header line of the input followed by the `error` column,
+    /// only once for all the inputs sharing the sink.
+    pub(crate) fn write_header(&self, headers: &StringRecord) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+
+        if !writer.header_written {
+            writer.header_written = true;
+            let mut header = headers.clone();
+            header.push_field("error");
+            writer.writer.write_record(&header)?;
+            writer.writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a rejected record padded to the given number of columns with the
+    /// reason of its rejection. Without record, as when the input could not
+    /// be read, only the reason is written. The record is flushed at once so
+    /// the rejects are kept if the run is interrupted.
+    pub(crate) fn write(
+        &self,
+        record: Option<&ByteRecord>,
+        width: usize,
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        let mut row = record.cloned().unwrap_or_default();
+        while row.len() < width {
+            row.push_field(b"");
+        }
+        row.push_field(error.to_string().as_bytes());
+        let mut writer = self.writer.lock().unwrap();
+        writer.writer.write_byte_record(&row)?;
+        writer.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Debug for RejectSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RejectSink").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::actor::{Orders, ReaderOptions};
+    use crate::test_utils::SharedBuffer;
+
+    #[test]
+    fn test_rejected_rows_are_padded() {
+        let buffer = SharedBuffer::default();
+        let sink = RejectSink::new(Box::new(buffer.clone()), b';');
+        sink.write(
+            Some(&ByteRecord::from(vec!["deposit", "1"])),
+            4,
+            &anyhow!("short"),
+        )
+        .unwrap();
+        sink.write(None, 4, &anyhow!("unreadable")).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "deposit;1;;;short\n;;;;unreadable\n"
+        );
+    }
+
+    #[test]
+    fn test_header_written_once() {
+        let buffer = SharedBuffer::default();
+        let sink = RejectSink::new(Box::new(buffer.clone()), b',');
+        for data in [
+            "type,client,tx,amount\nwd,1,1,1\n",
+            "type,client,tx,amount\n",
+        ] {
+            let options = ReaderOptions {
+                rejects: Some(sink.clone()),
+                ..Default::default()
+            };
+            assert_eq!(Orders::new(Box::new(data.as_bytes()), options).count(), 0);
+        }
+
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "type,client,tx,amount,error\nwd,1,1,1,Unknown transaction kind: 'wd'\n"
+        );
+    }
+}
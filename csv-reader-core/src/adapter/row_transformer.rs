@@ -0,0 +1,250 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use anyhow::anyhow;
+use csv::ByteRecord;
+use rust_decimal::Decimal;
+
+/// UTF-8 byte order mark.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Field of a transaction record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The transaction kind.
+    Type,
+
+    /// The client identifier.
+    Client,
+
+    /// The transaction identifier.
+    Tx,
+
+    /// The amount.
+    Amount,
+
+    /// The timestamp.
+    Timestamp,
+
+    /// The currency.
+    Currency,
+}
+
+impl Field {
+    /// All the fields, in the order of their positions.
+    const ALL: [Self; 6] = [
+        Self::Type,
+        Self::Client,
+        Self::Tx,
+        Self::Amount,
+        Self::Timestamp,
+        Self::Currency,
+    ];
+}
+
+/// A raw record of the input, before it is parsed, giving access to its fields
+/// by name.
+pub struct RawRecord<'r> {
+    /// The record.
+    record: &'r mut ByteRecord,
+
+    /// The positions of the `type`, `client`, `tx`, `amount`, `timestamp` and
+    /// `currency` fields in the record, if present.
+    positions: [Option<usize>; 6],
+}
+
+impl<'r> RawRecord<'r> {
+    /// Give access to the fields of a record at the given positions.
+    pub(crate) fn new(record: &'r mut ByteRecord, positions: [Option<usize>; 6]) -> Self {
+        Self { record, positions }
+    }
+
+    /// Get the trimmed bytes of a field, `None` if the input has no such
+    /// column.
+    pub fn get(&self, field: Field) -> Option<&[u8]> {
+        self.positions[field as usize]
+            .and_then(|position| self.record.get(position))
+            .map(<[u8]>::trim_ascii)
+    }
+
+    /// Replace the value of a field. Nothing is done if the input has no such
+    /// column.
+    pub fn set(&mut self, field: Field, value: &[u8]) {
+        let Some(position) = self.positions[field as usize] else {
+            return;
+        };
+        let mut record = ByteRecord::with_capacity(self.record.as_slice().len(), self.record.len());
+
+        for (index, current) in self.record.iter().enumerate() {
+            record.push_field(if index == position { value } else { current });
+        }
+        *self.record = record;
+    }
+}
+
+/// Transformation applied to the raw records of the input before they are
+/// parsed, to work around the quirks of an input without changing the code.
+/// A record failing a transformation is rejected as invalid.
+pub trait RowTransformer: Debug + Send + Sync {
+    /// Transform the given record in place.
+    fn transform(&self, record: &mut RawRecord) -> crate::Result<()>;
+}
+
+/// Remove the UTF-8 byte order mark written by some exporters at the start of
+/// the file. The reader already removes it from the start of the input, this
+/// handles the concatenated files where it sticks to the first field of a
+/// record.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrimBom;
+
+impl RowTransformer for TrimBom {
+    fn transform(&self, record: &mut RawRecord) -> crate::Result<()> {
+        for field in Field::ALL {
+            if let Some(value) = record.get(field).and_then(|value| value.strip_prefix(BOM)) {
+                let value = value.trim_ascii().to_vec();
+                record.set(field, &value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rename the transaction kinds used by a partner (e.g. `wd`) to the expected
+/// ones (e.g. `withdrawal`), ignoring case.
+///
+/// ```
+/// use csv_reader_core::actor::{Orders, ReaderOptions};
+/// use csv_reader_core::adapter::KindSynonyms;
+/// use csv_reader_core::TransactionKind;
+/// use std::sync::Arc;
+///
+/// let data = "type,client,tx,amount\ndep,1,1,2.5\nWD,1,2,1\n";
+/// let options = ReaderOptions {
+///     transformers: vec![Arc::new(KindSynonyms::new([
+///         ("dep", "deposit"),
+///         ("wd", "withdrawal"),
+///     ]))],
+///     ..Default::default()
+/// };
+/// let orders = Orders::new(Box::new(data.as_bytes()), options)
+///     .collect::<csv_reader_core::Result<Vec<_>>>()
+///     .unwrap();
+///
+/// assert_eq!(orders.len(), 2);
+/// assert!(matches!(orders[1].kind, TransactionKind::Withdrawal(_)));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct KindSynonyms {
+    /// The expected kinds by lowercase synonym.
+    synonyms: HashMap<String, String>,
+}
+
+impl KindSynonyms {
+    /// Create the transformer from the `(synonym, kind)` pairs.
+    pub fn new<S, K>(synonyms: impl IntoIterator<Item = (S, K)>) -> Self
+    where
+        S: AsRef<str>,
+        K: Into<String>,
+    {
+        Self {
+            synonyms: synonyms
+                .into_iter()
+                .map(|(synonym, kind)| (synonym.as_ref().to_lowercase(), kind.into()))
+                .collect(),
+        }
+    }
+}
+
+impl RowTransformer for KindSynonyms {
+    fn transform(&self, record: &mut RawRecord) -> crate::Result<()> {
+        let kind = record
+            .get(Field::Type)
+            .and_then(|kind| std::str::from_utf8(kind).ok())
+            .and_then(|kind| self.synonyms.get(&kind.to_lowercase()));
+
+        if let Some(kind) = kind.cloned() {
+            record.set(Field::Type, kind.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+/// Read the amounts as integers in minor units (e.g. cents) having the given
+/// number of decimal places, so `150` is read as `1.50` with 2 decimals.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaleAmount {
+    /// The number of decimal places of the amounts.
+    pub decimals: u32,
+}
+
+impl RowTransformer for ScaleAmount {
+    fn transform(&self, record: &mut RawRecord) -> crate::Result<()> {
+        let Some(amount) = record
+            .get(Field::Amount)
+            .filter(|amount| !amount.is_empty())
+        else {
+            return Ok(());
+        };
+        let amount = std::str::from_utf8(amount)?;
+        let units: i64 = amount
+            .parse()
+            .map_err(|e| anyhow!("Invalid amount in minor units '{amount}': {e}"))?;
+        let amount = Decimal::try_new(units, self.decimals)?.to_string();
+        record.set(Field::Amount, amount.as_bytes());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply a transformer to a `type,client,tx,amount` record.
+    fn transform(transformer: &dyn RowTransformer, fields: &[&str]) -> crate::Result<ByteRecord> {
+        let mut record = ByteRecord::from(fields.to_vec());
+        let positions = [Some(0), Some(1), Some(2), Some(3), None, None];
+        transformer.transform(&mut RawRecord::new(&mut record, positions))?;
+
+        Ok(record)
+    }
+
+    #[test]
+    fn test_trim_bom() {
+        let record = transform(&TrimBom, &["\u{feff}deposit", "1", "1", "1.0"]).unwrap();
+
+        assert_eq!(record, ByteRecord::from(vec!["deposit", "1", "1", "1.0"]));
+    }
+
+    #[test]
+    fn test_kind_synonyms() {
+        let synonyms = KindSynonyms::new([("WD", "withdrawal")]);
+
+        let record = transform(&synonyms, &["wd", "1", "1", "1.0"]).unwrap();
+        assert_eq!(record.get(0), Some(&b"withdrawal"[..]));
+        let record = transform(&synonyms, &["deposit", "1", "1", "1.0"]).unwrap();
+        assert_eq!(record.get(0), Some(&b"deposit"[..]));
+    }
+
+    #[test]
+    fn test_scale_amount() {
+        let scale = ScaleAmount { decimals: 4 };
+
+        let record = transform(&scale, &["deposit", "1", "1", "150000"]).unwrap();
+        assert_eq!(record.get(3), Some(&b"15.0000"[..]));
+        let record = transform(&scale, &["dispute", "1", "1", ""]).unwrap();
+        assert_eq!(record.get(3), Some(&b""[..]));
+        assert!(transform(&scale, &["deposit", "1", "1", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn test_missing_column() {
+        let mut record = ByteRecord::from(vec!["wd", "1", "1"]);
+        let mut raw = RawRecord::new(&mut record, [Some(0), Some(1), Some(2), None, None, None]);
+        raw.set(Field::Amount, b"1.0");
+
+        assert_eq!(raw.get(Field::Amount), None);
+        assert_eq!(record.len(), 3);
+    }
+}
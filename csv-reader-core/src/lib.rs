@@ -0,0 +1,36 @@
+#![warn(missing_docs)]
+//! CVS READER LIBRARY
+//!
+//! This library provides elements to read transaction data from a CSV file and
+//! compute accounts from it.
+//!
+//! The types re-exported by the [prelude] module, and at the root of the
+//! crate, are the public API this library commits to. The modules remain
+//! accessible for more specific needs. The items behind the `unstable` feature
+//! are experimental, they can change in any release.
+
+pub mod actor;
+pub mod adapter;
+pub mod model;
+pub mod prelude;
+pub mod service;
+#[cfg(test)]
+mod test_utils;
+
+pub use actor::{AccountExporter, Accountant, JournalExporter, Reader};
+pub use adapter::{AccountStorage, InMemoryAccountStorage, StorageStats};
+pub use model::{
+    Account, AccountError, ClientId, JournalEntry, LedgerAccount, Transaction,
+    TransactionKindError, TxId,
+};
+pub use service::{AccountManager, AccountService, TransactionError};
+
+#[cfg(feature = "unstable")]
+pub use actor::ReaderOptions;
+#[cfg(feature = "unstable")]
+pub use model::{TransactionKind, TransactionOrder};
+#[cfg(feature = "unstable")]
+pub use service::AccountManagerOptions;
+
+/// Global type alias for the result type used in this library.
+pub type Result<T> = anyhow::Result<T>;
@@ -1,42 +1,16 @@
 use anyhow::{anyhow, Context};
 use rust_decimal::Decimal;
 use serde::{ser::SerializeStruct, Serialize};
-use thiserror::Error;
 
-use crate::Result;
+pub use csv_reader_ledger::{AccountError, Balance, ClientId};
 
-/// The client ID type alias.
-pub type ClientId = u16;
-
-/// The error type for account operations.
-#[derive(Debug, Error)]
-pub enum AccountError {
-    /// Insufficient available funds to perform the operation.
-    #[error("Insufficient available funds: available {available}, requested {requested}.")]
-    InsufficientAvailableFunds {
-        /// The available funds in the account.
-        available: Decimal,
-
-        /// The withdraw amount requested
-        requested: Decimal,
-    },
-    /// Insufficient held funds to perform the operation.
-    #[error("Insufficient held funds: held {held}, requested {requested}.")]
-    InsufficientHeldFunds {
-        /// The held funds in the account.
-        held: Decimal,
-
-        /// The resolve amount requested
-        requested: Decimal,
-    },
-    /// Operation cannot be performed because the account is locked.
-    #[error("Account is locked.")]
-    AccountLocked,
-}
+use super::{Currency, TxId};
+use crate::Result;
 
 /// It represents the state of a client account. It contains the different types
 /// of funds held by the account.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Account {
     /// The client ID of the account.
     pub client_id: ClientId,
@@ -52,6 +26,71 @@ pub struct Account {
 
     /// The lock status of the account.
     pub locked: bool,
+
+    /// The currency of the account, the one of its first transaction giving
+    /// a currency.
+    pub currency: Option<Currency>,
+
+    /// The lifecycle state of the account.
+    pub state: AccountState,
+}
+
+/// The lifecycle state of an account. The accounts are open from their first
+/// transaction or their explicit opening until they are closed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AccountState {
+    /// The account accepts orders.
+    #[default]
+    Open,
+
+    /// The account is retired, its orders are rejected until it is opened
+    /// again.
+    Closed,
+}
+
+/// The transactions of an account currently under dispute.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisputeSummary {
+    /// The number of open disputes.
+    pub count: usize,
+
+    /// The sum of the disputed amounts.
+    pub sum: Decimal,
+}
+
+/// A dispute order rejected for a transaction of an account, kept as a note
+/// of the account so support can tell why a dispute did not go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RejectedDispute {
+    /// The client of the account, the one of the disputed transaction if it
+    /// exists, the one of the dispute order otherwise.
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+
+    /// The identifier of the disputed transaction.
+    #[serde(rename = "tx")]
+    pub tx_id: TxId,
+
+    /// Why the dispute was rejected.
+    pub reason: DisputeRejection,
+}
+
+/// Why a dispute order was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeRejection {
+    /// The disputed transaction does not exist.
+    NotFound,
+
+    /// The disputed transaction cannot be disputed under the dispute policy.
+    NotDisputable,
+
+    /// The client of the order may not dispute the transaction under the
+    /// dispute policy.
+    NotAllowed,
+
+    /// The disputed transaction is already disputed.
+    AlreadyDisputed,
 }
 
 impl Serialize for Account {
@@ -73,27 +112,42 @@ impl Serialize for Account {
 impl Account {
     /// Creates a new account with the given client ID. The account is initialized
     /// with zero funds and unlocked.
-    pub fn new(client_id: u16) -> Self {
+    pub fn new(client_id: ClientId) -> Self {
         Account {
             client_id,
             available: Decimal::ZERO,
             held: Decimal::ZERO,
             total: Decimal::ZERO,
             locked: false,
+            currency: None,
+            state: AccountState::Open,
         }
     }
 
-    fn check_locked(&self) -> Result<()> {
-        if self.locked {
-            Err(anyhow!(AccountError::AccountLocked))
-                .context(format!("Account {} is locked.", self.client_id))
-        } else {
-            Ok(())
+    /// Get a copy of the funds and the lock status of the account.
+    pub fn balance(&self) -> Balance {
+        Balance {
+            available: self.available,
+            held: self.held,
+            total: self.total,
+            locked: self.locked,
         }
     }
 
-    fn update_total(&mut self) -> Result<()> {
-        self.total = self.available + self.held;
+    /// Apply the given operation on the account balance. The account is left
+    /// untouched if the operation fails.
+    pub(crate) fn apply(
+        &mut self,
+        operation: impl FnOnce(&mut Balance) -> std::result::Result<(), AccountError>,
+    ) -> Result<()> {
+        let mut balance = self.balance();
+        operation(&mut balance)
+            .map_err(|error| anyhow!(error))
+            .context(format!("Account: {}", self.client_id))?;
+        self.available = balance.available;
+        self.held = balance.held;
+        self.total = balance.total;
+        self.locked = balance.locked;
 
         Ok(())
     }
@@ -103,7 +157,7 @@ impl Account {
     ///
     /// ```
     /// use rust_decimal::Decimal;
-    /// use csv_reader::model::{Account, AccountError};
+    /// use csv_reader_core::model::{Account, AccountError};
     ///
     /// let mut account = Account::new(1);
     /// account.deposit(Decimal::new(100, 0)).unwrap();
@@ -122,10 +176,7 @@ impl Account {
     /// ));
     /// ```
     pub fn deposit(&mut self, amount: Decimal) -> Result<()> {
-        self.check_locked()?;
-        self.available += amount;
-
-        self.update_total()
+        self.apply(|balance| balance.deposit(amount))
     }
 
     /// Withdraws the given amount from the account. The given amount is subtracted
@@ -134,7 +185,7 @@ impl Account {
     ///
     /// ```
     /// use rust_decimal::Decimal;
-    /// use csv_reader::model::{Account, AccountError};
+    /// use csv_reader_core::model::{Account, AccountError};
     ///
     /// let mut account = Account::new(1);
     /// account.deposit(Decimal::new(100, 0)).unwrap();
@@ -163,18 +214,7 @@ impl Account {
     ///
     /// ```
     pub fn withdraw(&mut self, amount: Decimal) -> Result<()> {
-        self.check_locked()?;
-
-        if self.available < amount {
-            return Err(anyhow!(AccountError::InsufficientAvailableFunds {
-                available: self.available,
-                requested: amount,
-            }))
-            .context(format!("Account: {}", self.client_id));
-        }
-        self.available -= amount;
-
-        self.update_total()
+        self.apply(|balance| balance.withdraw(amount))
     }
 
     /// Disputes the given amount. The amount is subtracted from the available funds
@@ -190,7 +230,7 @@ impl Account {
     ///
     /// ```
     /// use rust_decimal::Decimal;
-    /// use csv_reader::model::Account;
+    /// use csv_reader_core::model::Account;
     ///
     /// let mut account = Account::new(1);
     /// account.deposit(Decimal::new(100, 0)).unwrap();
@@ -216,10 +256,7 @@ impl Account {
     ///
     /// ```
     pub fn dispute(&mut self, amount: Decimal) -> Result<()> {
-        self.available -= amount;
-        self.held += amount;
-
-        self.update_total()
+        self.apply(|balance| balance.dispute(amount))
     }
 
     /// Resolves the disputed amount. The amount is added to the available funds and
@@ -229,7 +266,7 @@ impl Account {
     ///
     /// ```
     /// use rust_decimal::Decimal;
-    /// use csv_reader::model::{Account, AccountError};
+    /// use csv_reader_core::model::{Account, AccountError};
     ///
     /// let mut account = Account::new(1);
     /// account.deposit(Decimal::new(100, 0)).unwrap();
@@ -257,17 +294,7 @@ impl Account {
     ///
     /// ```
     pub fn resolve(&mut self, amount: Decimal) -> Result<()> {
-        if amount > self.held {
-            return Err(anyhow!(AccountError::InsufficientHeldFunds {
-                held: self.held,
-                requested: amount,
-            }))
-            .context(format!("Account: {}", self.client_id));
-        }
-        self.available += amount;
-        self.held -= amount;
-
-        self.update_total()
+        self.apply(|balance| balance.resolve(amount))
     }
 
     /// Charges back the disputed amount. The amount is subtracted from the held funds
@@ -277,7 +304,7 @@ impl Account {
     ///
     /// ```
     /// use rust_decimal::Decimal;
-    /// use csv_reader::model::{Account, AccountError};
+    /// use csv_reader_core::model::{Account, AccountError};
     ///
     /// let mut account = Account::new(1);
     /// account.deposit(Decimal::new(100, 0)).unwrap();
@@ -304,17 +331,57 @@ impl Account {
     /// ));
     /// ```
     pub fn chargeback(&mut self, amount: Decimal) -> Result<()> {
-        if amount > self.held {
-            return Err(anyhow!(AccountError::InsufficientHeldFunds {
-                held: self.held,
-                requested: amount,
-            }))
-            .context(format!("Account: {}", self.client_id));
-        }
-        self.held -= amount;
-        self.locked = true;
+        self.apply(|balance| balance.chargeback(amount))
+    }
 
-        self.update_total()
+    /// Clear the lock of the account after a manual review.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::Account;
+    ///
+    /// let mut account = Account::new(1);
+    /// account.locked = true;
+    /// account.unlock();
+    ///
+    /// assert!(!account.locked);
+    /// account.deposit(Decimal::ONE).unwrap();
+    /// ```
+    pub fn unlock(&mut self) {
+        self.locked = false;
+    }
+
+    /// Tell if the account has no funds at all, available or held.
+    pub fn is_empty(&self) -> bool {
+        self.available.is_zero() && self.held.is_zero() && self.total.is_zero()
+    }
+
+    /// Add the funds of another account of the same client to this account.
+    /// The account is locked if either account is.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::Account;
+    ///
+    /// let mut account = Account::new(1);
+    /// let mut duplicate = Account::new(2);
+    /// duplicate.deposit(Decimal::TEN).unwrap();
+    /// duplicate.dispute(Decimal::ONE).unwrap();
+    /// duplicate.locked = true;
+    /// account.merge(&duplicate);
+    ///
+    /// assert_eq!(account.client_id, 1);
+    /// assert_eq!(account.available, Decimal::new(9, 0));
+    /// assert_eq!(account.held, Decimal::ONE);
+    /// assert_eq!(account.total, Decimal::TEN);
+    /// assert!(account.locked);
+    /// ```
+    pub fn merge(&mut self, other: &Account) {
+        self.available += other.available;
+        self.held += other.held;
+        self.total += other.total;
+        self.locked |= other.locked;
+        self.currency = self.currency.or(other.currency);
     }
 }
 
@@ -0,0 +1,145 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::{ClientId, TxId};
+
+/// A notable change of the accounts, published by the
+/// [AccountManager](crate::service::AccountManager) to its subscribers once
+/// the transaction causing it is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DomainEvent {
+    /// A deposit or a withdrawal is disputed, its amount held on the account.
+    DisputeOpened {
+        /// The client of the disputed transaction.
+        client_id: ClientId,
+
+        /// The disputed transaction.
+        tx_id: TxId,
+
+        /// The amount held.
+        amount: Decimal,
+    },
+
+    /// A dispute is resolved, the amount released to the client.
+    DisputeResolved {
+        /// The client of the disputed transaction.
+        client_id: ClientId,
+
+        /// The disputed transaction.
+        tx_id: TxId,
+
+        /// The amount released.
+        amount: Decimal,
+    },
+
+    /// A disputed transaction is charged back, the amount withdrawn from the
+    /// held funds.
+    ChargebackApplied {
+        /// The client of the disputed transaction.
+        client_id: ClientId,
+
+        /// The disputed transaction.
+        tx_id: TxId,
+
+        /// The amount charged back.
+        amount: Decimal,
+    },
+
+    /// An account is locked, by a chargeback.
+    AccountLocked {
+        /// The client of the account.
+        client_id: ClientId,
+    },
+
+    /// An account is unlocked, after a manual review.
+    AccountUnlocked {
+        /// The client of the account.
+        client_id: ClientId,
+    },
+
+    /// An account is opened, or reopened once closed.
+    AccountOpened {
+        /// The client of the account.
+        client_id: ClientId,
+    },
+
+    /// An account is closed.
+    AccountClosed {
+        /// The client of the account.
+        client_id: ClientId,
+    },
+
+    /// The funds of an account are corrected by an adjustment.
+    AccountAdjusted {
+        /// The client of the account.
+        client_id: ClientId,
+
+        /// The adjustment transaction.
+        tx_id: TxId,
+
+        /// The signed amount of the correction.
+        amount: Decimal,
+    },
+
+    /// A client is suspended for review after consecutive rejected orders.
+    ClientSuspended {
+        /// The suspended client.
+        client_id: ClientId,
+    },
+
+    /// An order matches a rule of the fraud detection, see
+    /// [FraudDetector](crate::service::FraudDetector).
+    FraudFlagged(FraudFlag),
+}
+
+/// A rule of the fraud detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FraudRule {
+    /// Too many withdrawals of the client in a short window.
+    WithdrawalVelocity,
+
+    /// Too many disputes for the transactions of the client.
+    DisputeRate,
+
+    /// An amount far above the usual amounts of the client.
+    AmountOutlier,
+}
+
+/// An order flagged by a rule of the fraud detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FraudFlag {
+    /// The client of the order.
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+
+    /// The identifier of the order.
+    #[serde(rename = "tx")]
+    pub tx_id: TxId,
+
+    /// The rule matched.
+    pub rule: FraudRule,
+
+    /// Tell if the order was rejected for the flag, the rules blocking the
+    /// flagged orders, or applied anyway.
+    pub blocked: bool,
+}
+
+impl DomainEvent {
+    /// Get the client concerned by the event.
+    pub fn client_id(&self) -> ClientId {
+        match self {
+            Self::DisputeOpened { client_id, .. }
+            | Self::DisputeResolved { client_id, .. }
+            | Self::ChargebackApplied { client_id, .. }
+            | Self::AccountAdjusted { client_id, .. }
+            | Self::AccountLocked { client_id }
+            | Self::AccountUnlocked { client_id }
+            | Self::AccountOpened { client_id }
+            | Self::AccountClosed { client_id }
+            | Self::ClientSuspended { client_id } => *client_id,
+            Self::FraudFlagged(flag) => flag.client_id,
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::fmt::Display;
+
+use rust_decimal::Decimal;
+use serde::{ser::SerializeStruct, Serialize};
+
+use super::{ClientId, TxId};
+
+/// An account of the double-entry ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LedgerAccount {
+    /// The funds available to the client.
+    ClientAvailable(ClientId),
+
+    /// The client funds held because of a dispute.
+    ClientHeld(ClientId),
+
+    /// The omnibus account holding the funds of every client at the bank.
+    Omnibus,
+
+    /// The funds expected back from the payment scheme for the disputed
+    /// withdrawals, until the disputes are settled.
+    Suspense,
+
+    /// The funds taken back by the payment scheme with the chargebacks of the
+    /// deposits.
+    ChargebackLoss,
+}
+
+impl Display for LedgerAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClientAvailable(client_id) => write!(f, "client:{client_id}:available"),
+            Self::ClientHeld(client_id) => write!(f, "client:{client_id}:held"),
+            Self::Omnibus => write!(f, "omnibus"),
+            Self::Suspense => write!(f, "suspense"),
+            Self::ChargebackLoss => write!(f, "chargeback_loss"),
+        }
+    }
+}
+
+/// A balanced journal entry: the amount is debited from one ledger account and
+/// credited to another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct JournalEntry {
+    /// The identifier of the transaction that produced this entry, `None` for
+    /// the entries not produced by a transaction, like the adjustments of an
+    /// account merge.
+    pub tx_id: Option<TxId>,
+
+    /// The debited ledger account.
+    pub debit: LedgerAccount,
+
+    /// The credited ledger account.
+    pub credit: LedgerAccount,
+
+    /// The amount of the entry.
+    pub amount: Decimal,
+}
+
+impl JournalEntry {
+    /// Create the entry of a deposit: the money enters the omnibus account and
+    /// is owed to the client.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::{JournalEntry, LedgerAccount};
+    ///
+    /// let entry = JournalEntry::deposit(1, 2, Decimal::ONE);
+    /// assert_eq!(entry.debit, LedgerAccount::Omnibus);
+    /// assert_eq!(entry.credit, LedgerAccount::ClientAvailable(2));
+    /// ```
+    pub fn deposit(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::Omnibus,
+            credit: LedgerAccount::ClientAvailable(client_id),
+            amount,
+        }
+    }
+
+    /// Create the entry of a withdrawal: the money leaves the omnibus account.
+    pub fn withdrawal(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientAvailable(client_id),
+            credit: LedgerAccount::Omnibus,
+            amount,
+        }
+    }
+
+    /// Create the entry of an adjustment: a credit is owed to the client as a
+    /// deposit is, a debit leaves the client funds as a withdrawal does.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::{JournalEntry, LedgerAccount};
+    ///
+    /// let entry = JournalEntry::adjustment(1, 2, -Decimal::ONE);
+    /// assert_eq!(entry.debit, LedgerAccount::ClientAvailable(2));
+    /// assert_eq!(entry.credit, LedgerAccount::Omnibus);
+    /// assert_eq!(entry.amount, Decimal::ONE);
+    /// ```
+    pub fn adjustment(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        if amount.is_sign_negative() {
+            Self::withdrawal(tx_id, client_id, amount.abs())
+        } else {
+            Self::deposit(tx_id, client_id, amount)
+        }
+    }
+
+    /// Create the entry of a dispute: the disputed amount is held.
+    pub fn dispute(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientAvailable(client_id),
+            credit: LedgerAccount::ClientHeld(client_id),
+            amount,
+        }
+    }
+
+    /// Create the entry of a resolve: the held amount is available again.
+    pub fn resolve(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientHeld(client_id),
+            credit: LedgerAccount::ClientAvailable(client_id),
+            amount,
+        }
+    }
+
+    /// Create the entry of a chargeback: the held amount is given back to the
+    /// payment scheme and booked as a chargeback loss.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::{JournalEntry, LedgerAccount};
+    ///
+    /// let entry = JournalEntry::chargeback(1, 2, Decimal::ONE);
+    /// assert_eq!(entry.debit, LedgerAccount::ClientHeld(2));
+    /// assert_eq!(entry.credit, LedgerAccount::ChargebackLoss);
+    /// ```
+    pub fn chargeback(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientHeld(client_id),
+            credit: LedgerAccount::ChargebackLoss,
+            amount,
+        }
+    }
+
+    /// Create the entry of the dispute of a withdrawal: the withdrawn amount
+    /// is held for a potential re-credit, in suspense until the dispute is
+    /// settled.
+    pub fn withdrawal_dispute(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::Suspense,
+            credit: LedgerAccount::ClientHeld(client_id),
+            amount,
+        }
+    }
+
+    /// Create the entry of the resolve of a disputed withdrawal: the
+    /// withdrawal stands and the held amount is taken out of suspense.
+    pub fn withdrawal_resolve(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientHeld(client_id),
+            credit: LedgerAccount::Suspense,
+            amount,
+        }
+    }
+
+    /// Create the entry of the chargeback of a disputed withdrawal: the held
+    /// amount is credited back to the client, and stays in suspense until the
+    /// payment scheme returns it.
+    pub fn withdrawal_chargeback(tx_id: TxId, client_id: ClientId, amount: Decimal) -> Self {
+        Self {
+            tx_id: Some(tx_id),
+            debit: LedgerAccount::ClientHeld(client_id),
+            credit: LedgerAccount::ClientAvailable(client_id),
+            amount,
+        }
+    }
+
+    /// Create the adjustment entries of the merge of an account into another
+    /// one, without transaction identifier: its available and held funds are
+    /// moved to the same funds of the other client. A negative amount is moved the other way round and no
+    /// entry is created for a zero amount.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use csv_reader_core::model::{JournalEntry, LedgerAccount};
+    ///
+    /// let entries = JournalEntry::merge(2, 1, -Decimal::ONE, Decimal::ZERO);
+    /// assert_eq!(entries.len(), 1);
+    /// assert_eq!(entries[0].tx_id, None);
+    /// assert_eq!(entries[0].debit, LedgerAccount::ClientAvailable(1));
+    /// assert_eq!(entries[0].credit, LedgerAccount::ClientAvailable(2));
+    /// assert_eq!(entries[0].amount, Decimal::ONE);
+    /// ```
+    pub fn merge(from: ClientId, into: ClientId, available: Decimal, held: Decimal) -> Vec<Self> {
+        [
+            (
+                LedgerAccount::ClientAvailable(from),
+                LedgerAccount::ClientAvailable(into),
+                available,
+            ),
+            (
+                LedgerAccount::ClientHeld(from),
+                LedgerAccount::ClientHeld(into),
+                held,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, _, amount)| !amount.is_zero())
+        .map(|(debit, credit, amount)| {
+            let (debit, credit) = if amount.is_sign_negative() {
+                (credit, debit)
+            } else {
+                (debit, credit)
+            };
+
+            Self {
+                tx_id: None,
+                debit,
+                credit,
+                amount: amount.abs(),
+            }
+        })
+        .collect()
+    }
+}
+
+impl Serialize for JournalEntry {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("JournalEntry", 4)?;
+        state.serialize_field("tx", &self.tx_id)?;
+        state.serialize_field("debit", &self.debit.to_string())?;
+        state.serialize_field("credit", &self.credit.to_string())?;
+        state.serialize_field("amount", &self.amount.round_dp(4).normalize())?;
+
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_account_display() {
+        assert_eq!(
+            LedgerAccount::ClientAvailable(1).to_string(),
+            "client:1:available"
+        );
+        assert_eq!(LedgerAccount::ClientHeld(1).to_string(), "client:1:held");
+        assert_eq!(LedgerAccount::Omnibus.to_string(), "omnibus");
+        assert_eq!(LedgerAccount::Suspense.to_string(), "suspense");
+        assert_eq!(LedgerAccount::ChargebackLoss.to_string(), "chargeback_loss");
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .serialize(JournalEntry::dispute(3, 1, Decimal::new(15, 1)))
+            .unwrap();
+        for entry in JournalEntry::merge(2, 1, Decimal::ONE, Decimal::ZERO) {
+            writer.serialize(entry).unwrap();
+        }
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            "tx,debit,credit,amount\n\
+             3,client:1:available,client:1:held,1.5\n\
+             ,client:2:available,client:1:available,1\n"
+        );
+    }
+}
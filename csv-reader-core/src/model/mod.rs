@@ -1,7 +1,11 @@
 //! This module contains the data model for the exchange.
 
 mod account;
+mod event;
+mod journal;
 mod transaction;
 
 pub use account::*;
+pub use event::*;
+pub use journal::*;
 pub use transaction::*;
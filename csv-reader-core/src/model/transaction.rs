@@ -0,0 +1,237 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+pub use csv_reader_ledger::{TransactionKind, TransactionKindError, TxId};
+
+use super::ClientId;
+
+/// Default maximum number of decimal places of the amounts, the precision of
+/// the exported accounts.
+pub const MAX_DECIMALS: u32 = 4;
+
+/// The time a transaction was made, as given by the input.
+pub type Timestamp = DateTime<Utc>;
+
+/// ISO 4217 code of the currency of a transaction (e.g. `EUR`), always in
+/// uppercase.
+///
+/// ```
+/// use csv_reader_core::model::Currency;
+///
+/// let currency: Currency = "eur".parse().unwrap();
+/// assert_eq!(currency.to_string(), "EUR");
+///
+/// assert!("EURO".parse::<Currency>().is_err());
+/// assert!("E1R".parse::<Currency>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Currency([u8; 3]);
+
+impl FromStr for Currency {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.as_bytes() {
+            code @ [_, _, _] if code.iter().all(u8::is_ascii_alphabetic) => Ok(Self(
+                [code[0], code[1], code[2]].map(|c| c.to_ascii_uppercase()),
+            )),
+            _ => bail!("Invalid currency '{value}' (expected a 3 letter ISO 4217 code)."),
+        }
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the code is ASCII by construction
+        f.write_str(std::str::from_utf8(&self.0).unwrap_or_default())
+    }
+}
+
+/// A Transaction represents a single transaction that happened on the exchange.
+/// A Transaction has already modified the ledgers and it cannot be modified or
+/// deleted. The transaction identifier is unique. Unexpected behavior can
+/// happen if two different transactions have the same identifier.
+/// If a transaction relates to another transaction, the identifier is valid and
+/// the related transaction can be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Transaction {
+    /// The unique identifier of the transaction.
+    pub tx_id: TxId,
+
+    /// The client identifier that made the transaction.
+    pub client_id: ClientId,
+
+    /// The transaction kind.
+    pub kind: TransactionKind,
+
+    /// The time of the transaction, when the input gives it.
+    pub timestamp: Option<Timestamp>,
+
+    /// The currency of the transaction, when the input gives it.
+    pub currency: Option<Currency>,
+}
+
+/// TransactionOrder represents the order of a transaction in the CSV file. It
+/// is a wish emitted by a client that Transaction should be processed in the
+/// given order. This transaction has not yet been validated against the account.
+#[derive(Debug, Clone)]
+pub struct TransactionOrder {
+    /// The unique identifier of the transaction.
+    pub tx_id: TxId,
+
+    /// The client identifier that made the order.
+    pub client_id: ClientId,
+
+    /// The transaction kind.
+    pub kind: TransactionKind,
+
+    /// The time of the order, when the input gives it.
+    pub timestamp: Option<Timestamp>,
+
+    /// The currency of the order, when the input gives it. It must be the one
+    /// of the account.
+    pub currency: Option<Currency>,
+}
+
+impl From<TransactionOrder> for Transaction {
+    fn from(order: TransactionOrder) -> Self {
+        Self {
+            tx_id: order.tx_id,
+            client_id: order.client_id,
+            kind: order.kind,
+            timestamp: order.timestamp,
+            currency: order.currency,
+        }
+    }
+}
+
+/// An entry of the transaction log of a storage, the accounts being rebuilt
+/// from it: an applied transaction or a change of the accounts made outside
+/// of the orders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEntry {
+    /// An applied transaction.
+    Transaction(Transaction),
+
+    /// The account of a client merged into the account of another one, see
+    /// [AccountManager::merge_clients](crate::service::AccountManager::merge_clients).
+    Merge {
+        /// The client whose account is removed.
+        from: ClientId,
+
+        /// The client whose account receives the funds.
+        into: ClientId,
+    },
+
+    /// A transaction disputed from a case file, see
+    /// [AccountManager::preload_disputes](crate::service::AccountManager::preload_disputes).
+    PreloadedDispute(TxId),
+}
+
+impl LogEntry {
+    /// Get the transaction of the entry, if it is one.
+    pub fn transaction(&self) -> Option<&Transaction> {
+        match self {
+            Self::Transaction(transaction) => Some(transaction),
+            _ => None,
+        }
+    }
+}
+
+impl From<Transaction> for LogEntry {
+    fn from(transaction: Transaction) -> Self {
+        Self::Transaction(transaction)
+    }
+}
+
+/// Transaction entity read from CSV file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CSVTransactionEntity {
+    /// The transaction kind.
+    pub r#type: String,
+
+    /// The client identifier that made the transaction.
+    pub client: ClientId,
+
+    /// The unique identifier of the transaction.
+    pub tx: TxId,
+
+    /// The amount of the transaction.
+    pub amount: Option<Decimal>,
+
+    /// The time of the transaction, in RFC 3339 format.
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
+
+    /// The ISO 4217 code of the currency of the transaction.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+impl TryFrom<CSVTransactionEntity> for TransactionOrder {
+    type Error = TransactionKindError;
+
+    fn try_from(entity: CSVTransactionEntity) -> Result<Self, Self::Error> {
+        let kind = match entity.r#type.as_str().to_lowercase().as_str() {
+            "deposit" => {
+                if let Some(amount) = entity.amount {
+                    TransactionKind::deposit(TransactionKind::check_precision(
+                        amount,
+                        MAX_DECIMALS,
+                    )?)?
+                } else {
+                    return Err(TransactionKindError::MissingAmount);
+                }
+            }
+            "withdrawal" => {
+                if let Some(amount) = entity.amount {
+                    TransactionKind::withdrawal(TransactionKind::check_precision(
+                        amount,
+                        MAX_DECIMALS,
+                    )?)?
+                } else {
+                    return Err(TransactionKindError::MissingAmount);
+                }
+            }
+            "dispute" => TransactionKind::dispute(entity.tx),
+            "resolve" => TransactionKind::resolve(entity.tx),
+            "chargeback" => TransactionKind::chargeback(entity.tx),
+            "unlock" => TransactionKind::Unlock,
+            "open" => TransactionKind::Open,
+            "close" => TransactionKind::Close,
+            "adjustment" => {
+                if let Some(amount) = entity.amount {
+                    TransactionKind::adjustment(TransactionKind::check_precision(
+                        amount,
+                        MAX_DECIMALS,
+                    )?)?
+                } else {
+                    return Err(TransactionKindError::MissingAmount);
+                }
+            }
+            val => return Err(TransactionKindError::UnknownKind(val.to_owned())),
+        };
+
+        Ok(Self {
+            tx_id: entity.tx,
+            client_id: entity.client,
+            kind,
+            timestamp: entity.timestamp,
+            currency: entity.currency,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! The stable public API.
+//!
+//! This module re-exports the types this library commits to semantic
+//! versioning for: a breaking change of any of them only comes with a new
+//! major version, which `cargo semver-checks` verifies before a release.
+//! Downstream crates importing from here can upgrade the minor versions
+//! safely. The other items of the modules can change in a minor release,
+//! the ones of the `unstable` feature in any release.
+//!
+//! The structures and enumerations of the stable API are non exhaustive so
+//! that fields and variants can be added in a minor release. The transaction
+//! kinds, the orders and the options of the manager and of the reader grow
+//! with the features and are built field by field, so they are only
+//! re-exported here with the `unstable` feature.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use csv_reader_core::actor::channel;
+//! use csv_reader_core::prelude::*;
+//!
+//! let data = "type,client,tx,amount\ndeposit,1,1,2.5\n";
+//! let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+//! let (sender, receiver) = channel();
+//! Reader::new(sender, Box::new(data.as_bytes())).run().unwrap();
+//! Accountant::new(manager.clone(), receiver).run().unwrap();
+//! let account: Account = manager.get_account(1).unwrap();
+//!
+//! assert_eq!(account.total, rust_decimal::Decimal::new(25, 1));
+//! ```
+
+pub use crate::actor::{
+    AccountExporter, Accountant, JournalExporter, Orders, Reader, ReaderProgress,
+};
+pub use crate::adapter::{AccountStorage, InMemoryAccountStorage, InputSource, StorageStats};
+pub use crate::model::{
+    Account, AccountError, ClientId, JournalEntry, LedgerAccount, Transaction,
+    TransactionKindError, TxId,
+};
+pub use crate::service::{AccountManager, AccountService, TransactionError};
+pub use crate::Result;
+
+#[cfg(feature = "unstable")]
+pub use crate::actor::ReaderOptions;
+#[cfg(feature = "unstable")]
+pub use crate::model::{TransactionKind, TransactionOrder};
+#[cfg(feature = "unstable")]
+pub use crate::service::AccountManagerOptions;
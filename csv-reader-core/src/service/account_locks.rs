@@ -0,0 +1,186 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Mutex, MutexGuard, RwLock},
+};
+
+#[cfg(feature = "concurrent-storage")]
+use crate::adapter::ConcurrentAccountStorage;
+use crate::adapter::{AccountStorage, StorageStats};
+use crate::model::{Account, ClientId, LogEntry, Transaction, TxId};
+use crate::Result;
+
+/// Number of locks the accounts and transactions are spread over.
+const STRIPES: usize = 64;
+
+/// What an order locks: the accounts it reads or writes and the identifier
+/// of the transaction it creates, so two orders cannot create it at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum LockKey {
+    /// The account of a client.
+    Client(ClientId),
+
+    /// A transaction identifier.
+    Tx(TxId),
+}
+
+/// The locks of the accounts of an [AccountManager](super::AccountManager).
+/// The keys are spread over a fixed number of stripes, an order holding the
+/// stripes of all its keys while it is processed: the orders of different
+/// clients run in parallel, the ones sharing an account wait for each other.
+/// The stripes are always acquired in the same order so two orders cannot
+/// wait for each other.
+#[derive(Debug)]
+pub(crate) struct AccountLocks {
+    stripes: Vec<Mutex<()>>,
+}
+
+impl Default for AccountLocks {
+    fn default() -> Self {
+        Self {
+            stripes: (0..STRIPES).map(|_| Mutex::new(())).collect(),
+        }
+    }
+}
+
+impl AccountLocks {
+    /// Lock the given keys until the guards are dropped.
+    pub(crate) fn lock<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a LockKey>,
+    ) -> Vec<MutexGuard<'_, ()>> {
+        let mut stripes: Vec<usize> = keys.into_iter().map(Self::stripe_of).collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+
+        stripes
+            .into_iter()
+            .map(|stripe| self.stripes[stripe].lock().unwrap())
+            .collect()
+    }
+
+    /// Lock every account, waiting for the orders being processed.
+    pub(crate) fn lock_all(&self) -> Vec<MutexGuard<'_, ()>> {
+        self.stripes
+            .iter()
+            .map(|stripe| stripe.lock().unwrap())
+            .collect()
+    }
+
+    /// Get the stripe of the given key.
+    fn stripe_of(key: &LockKey) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        (hasher.finish() % STRIPES as u64) as usize
+    }
+}
+
+/// The storage of a manager.
+pub(crate) enum Storage {
+    /// A storage locked for each operation.
+    Locked(RwLock<Box<dyn AccountStorage + Sync + Send>>),
+
+    /// A storage written concurrently, without lock.
+    #[cfg(feature = "concurrent-storage")]
+    Concurrent(ConcurrentAccountStorage),
+}
+
+/// The storage of a manager, locked for each operation only if it cannot be
+/// written concurrently. The orders writing to it hold the [AccountLocks] of
+/// the accounts they change.
+pub(crate) struct SharedStore<'a>(pub(crate) &'a Storage);
+
+impl SharedStore<'_> {
+    /// Read the storage.
+    fn read<T>(&self, read: impl FnOnce(&dyn AccountStorage) -> T) -> T {
+        match self.0 {
+            Storage::Locked(lock) => read(lock.read().unwrap().as_ref()),
+            #[cfg(feature = "concurrent-storage")]
+            Storage::Concurrent(storage) => read(storage),
+        }
+    }
+
+    /// Write the storage.
+    fn write<T>(&mut self, write: impl FnOnce(&mut dyn AccountStorage) -> T) -> T {
+        match self.0 {
+            Storage::Locked(lock) => write(lock.write().unwrap().as_mut()),
+            #[cfg(feature = "concurrent-storage")]
+            Storage::Concurrent(storage) => write(&mut &*storage),
+        }
+    }
+}
+
+impl AccountStorage for SharedStore<'_> {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        self.read(|store| store.get_account(client_id))
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        self.read(|store| store.get_accounts())
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        self.read(|store| store.get_accounts_after(after, limit))
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        self.read(|store| store.get_transaction(tx_id))
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        self.read(|store| store.get_client_transactions(client_id))
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        self.read(|store| store.is_disputed(tx_id))
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        self.read(|store| store.get_disputed_transactions())
+    }
+
+    fn stats(&self) -> StorageStats {
+        self.read(|store| store.stats())
+    }
+
+    fn store_account(&mut self, account: Account) -> Result<Account> {
+        self.write(|store| store.store_account(account))
+    }
+
+    fn store_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        self.write(|store| store.store_transaction(transaction))
+    }
+
+    fn set_disputed(&mut self, tx_id: TxId, disputed: bool) -> Result<()> {
+        self.write(|store| store.set_disputed(tx_id, disputed))
+    }
+
+    fn reassign_transaction(&mut self, tx_id: TxId, client_id: ClientId) -> Result<()> {
+        self.write(|store| store.reassign_transaction(tx_id, client_id))
+    }
+
+    fn remove_account(&mut self, client_id: &ClientId) -> Result<Option<Account>> {
+        self.write(|store| store.remove_account(client_id))
+    }
+
+    fn append_log(&mut self, entry: LogEntry) -> Result<()> {
+        self.write(|store| store.append_log(entry))
+    }
+
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        self.read(|store| store.get_log())
+    }
+
+    fn check_schema(&self) -> Result<()> {
+        self.read(|store| store.check_schema())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        self.write(|store| store.commit())
+    }
+
+    fn discard(&mut self) {
+        self.write(|store| store.discard())
+    }
+}
@@ -0,0 +1,4652 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    str::FromStr,
+    sync::{mpsc::Receiver, Arc, Mutex, MutexGuard, RwLock},
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use csv_reader_ledger::{Balance, DisputeError, DisputeState};
+
+#[cfg(feature = "concurrent-storage")]
+use crate::adapter::ConcurrentAccountStorage;
+use crate::adapter::{AccountStorage, InMemoryAccountStorage, OverlayStorage, StorageStats};
+use crate::model::{
+    Account, AccountError, AccountState, ClientId, Currency, DisputeRejection, DisputeSummary,
+    DomainEvent, FraudFlag, JournalEntry, LogEntry, RejectedDispute, Timestamp, Transaction,
+    TransactionKind, TransactionOrder, TxId, MAX_DECIMALS,
+};
+use crate::Result;
+
+use super::account_locks::{AccountLocks, LockKey, SharedStore, Storage};
+use super::{
+    AuditLog, AuditRecord, DefaultDisputePolicy, DisputePolicy, EventBus, FraudDetector,
+    FraudRules, OrderLimits,
+};
+
+/// Transaction related errors.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TransactionError {
+    /// The transaction idenfier is already in use.
+    #[error("Transaction id='{0}' already in use.")]
+    DuplicateTransactionId(TxId),
+
+    /// Related transaction not found, either it does not exist or it was not a
+    /// disputable transaction.
+    #[error("Related transaction id='{0}' not found.")]
+    RelatedTransactionNotFound(TxId),
+
+    /// The related transaction is not disputed.
+    #[error("Transaction id='{0}' is not disputed.")]
+    NonDisputedTransaction(TxId),
+
+    /// The related transaction is already disputed.
+    #[error("Transaction id='{0}' is already disputed")]
+    AlreadyDisputedTransaction(TxId),
+
+    /// The related transaction is not disputable.
+    #[error("Related transaction id='{0}' is not disputable (must be a deposit or a withdrawal).")]
+    RelatedTransactionNotDisputable(TxId),
+
+    /// The account refuses the operation (locked account, insufficient funds).
+    #[error(transparent)]
+    Account(#[from] AccountError),
+
+    /// The client is suspended for review after repeated rejected orders.
+    #[error("Client id='{0}' is suspended for review.")]
+    ClientSuspended(ClientId),
+
+    /// The client reached the maximum number of transactions of the run.
+    #[error("Client id='{0}' reached the maximum of {1} transactions.")]
+    TooManyTransactions(ClientId, usize),
+
+    /// The currency of the order is not the one of the account.
+    #[error("Client id='{0}' account is in {1}, the order is in {2}.")]
+    CurrencyMismatch(ClientId, Currency, Currency),
+
+    /// The client of the order may not dispute the transaction under the
+    /// dispute policy.
+    #[error("Client id='{0}' may not dispute the transaction id='{1}'.")]
+    DisputeNotAllowed(ClientId, TxId),
+
+    /// The account to unlock does not exist or is not locked.
+    #[error("Client id='{0}' account is not locked.")]
+    AccountNotLocked(ClientId),
+
+    /// The account is closed, or was never opened for a closing.
+    #[error("Client id='{0}' account is not open.")]
+    AccountNotOpen(ClientId),
+
+    /// The account to open is already open.
+    #[error("Client id='{0}' account is already open.")]
+    AccountAlreadyOpen(ClientId),
+
+    /// The account to close still has funds.
+    #[error("Client id='{0}' account still has funds.")]
+    AccountNotEmpty(ClientId),
+
+    /// The same dispute, resolve or chargeback order of the transaction was
+    /// already applied, the order is a repeat skipped.
+    #[error("The {0} of the transaction id='{1}' was already applied.")]
+    RepeatedOrder(&'static str, TxId),
+
+    /// The order is flagged by the fraud detection, whose rules block the
+    /// flagged orders.
+    #[error("Client id='{0}' order id='{1}' is flagged as suspicious.")]
+    FraudSuspected(ClientId, TxId),
+
+    /// The amount of the order exceeds a cap of the [OrderLimits]: the
+    /// `deposit`, `withdrawal` or `daily total` one.
+    #[error("Client id='{0}' order exceeds the {1} limit of {2}.")]
+    LimitExceeded(ClientId, &'static str, Decimal),
+
+    /// The order is an adjustment while they are not allowed, see
+    /// [AccountManagerOptions::adjustments].
+    #[error("Client id='{0}' adjustment id='{1}' is not allowed.")]
+    AdjustmentNotAllowed(ClientId, TxId),
+
+    /// The dispute state of the related transaction refuses the order, for
+    /// the transitions without a variant of their own.
+    #[error(transparent)]
+    InvalidDisputeState(DisputeError),
+}
+
+impl From<DisputeError> for TransactionError {
+    fn from(error: DisputeError) -> Self {
+        match error {
+            DisputeError::AlreadyDisputed(tx_id) => Self::AlreadyDisputedTransaction(tx_id),
+            DisputeError::NotDisputed(tx_id) => Self::NonDisputedTransaction(tx_id),
+            error => Self::InvalidDisputeState(error),
+        }
+    }
+}
+
+impl TransactionError {
+    /// Get the kind of the given processing error, to aggregate the errors:
+    /// the snake case name of its [TransactionError] or [AccountError]
+    /// variant, `other` for the other errors.
+    ///
+    /// ```
+    /// use anyhow::anyhow;
+    ///
+    /// use csv_reader_core::model::AccountError;
+    /// use csv_reader_core::service::TransactionError;
+    ///
+    /// let error = anyhow!(TransactionError::DuplicateTransactionId(1));
+    /// assert_eq!(TransactionError::kind_of(&error), "duplicate_transaction_id");
+    /// let error = anyhow!(TransactionError::Account(AccountError::AccountLocked));
+    /// assert_eq!(TransactionError::kind_of(&error), "account_locked");
+    /// assert_eq!(TransactionError::kind_of(&anyhow!("whatever")), "other");
+    /// ```
+    pub fn kind_of(error: &anyhow::Error) -> &'static str {
+        match error.downcast_ref::<Self>() {
+            Some(Self::DuplicateTransactionId(_)) => "duplicate_transaction_id",
+            Some(Self::RelatedTransactionNotFound(_)) => "related_transaction_not_found",
+            Some(Self::NonDisputedTransaction(_)) => "non_disputed_transaction",
+            Some(Self::AlreadyDisputedTransaction(_)) => "already_disputed_transaction",
+            Some(Self::RelatedTransactionNotDisputable(_)) => "related_transaction_not_disputable",
+            Some(Self::Account(error)) => account_error_kind(error),
+            Some(Self::ClientSuspended(_)) => "client_suspended",
+            Some(Self::TooManyTransactions(..)) => "too_many_transactions",
+            Some(Self::CurrencyMismatch(..)) => "currency_mismatch",
+            Some(Self::DisputeNotAllowed(..)) => "dispute_not_allowed",
+            Some(Self::AccountNotLocked(_)) => "account_not_locked",
+            Some(Self::AccountNotOpen(_)) => "account_not_open",
+            Some(Self::AccountAlreadyOpen(_)) => "account_already_open",
+            Some(Self::AccountNotEmpty(_)) => "account_not_empty",
+            Some(Self::RepeatedOrder(..)) => "repeated_order",
+            Some(Self::FraudSuspected(..)) => "fraud_suspected",
+            Some(Self::LimitExceeded(..)) => "limit_exceeded",
+            Some(Self::AdjustmentNotAllowed(..)) => "adjustment_not_allowed",
+            Some(Self::InvalidDisputeState(_)) => "invalid_dispute_state",
+            None => error
+                .downcast_ref::<AccountError>()
+                .map_or("other", account_error_kind),
+        }
+    }
+}
+
+/// Get the snake case name of the variant of an [AccountError].
+fn account_error_kind(error: &AccountError) -> &'static str {
+    match error {
+        AccountError::InsufficientAvailableFunds { .. } => "insufficient_available_funds",
+        AccountError::InsufficientHeldFunds { .. } => "insufficient_held_funds",
+        AccountError::AccountLocked => "account_locked",
+        _ => "other",
+    }
+}
+
+/// Options of the [AccountManager].
+#[derive(Debug, Clone)]
+pub struct AccountManagerOptions {
+    /// Record the balanced [JournalEntry] of every applied transaction.
+    pub double_entry: bool,
+
+    /// Record the applied transactions in order in the log of the storage,
+    /// for the point-in-time balances and the rebuilding of the accounts, see
+    /// [AccountManager::balance_at] and [AccountManager::rebuild].
+    pub history: bool,
+
+    /// The funds of a deposit made on a locked account have physically been
+    /// received. When set, such deposits are credited to this suspense account
+    /// instead of being rejected so no received funds vanish from the totals.
+    pub suspense_account: Option<ClientId>,
+
+    /// When set, a client is suspended for review after this number of
+    /// consecutive rejected orders. The orders of a suspended client are
+    /// rejected for the rest of the run.
+    pub suspend_after: Option<usize>,
+
+    /// When set, the clients applying more than this number of transactions
+    /// during the run are handled according to `excess_transactions`. Such
+    /// volumes are likely bad data or abuse.
+    pub max_transactions: Option<usize>,
+
+    /// What to do with the clients exceeding `max_transactions`.
+    pub excess_transactions: ExcessTransactions,
+
+    /// How the resolutions and chargebacks settle a deposit whose amount is
+    /// no longer fully held.
+    pub held_shortfall: HeldShortfall,
+
+    /// How far below zero the withdrawals may take the available funds of
+    /// the clients.
+    pub credit_limits: CreditLimits,
+
+    /// The rules of the disputes of the processed payment scheme.
+    pub dispute_policy: Arc<dyn DisputePolicy>,
+
+    /// When set, the orders are scored against these rules before being
+    /// applied, see [FraudDetector].
+    pub fraud_rules: Option<FraudRules>,
+
+    /// The caps on the amounts of the orders.
+    pub order_limits: OrderLimits,
+
+    /// When set, every processed order is recorded in this audit log.
+    pub audit_log: Option<Arc<AuditLog>>,
+
+    /// Accept the adjustment orders, the signed manual corrections of the
+    /// administrators. They are rejected otherwise so a regular input cannot
+    /// correct the accounts.
+    pub adjustments: bool,
+}
+
+impl Default for AccountManagerOptions {
+    fn default() -> Self {
+        Self {
+            double_entry: false,
+            history: false,
+            suspense_account: None,
+            suspend_after: None,
+            max_transactions: None,
+            excess_transactions: ExcessTransactions::default(),
+            held_shortfall: HeldShortfall::default(),
+            credit_limits: CreditLimits::default(),
+            dispute_policy: Arc::new(DefaultDisputePolicy),
+            fraud_rules: None,
+            order_limits: OrderLimits::default(),
+            audit_log: None,
+            adjustments: false,
+        }
+    }
+}
+
+/// The credit limits of the clients having an overdraft facility: their
+/// withdrawals may take the available funds negative down to minus their
+/// limit instead of being rejected.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::service::CreditLimits;
+///
+/// let limits = CreditLimits::new(Decimal::ONE).with_client(7, Decimal::TEN);
+///
+/// assert_eq!(limits.limit_of(7), Decimal::TEN);
+/// assert_eq!(limits.limit_of(8), Decimal::ONE);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct CreditLimits {
+    /// The limit of the clients without a limit of their own, none by
+    /// default.
+    default: Decimal,
+
+    /// The limits of the clients.
+    clients: HashMap<ClientId, Decimal>,
+}
+
+impl CreditLimits {
+    /// Create the credit limits with the given limit for every client.
+    pub fn new(default: Decimal) -> Self {
+        Self {
+            default,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Give the client a limit of its own, instead of the default one.
+    pub fn with_client(mut self, client_id: ClientId, limit: Decimal) -> Self {
+        self.clients.insert(client_id, limit);
+
+        self
+    }
+
+    /// Get the credit limit of the given client.
+    pub fn limit_of(&self, client_id: ClientId) -> Decimal {
+        self.clients
+            .get(&client_id)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// What to do with a client exceeding the maximum number of transactions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExcessTransactions {
+    /// The transactions are applied and the client is reported.
+    #[default]
+    Flag,
+
+    /// The transactions over the maximum are rejected and the client is
+    /// reported.
+    Reject,
+}
+
+impl FromStr for ExcessTransactions {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "flag" => Ok(Self::Flag),
+            "reject" => Ok(Self::Reject),
+            _ => bail!(
+                "Unknown excess transactions handling '{value}' (expected 'flag' or 'reject')."
+            ),
+        }
+    }
+}
+
+/// How a resolve or a chargeback settles a disputed transaction when the held
+/// funds of the account are lower than its amount, as when they were partially
+/// consumed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HeldShortfall {
+    /// The order is rejected for insufficient held funds, the transaction
+    /// stays disputed.
+    #[default]
+    Reject,
+
+    /// The order settles the held funds left, nothing if there are none.
+    Clamp,
+
+    /// The order settles the share of the held funds left matching the share
+    /// of the transaction in the amounts disputed on the account, so the
+    /// other open disputes keep their share.
+    Proportional,
+}
+
+impl HeldShortfall {
+    /// Get the amount settled for a disputed transaction of the given amount on
+    /// the given account, `disputed` being the amount of all the open disputes
+    /// of the account.
+    fn settle(
+        self,
+        account: &Account,
+        amount: Decimal,
+        disputed: impl FnOnce() -> Decimal,
+    ) -> Decimal {
+        let held = account.held.max(Decimal::ZERO);
+        if held >= amount {
+            return amount;
+        }
+        match self {
+            Self::Reject => amount,
+            Self::Clamp => held,
+            Self::Proportional => {
+                let disputed = disputed();
+                if disputed <= amount {
+                    return held;
+                }
+                // rounded down so the share never exceeds the held funds
+                (amount * held / disputed)
+                    .round_dp_with_strategy(MAX_DECIMALS, RoundingStrategy::ToZero)
+            }
+        }
+    }
+}
+
+impl FromStr for HeldShortfall {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "clamp" => Ok(Self::Clamp),
+            "proportional" => Ok(Self::Proportional),
+            _ => bail!(
+                "Unknown held shortfall handling '{value}' (expected 'reject', 'clamp' or 'proportional')."
+            ),
+        }
+    }
+}
+
+/// An account whose stored state is not the one rebuilt from the history, see
+/// [AccountManager::rebuild].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDrift {
+    /// The client of the account.
+    pub client_id: ClientId,
+
+    /// The stored account, `None` if it is not stored.
+    pub stored: Option<Account>,
+
+    /// The account rebuilt from the history, `None` if the history has no
+    /// transaction of the client.
+    pub rebuilt: Option<Account>,
+}
+
+/// Report of the rebuilding of the accounts, see [AccountManager::rebuild].
+#[derive(Debug, Clone, Default)]
+pub struct RebuildReport {
+    /// The number of accounts stored or rebuilt.
+    pub accounts_checked: usize,
+
+    /// The number of entries of the history replayed: the transactions, the
+    /// merges of clients and the preloaded disputes.
+    pub transactions_replayed: usize,
+
+    /// The accounts differing from their rebuilt state.
+    pub drifts: Vec<AccountDrift>,
+}
+
+impl RebuildReport {
+    /// Tell if the stored accounts match the history.
+    pub fn is_consistent(&self) -> bool {
+        self.drifts.is_empty()
+    }
+}
+
+/// Compare the stored accounts to the ones rebuilt from the history, and get
+/// the number of accounts compared with the ones differing, by client.
+pub(crate) fn account_drifts(
+    stored: Vec<Account>,
+    rebuilt: Vec<Account>,
+) -> (usize, Vec<AccountDrift>) {
+    let mut stored: HashMap<ClientId, Account> = stored
+        .into_iter()
+        .map(|account| (account.client_id, account))
+        .collect();
+    let mut rebuilt: HashMap<ClientId, Account> = rebuilt
+        .into_iter()
+        .map(|account| (account.client_id, account))
+        .collect();
+    let clients: BTreeSet<ClientId> = stored.keys().chain(rebuilt.keys()).copied().collect();
+    let drifts = clients
+        .iter()
+        .filter_map(|client_id| {
+            let stored = stored.remove(client_id);
+            let rebuilt = rebuilt.remove(client_id);
+            (stored != rebuilt).then_some(AccountDrift {
+                client_id: *client_id,
+                stored,
+                rebuilt,
+            })
+        })
+        .collect();
+
+    (clients.len(), drifts)
+}
+
+/// The funds a dispute is about, held until it is settled: the ones of a
+/// deposit are held out of the available funds, the ones of a withdrawal are
+/// held on top of them for a potential re-credit.
+#[derive(Debug, Clone, Copy)]
+enum DisputedFunds {
+    /// The amount of a disputed deposit.
+    Deposit(Decimal),
+
+    /// The amount of a disputed withdrawal.
+    Withdrawal(Decimal),
+}
+
+impl DisputedFunds {
+    /// Get the funds of the given transaction, if it can be disputed.
+    fn of(transaction: &Transaction) -> Option<Self> {
+        match transaction.kind {
+            TransactionKind::Deposit(amount) => Some(Self::Deposit(amount)),
+            TransactionKind::Withdrawal(amount) => Some(Self::Withdrawal(amount)),
+            _ => None,
+        }
+    }
+
+    /// The disputed amount.
+    fn amount(self) -> Decimal {
+        match self {
+            Self::Deposit(amount) | Self::Withdrawal(amount) => amount,
+        }
+    }
+
+    /// The same funds with another amount, the one a settlement settles.
+    fn with_amount(self, amount: Decimal) -> Self {
+        match self {
+            Self::Deposit(_) => Self::Deposit(amount),
+            Self::Withdrawal(_) => Self::Withdrawal(amount),
+        }
+    }
+
+    /// Hold the funds on the given balance, the ones of a deposit only within
+    /// the available funds unless the dispute policy allows them to become
+    /// negative.
+    fn dispute(
+        self,
+        balance: &mut Balance,
+        policy: &dyn DisputePolicy,
+    ) -> std::result::Result<(), AccountError> {
+        match self {
+            Self::Deposit(amount)
+                if !policy.allows_negative_available() && balance.available < amount =>
+            {
+                Err(AccountError::InsufficientAvailableFunds {
+                    available: balance.available,
+                    requested: amount,
+                })
+            }
+            Self::Deposit(amount) => balance.dispute(amount),
+            Self::Withdrawal(amount) => balance.dispute_withdrawal(amount),
+        }
+    }
+
+    /// Release the held funds, the disputed transaction standing.
+    fn resolve(self, balance: &mut Balance) -> std::result::Result<(), AccountError> {
+        match self {
+            Self::Deposit(amount) => balance.resolve(amount),
+            Self::Withdrawal(amount) => balance.resolve_withdrawal(amount),
+        }
+    }
+
+    /// Reverse the disputed transaction and lock the balance.
+    fn chargeback(self, balance: &mut Balance) -> std::result::Result<(), AccountError> {
+        match self {
+            Self::Deposit(amount) => balance.chargeback(amount),
+            Self::Withdrawal(amount) => balance.chargeback_withdrawal(amount),
+        }
+    }
+}
+
+/// Tracks the consecutive rejected orders of the clients.
+#[derive(Debug, Default, Clone)]
+struct RejectionTracker {
+    /// Number of consecutive rejected orders per client.
+    consecutive: HashMap<ClientId, usize>,
+
+    /// Clients suspended for review.
+    suspended: BTreeSet<ClientId>,
+}
+
+/// Counts the transactions applied for the clients.
+#[derive(Debug, Default, Clone)]
+struct TransactionCounter {
+    /// Number of applied transactions per client.
+    counts: HashMap<ClientId, usize>,
+
+    /// Clients that exceeded or reached the maximum number of transactions.
+    exceeded: BTreeSet<ClientId>,
+}
+
+/// The [AccountManager] is responsible for managing the accounts and
+/// transactions of the system.  It turns [TransactionOrder]s into
+/// [Transaction]s and applies them to the accounts.
+///
+/// This service can be shared amongst multiple actors hence muliple threads.
+/// This means it will be stored in an `Arc so its internal state must use
+/// interior mutability.
+/// For now we will use a simple hash map to store the accounts and transactions
+/// but adapters can be used to store the data in a database.
+pub struct AccountManager {
+    /// Storing the internal state in one place, locked for each operation
+    /// only. The orders hold the locks of the accounts they change instead.
+    store: Storage,
+
+    /// The locks of the accounts, held by the orders while they are
+    /// processed.
+    locks: AccountLocks,
+
+    /// The double-entry journal, if enabled.
+    journal: Option<Mutex<Vec<JournalEntry>>>,
+
+    /// The rejected orders of the clients.
+    rejections: Mutex<RejectionTracker>,
+
+    /// The applied transactions of the clients.
+    transactions: Mutex<TransactionCounter>,
+
+    /// The deposits to dispute as soon as they are applied, see
+    /// [AccountManager::preload_disputes].
+    pending_disputes: Mutex<HashSet<TxId>>,
+
+    /// The rejected dispute orders noted on each account.
+    dispute_notes: Mutex<BTreeMap<ClientId, Vec<RejectedDispute>>>,
+
+    /// The dispute, resolve and chargeback orders applied, by kind, own
+    /// transaction identifier and disputed transaction, to skip their
+    /// repeats.
+    dispute_orders: Mutex<HashSet<(&'static str, TxId, TxId)>>,
+
+    /// The fraud detection of the orders, if enabled.
+    fraud_detector: Option<FraudDetector>,
+
+    /// The amounts moved by the clients per day, for the daily cap of the
+    /// [OrderLimits].
+    daily_totals: Mutex<HashMap<(ClientId, NaiveDate), Decimal>>,
+
+    /// The events published to the subscribers.
+    events: EventBus,
+
+    /// The manager options.
+    options: AccountManagerOptions,
+}
+
+impl AccountManager {
+    /// Create a new account manager with the default options.
+    pub fn new(storage: impl AccountStorage + Sync + Send + 'static) -> Self {
+        Self::with_options(storage, AccountManagerOptions::default())
+    }
+
+    /// Create a new account manager with the given options.
+    pub fn with_options(
+        storage: impl AccountStorage + Sync + Send + 'static,
+        options: AccountManagerOptions,
+    ) -> Self {
+        Self::with_store(Storage::Locked(RwLock::new(Box::new(storage))), options)
+    }
+
+    /// Create a new account manager with the given options on a storage
+    /// written concurrently. Unlike the other storages, it is not locked for
+    /// each operation, only the accounts of the orders are.
+    #[cfg(feature = "concurrent-storage")]
+    pub fn with_concurrent_storage(
+        storage: ConcurrentAccountStorage,
+        options: AccountManagerOptions,
+    ) -> Self {
+        Self::with_store(Storage::Concurrent(storage), options)
+    }
+
+    /// Create a new account manager on the given storage.
+    fn with_store(store: Storage, options: AccountManagerOptions) -> Self {
+        Self {
+            store,
+            locks: AccountLocks::default(),
+            journal: options.double_entry.then(|| Mutex::new(Vec::new())),
+            rejections: Mutex::new(RejectionTracker::default()),
+            transactions: Mutex::new(TransactionCounter::default()),
+            pending_disputes: Mutex::new(HashSet::new()),
+            dispute_notes: Mutex::new(BTreeMap::new()),
+            dispute_orders: Mutex::new(HashSet::new()),
+            fraud_detector: options.fraud_rules.clone().map(FraudDetector::new),
+            daily_totals: Mutex::new(HashMap::new()),
+            events: EventBus::default(),
+            options,
+        }
+    }
+
+    /// Create a shadow of this manager: the orders it processes are checked
+    /// and applied against the current state of this manager but their
+    /// changes are kept apart, this manager being left untouched. The shadow
+    /// cannot be committed. This allows a dry run against a production
+    /// storage without copying it.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _transaction = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+    /// let shadow = manager.shadow();
+    /// let _transaction = shadow.process_order(order(2, TransactionKind::Withdrawal(Decimal::ONE))).unwrap();
+    ///
+    /// assert_eq!(shadow.get_account(1).unwrap().available, Decimal::from(9));
+    /// assert_eq!(manager.get_account(1).unwrap().available, Decimal::TEN);
+    /// assert!(shadow.commit().is_err());
+    /// ```
+    pub fn shadow(self: &Arc<Self>) -> Self {
+        Self {
+            store: Storage::Locked(RwLock::new(Box::new(OverlayStorage::new(ShadowedStore(
+                self.clone(),
+            ))))),
+            locks: AccountLocks::default(),
+            journal: self.options.double_entry.then(|| Mutex::new(Vec::new())),
+            rejections: Mutex::new(self.rejections.lock().unwrap().clone()),
+            transactions: Mutex::new(self.transactions.lock().unwrap().clone()),
+            pending_disputes: Mutex::new(self.pending_disputes.lock().unwrap().clone()),
+            dispute_notes: Mutex::new(BTreeMap::new()),
+            dispute_orders: Mutex::new(self.dispute_orders.lock().unwrap().clone()),
+            fraud_detector: self.fraud_detector.clone(),
+            daily_totals: Mutex::new(self.daily_totals.lock().unwrap().clone()),
+            events: EventBus::default(),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Try to process the given order and return the resulting transaction.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal::Decimal;
+    /// use rust_decimal_macros::dec;
+    ///
+    /// use csv_reader_core::model::{TransactionOrder, TransactionKind};
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// let transaction = manager.process_order(TransactionOrder { tx_id: 1, client_id: 1, kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED), timestamp: None, currency: None }).unwrap();
+    ///
+    /// assert_eq!(transaction.tx_id, 1);
+    /// let account = manager.get_account(1).unwrap();
+    ///
+    /// assert_eq!(account.available, Decimal::ONE_HUNDRED);
+    ///
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 2, client_id: 1, kind: TransactionKind::Withdrawal(dec!(30)), timestamp: None, currency: None }).unwrap();
+    /// let account = manager.get_account(1).unwrap();
+    ///
+    /// assert_eq!(account.available, dec!(70));
+    ///
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 3, client_id: 2, kind: TransactionKind::Dispute(1), timestamp: None, currency: None }).unwrap();
+    /// let account = manager.get_account(1).unwrap();
+    ///
+    /// assert_eq!(account.available, dec!(-30));
+    ///
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 4, client_id: 1, kind: TransactionKind::Deposit(Decimal::ONE_HUNDRED), timestamp: None, currency: None }).unwrap();
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 5, client_id: 2, kind: TransactionKind::Resolve(1), timestamp: None, currency: None }).unwrap();
+    /// let account = manager.get_account(1).unwrap();
+    ///
+    /// assert_eq!(account.available, dec!(170));
+    ///
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 6, client_id: 2, kind: TransactionKind::Dispute(4), timestamp: None, currency: None }).unwrap();
+    /// let _tx = manager.process_order(TransactionOrder { tx_id: 7, client_id: 2, kind: TransactionKind::ChargeBack(4), timestamp: None, currency: None }).unwrap();
+    /// let account = manager.get_account(1).unwrap();
+    ///
+    /// assert_eq!(account.available, dec!(70));
+    /// assert!(account.locked);
+    /// ```
+    ///
+    pub fn process_order(&self, order: TransactionOrder) -> Result<Transaction> {
+        let _guards = self.lock_orders(std::slice::from_ref(&order));
+
+        self.process_locked(&mut self.shared_store(), order)
+    }
+
+    /// Process the given orders in turn under a single lock of their
+    /// accounts, and return the outcome of each. This spares the lock
+    /// acquisitions of [AccountManager::process_order] when the accounts are
+    /// contended, the orders of other threads on the same accounts waiting
+    /// for the whole batch.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let results = manager.process_orders(vec![
+    ///     order(1, TransactionKind::Deposit(Decimal::TEN)),
+    ///     order(2, TransactionKind::Withdrawal(Decimal::ONE_HUNDRED)),
+    ///     order(3, TransactionKind::Withdrawal(Decimal::ONE)),
+    /// ]);
+    ///
+    /// assert_eq!(results.len(), 3);
+    /// assert!(results[1].is_err());
+    /// assert_eq!(manager.get_account(1).unwrap().available, Decimal::from(9));
+    /// ```
+    pub fn process_orders(
+        &self,
+        orders: impl IntoIterator<Item = TransactionOrder>,
+    ) -> Vec<Result<Transaction>> {
+        let orders: Vec<TransactionOrder> = orders.into_iter().collect();
+        let _guards = self.lock_orders(&orders);
+        let mut store = self.shared_store();
+
+        orders
+            .into_iter()
+            .map(|order| self.process_locked(&mut store, order))
+            .collect()
+    }
+
+    /// Process the given orders as a whole: either all of them are applied,
+    /// in turn, or none is. The orders are first processed by a shadow of
+    /// this manager, see [AccountManager::shadow], then applied if all of
+    /// them succeeded, the accounts they concern being locked meanwhile.
+    /// Fails with the error of the first order rejected. Only a failure of
+    /// the storage itself while applying the checked orders can leave the
+    /// bundle partially applied, the error then tells so.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, TransactionError};
+    ///
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// let order = |tx_id, client_id, kind| TransactionOrder { tx_id, client_id, kind, timestamp: None, currency: None };
+    ///
+    /// // the transfer exceeds the deposit, nothing is applied
+    /// let error = manager
+    ///     .process_bundle(vec![
+    ///         order(1, 1, TransactionKind::Deposit(Decimal::TEN)),
+    ///         order(2, 1, TransactionKind::Withdrawal(Decimal::ONE_HUNDRED)),
+    ///         order(3, 2, TransactionKind::Deposit(Decimal::ONE_HUNDRED)),
+    ///     ])
+    ///     .unwrap_err();
+    /// assert_eq!(TransactionError::kind_of(&error), "insufficient_available_funds");
+    /// assert!(manager.get_account(1).is_none());
+    ///
+    /// let transactions = manager
+    ///     .process_bundle(vec![
+    ///         order(1, 1, TransactionKind::Deposit(Decimal::TEN)),
+    ///         order(2, 1, TransactionKind::Withdrawal(Decimal::ONE)),
+    ///         order(3, 2, TransactionKind::Deposit(Decimal::ONE)),
+    ///     ])
+    ///     .unwrap();
+    /// assert_eq!(transactions.len(), 3);
+    /// assert_eq!(manager.get_account(2).unwrap().available, Decimal::ONE);
+    /// ```
+    pub fn process_bundle(
+        self: &Arc<Self>,
+        orders: Vec<TransactionOrder>,
+    ) -> Result<Vec<Transaction>> {
+        let _guards = self.lock_orders(&orders);
+        let mut shadow = self.shadow();
+        // the decisions of the dry run are not the ones of this manager
+        shadow.options.audit_log = None;
+
+        for (index, order) in orders.iter().enumerate() {
+            let tx_id = order.tx_id;
+            shadow
+                .process_locked(&mut shadow.shared_store(), order.clone())
+                .with_context(|| {
+                    format!("Order {index} of the bundle (tx={tx_id}) rejected, no order applied")
+                })?;
+        }
+        let count = orders.len();
+
+        orders
+            .into_iter()
+            .enumerate()
+            .map(|(index, order)| {
+                let tx_id = order.tx_id;
+                self.process_locked(&mut self.shared_store(), order)
+                    .with_context(|| {
+                        format!(
+                            "Order {index} of the bundle (tx={tx_id}) failed, the {index} orders before it out of {count} are applied"
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Get the storage, locked for each operation if needed.
+    fn shared_store(&self) -> SharedStore<'_> {
+        SharedStore(&self.store)
+    }
+
+    /// Lock the accounts the given orders read or write: the one of their
+    /// client, the one of the disputed transaction for the dispute orders,
+    /// the suspense account for the deposits if any, and their transaction
+    /// identifier. The owners of the disputed transactions are checked again
+    /// once locked, as a concurrent order may have created them or a merge
+    /// moved them.
+    fn lock_orders(&self, orders: &[TransactionOrder]) -> Vec<MutexGuard<'_, ()>> {
+        let keys_of = || -> Vec<LockKey> {
+            orders
+                .iter()
+                .flat_map(|order| self.lock_keys(order))
+                .collect()
+        };
+        loop {
+            let keys = keys_of();
+            let guards = self.locks.lock(&keys);
+            if keys_of() == keys {
+                return guards;
+            }
+        }
+    }
+
+    /// Get the keys to lock to process the given order.
+    fn lock_keys(&self, order: &TransactionOrder) -> Vec<LockKey> {
+        let mut keys = vec![LockKey::Client(order.client_id)];
+        if let (TransactionKind::Deposit(_), Some(suspense_account)) =
+            (&order.kind, self.options.suspense_account)
+        {
+            keys.push(LockKey::Client(suspense_account));
+        }
+        match order.kind {
+            TransactionKind::Dispute(tx_id)
+            | TransactionKind::Resolve(tx_id)
+            | TransactionKind::ChargeBack(tx_id) => {
+                if let Some(transaction) = self.shared_store().get_transaction(&tx_id) {
+                    keys.push(LockKey::Client(transaction.client_id));
+                }
+            }
+            _ => keys.push(LockKey::Tx(order.tx_id)),
+        }
+
+        keys
+    }
+
+    /// Process the given order with its accounts locked.
+    fn process_locked(
+        &self,
+        store: &mut (dyn AccountStorage + Sync + Send),
+        order: TransactionOrder,
+    ) -> Result<Transaction> {
+        let client_id = order.client_id;
+        let disputed = match order.kind {
+            TransactionKind::Dispute(tx_id) => Some(tx_id),
+            _ => None,
+        };
+        let audit = self.options.audit_log.as_ref().map(|audit_log| {
+            (
+                audit_log,
+                Self::audited_account(store, &order),
+                order.clone(),
+            )
+        });
+        let result = self.apply_order(store, order);
+        if let Some((audit_log, before, order)) = audit {
+            let after = Self::get_or_create_account(store, before.client_id);
+            let error = result.as_ref().err().map(ToString::to_string);
+            audit_log.record(&AuditRecord::new(&order, &before, &after, error));
+        }
+        if let Err(Some(TransactionError::RepeatedOrder(..))) =
+            result.as_ref().map_err(|error| error.downcast_ref())
+        {
+            // a replayed order is no sign of the behavior of the client
+            return result;
+        }
+        self.track_rejection(client_id, result.is_err());
+
+        if let Err(error) = &result {
+            self.flag_rejected_transaction(error);
+            if let Some(tx_id) = disputed {
+                self.note_rejected_dispute(store, client_id, tx_id, error);
+            }
+        }
+
+        result
+    }
+
+    /// Check and apply the given order to the storage locked for writing.
+    fn apply_order(
+        &self,
+        store: &mut (dyn AccountStorage + Sync + Send),
+        order: TransactionOrder,
+    ) -> Result<Transaction> {
+        let client_id = order.client_id;
+        let order = self
+            .route_order(store, order)
+            .map_err(|error| anyhow!(error))?;
+        let flags = self.screen_order(&order);
+        if flags.iter().any(|flag| flag.blocked) {
+            self.raise_flags(flags);
+            bail!(TransactionError::FraudSuspected(client_id, order.tx_id));
+        }
+        let transaction: Transaction = order.into();
+        let held_shortfall = self.options.held_shortfall;
+        // made before the transaction changes the held funds it settles
+        let entry = match &self.journal {
+            Some(_) => Some(Self::journal_entry(store, &transaction, held_shortfall)?),
+            None => None,
+        };
+
+        let transaction = match transaction.kind {
+            TransactionKind::Deposit(amount) => Self::apply_deposit(store, transaction, amount),
+            TransactionKind::Withdrawal(amount) => {
+                let credit_limit = self.options.credit_limits.limit_of(transaction.client_id);
+                Self::apply_withdrawal(store, transaction, amount, credit_limit)
+            }
+            TransactionKind::Dispute(tx_id) => self.apply_dispute(store, transaction, tx_id),
+            TransactionKind::Resolve(tx_id) => {
+                self.apply_resolve(store, transaction, tx_id, held_shortfall)
+            }
+            TransactionKind::ChargeBack(tx_id) => {
+                self.apply_chargeback(store, transaction, tx_id, held_shortfall)
+            }
+            TransactionKind::Unlock => self.apply_unlock(store, transaction),
+            TransactionKind::Open => self.apply_open(store, transaction),
+            TransactionKind::Close => self.apply_close(store, transaction),
+            TransactionKind::Adjustment(amount) => {
+                self.apply_adjustment(store, transaction, amount)
+            }
+        }?;
+
+        if self.options.history {
+            store.append_log(transaction.clone().into())?;
+        }
+        if let (Some(journal), Some(Some(entry))) = (&self.journal, entry) {
+            journal.lock().unwrap().push(entry);
+        }
+        if let Some(key) = Self::dispute_order_key(transaction.tx_id, &transaction.kind) {
+            self.dispute_orders.lock().unwrap().insert(key);
+        }
+        if let Some(detector) = &self.fraud_detector {
+            detector.record(&transaction);
+        }
+        self.raise_flags(flags);
+        if let TransactionKind::Deposit(amount) | TransactionKind::Withdrawal(amount) =
+            transaction.kind
+        {
+            if self.options.order_limits.max_daily_total.is_some() {
+                *self
+                    .daily_totals
+                    .lock()
+                    .unwrap()
+                    .entry((client_id, OrderLimits::day_of(transaction.timestamp)))
+                    .or_default() += amount;
+            }
+        }
+        if let TransactionKind::Deposit(_) | TransactionKind::Withdrawal(_) = transaction.kind {
+            if self
+                .pending_disputes
+                .lock()
+                .unwrap()
+                .remove(&transaction.tx_id)
+            {
+                self.preload_dispute(store, transaction.tx_id);
+            }
+        }
+        // counted under the lock of the account so concurrent orders see the
+        // count
+        self.count_transaction(client_id);
+
+        Ok(transaction)
+    }
+
+    /// Check the given order would be successfully processed against the
+    /// current state without modifying it. This performs exactly the checks
+    /// done by [AccountManager::process_order], the fraud screening included,
+    /// without noting nor publishing the fraud flags.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{AccountError, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, TransactionError};
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    ///
+    /// // validating an order does not apply it
+    /// manager.validate_order(&order).unwrap();
+    /// assert!(manager.get_account(1).is_none());
+    ///
+    /// let _transaction = manager.process_order(order.clone()).unwrap();
+    /// assert!(matches!(
+    ///     manager.validate_order(&order),
+    ///     Err(TransactionError::DuplicateTransactionId(1))
+    /// ));
+    ///
+    /// let order = TransactionOrder {
+    ///     tx_id: 2,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Withdrawal(Decimal::TWO),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// assert!(matches!(
+    ///     manager.validate_order(&order),
+    ///     Err(TransactionError::Account(AccountError::InsufficientAvailableFunds { .. }))
+    /// ));
+    /// ```
+    pub fn validate_order(
+        &self,
+        order: &TransactionOrder,
+    ) -> std::result::Result<(), TransactionError> {
+        let _guards = self.lock_orders(std::slice::from_ref(order));
+        let order = self.route_order(&self.shared_store(), order.clone())?;
+        if self.screen_order(&order).iter().any(|flag| flag.blocked) {
+            return Err(TransactionError::FraudSuspected(
+                order.client_id,
+                order.tx_id,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the account for the given client identifier.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{Account, ClientId, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    ///
+    /// // If the account does not exist, None is returned.
+    /// assert!(manager.get_account(1).is_none());
+    ///
+    /// // If the account exists, it is returned.
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// let _transaction = manager.process_order(order).unwrap();
+    /// let account = manager.get_account(1).unwrap();
+    /// assert_eq!(account.client_id, 1);
+    /// assert_eq!(account.available, Decimal::ONE);
+    ///
+    /// ```
+    pub fn get_account(&self, client_id: ClientId) -> Option<Account> {
+        // If the lock returns an error, it means that a thread panicked while
+        // holding the lock so this thread should panic as well.
+        self.shared_store().get_account(&client_id)
+    }
+
+    /// Export the accounts.
+    pub fn get_accounts(&self) -> Vec<Account> {
+        self.shared_store().get_accounts()
+    }
+
+    /// Get at most `limit` accounts of client identifier greater than the
+    /// given one, by increasing client identifier, to read the accounts by
+    /// chunks.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// for client_id in [3, 1, 2] {
+    ///     let kind = TransactionKind::Deposit(Decimal::ONE);
+    ///     manager.process_order(TransactionOrder { tx_id: client_id, client_id, kind, timestamp: None, currency: None }).unwrap();
+    /// }
+    /// let chunk = manager.get_accounts_after(Some(1), 5);
+    ///
+    /// assert_eq!(chunk.iter().map(|account| account.client_id).collect::<Vec<_>>(), vec![2, 3]);
+    /// ```
+    pub fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        self.shared_store().get_accounts_after(after, limit)
+    }
+
+    /// Get the applied transaction with the given identifier if any.
+    pub fn get_transaction(&self, tx_id: TxId) -> Option<Transaction> {
+        self.shared_store().get_transaction(&tx_id)
+    }
+
+    /// Get the number and the sum of the disputed transactions of every
+    /// account with open disputes.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// for (tx_id, kind) in [
+    ///     (1, TransactionKind::Deposit(dec!(10))),
+    ///     (2, TransactionKind::Deposit(dec!(5))),
+    ///     (3, TransactionKind::Dispute(1)),
+    ///     (4, TransactionKind::Dispute(2)),
+    /// ] {
+    ///     manager.process_order(TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None }).unwrap();
+    /// }
+    /// let summary = manager.get_dispute_summaries()[&1];
+    ///
+    /// assert_eq!(summary.count, 2);
+    /// assert_eq!(summary.sum, dec!(15));
+    /// ```
+    pub fn get_dispute_summaries(&self) -> HashMap<ClientId, DisputeSummary> {
+        let mut summaries: HashMap<ClientId, DisputeSummary> = HashMap::new();
+
+        for transaction in self.shared_store().get_disputed_transactions() {
+            if let Some(funds) = DisputedFunds::of(&transaction) {
+                let summary = summaries.entry(transaction.client_id).or_default();
+                summary.count += 1;
+                summary.sum += funds.amount();
+            }
+        }
+
+        summaries
+    }
+
+    /// Get the double-entry journal of the applied transactions in the order
+    /// they were applied. It is empty unless the `double_entry` option is set.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{LedgerAccount, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     double_entry: true,
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// let _transaction = manager.process_order(order).unwrap();
+    /// let journal = manager.get_journal();
+    ///
+    /// assert_eq!(journal.len(), 1);
+    /// assert_eq!(journal[0].debit, LedgerAccount::Omnibus);
+    /// assert_eq!(journal[0].credit, LedgerAccount::ClientAvailable(1));
+    /// ```
+    pub fn get_journal(&self) -> Vec<JournalEntry> {
+        self.journal
+            .as_ref()
+            .map(|journal| journal.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    /// Load the open disputes of an external case management system: the
+    /// deposits with the given transaction identifiers are disputed and their
+    /// funds held. The deposits not applied yet are disputed as soon as they
+    /// are. A deposit that cannot be disputed is logged and skipped.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// manager.preload_disputes([1, 2]);
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// let _transaction = manager.process_order(order).unwrap();
+    ///
+    /// assert_eq!(manager.get_account(1).unwrap().held, Decimal::ONE);
+    /// assert_eq!(manager.get_pending_disputes(), vec![2]);
+    /// ```
+    pub fn preload_disputes(&self, tx_ids: impl IntoIterator<Item = TxId>) {
+        let _guards = self.locks.lock_all();
+        let mut store = self.shared_store();
+        let mut pending = self.pending_disputes.lock().unwrap();
+
+        for tx_id in tx_ids {
+            if store.get_transaction(&tx_id).is_some() {
+                self.preload_dispute(&mut store, tx_id);
+            } else {
+                pending.insert(tx_id);
+            }
+        }
+    }
+
+    /// Get the preloaded disputes whose deposit has not been applied, see
+    /// [AccountManager::preload_disputes].
+    pub fn get_pending_disputes(&self) -> Vec<TxId> {
+        let mut pending: Vec<TxId> = self
+            .pending_disputes
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect();
+        pending.sort_unstable();
+
+        pending
+    }
+
+    /// Get the dispute orders rejected for the transactions of the given
+    /// client's account, because the disputed transaction does not exist, is
+    /// already disputed or cannot be disputed, by the client of the order, under
+    /// the dispute policy. The dispute orders of a transaction that does not
+    /// exist are noted on the account of the client of the order.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{DisputeRejection, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// for (tx_id, kind) in [
+    ///     (1, TransactionKind::Deposit(Decimal::ONE)),
+    ///     (1, TransactionKind::Dispute(1)),
+    ///     (1, TransactionKind::Dispute(1)),
+    ///     (2, TransactionKind::Dispute(2)),
+    /// ] {
+    ///     let order = TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    ///     let _result = manager.process_order(order);
+    /// }
+    /// let notes = manager.get_dispute_notes(1);
+    ///
+    /// assert_eq!(notes.len(), 2);
+    /// assert_eq!((notes[0].tx_id, notes[0].reason), (1, DisputeRejection::AlreadyDisputed));
+    /// assert_eq!((notes[1].tx_id, notes[1].reason), (2, DisputeRejection::NotFound));
+    /// ```
+    pub fn get_dispute_notes(&self, client_id: ClientId) -> Vec<RejectedDispute> {
+        self.dispute_notes
+            .lock()
+            .unwrap()
+            .get(&client_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Get the rejected dispute orders noted on every account, by client, see
+    /// [AccountManager::get_dispute_notes].
+    pub fn get_rejected_disputes(&self) -> Vec<RejectedDispute> {
+        self.dispute_notes
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Get the orders flagged by the fraud detection, in order, none when it
+    /// is disabled.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{DomainEvent, FraudRule, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions, FraudRules};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     fraud_rules: Some(FraudRules {
+    ///         withdrawal_velocity: Some((1, Duration::from_secs(60))),
+    ///         block: true,
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// let events = manager.subscribe();
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _tx = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+    /// let _tx = manager.process_order(order(2, TransactionKind::Withdrawal(Decimal::ONE))).unwrap();
+    ///
+    /// assert!(manager.process_order(order(3, TransactionKind::Withdrawal(Decimal::ONE))).is_err());
+    /// assert_eq!(manager.get_account(1).unwrap().available, Decimal::from(9));
+    /// let flags = manager.get_fraud_flags();
+    /// assert_eq!((flags[0].tx_id, flags[0].rule), (3, FraudRule::WithdrawalVelocity));
+    /// assert!(flags[0].blocked);
+    /// assert_eq!(events.try_recv().unwrap(), DomainEvent::FraudFlagged(flags[0]));
+    /// ```
+    pub fn get_fraud_flags(&self) -> Vec<FraudFlag> {
+        self.fraud_detector
+            .as_ref()
+            .map(FraudDetector::flags)
+            .unwrap_or_default()
+    }
+
+    /// Merge the account of a client into the account of another one, when a
+    /// client was assigned two identifiers upstream. The transactions of
+    /// `from` are attributed to `into`, its funds are added to the account of
+    /// `into` and its account is removed. The merged account is locked if
+    /// either account was. The moves of funds are recorded in the journal as
+    /// adjustment entries and the merge, applied or not, in the audit log.
+    /// Return the number of transactions attributed to `into`.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// for (tx_id, client_id) in [(1, 1), (2, 2)] {
+    ///     let order = TransactionOrder {
+    ///         tx_id,
+    ///         client_id,
+    ///         kind: TransactionKind::Deposit(Decimal::ONE),
+    ///         timestamp: None,
+    ///         currency: None,
+    ///     };
+    ///     let _transaction = manager.process_order(order).unwrap();
+    /// }
+    ///
+    /// assert_eq!(manager.merge_clients(2, 1).unwrap(), 1);
+    /// assert_eq!(manager.get_account(1).unwrap().total, Decimal::TWO);
+    /// assert!(manager.get_account(2).is_none());
+    /// assert!(manager.merge_clients(2, 1).is_err());
+    /// ```
+    pub fn merge_clients(&self, from: ClientId, into: ClientId) -> Result<usize> {
+        let _guards = self
+            .locks
+            .lock(&[LockKey::Client(from), LockKey::Client(into)]);
+        let mut store = self.shared_store();
+        let before = Self::get_or_create_account(&store, into);
+        let result = self.merge_locked(&mut store, from, into);
+        if let Some(audit_log) = &self.options.audit_log {
+            let after = Self::get_or_create_account(&store, into);
+            let error = result.as_ref().err().map(ToString::to_string);
+            audit_log.record(&AuditRecord::merge(from, &before, &after, error));
+        }
+
+        result
+    }
+
+    /// Merge the account of a client into the account of another one with
+    /// both accounts locked.
+    fn merge_locked(
+        &self,
+        store: &mut dyn AccountStorage,
+        from: ClientId,
+        into: ClientId,
+    ) -> Result<usize> {
+        if from == into {
+            bail!("Cannot merge the client id='{from}' into itself.");
+        }
+        let source = store
+            .get_account(&from)
+            .ok_or_else(|| anyhow!("Client id='{from}' has no account to merge."))?;
+        let mut account = Self::get_or_create_account(store, into);
+        if let (Some(expected), Some(currency)) = (account.currency, source.currency) {
+            if expected != currency {
+                return Err(TransactionError::CurrencyMismatch(into, expected, currency).into());
+            }
+        }
+        account.merge(&source);
+        store.store_account(account)?;
+
+        let transactions = store.get_client_transactions(&from);
+        for transaction in &transactions {
+            store.reassign_transaction(transaction.tx_id, into)?;
+        }
+        store.remove_account(&from)?;
+        if self.options.history {
+            store.append_log(LogEntry::Merge { from, into })?;
+        }
+
+        let mut dispute_notes = self.dispute_notes.lock().unwrap();
+        if let Some(notes) = dispute_notes.remove(&from) {
+            let merged = notes.into_iter().map(|note| RejectedDispute {
+                client_id: into,
+                ..note
+            });
+            dispute_notes.entry(into).or_default().extend(merged);
+        }
+        drop(dispute_notes);
+
+        if let Some(journal) = &self.journal {
+            journal.lock().unwrap().extend(JournalEntry::merge(
+                from,
+                into,
+                source.available,
+                source.held,
+            ));
+        }
+
+        Ok(transactions.len())
+    }
+
+    /// Unlock the account of the given client after a manual review of the
+    /// chargeback that locked it. This processes an unlock order with the
+    /// given transaction identifier, recorded in the transaction history of
+    /// the client. Fails if the account does not exist or is not locked.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _tx = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+    /// let _tx = manager.process_order(order(1, TransactionKind::Dispute(1))).unwrap();
+    /// let _tx = manager.process_order(order(1, TransactionKind::ChargeBack(1))).unwrap();
+    /// assert!(manager.get_account(1).unwrap().locked);
+    ///
+    /// let unlock = manager.unlock_account(1, 2).unwrap();
+    /// assert_eq!(unlock.kind, TransactionKind::Unlock);
+    /// assert!(!manager.get_account(1).unwrap().locked);
+    /// assert!(manager.get_transaction(2).is_some());
+    /// assert!(manager.unlock_account(1, 3).is_err());
+    /// ```
+    pub fn unlock_account(&self, client_id: ClientId, tx_id: TxId) -> Result<Transaction> {
+        self.process_order(TransactionOrder {
+            tx_id,
+            client_id,
+            kind: TransactionKind::Unlock,
+            timestamp: None,
+            currency: None,
+        })
+    }
+
+    /// Get the clients suspended for review because of repeated rejected
+    /// orders, see [AccountManagerOptions::suspend_after].
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     suspend_after: Some(2),
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// for tx_id in 1..=2 {
+    ///     let order = TransactionOrder {
+    ///         tx_id,
+    ///         client_id: 1,
+    ///         kind: TransactionKind::Withdrawal(Decimal::ONE),
+    ///         timestamp: None,
+    ///         currency: None,
+    ///     };
+    ///     assert!(manager.process_order(order).is_err());
+    /// }
+    ///
+    /// assert_eq!(manager.get_suspended_clients(), vec![1]);
+    /// ```
+    pub fn get_suspended_clients(&self) -> Vec<ClientId> {
+        self.rejections
+            .lock()
+            .unwrap()
+            .suspended
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Get the clients that exceeded the maximum number of transactions of the
+    /// run, or reached it and had orders rejected, see
+    /// [AccountManagerOptions::max_transactions].
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions, ExcessTransactions};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     max_transactions: Some(2),
+    ///     excess_transactions: ExcessTransactions::Reject,
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// for tx_id in 1..=3 {
+    ///     let order = TransactionOrder {
+    ///         tx_id,
+    ///         client_id: 1,
+    ///         kind: TransactionKind::Deposit(Decimal::ONE),
+    ///         timestamp: None,
+    ///         currency: None,
+    ///     };
+    ///     assert_eq!(manager.process_order(order).is_ok(), tx_id <= 2);
+    /// }
+    ///
+    /// assert_eq!(manager.get_excessive_clients(), vec![1]);
+    /// assert_eq!(manager.get_account(1).unwrap().available, Decimal::TWO);
+    /// ```
+    pub fn get_excessive_clients(&self) -> Vec<ClientId> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .exceeded
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Get the account of the given client as it was once the transactions
+    /// with the given identifier were applied, by replaying the recorded
+    /// history from the start. The dispute, resolve and chargeback orders
+    /// being identified by the disputed transaction, the balance as of a
+    /// transaction includes the settlement of its dispute if any; the balance
+    /// before a chargeback is the one as of the transaction applied before.
+    ///
+    /// Returns `None` if the client had no account yet. Fails if the history
+    /// is not recorded, see [AccountManagerOptions::history], or if no
+    /// transaction with this identifier was applied. The merges of clients
+    /// and the disputes preloaded from a case file are replayed as well.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     history: true,
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _tx = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+    /// let _tx = manager.process_order(order(2, TransactionKind::Withdrawal(Decimal::ONE))).unwrap();
+    /// let _tx = manager.process_order(order(1, TransactionKind::Dispute(1))).unwrap();
+    /// let _tx = manager.process_order(order(1, TransactionKind::ChargeBack(1))).unwrap();
+    ///
+    /// let before = manager.balance_at(1, 2).unwrap().unwrap();
+    /// assert_eq!(before.available, Decimal::from(9));
+    /// assert!(!before.locked);
+    /// let after = manager.balance_at(1, 1).unwrap().unwrap();
+    /// assert_eq!(after.available, Decimal::from(-1));
+    /// assert!(after.locked);
+    /// assert!(manager.balance_at(1, 3).is_err());
+    /// ```
+    pub fn balance_at(&self, client_id: ClientId, tx_id: TxId) -> Result<Option<Account>> {
+        let history = self.get_history()?;
+        let length = history
+            .iter()
+            .rposition(|entry| entry.transaction().is_some_and(|t| t.tx_id == tx_id))
+            .map(|position| position + 1)
+            .ok_or_else(|| anyhow!("No transaction id='{tx_id}' in the history."))?;
+
+        Ok(self.replay(&history[..length])?.get_account(client_id))
+    }
+
+    /// Get the account of the given client as it was at the given time, by
+    /// replaying the recorded history up to the last transaction timestamped
+    /// no later, see [AccountManager::balance_at].
+    pub fn balance_at_time(
+        &self,
+        client_id: ClientId,
+        timestamp: Timestamp,
+    ) -> Result<Option<Account>> {
+        let history = self.get_history()?;
+        let length = history
+            .iter()
+            .rposition(|entry| {
+                entry
+                    .transaction()
+                    .and_then(|transaction| transaction.timestamp)
+                    .is_some_and(|t| t <= timestamp)
+            })
+            .map_or(0, |position| position + 1);
+
+        Ok(self.replay(&history[..length])?.get_account(client_id))
+    }
+
+    /// Rebuild every account by replaying the recorded history from the
+    /// start, and compare them to the stored accounts: any difference is a
+    /// drift of the stored balances from the transactions they result from.
+    /// The orders are held while the accounts are rebuilt.
+    ///
+    /// Fails if the history is not recorded, see
+    /// [AccountManagerOptions::history], or if a transaction cannot be
+    /// replayed. As for [AccountManager::balance_at], the merges of clients
+    /// and the disputes preloaded from a case file are replayed in turn with
+    /// the transactions.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, AccountManagerOptions};
+    ///
+    /// let options = AccountManagerOptions {
+    ///     history: true,
+    ///     ..Default::default()
+    /// };
+    /// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _tx = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+    /// let _tx = manager.process_order(order(2, TransactionKind::Withdrawal(Decimal::ONE))).unwrap();
+    /// let _tx = manager.process_order(order(1, TransactionKind::Dispute(1))).unwrap();
+    ///
+    /// let report = manager.rebuild().unwrap();
+    /// assert!(report.is_consistent());
+    /// assert_eq!(report.accounts_checked, 1);
+    /// assert_eq!(report.transactions_replayed, 3);
+    /// ```
+    pub fn rebuild(&self) -> Result<RebuildReport> {
+        let _guards = self.locks.lock_all();
+        let history = self.get_history()?;
+        let rebuilt = self.replay(&history)?.get_accounts();
+        let (accounts_checked, drifts) = account_drifts(self.get_accounts(), rebuilt);
+
+        Ok(RebuildReport {
+            accounts_checked,
+            transactions_replayed: history.len(),
+            drifts,
+        })
+    }
+
+    /// Get the recorded history from the log of the storage.
+    pub(crate) fn get_history(&self) -> Result<Vec<LogEntry>> {
+        if !self.options.history {
+            bail!("The history of the transactions is not recorded.");
+        }
+
+        self.shared_store()
+            .get_log()
+            .ok_or_else(|| anyhow!("The storage keeps no transaction log."))
+    }
+
+    /// Replay the given log entries on an empty storage with the same
+    /// accounting rules and get the resulting manager.
+    fn replay(&self, history: &[LogEntry]) -> Result<Self> {
+        Self::replay_log(history, &self.options, Err)
+    }
+
+    /// Replay the given log entries on an empty storage with the accounting
+    /// rules of the given options and get the resulting manager. This is the
+    /// only replay of the history, the one of the rebuilding and of the
+    /// balances in the past as the one of
+    /// `recompute_accounts`.
+    ///
+    /// An entry which cannot be replayed is given to `on_error`, which either
+    /// fails the replay by returning the error or skips the entry.
+    pub(crate) fn replay_log(
+        history: &[LogEntry],
+        options: &AccountManagerOptions,
+        mut on_error: impl FnMut(anyhow::Error) -> Result<()>,
+    ) -> Result<Self> {
+        let options = AccountManagerOptions {
+            held_shortfall: options.held_shortfall,
+            credit_limits: options.credit_limits.clone(),
+            dispute_policy: options.dispute_policy.clone(),
+            adjustments: options.adjustments,
+            ..Default::default()
+        };
+        let replayed = Self::with_options(InMemoryAccountStorage::default(), options);
+        for entry in history {
+            let replay = match entry {
+                LogEntry::Transaction(transaction) => {
+                    let order = TransactionOrder {
+                        tx_id: transaction.tx_id,
+                        client_id: transaction.client_id,
+                        kind: transaction.kind.clone(),
+                        timestamp: transaction.timestamp,
+                        currency: transaction.currency,
+                    };
+                    replayed.process_order(order).map(drop).with_context(|| {
+                        format!("Cannot replay the transaction id='{}'.", transaction.tx_id)
+                    })
+                }
+                LogEntry::Merge { from, into } => replayed
+                    .merge_clients(*from, *into)
+                    .map(drop)
+                    .with_context(|| {
+                        format!(
+                            "Cannot replay the merge of the client id='{from}' into id='{into}'."
+                        )
+                    }),
+                LogEntry::PreloadedDispute(tx_id) => {
+                    replayed.preload_disputes([*tx_id]);
+                    Ok(())
+                }
+            };
+            if let Err(error) = replay {
+                on_error(error)?;
+            }
+        }
+
+        Ok(replayed)
+    }
+
+    /// Commit the changes of the storage session, see
+    /// [OverlayStorage](crate::adapter::OverlayStorage). The state of the
+    /// manager itself, like the journal, is not affected.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::{InMemoryAccountStorage, OverlayStorage};
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(OverlayStorage::new(InMemoryAccountStorage::default()));
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// let _transaction = manager.process_order(order).unwrap();
+    /// manager.discard();
+    ///
+    /// assert!(manager.get_account(1).is_none());
+    /// ```
+    pub fn commit(&self) -> Result<()> {
+        let _guards = self.locks.lock_all();
+
+        self.shared_store().commit()
+    }
+
+    /// Discard the changes of the storage session not committed yet, see
+    /// [AccountManager::commit].
+    pub fn discard(&self) {
+        let _guards = self.locks.lock_all();
+
+        self.shared_store().discard();
+    }
+
+    /// Get the statistics of the underlying storage.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(Decimal::ONE),
+    ///     timestamp: None,
+    ///     currency: None,
+    /// };
+    /// let _transaction = manager.process_order(order).unwrap();
+    /// let stats = manager.stats();
+    ///
+    /// assert_eq!(stats.accounts, 1);
+    /// assert_eq!(stats.transactions, 1);
+    /// assert_eq!(stats.open_disputes, 0);
+    /// ```
+    pub fn stats(&self) -> StorageStats {
+        self.shared_store().stats()
+    }
+
+    /// Subscribe to the [DomainEvent]s of the transactions applied from now
+    /// on, see [EventBus]. Every subscriber receives all the events, in the
+    /// order the transactions are applied, until the manager is dropped.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{DomainEvent, TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::AccountManager;
+    ///
+    /// let manager = AccountManager::new(InMemoryAccountStorage::default());
+    /// let events = manager.subscribe();
+    /// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+    /// let _results = manager.process_orders(vec![
+    ///     order(1, TransactionKind::Deposit(Decimal::TEN)),
+    ///     order(2, TransactionKind::Dispute(1)),
+    ///     order(3, TransactionKind::ChargeBack(1)),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     events.try_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         DomainEvent::DisputeOpened { client_id: 1, tx_id: 1, amount: Decimal::TEN },
+    ///         DomainEvent::ChargebackApplied { client_id: 1, tx_id: 1, amount: Decimal::TEN },
+    ///         DomainEvent::AccountLocked { client_id: 1 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn subscribe(&self) -> Receiver<DomainEvent> {
+        self.events.subscribe()
+    }
+
+    /// Check the given order against the storage state and return the order to
+    /// apply. Deposits on locked accounts are redirected to the suspense account
+    /// if any.
+    fn route_order(
+        &self,
+        store: &dyn AccountStorage,
+        order: TransactionOrder,
+    ) -> std::result::Result<TransactionOrder, TransactionError> {
+        if self.is_suspended(order.client_id) {
+            return Err(TransactionError::ClientSuspended(order.client_id));
+        }
+        self.check_transaction_count(order.client_id)?;
+        self.check_order_limits(&order)?;
+
+        match (Self::check_order(store, &order, &self.options), &order.kind) {
+            (
+                Err(TransactionError::Account(AccountError::AccountLocked)),
+                TransactionKind::Deposit(_),
+            ) if self.options.suspense_account.is_some() => {
+                let suspense_order = TransactionOrder {
+                    client_id: self.options.suspense_account.unwrap(),
+                    ..order
+                };
+                Self::check_order(store, &suspense_order, &self.options)?;
+                log::info!(
+                    "Deposit tx={} on locked account {} credited to the suspense account {}.",
+                    suspense_order.tx_id,
+                    order.client_id,
+                    suspense_order.client_id
+                );
+
+                Ok(suspense_order)
+            }
+            (result, _) => {
+                result?;
+                // only checked for the orders that would be applied so the
+                // repeats rejected by the state of the dispute keep their error
+                if let Some(key @ (kind, _, tx_id)) =
+                    Self::dispute_order_key(order.tx_id, &order.kind)
+                {
+                    if self.dispute_orders.lock().unwrap().contains(&key) {
+                        return Err(TransactionError::RepeatedOrder(kind, tx_id));
+                    }
+                }
+
+                Ok(order)
+            }
+        }
+    }
+
+    /// Check the given order against the caps of the [OrderLimits].
+    fn check_order_limits(
+        &self,
+        order: &TransactionOrder,
+    ) -> std::result::Result<(), TransactionError> {
+        let limits = &self.options.order_limits;
+        if limits.is_empty() {
+            return Ok(());
+        }
+        let daily_total = self
+            .daily_totals
+            .lock()
+            .unwrap()
+            .get(&(order.client_id, OrderLimits::day_of(order.timestamp)))
+            .copied()
+            .unwrap_or_default();
+
+        limits.check(order, daily_total)
+    }
+
+    /// Score the given order with the fraud detection, if enabled, and get
+    /// the flags it raises, blocking it when the rules block the flagged
+    /// orders. Nothing is recorded, see [AccountManager::raise_flags].
+    fn screen_order(&self, order: &TransactionOrder) -> Vec<FraudFlag> {
+        let Some(detector) = &self.fraud_detector else {
+            return Vec::new();
+        };
+
+        detector
+            .score(order)
+            .into_iter()
+            .map(|rule| FraudFlag {
+                client_id: order.client_id,
+                tx_id: order.tx_id,
+                rule,
+                blocked: detector.blocks(),
+            })
+            .collect()
+    }
+
+    /// Note the flags of an order applied or blocked with the fraud detection
+    /// and publish them.
+    fn raise_flags(&self, flags: Vec<FraudFlag>) {
+        let Some(detector) = &self.fraud_detector else {
+            return;
+        };
+        for flag in flags {
+            log::warn!(
+                "Order tx={} of client {} flagged by the fraud rule {:?}.",
+                flag.tx_id,
+                flag.client_id,
+                flag.rule
+            );
+            detector.flag(flag);
+            self.events.publish(DomainEvent::FraudFlagged(flag));
+        }
+    }
+
+    /// Get the key of a dispute, resolve or chargeback order: its kind, its
+    /// own transaction identifier and the disputed transaction, so only the
+    /// exact repeats are skipped. The other orders create a transaction of
+    /// their own and have no key.
+    fn dispute_order_key(
+        order_tx_id: TxId,
+        kind: &TransactionKind,
+    ) -> Option<(&'static str, TxId, TxId)> {
+        match *kind {
+            TransactionKind::Dispute(tx_id) => Some(("dispute", order_tx_id, tx_id)),
+            TransactionKind::Resolve(tx_id) => Some(("resolve", order_tx_id, tx_id)),
+            TransactionKind::ChargeBack(tx_id) => Some(("chargeback", order_tx_id, tx_id)),
+            _ => None,
+        }
+    }
+
+    /// Tell if the given client is suspended for review.
+    fn is_suspended(&self, client_id: ClientId) -> bool {
+        self.rejections
+            .lock()
+            .unwrap()
+            .suspended
+            .contains(&client_id)
+    }
+
+    /// Count the consecutive rejected orders of the given client and suspend
+    /// it once the `suspend_after` limit is reached.
+    fn track_rejection(&self, client_id: ClientId, rejected: bool) {
+        let Some(limit) = self.options.suspend_after else {
+            return;
+        };
+        let mut rejections = self.rejections.lock().unwrap();
+
+        if !rejected {
+            rejections.consecutive.remove(&client_id);
+            return;
+        }
+        if rejections.suspended.contains(&client_id) {
+            return;
+        }
+        let count = rejections.consecutive.entry(client_id).or_default();
+        *count += 1;
+
+        if *count >= limit {
+            rejections.consecutive.remove(&client_id);
+            rejections.suspended.insert(client_id);
+            self.events
+                .publish(DomainEvent::ClientSuspended { client_id });
+            log::warn!(
+                "Client {} suspended for review after {} consecutive rejected orders.",
+                client_id,
+                limit
+            );
+        }
+    }
+
+    /// Reject the orders of a client having reached the maximum number of
+    /// transactions when they must be rejected.
+    fn check_transaction_count(
+        &self,
+        client_id: ClientId,
+    ) -> std::result::Result<(), TransactionError> {
+        let Some(limit) = self.options.max_transactions else {
+            return Ok(());
+        };
+        if self.options.excess_transactions != ExcessTransactions::Reject {
+            return Ok(());
+        }
+        let transactions = self.transactions.lock().unwrap();
+
+        if transactions.counts.get(&client_id).copied().unwrap_or(0) < limit {
+            return Ok(());
+        }
+
+        Err(TransactionError::TooManyTransactions(client_id, limit))
+    }
+
+    /// Report a client whose order was rejected because it reached the
+    /// maximum number of transactions.
+    fn flag_rejected_transaction(&self, error: &anyhow::Error) {
+        if let Some(TransactionError::TooManyTransactions(client_id, limit)) = error.downcast_ref()
+        {
+            if self
+                .transactions
+                .lock()
+                .unwrap()
+                .exceeded
+                .insert(*client_id)
+            {
+                log::warn!(
+                    "Client {} reached the maximum of {} transactions, its further orders are rejected.",
+                    client_id,
+                    limit
+                );
+            }
+        }
+    }
+
+    /// Note a dispute order of the given client rejected because of the
+    /// disputed transaction on the account it belongs to.
+    fn note_rejected_dispute(
+        &self,
+        store: &dyn AccountStorage,
+        client_id: ClientId,
+        tx_id: TxId,
+        error: &anyhow::Error,
+    ) {
+        let reason = match error.downcast_ref() {
+            Some(TransactionError::RelatedTransactionNotFound(_)) => DisputeRejection::NotFound,
+            Some(TransactionError::RelatedTransactionNotDisputable(_)) => {
+                DisputeRejection::NotDisputable
+            }
+            Some(TransactionError::AlreadyDisputedTransaction(_)) => {
+                DisputeRejection::AlreadyDisputed
+            }
+            Some(TransactionError::DisputeNotAllowed(..)) => DisputeRejection::NotAllowed,
+            _ => return,
+        };
+        let client_id = store
+            .get_transaction(&tx_id)
+            .map_or(client_id, |transaction| transaction.client_id);
+
+        self.dispute_notes
+            .lock()
+            .unwrap()
+            .entry(client_id)
+            .or_default()
+            .push(RejectedDispute {
+                client_id,
+                tx_id,
+                reason,
+            });
+    }
+
+    /// Count a transaction applied for the given client and flag the client
+    /// once it exceeds the maximum number of transactions.
+    fn count_transaction(&self, client_id: ClientId) {
+        let Some(limit) = self.options.max_transactions else {
+            return;
+        };
+        let mut transactions = self.transactions.lock().unwrap();
+        let count = transactions.counts.entry(client_id).or_default();
+        *count += 1;
+
+        if *count > limit && transactions.exceeded.insert(client_id) {
+            log::warn!(
+                "Client {} exceeded the maximum of {} transactions.",
+                client_id,
+                limit
+            );
+        }
+    }
+
+    /// Check the given order against the storage state.
+    fn check_order(
+        store: &dyn AccountStorage,
+        order: &TransactionOrder,
+        options: &AccountManagerOptions,
+    ) -> std::result::Result<(), TransactionError> {
+        let held_shortfall = options.held_shortfall;
+        let policy = options.dispute_policy.as_ref();
+        match order.kind {
+            TransactionKind::Deposit(amount) => {
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                let account = Self::get_or_create_account(store, order.client_id);
+                Self::check_account_open(&account)?;
+                account.balance().deposit(amount)?;
+            }
+            TransactionKind::Withdrawal(amount) => {
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                let account = Self::get_or_create_account(store, order.client_id);
+                Self::check_account_open(&account)?;
+                account
+                    .balance()
+                    .withdraw_on_credit(amount, options.credit_limits.limit_of(order.client_id))?;
+            }
+            TransactionKind::Dispute(tx_id) => {
+                DisputeState::from(store.is_disputed(&tx_id)).dispute(tx_id)?;
+                Self::check_dispute_policy(store, policy, Some(order.client_id), tx_id)?;
+                let (account, funds) = Self::get_disputed_funds(store, tx_id)?;
+                Self::check_account_open(&account)?;
+                funds.dispute(&mut account.balance(), policy)?;
+            }
+            TransactionKind::Resolve(tx_id) => {
+                DisputeState::from(store.is_disputed(&tx_id)).resolve(tx_id)?;
+                Self::check_dispute_policy(store, policy, Some(order.client_id), tx_id)?;
+                let (account, funds) = Self::get_settled_funds(store, tx_id, held_shortfall)?;
+                Self::check_account_open(&account)?;
+                funds.resolve(&mut account.balance())?;
+            }
+            TransactionKind::ChargeBack(tx_id) => {
+                DisputeState::from(store.is_disputed(&tx_id)).chargeback(tx_id)?;
+                Self::check_dispute_policy(store, policy, Some(order.client_id), tx_id)?;
+                let (account, funds) = Self::get_settled_funds(store, tx_id, held_shortfall)?;
+                Self::check_account_open(&account)?;
+                funds.chargeback(&mut account.balance())?;
+            }
+            TransactionKind::Unlock => {
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                if !store
+                    .get_account(&order.client_id)
+                    .is_some_and(|account| account.locked)
+                {
+                    return Err(TransactionError::AccountNotLocked(order.client_id));
+                }
+                Self::check_account_open(&Self::get_or_create_account(store, order.client_id))?;
+            }
+            TransactionKind::Open => {
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                if store
+                    .get_account(&order.client_id)
+                    .is_some_and(|account| account.state == AccountState::Open)
+                {
+                    return Err(TransactionError::AccountAlreadyOpen(order.client_id));
+                }
+            }
+            TransactionKind::Close => {
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                let Some(account) = store
+                    .get_account(&order.client_id)
+                    .filter(|account| account.state == AccountState::Open)
+                else {
+                    return Err(TransactionError::AccountNotOpen(order.client_id));
+                };
+                if !account.is_empty() {
+                    return Err(TransactionError::AccountNotEmpty(order.client_id));
+                }
+            }
+            TransactionKind::Adjustment(amount) => {
+                if !options.adjustments {
+                    return Err(TransactionError::AdjustmentNotAllowed(
+                        order.client_id,
+                        order.tx_id,
+                    ));
+                }
+                Self::check_unique_tx_id(store, order.tx_id)?;
+                let account = Self::get_or_create_account(store, order.client_id);
+                Self::check_account_open(&account)?;
+                account.balance().adjust(amount)?;
+            }
+        }
+
+        Self::check_currency(store, order)
+    }
+
+    /// Check the given account is not closed.
+    fn check_account_open(account: &Account) -> std::result::Result<(), TransactionError> {
+        match account.state {
+            AccountState::Open => Ok(()),
+            AccountState::Closed => Err(TransactionError::AccountNotOpen(account.client_id)),
+        }
+    }
+
+    /// Check the currency of the order, if any, is the one of the account.
+    /// Accounts without currency take the one of their first transaction.
+    fn check_currency(
+        store: &dyn AccountStorage,
+        order: &TransactionOrder,
+    ) -> std::result::Result<(), TransactionError> {
+        let Some(currency) = order.currency else {
+            return Ok(());
+        };
+
+        match store
+            .get_account(&order.client_id)
+            .and_then(|account| account.currency)
+        {
+            Some(expected) if expected != currency => Err(TransactionError::CurrencyMismatch(
+                order.client_id,
+                expected,
+                currency,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the given transaction can be disputed under the dispute policy,
+    /// by the given client if any.
+    fn check_dispute_policy(
+        store: &dyn AccountStorage,
+        policy: &dyn DisputePolicy,
+        client_id: Option<ClientId>,
+        tx_id: TxId,
+    ) -> std::result::Result<(), TransactionError> {
+        let transaction = store
+            .get_transaction(&tx_id)
+            .ok_or(TransactionError::RelatedTransactionNotFound(tx_id))?;
+        if !policy.is_disputable(&transaction) {
+            return Err(TransactionError::RelatedTransactionNotDisputable(tx_id));
+        }
+        match client_id {
+            Some(client_id) if !policy.may_dispute(client_id, &transaction) => {
+                Err(TransactionError::DisputeNotAllowed(client_id, tx_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the transaction identifier is not already in use.
+    fn check_unique_tx_id(
+        store: &dyn AccountStorage,
+        tx_id: TxId,
+    ) -> std::result::Result<(), TransactionError> {
+        if store.get_transaction(&tx_id).is_some() {
+            return Err(TransactionError::DuplicateTransactionId(tx_id));
+        }
+
+        Ok(())
+    }
+
+    /// Get the account concerned by the given order: the one of the disputed
+    /// transaction for the dispute orders, the one of the client otherwise.
+    fn audited_account(store: &dyn AccountStorage, order: &TransactionOrder) -> Account {
+        let client_id = match order.kind {
+            TransactionKind::Dispute(tx_id)
+            | TransactionKind::Resolve(tx_id)
+            | TransactionKind::ChargeBack(tx_id) => store
+                .get_transaction(&tx_id)
+                .map_or(order.client_id, |transaction| transaction.client_id),
+            _ => order.client_id,
+        };
+
+        Self::get_or_create_account(store, client_id)
+    }
+
+    /// Get the account of the given client, a new account if it does not exist.
+    fn get_or_create_account(store: &dyn AccountStorage, client_id: ClientId) -> Account {
+        store
+            .get_account(&client_id)
+            .unwrap_or(Account::new(client_id))
+    }
+
+    /// Get the funds of the deposit or withdrawal with the given identifier
+    /// along with the account it was made on.
+    fn get_disputed_funds(
+        store: &dyn AccountStorage,
+        tx_id: TxId,
+    ) -> std::result::Result<(Account, DisputedFunds), TransactionError> {
+        let related_transaction = store
+            .get_transaction(&tx_id)
+            .ok_or(TransactionError::RelatedTransactionNotFound(tx_id))?;
+        let funds = DisputedFunds::of(&related_transaction)
+            .ok_or(TransactionError::RelatedTransactionNotDisputable(tx_id))?;
+        let account = store.get_account(&related_transaction.client_id).unwrap(); // We know the account exists because the transaction exists.
+
+        Ok((account, funds))
+    }
+
+    /// Get the funds of the disputed transaction with the given identifier
+    /// along with the account it was made on, their amount being the one a
+    /// resolution or a chargeback settles according to the given shortfall
+    /// handling.
+    fn get_settled_funds(
+        store: &dyn AccountStorage,
+        tx_id: TxId,
+        held_shortfall: HeldShortfall,
+    ) -> std::result::Result<(Account, DisputedFunds), TransactionError> {
+        let (account, funds) = Self::get_disputed_funds(store, tx_id)?;
+        let amount = held_shortfall.settle(&account, funds.amount(), || {
+            store
+                .get_disputed_transactions()
+                .iter()
+                .filter(|transaction| transaction.client_id == account.client_id)
+                .filter_map(DisputedFunds::of)
+                .map(DisputedFunds::amount)
+                .sum()
+        });
+
+        Ok((account, funds.with_amount(amount)))
+    }
+
+    /// Create the journal entry of a checked transaction, before it is
+    /// applied. The unlocks, openings and closings move no funds and have
+    /// none.
+    fn journal_entry(
+        store: &dyn AccountStorage,
+        transaction: &Transaction,
+        held_shortfall: HeldShortfall,
+    ) -> Result<Option<JournalEntry>> {
+        let tx_id = transaction.tx_id;
+        let client_id = transaction.client_id;
+        let entry = match transaction.kind {
+            TransactionKind::Deposit(amount) => JournalEntry::deposit(tx_id, client_id, amount),
+            TransactionKind::Withdrawal(amount) => {
+                JournalEntry::withdrawal(tx_id, client_id, amount)
+            }
+            TransactionKind::Adjustment(amount) => {
+                JournalEntry::adjustment(tx_id, client_id, amount)
+            }
+            TransactionKind::Dispute(related_tx_id) => {
+                match Self::get_disputed_funds(store, related_tx_id)? {
+                    (account, DisputedFunds::Deposit(amount)) => {
+                        JournalEntry::dispute(tx_id, account.client_id, amount)
+                    }
+                    (account, DisputedFunds::Withdrawal(amount)) => {
+                        JournalEntry::withdrawal_dispute(tx_id, account.client_id, amount)
+                    }
+                }
+            }
+            TransactionKind::Resolve(related_tx_id) => {
+                match Self::get_settled_funds(store, related_tx_id, held_shortfall)? {
+                    (account, DisputedFunds::Deposit(amount)) => {
+                        JournalEntry::resolve(tx_id, account.client_id, amount)
+                    }
+                    (account, DisputedFunds::Withdrawal(amount)) => {
+                        JournalEntry::withdrawal_resolve(tx_id, account.client_id, amount)
+                    }
+                }
+            }
+            TransactionKind::ChargeBack(related_tx_id) => {
+                match Self::get_settled_funds(store, related_tx_id, held_shortfall)? {
+                    (account, DisputedFunds::Deposit(amount)) => {
+                        JournalEntry::chargeback(tx_id, account.client_id, amount)
+                    }
+                    (account, DisputedFunds::Withdrawal(amount)) => {
+                        JournalEntry::withdrawal_chargeback(tx_id, account.client_id, amount)
+                    }
+                }
+            }
+            // no funds move
+            TransactionKind::Unlock | TransactionKind::Open | TransactionKind::Close => {
+                return Ok(None)
+            }
+        };
+
+        Ok(Some(entry))
+    }
+
+    /// Dispute a transaction loaded from the case file. It is recorded in the
+    /// journal as a dispute of the transaction itself.
+    fn preload_dispute(&self, store: &mut dyn AccountStorage, tx_id: TxId) {
+        let policy = self.options.dispute_policy.as_ref();
+        let result = Self::check_dispute_policy(store, policy, None, tx_id)
+            .and_then(|_| Self::get_disputed_funds(store, tx_id))
+            .map_err(anyhow::Error::from)
+            .and_then(|(mut account, funds)| {
+                if store.is_disputed(&tx_id) {
+                    return Err(TransactionError::AlreadyDisputedTransaction(tx_id).into());
+                }
+                account.apply(|balance| funds.dispute(balance, policy))?;
+                let client_id = account.client_id;
+                store.store_account(account)?;
+                store.set_disputed(tx_id, true)?;
+                if self.options.history {
+                    store.append_log(LogEntry::PreloadedDispute(tx_id))?;
+                }
+                let amount = funds.amount();
+                self.events.publish(DomainEvent::DisputeOpened {
+                    client_id,
+                    tx_id,
+                    amount,
+                });
+
+                Ok(match funds {
+                    DisputedFunds::Deposit(_) => JournalEntry::dispute(tx_id, client_id, amount),
+                    DisputedFunds::Withdrawal(_) => {
+                        JournalEntry::withdrawal_dispute(tx_id, client_id, amount)
+                    }
+                })
+            });
+
+        match result {
+            Ok(entry) => {
+                if let Some(journal) = &self.journal {
+                    journal.lock().unwrap().push(entry);
+                }
+            }
+            Err(error) => log::warn!("Cannot preload the dispute of tx={}: {}", tx_id, error),
+        }
+    }
+
+    /// Apply a checked deposit order.
+    fn apply_deposit(
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        amount: Decimal,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.deposit(amount)?;
+        account.currency = account.currency.or(transaction.currency);
+        store.store_account(account)?;
+
+        store.store_transaction(transaction)
+    }
+
+    /// Apply a checked withdrawal order, within the given credit limit.
+    fn apply_withdrawal(
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        amount: Decimal,
+        credit_limit: Decimal,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.apply(|balance| balance.withdraw_on_credit(amount, credit_limit))?;
+        account.currency = account.currency.or(transaction.currency);
+        store.store_account(account)?;
+
+        store.store_transaction(transaction)
+    }
+
+    /// Apply a checked dispute order.
+    fn apply_dispute(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        related_transaction_id: TxId,
+    ) -> Result<Transaction> {
+        let (mut account, funds) = Self::get_disputed_funds(store, related_transaction_id)?;
+        let policy = self.options.dispute_policy.as_ref();
+        account.apply(|balance| funds.dispute(balance, policy))?;
+        let (client_id, amount) = (account.client_id, funds.amount());
+        store.store_account(account)?;
+        store.set_disputed(related_transaction_id, true)?;
+        self.events.publish(DomainEvent::DisputeOpened {
+            client_id,
+            tx_id: related_transaction_id,
+            amount,
+        });
+
+        Ok(transaction)
+    }
+
+    /// Apply a checked resolve order.
+    fn apply_resolve(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        related_transaction_id: TxId,
+        held_shortfall: HeldShortfall,
+    ) -> Result<Transaction> {
+        let (mut account, funds) =
+            Self::get_settled_funds(store, related_transaction_id, held_shortfall)?;
+        account.apply(|balance| funds.resolve(balance))?;
+        let (client_id, amount) = (account.client_id, funds.amount());
+        store.store_account(account)?;
+        store.set_disputed(related_transaction_id, false)?;
+        self.events.publish(DomainEvent::DisputeResolved {
+            client_id,
+            tx_id: related_transaction_id,
+            amount,
+        });
+
+        Ok(transaction)
+    }
+
+    /// Apply a checked chargeback order.
+    fn apply_chargeback(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        related_transaction_id: TxId,
+        held_shortfall: HeldShortfall,
+    ) -> Result<Transaction> {
+        let (mut account, funds) =
+            Self::get_settled_funds(store, related_transaction_id, held_shortfall)?;
+        let was_locked = account.locked;
+        account.apply(|balance| funds.chargeback(balance))?;
+        let (client_id, locked, amount) = (account.client_id, account.locked, funds.amount());
+        store.store_account(account)?;
+        store.set_disputed(related_transaction_id, false)?;
+        self.events.publish(DomainEvent::ChargebackApplied {
+            client_id,
+            tx_id: related_transaction_id,
+            amount,
+        });
+        if locked && !was_locked {
+            self.events
+                .publish(DomainEvent::AccountLocked { client_id });
+        }
+
+        Ok(transaction)
+    }
+
+    /// Apply a checked unlock order. It is stored with the transactions of the
+    /// client so the unlock stays in its history.
+    fn apply_unlock(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.unlock();
+        let client_id = account.client_id;
+        store.store_account(account)?;
+        self.events
+            .publish(DomainEvent::AccountUnlocked { client_id });
+
+        store.store_transaction(transaction)
+    }
+
+    /// Apply a checked open order.
+    fn apply_open(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.state = AccountState::Open;
+        account.currency = account.currency.or(transaction.currency);
+        let client_id = account.client_id;
+        store.store_account(account)?;
+        self.events
+            .publish(DomainEvent::AccountOpened { client_id });
+
+        store.store_transaction(transaction)
+    }
+
+    /// Apply a checked close order.
+    fn apply_close(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.state = AccountState::Closed;
+        let client_id = account.client_id;
+        store.store_account(account)?;
+        self.events
+            .publish(DomainEvent::AccountClosed { client_id });
+
+        store.store_transaction(transaction)
+    }
+
+    /// Apply a checked adjustment order. Locked accounts are adjusted too.
+    fn apply_adjustment(
+        &self,
+        store: &mut dyn AccountStorage,
+        transaction: Transaction,
+        amount: Decimal,
+    ) -> Result<Transaction> {
+        let mut account = Self::get_or_create_account(store, transaction.client_id);
+        account.apply(|balance| balance.adjust(amount))?;
+        account.currency = account.currency.or(transaction.currency);
+        let client_id = account.client_id;
+        store.store_account(account)?;
+        self.events.publish(DomainEvent::AccountAdjusted {
+            client_id,
+            tx_id: transaction.tx_id,
+            amount,
+        });
+
+        store.store_transaction(transaction)
+    }
+}
+
+/// The storage of a manager read through by its shadows, see
+/// [AccountManager::shadow]. It cannot be written.
+struct ShadowedStore(Arc<AccountManager>);
+
+impl ShadowedStore {
+    /// Fail the writes of the shadows.
+    fn read_only<T>(&self) -> Result<T> {
+        bail!("The storage of a shadowed account manager cannot be written.")
+    }
+}
+
+impl AccountStorage for ShadowedStore {
+    fn get_account(&self, client_id: &ClientId) -> Option<Account> {
+        self.0.shared_store().get_account(client_id)
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        self.0.shared_store().get_accounts()
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        self.0.shared_store().get_accounts_after(after, limit)
+    }
+
+    fn get_transaction(&self, tx_id: &TxId) -> Option<Transaction> {
+        self.0.shared_store().get_transaction(tx_id)
+    }
+
+    fn get_client_transactions(&self, client_id: &ClientId) -> Vec<Transaction> {
+        self.0.shared_store().get_client_transactions(client_id)
+    }
+
+    fn is_disputed(&self, tx_id: &TxId) -> bool {
+        self.0.shared_store().is_disputed(tx_id)
+    }
+
+    fn get_disputed_transactions(&self) -> Vec<Transaction> {
+        self.0.shared_store().get_disputed_transactions()
+    }
+
+    fn stats(&self) -> StorageStats {
+        self.0.shared_store().stats()
+    }
+
+    fn store_account(&mut self, _account: Account) -> Result<Account> {
+        self.read_only()
+    }
+
+    fn store_transaction(&mut self, _transaction: Transaction) -> Result<Transaction> {
+        self.read_only()
+    }
+
+    fn set_disputed(&mut self, _tx_id: TxId, _disputed: bool) -> Result<()> {
+        self.read_only()
+    }
+
+    fn reassign_transaction(&mut self, _tx_id: TxId, _client_id: ClientId) -> Result<()> {
+        self.read_only()
+    }
+
+    fn remove_account(&mut self, _client_id: &ClientId) -> Result<Option<Account>> {
+        self.read_only()
+    }
+
+    fn append_log(&mut self, _entry: LogEntry) -> Result<()> {
+        self.read_only()
+    }
+
+    fn get_log(&self) -> Option<Vec<LogEntry>> {
+        self.0.shared_store().get_log()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use crate::{adapter::InMemoryAccountStorage, model::LedgerAccount};
+
+    use super::*;
+
+    #[test]
+    fn test_duplicate_disputable_transactions() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order.clone()).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Withdrawal(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::DuplicateTransactionId(tx_id)) if tx_id == &1
+        ));
+    }
+
+    #[test]
+    fn test_process_orders() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        let results = manager.process_orders([
+            order(1, TransactionKind::Deposit(Decimal::TEN)),
+            order(1, TransactionKind::Deposit(Decimal::ONE)),
+            order(2, TransactionKind::Dispute(1)),
+            order(3, TransactionKind::Withdrawal(Decimal::ONE)),
+        ]);
+
+        // the orders of the batch see the ones applied before them
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1]
+                .as_ref()
+                .unwrap_err()
+                .downcast_ref::<TransactionError>(),
+            Some(TransactionError::DuplicateTransactionId(1))
+        ));
+        assert!(results[2].is_ok());
+        assert!(matches!(
+            results[3]
+                .as_ref()
+                .unwrap_err()
+                .downcast_ref::<TransactionError>(),
+            Some(TransactionError::Account(_))
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.held, dec!(10));
+        assert!(manager.process_orders(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn concurrent_orders_lock_their_accounts() {
+        process_concurrently(Arc::new(AccountManager::new(
+            InMemoryAccountStorage::default(),
+        )));
+    }
+
+    #[cfg(feature = "concurrent-storage")]
+    #[test]
+    fn concurrent_orders_on_a_concurrent_storage() {
+        process_concurrently(Arc::new(AccountManager::with_concurrent_storage(
+            crate::adapter::ConcurrentAccountStorage::default(),
+            AccountManagerOptions::default(),
+        )));
+    }
+
+    /// Process orders of several clients, disputing the transactions of each
+    /// other, in parallel.
+    fn process_concurrently(manager: Arc<AccountManager>) {
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        let threads: Vec<_> = (1..=8)
+            .map(|client_id: ClientId| {
+                let manager = manager.clone();
+                std::thread::spawn(move || {
+                    let first = client_id * 1000;
+                    for tx_id in first..first + 100 {
+                        manager
+                            .process_order(order(
+                                tx_id,
+                                client_id,
+                                TransactionKind::Deposit(Decimal::ONE),
+                            ))
+                            .unwrap();
+                    }
+                    // every client races for the same transaction identifier
+                    let raced = manager
+                        .process_order(order(1, client_id, TransactionKind::Deposit(Decimal::TEN)))
+                        .is_ok();
+                    // the deposits of the next client, being applied meanwhile
+                    let next = (client_id % 8 + 1) * 1000;
+                    let disputes = (next..next + 50)
+                        .filter(|tx_id| {
+                            manager
+                                .process_order(order(
+                                    *tx_id,
+                                    client_id,
+                                    TransactionKind::Dispute(*tx_id),
+                                ))
+                                .is_ok()
+                        })
+                        .count();
+
+                    (raced, disputes)
+                })
+            })
+            .collect();
+        let results: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        assert_eq!(results.iter().filter(|(raced, _)| *raced).count(), 1);
+        let total: Decimal = manager
+            .get_accounts()
+            .iter()
+            .map(|account| account.total)
+            .sum();
+        assert_eq!(total, dec!(810));
+        let held: Decimal = manager
+            .get_accounts()
+            .iter()
+            .map(|account| account.held)
+            .sum();
+        let disputes: usize = results.iter().map(|(_, disputes)| disputes).sum();
+        assert_eq!(held, Decimal::from(disputes as u32));
+        assert_eq!(manager.stats().open_disputes, disputes);
+    }
+
+    #[test]
+    fn test_deposit() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let transaction = manager.process_order(order).unwrap();
+        assert!(matches!(
+            transaction.kind,
+            TransactionKind::Deposit(amount) if amount == Decimal::TEN
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(10));
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let account = manager.get_account(1).unwrap();
+
+        assert_eq!(account.available, dec!(11));
+    }
+
+    #[test]
+    fn test_withdrawal() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Withdrawal(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        let transaction = manager.process_order(order).unwrap();
+        assert!(matches!(
+            transaction.kind,
+            TransactionKind::Withdrawal(amount) if amount == Decimal::ONE
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(9));
+    }
+
+    #[test]
+    fn test_dispute_ok() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        let transaction = manager.process_order(order).unwrap();
+        assert!(matches!(
+            transaction.kind,
+            TransactionKind::Dispute(related_tx_id) if related_tx_id == 1
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.held, dec!(10));
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_preload_disputes() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        manager.preload_disputes([1, 2]);
+        assert_eq!(manager.get_account(1).unwrap().held, dec!(10));
+        assert_eq!(manager.get_pending_disputes(), vec![2]);
+
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 2,
+            kind: TransactionKind::Deposit(dec!(5)),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let account = manager.get_account(2).unwrap();
+        assert_eq!(account.held, dec!(5));
+        assert_eq!(account.available, dec!(0));
+        assert!(manager.get_pending_disputes().is_empty());
+
+        // the preloaded disputes are resolved as any other
+        let order = TransactionOrder {
+            tx_id: 3,
+            client_id: 2,
+            kind: TransactionKind::ChargeBack(2),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let account = manager.get_account(2).unwrap();
+        assert_eq!(account.total, dec!(0));
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn test_preload_disputes_skips_invalid() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        for order in [
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 2,
+                client_id: 1,
+                kind: TransactionKind::Withdrawal(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            },
+        ] {
+            let _tx = manager.process_order(order).unwrap();
+        }
+        // an unknown transaction cannot be disputed, a deposit only once
+        manager.preload_disputes([3, 1, 1]);
+
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.held, dec!(10));
+        assert_eq!(account.available, dec!(-1));
+    }
+
+    #[test]
+    fn test_currency_mismatch() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let eur = Some("EUR".parse().unwrap());
+        let usd = Some("USD".parse().unwrap());
+        let order = |tx_id, kind, currency| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency,
+        };
+        // the first order giving a currency sets the one of the account
+        let _tx = manager
+            .process_order(order(1, TransactionKind::Deposit(Decimal::TEN), None))
+            .unwrap();
+        let _tx = manager
+            .process_order(order(2, TransactionKind::Deposit(Decimal::TEN), eur))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().currency, eur);
+
+        let error = manager
+            .process_order(order(3, TransactionKind::Withdrawal(Decimal::ONE), usd))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::CurrencyMismatch(1, expected, given))
+                if Some(*expected) == eur && Some(*given) == usd
+        ));
+
+        let _tx = manager
+            .process_order(order(4, TransactionKind::Withdrawal(Decimal::ONE), None))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(19));
+    }
+
+    #[test]
+    fn test_dispute_non_existing_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Dispute(2),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::RelatedTransactionNotFound(tx_id)) if tx_id == &2
+        ));
+    }
+
+    #[test]
+    fn test_dispute_a_withdrawal() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for (tx_id, kind) in [
+            (1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, TransactionKind::Withdrawal(dec!(4))),
+            (3, TransactionKind::Withdrawal(dec!(1))),
+            (2, TransactionKind::Dispute(2)),
+            (3, TransactionKind::Dispute(3)),
+        ] {
+            manager.process_order(order(tx_id, kind)).unwrap();
+        }
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(5));
+        assert_eq!(account.held, dec!(5));
+        assert_eq!(account.total, Decimal::TEN);
+
+        manager
+            .process_order(order(3, TransactionKind::Resolve(3)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(5));
+        assert_eq!(account.held, dec!(4));
+        assert_eq!(account.total, dec!(9));
+
+        manager
+            .process_order(order(2, TransactionKind::ChargeBack(2)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(9));
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.total, dec!(9));
+        assert!(account.locked);
+        assert_eq!(
+            manager.get_journal()[3..],
+            [
+                JournalEntry::withdrawal_dispute(2, 1, dec!(4)),
+                JournalEntry::withdrawal_dispute(3, 1, dec!(1)),
+                JournalEntry::withdrawal_resolve(3, 1, dec!(1)),
+                JournalEntry::withdrawal_chargeback(2, 1, dec!(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dispute_an_already_disputed_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 3,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::AlreadyDisputedTransaction(tx_id)) if tx_id == &1
+        ));
+    }
+
+    #[test]
+    fn resolve_a_disputed_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Resolve(1),
+            timestamp: None,
+            currency: None,
+        };
+        let transaction = manager.process_order(order).unwrap();
+        assert!(matches!(
+            transaction.kind,
+            TransactionKind::Resolve(related_tx_id) if related_tx_id == 1
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(10));
+        assert_eq!(account.held, dec!(0));
+    }
+
+    #[test]
+    fn resolve_a_non_disputed_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Resolve(1),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(tx_id)) if tx_id == &1
+        ));
+    }
+
+    #[test]
+    fn resolve_a_non_existing_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Resolve(2),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(tx_id)) if tx_id == &2
+        ));
+    }
+
+    #[test]
+    fn chargeback_a_disputed_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::ChargeBack(1),
+            timestamp: None,
+            currency: None,
+        };
+        let transaction = manager.process_order(order).unwrap();
+        assert!(matches!(
+            transaction.kind,
+            TransactionKind::ChargeBack(related_tx_id) if related_tx_id == 1
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(0));
+        assert_eq!(account.held, dec!(0));
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn chargeback_a_non_disputed_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::TEN),
+            timestamp: None,
+            currency: None,
+        };
+        let _tx = manager.process_order(order).unwrap();
+        let order = TransactionOrder {
+            tx_id: 1,
+            client_id: 2,
+            kind: TransactionKind::ChargeBack(1),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(tx_id)) if tx_id == &1
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(10));
+        assert_eq!(account.held, dec!(0));
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn chargeback_a_non_existing_transaction() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::ChargeBack(2),
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager.process_order(order).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(tx_id)) if tx_id == &2
+        ));
+    }
+
+    #[test]
+    fn replayed_dispute_orders_are_skipped() {
+        let options = AccountManagerOptions {
+            // the repeats are not counted as rejections
+            suspend_after: Some(2),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |kind| TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        let orders = [TransactionKind::Dispute(1), TransactionKind::Resolve(1)];
+        manager
+            .process_order(order(TransactionKind::Deposit(Decimal::TEN)))
+            .unwrap();
+        for kind in orders.clone() {
+            manager.process_order(order(kind)).unwrap();
+        }
+
+        // the file is processed again, the resolve is rejected as the
+        // transaction is no longer disputed
+        let errors: Vec<_> = orders
+            .into_iter()
+            .map(|kind| manager.process_order(order(kind)).unwrap_err())
+            .collect();
+        assert!(matches!(
+            errors[0].downcast_ref::<TransactionError>(),
+            Some(TransactionError::RepeatedOrder("dispute", 1))
+        ));
+        assert_eq!(TransactionError::kind_of(&errors[0]), "repeated_order");
+        assert!(matches!(
+            errors[1].downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(1))
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(10));
+        assert_eq!(account.held, dec!(0));
+        assert!(manager.get_suspended_clients().is_empty());
+
+        // the chargeback was never applied
+        let error = manager
+            .process_order(order(TransactionKind::ChargeBack(1)))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::NonDisputedTransaction(1))
+        ));
+    }
+
+    #[test]
+    fn new_disputes_after_a_resolve_are_applied() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for order in [
+            order(1, TransactionKind::Deposit(Decimal::TEN)),
+            order(1, TransactionKind::Dispute(1)),
+            order(1, TransactionKind::Resolve(1)),
+        ] {
+            manager.process_order(order).unwrap();
+        }
+
+        // another dispute of the same transaction is not a repeat
+        manager
+            .process_order(order(2, TransactionKind::Dispute(1)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(0));
+        assert_eq!(account.held, dec!(10));
+
+        // the first dispute replayed once the second is resolved
+        manager
+            .process_order(order(2, TransactionKind::Resolve(1)))
+            .unwrap();
+        let error = manager
+            .process_order(order(1, TransactionKind::Dispute(1)))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::RepeatedOrder("dispute", 1))
+        ));
+    }
+
+    #[test]
+    fn validate_order_does_not_mutate_state() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let _tx = manager
+            .process_order(TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+        let dispute = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Dispute(1),
+            timestamp: None,
+            currency: None,
+        };
+        manager.validate_order(&dispute).unwrap();
+        manager.validate_order(&dispute).unwrap();
+        let account = manager.get_account(1).unwrap();
+
+        assert_eq!(account.held, dec!(0));
+        assert_eq!(manager.stats().open_disputes, 0);
+        assert!(matches!(
+            manager.validate_order(&TransactionOrder {
+                tx_id: 3,
+                client_id: 1,
+                kind: TransactionKind::ChargeBack(1),
+                timestamp: None,
+                currency: None,
+            }),
+            Err(TransactionError::NonDisputedTransaction(1))
+        ));
+    }
+
+    #[test]
+    fn validate_order_on_locked_account() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        for order in [
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 2,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Dispute(1),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::ChargeBack(1),
+                timestamp: None,
+                currency: None,
+            },
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let error = manager
+            .validate_order(&TransactionOrder {
+                tx_id: 3,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            TransactionError::Account(AccountError::AccountLocked)
+        ));
+
+        // disputes remain possible on locked accounts
+        manager
+            .validate_order(&TransactionOrder {
+                tx_id: 2,
+                client_id: 1,
+                kind: TransactionKind::Dispute(2),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn fraud_flags_of_applied_and_blocked_orders_only() {
+        let rules = |block| FraudRules {
+            outlier_factor: Some(dec!(2)),
+            block,
+            ..Default::default()
+        };
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for block in [false, true] {
+            let options = AccountManagerOptions {
+                fraud_rules: Some(rules(block)),
+                ..Default::default()
+            };
+            let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+            let events = manager.subscribe();
+            let _tx = manager
+                .process_order(order(1, TransactionKind::Deposit(Decimal::ONE)))
+                .unwrap();
+            let outlier = order(2, TransactionKind::Deposit(Decimal::TEN));
+
+            // the validation screens the order without flagging it
+            let validated = manager.validate_order(&outlier);
+            assert_eq!(
+                validated
+                    .is_err_and(|error| matches!(error, TransactionError::FraudSuspected(1, 2))),
+                block
+            );
+            assert!(manager.get_fraud_flags().is_empty());
+
+            // the rejected orders are not flagged
+            let withdrawal = order(3, TransactionKind::Withdrawal(dec!(20)));
+            assert!(manager.process_order(withdrawal).is_err());
+            assert!(manager.get_fraud_flags().is_empty());
+
+            // the flags are noted once the order is applied or blocked
+            assert_eq!(manager.process_order(outlier).is_err(), block);
+            let flags = manager.get_fraud_flags();
+            assert_eq!(flags.len(), 1);
+            assert_eq!(flags[0].blocked, block);
+            let published: Vec<_> = events
+                .try_iter()
+                .filter_map(|event| match event {
+                    DomainEvent::FraudFlagged(flag) => Some(flag),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(published, flags);
+        }
+    }
+
+    #[test]
+    fn double_entry_journal_is_balanced() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for order in [
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 2,
+                client_id: 1,
+                kind: TransactionKind::Withdrawal(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 2,
+                kind: TransactionKind::Dispute(1),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 2,
+                kind: TransactionKind::ChargeBack(1),
+                timestamp: None,
+                currency: None,
+            },
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        // rejected orders do not produce entries
+        let _error = manager
+            .process_order(TransactionOrder {
+                tx_id: 3,
+                client_id: 1,
+                kind: TransactionKind::Withdrawal(Decimal::ONE_HUNDRED),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap_err();
+        let journal = manager.get_journal();
+
+        assert_eq!(journal.len(), 4);
+        assert_eq!(journal[2].debit, LedgerAccount::ClientAvailable(1));
+        assert_eq!(journal[2].credit, LedgerAccount::ClientHeld(1));
+
+        // the ledger balances replay the client account
+        let balance = |ledger_account: LedgerAccount| -> Decimal {
+            journal
+                .iter()
+                .map(|entry| {
+                    if entry.credit == ledger_account {
+                        entry.amount
+                    } else if entry.debit == ledger_account {
+                        -entry.amount
+                    } else {
+                        Decimal::ZERO
+                    }
+                })
+                .sum()
+        };
+        let account = manager.get_account(1).unwrap();
+
+        assert_eq!(
+            balance(LedgerAccount::ClientAvailable(1)),
+            account.available
+        );
+        assert_eq!(balance(LedgerAccount::ClientHeld(1)), account.held);
+        assert_eq!(balance(LedgerAccount::ChargebackLoss), Decimal::TEN);
+        assert_eq!(
+            -balance(LedgerAccount::Omnibus) - balance(LedgerAccount::ChargebackLoss),
+            account.total
+        );
+    }
+
+    #[test]
+    fn merged_clients_keep_the_journal_balanced() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for (tx_id, client_id, kind) in [
+            (1, 1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, 2, TransactionKind::Deposit(Decimal::TWO)),
+            (3, 2, TransactionKind::Dispute(2)),
+        ] {
+            let order = TransactionOrder {
+                tx_id,
+                client_id,
+                kind,
+                timestamp: None,
+                currency: None,
+            };
+            manager.process_order(order).unwrap();
+        }
+
+        assert!(manager.merge_clients(1, 1).is_err());
+        assert!(manager.merge_clients(3, 1).is_err());
+        assert_eq!(manager.merge_clients(2, 1).unwrap(), 1);
+
+        // the disputes of the merged client are now the ones of the account
+        let order = TransactionOrder {
+            tx_id: 4,
+            client_id: 1,
+            kind: TransactionKind::Resolve(2),
+            timestamp: None,
+            currency: None,
+        };
+        manager.process_order(order).unwrap();
+        let account = manager.get_account(1).unwrap();
+        let journal = manager.get_journal();
+
+        assert!(manager.get_account(2).is_none());
+        assert_eq!(account.available, dec!(12));
+        assert_eq!(account.held, Decimal::ZERO);
+        for ledger_account in [
+            LedgerAccount::ClientAvailable(2),
+            LedgerAccount::ClientHeld(2),
+        ] {
+            let balance: Decimal = journal
+                .iter()
+                .filter(|entry| entry.credit == ledger_account || entry.debit == ledger_account)
+                .map(|entry| {
+                    if entry.credit == ledger_account {
+                        entry.amount
+                    } else {
+                        -entry.amount
+                    }
+                })
+                .sum();
+            assert_eq!(balance, Decimal::ZERO);
+        }
+    }
+
+    #[test]
+    fn merged_clients_must_share_the_currency() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        for (tx_id, client_id, currency) in [(1, 1, "EUR"), (2, 2, "USD")] {
+            let order = TransactionOrder {
+                tx_id,
+                client_id,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: Some(currency.parse().unwrap()),
+            };
+            manager.process_order(order).unwrap();
+        }
+        let error = manager.merge_clients(2, 1).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Client id='1' account is in EUR, the order is in USD."
+        );
+        assert_eq!(manager.get_account(2).unwrap().total, Decimal::ONE);
+    }
+
+    #[test]
+    fn rejected_disputes_are_noted_on_the_account() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        manager
+            .process_order(order(1, 1, TransactionKind::Deposit(Decimal::TEN)))
+            .unwrap();
+        manager
+            .process_order(order(2, 1, TransactionKind::Withdrawal(Decimal::ONE)))
+            .unwrap();
+        manager
+            .process_order(order(1, 2, TransactionKind::Dispute(1)))
+            .unwrap();
+        for (client_id, kind) in [
+            // noted on the account of the disputed transaction
+            (2, TransactionKind::Dispute(1)),
+            (2, TransactionKind::Dispute(3)),
+            // only the rejected dispute orders are noted
+            (2, TransactionKind::Resolve(3)),
+            (2, TransactionKind::Withdrawal(Decimal::ONE)),
+        ] {
+            assert!(manager.process_order(order(3, client_id, kind)).is_err());
+        }
+        let note = |client_id, tx_id, reason| RejectedDispute {
+            client_id,
+            tx_id,
+            reason,
+        };
+
+        assert_eq!(
+            manager.get_dispute_notes(1),
+            vec![note(1, 1, DisputeRejection::AlreadyDisputed)]
+        );
+        assert_eq!(
+            manager.get_dispute_notes(2),
+            vec![note(2, 3, DisputeRejection::NotFound)]
+        );
+
+        manager
+            .process_order(order(4, 2, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+        manager.merge_clients(2, 1).unwrap();
+        assert!(manager.get_dispute_notes(2).is_empty());
+        assert_eq!(manager.get_rejected_disputes().len(), 2);
+        assert_eq!(
+            manager.get_dispute_notes(1)[1],
+            note(1, 3, DisputeRejection::NotFound)
+        );
+    }
+
+    #[test]
+    fn locked_deposits_go_to_the_suspense_account() {
+        let options = AccountManagerOptions {
+            suspense_account: Some(0),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for order in [
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Dispute(1),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::ChargeBack(1),
+                timestamp: None,
+                currency: None,
+            },
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let transaction = manager
+            .process_order(TransactionOrder {
+                tx_id: 2,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+
+        assert_eq!(transaction.client_id, 0);
+        assert_eq!(manager.get_account(0).unwrap().total, Decimal::ONE);
+        assert_eq!(manager.get_account(1).unwrap().total, Decimal::ZERO);
+
+        // withdrawals on locked accounts are still rejected
+        let error = manager
+            .process_order(TransactionOrder {
+                tx_id: 3,
+                client_id: 1,
+                kind: TransactionKind::Withdrawal(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::Account(AccountError::AccountLocked))
+        ));
+    }
+
+    #[test]
+    fn locked_deposits_are_rejected_without_suspense_account() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        for order in [
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Dispute(1),
+                timestamp: None,
+                currency: None,
+            },
+            TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::ChargeBack(1),
+                timestamp: None,
+                currency: None,
+            },
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let order = TransactionOrder {
+            tx_id: 2,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+
+        assert!(manager.process_order(order).is_err());
+        assert_eq!(manager.get_accounts().len(), 1);
+    }
+
+    #[test]
+    fn balances_at_a_point_in_time() {
+        let options = AccountManagerOptions {
+            history: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let at =
+            |day| "2024-03-01T00:00:00Z".parse::<Timestamp>().unwrap() + chrono::Days::new(day);
+        for (tx_id, client_id, kind, day) in [
+            (1, 1, TransactionKind::Deposit(Decimal::TEN), 0),
+            (2, 2, TransactionKind::Deposit(Decimal::TEN), 1),
+            (3, 1, TransactionKind::Withdrawal(dec!(4)), 2),
+            (1, 1, TransactionKind::Dispute(1), 3),
+        ] {
+            let order = TransactionOrder {
+                tx_id,
+                client_id,
+                kind,
+                timestamp: Some(at(day)),
+                currency: None,
+            };
+            manager.process_order(order).unwrap();
+        }
+
+        assert!(manager
+            .balance_at_time(1, at(0) - chrono::Days::new(1))
+            .unwrap()
+            .is_none());
+        let account = manager.balance_at_time(1, at(2)).unwrap().unwrap();
+        assert_eq!((account.available, account.held), (dec!(6), Decimal::ZERO));
+        let account = manager.balance_at_time(1, at(9)).unwrap().unwrap();
+        assert_eq!((account.available, account.held), (dec!(-4), Decimal::TEN));
+        assert_eq!(
+            manager.balance_at(2, 2).unwrap().unwrap().total,
+            Decimal::TEN
+        );
+
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        assert!(manager.balance_at(1, 1).is_err());
+    }
+
+    #[test]
+    fn accounts_are_rebuilt_from_the_history() {
+        let options = AccountManagerOptions {
+            history: true,
+            ..Default::default()
+        };
+        let manager = Arc::new(AccountManager::with_options(
+            InMemoryAccountStorage::default(),
+            options,
+        ));
+        for (tx_id, client_id, kind) in [
+            (1, 1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, 2, TransactionKind::Deposit(Decimal::TEN)),
+            (3, 1, TransactionKind::Withdrawal(dec!(4))),
+            (1, 1, TransactionKind::Dispute(1)),
+            (1, 1, TransactionKind::ChargeBack(1)),
+            (4, 1, TransactionKind::Unlock),
+        ] {
+            let order = TransactionOrder {
+                tx_id,
+                client_id,
+                kind,
+                timestamp: None,
+                currency: None,
+            };
+            manager.process_order(order).unwrap();
+        }
+
+        let report = manager.rebuild().unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(
+            (report.accounts_checked, report.transactions_replayed),
+            (2, 6)
+        );
+
+        let shadow = manager.shadow();
+        let order = TransactionOrder {
+            tx_id: 5,
+            client_id: 3,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        shadow.process_order(order).unwrap();
+        assert_eq!(shadow.rebuild().unwrap().transactions_replayed, 7);
+        assert_eq!(manager.rebuild().unwrap().transactions_replayed, 6);
+
+        // the merges and the preloaded disputes are in the history
+        manager.preload_disputes([2, 5]);
+        manager.merge_clients(2, 1).unwrap();
+        let order = TransactionOrder {
+            tx_id: 5,
+            client_id: 1,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+        manager.process_order(order).unwrap();
+        assert_eq!(manager.get_account(1).unwrap().held, dec!(11));
+        let report = manager.rebuild().unwrap();
+        assert!(report.is_consistent(), "{:?}", report.drifts);
+        assert_eq!(
+            (report.accounts_checked, report.transactions_replayed),
+            (1, 10)
+        );
+
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        assert!(manager.rebuild().is_err());
+    }
+
+    #[test]
+    fn disputes_within_the_available_funds() {
+        #[derive(Debug)]
+        struct NoNegativeAvailable;
+
+        impl DisputePolicy for NoNegativeAvailable {
+            fn allows_negative_available(&self) -> bool {
+                false
+            }
+        }
+
+        let options = AccountManagerOptions {
+            dispute_policy: Arc::new(NoNegativeAvailable),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for (tx_id, kind) in [
+            (1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, TransactionKind::Deposit(Decimal::TEN)),
+            (3, TransactionKind::Withdrawal(dec!(15))),
+        ] {
+            manager.process_order(order(tx_id, kind)).unwrap();
+        }
+
+        let error = manager
+            .process_order(order(1, TransactionKind::Dispute(1)))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::Account(
+                AccountError::InsufficientAvailableFunds { .. }
+            ))
+        ));
+        manager
+            .process_order(order(3, TransactionKind::Dispute(3)))
+            .unwrap();
+        manager.preload_disputes([2]);
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.available, dec!(5));
+        assert_eq!(account.held, dec!(15));
+    }
+
+    #[test]
+    fn withdrawals_within_the_credit_limit() {
+        let options = AccountManagerOptions {
+            credit_limits: CreditLimits::new(dec!(5)).with_client(2, Decimal::ZERO),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for (tx_id, client_id) in [(1, 1), (2, 2)] {
+            manager
+                .process_order(order(
+                    tx_id,
+                    client_id,
+                    TransactionKind::Deposit(Decimal::TEN),
+                ))
+                .unwrap();
+        }
+
+        manager
+            .process_order(order(3, 1, TransactionKind::Withdrawal(dec!(15))))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(-5));
+        let error = manager
+            .process_order(order(4, 1, TransactionKind::Withdrawal(dec!(0.01))))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::Account(
+                AccountError::InsufficientAvailableFunds { .. }
+            ))
+        ));
+        assert!(manager
+            .process_order(order(5, 2, TransactionKind::Withdrawal(dec!(11))))
+            .is_err());
+        assert_eq!(manager.get_account(2).unwrap().available, Decimal::TEN);
+    }
+
+    #[test]
+    fn daily_totals_are_capped() {
+        let options = AccountManagerOptions {
+            order_limits: OrderLimits {
+                max_withdrawal: Some(dec!(50)),
+                max_daily_total: Some(dec!(100)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, kind, day: i64| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: Timestamp::from_timestamp(day * 86_400, 0),
+            currency: None,
+        };
+        manager
+            .process_order(order(1, TransactionKind::Deposit(dec!(80)), 0))
+            .unwrap();
+
+        let error = manager
+            .process_order(order(2, TransactionKind::Withdrawal(dec!(60)), 0))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::LimitExceeded(1, "withdrawal", _))
+        ));
+        let error = manager
+            .process_order(order(3, TransactionKind::Withdrawal(dec!(30)), 0))
+            .unwrap_err();
+        assert_eq!(TransactionError::kind_of(&error), "limit_exceeded");
+
+        // the next day
+        manager
+            .process_order(order(4, TransactionKind::Withdrawal(dec!(30)), 1))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(50));
+    }
+
+    #[test]
+    fn unlocked_accounts_accept_orders_again() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let events = manager.subscribe();
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        for order in [
+            order(1, 1, TransactionKind::Deposit(Decimal::TEN)),
+            order(2, 1, TransactionKind::Deposit(Decimal::TEN)),
+            order(1, 1, TransactionKind::Dispute(1)),
+            order(1, 1, TransactionKind::ChargeBack(1)),
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let error = manager
+            .process_order(order(3, 2, TransactionKind::Unlock))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::AccountNotLocked(2))
+        ));
+        let error = manager
+            .process_order(order(2, 1, TransactionKind::Unlock))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::DuplicateTransactionId(2))
+        ));
+
+        manager.unlock_account(1, 3).unwrap();
+        manager
+            .process_order(order(4, 1, TransactionKind::Withdrawal(Decimal::ONE)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert!(!account.locked);
+        assert_eq!(account.total, dec!(9));
+        assert_eq!(manager.get_journal().len(), 5);
+        assert!(events
+            .try_iter()
+            .any(|event| event == DomainEvent::AccountUnlocked { client_id: 1 }));
+
+        assert!(manager.unlock_account(1, 5).is_err());
+        let error = manager
+            .process_order(order(5, 1, TransactionKind::Dispute(3)))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::RelatedTransactionNotDisputable(3))
+        ));
+    }
+
+    #[test]
+    fn adjustments_are_applied_under_the_admin_option() {
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let error = manager
+            .process_order(order(1, TransactionKind::Adjustment(Decimal::TEN)))
+            .unwrap_err();
+        assert_eq!(TransactionError::kind_of(&error), "adjustment_not_allowed");
+        assert!(manager.get_account(1).is_none());
+
+        let options = AccountManagerOptions {
+            adjustments: true,
+            double_entry: true,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let events = manager.subscribe();
+        for order in [
+            order(1, TransactionKind::Deposit(Decimal::TEN)),
+            order(1, TransactionKind::Dispute(1)),
+            order(1, TransactionKind::ChargeBack(1)),
+            // the locked accounts are adjusted too
+            order(2, TransactionKind::Adjustment(dec!(5))),
+            order(3, TransactionKind::Adjustment(dec!(-2))),
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let error = manager
+            .process_order(order(4, TransactionKind::Adjustment(dec!(-4))))
+            .unwrap_err();
+        assert_eq!(
+            TransactionError::kind_of(&error),
+            "insufficient_available_funds"
+        );
+
+        let account = manager.get_account(1).unwrap();
+        assert_eq!((account.available, account.total), (dec!(3), dec!(3)));
+        assert!(account.locked);
+        assert_eq!(
+            manager.get_journal()[3..],
+            [
+                JournalEntry::deposit(2, 1, dec!(5)),
+                JournalEntry::withdrawal(3, 1, dec!(2))
+            ]
+        );
+        assert!(events.try_iter().any(|event| event
+            == DomainEvent::AccountAdjusted {
+                client_id: 1,
+                tx_id: 3,
+                amount: dec!(-2)
+            }));
+    }
+
+    #[test]
+    fn closed_accounts_reject_orders() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let events = manager.subscribe();
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        let error = manager
+            .process_order(order(1, 1, TransactionKind::Close))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::AccountNotOpen(1))
+        ));
+        for order in [
+            order(2, 1, TransactionKind::Open),
+            order(3, 1, TransactionKind::Deposit(Decimal::TEN)),
+            order(4, 2, TransactionKind::Deposit(Decimal::ONE)),
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let error = manager
+            .process_order(order(5, 1, TransactionKind::Open))
+            .unwrap_err();
+        assert_eq!(TransactionError::kind_of(&error), "account_already_open");
+        let error = manager
+            .process_order(order(6, 1, TransactionKind::Close))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::AccountNotEmpty(1))
+        ));
+
+        manager
+            .process_order(order(7, 1, TransactionKind::Withdrawal(Decimal::TEN)))
+            .unwrap();
+        manager
+            .process_order(order(8, 1, TransactionKind::Close))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().state, AccountState::Closed);
+        assert!(events
+            .try_iter()
+            .any(|event| event == DomainEvent::AccountClosed { client_id: 1 }));
+        for kind in [
+            TransactionKind::Deposit(Decimal::ONE),
+            TransactionKind::Dispute(3),
+        ] {
+            let error = manager.process_order(order(9, 1, kind)).unwrap_err();
+            assert_eq!(TransactionError::kind_of(&error), "account_not_open");
+        }
+
+        // a closed account can be opened again
+        manager
+            .process_order(order(10, 1, TransactionKind::Open))
+            .unwrap();
+        manager
+            .process_order(order(11, 1, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(1));
+    }
+
+    #[test]
+    fn rejected_bundles_leave_no_trace() {
+        let options = AccountManagerOptions {
+            double_entry: true,
+            suspend_after: Some(1),
+            ..Default::default()
+        };
+        let manager = Arc::new(AccountManager::with_options(
+            InMemoryAccountStorage::default(),
+            options,
+        ));
+        let events = manager.subscribe();
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+        manager
+            .process_order(order(1, 1, TransactionKind::Deposit(Decimal::TEN)))
+            .unwrap();
+        let error = manager
+            .process_bundle(vec![
+                order(2, 1, TransactionKind::Deposit(Decimal::TEN)),
+                order(1, 1, TransactionKind::Dispute(1)),
+                order(3, 2, TransactionKind::Withdrawal(Decimal::ONE)),
+            ])
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("Order 2 of the bundle (tx=3)"));
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(10));
+        assert!(manager.get_transaction(2).is_none());
+        assert_eq!(manager.stats().open_disputes, 0);
+        assert_eq!(manager.get_journal().len(), 1);
+        assert!(events.try_iter().next().is_none());
+        assert!(manager.get_suspended_clients().is_empty());
+
+        // the orders of a bundle see the ones before them
+        let transactions = manager
+            .process_bundle(vec![
+                order(2, 1, TransactionKind::Deposit(Decimal::TEN)),
+                order(1, 2, TransactionKind::Dispute(1)),
+                order(3, 1, TransactionKind::Withdrawal(Decimal::TEN)),
+            ])
+            .unwrap();
+        assert_eq!(transactions.len(), 3);
+        let account = manager.get_account(1).unwrap();
+        assert_eq!((account.available, account.held), (dec!(0), dec!(10)));
+        assert!(manager.process_bundle(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn journal_is_disabled_by_default() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        let _tx = manager
+            .process_order(TransactionOrder {
+                tx_id: 1,
+                client_id: 1,
+                kind: TransactionKind::Deposit(Decimal::TEN),
+                timestamp: None,
+                currency: None,
+            })
+            .unwrap();
+
+        assert!(manager.get_journal().is_empty());
+    }
+
+    #[test]
+    fn clients_are_suspended_after_consecutive_rejections() {
+        let options = AccountManagerOptions {
+            suspend_after: Some(2),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, client_id, kind| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+
+        manager
+            .process_order(order(1, 1, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+        // a successful order resets the count of consecutive rejections
+        assert!(manager
+            .process_order(order(2, 1, TransactionKind::Withdrawal(Decimal::TEN)))
+            .is_err());
+        manager
+            .process_order(order(3, 1, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+        assert!(manager
+            .process_order(order(4, 1, TransactionKind::Withdrawal(Decimal::TEN)))
+            .is_err());
+        // rejections of other clients are counted separately
+        assert!(manager
+            .process_order(order(5, 2, TransactionKind::Dispute(42)))
+            .is_err());
+        assert!(manager.get_suspended_clients().is_empty());
+
+        assert!(manager
+            .process_order(order(6, 1, TransactionKind::Resolve(1)))
+            .is_err());
+        assert_eq!(manager.get_suspended_clients(), vec![1]);
+
+        let error = manager
+            .process_order(order(7, 1, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::ClientSuspended(1))
+        ));
+        assert!(matches!(
+            manager.validate_order(&order(7, 1, TransactionKind::Deposit(Decimal::ONE))),
+            Err(TransactionError::ClientSuspended(1))
+        ));
+        assert_eq!(manager.get_account(1).unwrap().available, Decimal::TWO);
+        manager
+            .process_order(order(8, 2, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+    }
+
+    #[test]
+    fn clients_exceeding_max_transactions_are_flagged() {
+        let options = AccountManagerOptions {
+            max_transactions: Some(2),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let deposit = |tx_id, client_id| TransactionOrder {
+            tx_id,
+            client_id,
+            kind: TransactionKind::Deposit(Decimal::ONE),
+            timestamp: None,
+            currency: None,
+        };
+
+        manager.process_order(deposit(1, 1)).unwrap();
+        manager.process_order(deposit(2, 1)).unwrap();
+        // rejected orders are not counted
+        assert!(manager.process_order(deposit(2, 1)).is_err());
+        manager.process_order(deposit(3, 2)).unwrap();
+        assert!(manager.get_excessive_clients().is_empty());
+
+        // the transactions over the maximum are still applied
+        manager.process_order(deposit(4, 1)).unwrap();
+        assert_eq!(manager.get_excessive_clients(), vec![1]);
+        assert_eq!(manager.get_account(1).unwrap().available, dec!(3));
+    }
+
+    #[test]
+    fn clients_exceeding_max_transactions_are_rejected() {
+        let options = AccountManagerOptions {
+            max_transactions: Some(1),
+            excess_transactions: ExcessTransactions::Reject,
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        let order = |tx_id, kind| TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        };
+
+        manager
+            .process_order(order(1, TransactionKind::Deposit(Decimal::ONE)))
+            .unwrap();
+        assert!(matches!(
+            manager.validate_order(&order(2, TransactionKind::Dispute(1))),
+            Err(TransactionError::TooManyTransactions(1, 1))
+        ));
+        assert!(manager.get_excessive_clients().is_empty());
+        let error = manager
+            .process_order(order(2, TransactionKind::Dispute(1)))
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::TooManyTransactions(1, 1))
+        ));
+        assert_eq!(manager.get_excessive_clients(), vec![1]);
+        assert_eq!(manager.get_account(1).unwrap().held, Decimal::ZERO);
+        assert!("ignore".parse::<ExcessTransactions>().is_err());
+    }
+
+    #[test]
+    fn clients_are_not_suspended_by_default() {
+        let manager = AccountManager::new(InMemoryAccountStorage::default());
+        for tx_id in 1..10 {
+            let _ = manager.process_order(TransactionOrder {
+                tx_id,
+                client_id: 1,
+                kind: TransactionKind::Withdrawal(Decimal::ONE),
+                timestamp: None,
+                currency: None,
+            });
+        }
+
+        assert!(manager.get_suspended_clients().is_empty());
+    }
+
+    /// A manager whose client 1 has disputed deposits of 60 and 40 but only
+    /// 50 held funds left.
+    fn short_held_manager(held_shortfall: HeldShortfall) -> AccountManager {
+        let mut storage = InMemoryAccountStorage::default();
+        let mut account = Account::new(1);
+        account.deposit(dec!(100)).unwrap();
+        account.dispute(dec!(50)).unwrap();
+        storage.store_account(account).unwrap();
+        for (tx_id, amount) in [(1, dec!(60)), (2, dec!(40))] {
+            let deposit = TransactionOrder {
+                tx_id,
+                client_id: 1,
+                kind: TransactionKind::Deposit(amount),
+                timestamp: None,
+                currency: None,
+            };
+            storage.store_transaction(deposit.into()).unwrap();
+            storage.set_disputed(tx_id, true).unwrap();
+        }
+        let options = AccountManagerOptions {
+            double_entry: true,
+            held_shortfall,
+            ..Default::default()
+        };
+
+        AccountManager::with_options(storage, options)
+    }
+
+    fn settle(tx_id: TxId, kind: TransactionKind) -> TransactionOrder {
+        TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn short_held_funds_are_rejected_by_default() {
+        let manager = short_held_manager(HeldShortfall::default());
+        for kind in [TransactionKind::Resolve(1), TransactionKind::ChargeBack(1)] {
+            let error = manager.process_order(settle(3, kind)).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<TransactionError>(),
+                Some(TransactionError::Account(
+                    AccountError::InsufficientHeldFunds { .. }
+                ))
+            ));
+        }
+        // the deposits held in full are settled in full
+        manager
+            .process_order(settle(3, TransactionKind::Resolve(2)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+
+        assert_eq!((account.available, account.held), (dec!(90), dec!(10)));
+        assert_eq!(manager.stats().open_disputes, 1);
+        assert_eq!(manager.get_journal()[0].amount, dec!(40));
+    }
+
+    #[test]
+    fn short_held_funds_are_clamped() {
+        let manager = short_held_manager(HeldShortfall::Clamp);
+        manager
+            .process_order(settle(3, TransactionKind::Resolve(1)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!((account.available, account.held), (dec!(100), dec!(0)));
+        assert_eq!(manager.get_journal()[0].amount, dec!(50));
+
+        // nothing left to settle
+        manager
+            .process_order(settle(4, TransactionKind::ChargeBack(2)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!((account.total, account.held), (dec!(100), dec!(0)));
+        assert!(account.locked);
+        assert_eq!(manager.get_journal()[1].amount, dec!(0));
+        assert_eq!(manager.stats().open_disputes, 0);
+    }
+
+    #[test]
+    fn short_held_funds_are_shared() {
+        let manager = short_held_manager(HeldShortfall::Proportional);
+        manager
+            .process_order(settle(3, TransactionKind::ChargeBack(1)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(
+            (account.available, account.held, account.total),
+            (dec!(50), dec!(20), dec!(70))
+        );
+        assert_eq!(manager.get_journal()[0].amount, dec!(30));
+
+        // the last open dispute gets the held funds left
+        manager
+            .process_order(settle(4, TransactionKind::Resolve(2)))
+            .unwrap();
+        let account = manager.get_account(1).unwrap();
+        assert_eq!((account.available, account.held), (dec!(70), dec!(0)));
+        assert_eq!(manager.get_journal()[1].amount, dec!(20));
+        assert!("prorata".parse::<HeldShortfall>().is_err());
+        assert_eq!(
+            "Proportional".parse::<HeldShortfall>().unwrap(),
+            HeldShortfall::Proportional
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::adapter::accounts_after;
+use crate::model::{Account, ClientId, DisputeSummary, Transaction, TransactionOrder};
+use crate::Result;
+
+use super::AccountManager;
+
+/// The accounts as the actors see them: the orders are processed and the
+/// accounts read through this trait, the [AccountManager] being its
+/// implementation. Embedders implement it to put the actors in front of their
+/// own manager, a remote one or a decorator caching the accounts of another
+/// for instance.
+///
+/// The [Accountant](crate::actor::Accountant) remains tied to the
+/// [AccountManager]: it applies the orders by batches and runs dry on its
+/// shadows.
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::actor::JournalReplayer;
+/// use csv_reader_core::model::{Account, ClientId, Transaction, TransactionOrder};
+/// use csv_reader_core::service::AccountService;
+/// use csv_reader_core::{AccountManager, InMemoryAccountStorage};
+///
+/// /// Counts the orders processed by the manager it decorates.
+/// struct Counting(AccountManager, Mutex<usize>);
+///
+/// impl AccountService for Counting {
+///     fn process_order(&self, order: TransactionOrder) -> csv_reader_core::Result<Transaction> {
+///         *self.1.lock().unwrap() += 1;
+///         self.0.process_order(order)
+///     }
+///
+///     fn get_account(&self, client_id: ClientId) -> Option<Account> {
+///         self.0.get_account(client_id)
+///     }
+///
+///     fn get_accounts(&self) -> Vec<Account> {
+///         self.0.get_accounts()
+///     }
+/// }
+///
+/// let service = Arc::new(Counting(AccountManager::new(InMemoryAccountStorage::default()), Mutex::new(0)));
+/// let journal = "type,client,tx,amount,timestamp,currency\ndeposit,1,1,10,,\nwithdrawal,1,2,4,,\n";
+/// JournalReplayer::new(service.clone(), Box::new(journal.as_bytes()))
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(*service.1.lock().unwrap(), 2);
+/// assert_eq!(service.get_account(1).unwrap().available, Decimal::from(6));
+/// ```
+pub trait AccountService: Send + Sync {
+    /// Process the given order and return the transaction applied, or the
+    /// reason it was rejected.
+    fn process_order(&self, order: TransactionOrder) -> Result<Transaction>;
+
+    /// Get the account of the given client if any.
+    fn get_account(&self, client_id: ClientId) -> Option<Account>;
+
+    /// Get all the accounts.
+    fn get_accounts(&self) -> Vec<Account>;
+
+    /// Get at most `limit` accounts of client identifier greater than the
+    /// given one, by increasing client identifier. By default, all the
+    /// accounts are loaded to pick them.
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        accounts_after(self.get_accounts(), after, limit)
+    }
+
+    /// Get the number and the sum of the disputed transactions of every
+    /// account with open disputes. By default, none is known.
+    fn get_dispute_summaries(&self) -> HashMap<ClientId, DisputeSummary> {
+        HashMap::new()
+    }
+}
+
+impl AccountService for AccountManager {
+    fn process_order(&self, order: TransactionOrder) -> Result<Transaction> {
+        AccountManager::process_order(self, order)
+    }
+
+    fn get_account(&self, client_id: ClientId) -> Option<Account> {
+        AccountManager::get_account(self, client_id)
+    }
+
+    fn get_accounts(&self) -> Vec<Account> {
+        AccountManager::get_accounts(self)
+    }
+
+    fn get_accounts_after(&self, after: Option<ClientId>, limit: usize) -> Vec<Account> {
+        AccountManager::get_accounts_after(self, after, limit)
+    }
+
+    fn get_dispute_summaries(&self) -> HashMap<ClientId, DisputeSummary> {
+        AccountManager::get_dispute_summaries(self)
+    }
+}
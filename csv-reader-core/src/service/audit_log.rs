@@ -0,0 +1,301 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::model::{Account, ClientId, TransactionKind, TransactionOrder, TxId};
+use crate::Result;
+
+/// A decision of the [AccountManager](super::AccountManager) on an order:
+/// the order received, whether it was applied or why it was rejected and the
+/// balances of the account it concerns before and after. The merges of
+/// clients are recorded the same way, see [AuditRecord::merge].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+    /// The transaction kind of the order.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+
+    /// The client of the order.
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+
+    /// The identifier of the order, none for the merges.
+    #[serde(rename = "tx")]
+    pub tx_id: Option<TxId>,
+
+    /// The amount of the deposits and withdrawals.
+    pub amount: Option<Decimal>,
+
+    /// The client of the account concerned, the one of the disputed
+    /// transaction for the dispute orders.
+    pub account: ClientId,
+
+    /// Why the order was rejected, none when it was applied.
+    pub error: Option<String>,
+
+    /// The available funds of the account before the order.
+    pub available_before: Decimal,
+
+    /// The held funds of the account before the order.
+    pub held_before: Decimal,
+
+    /// The lock status of the account before the order.
+    pub locked_before: bool,
+
+    /// The available funds of the account after the order.
+    pub available_after: Decimal,
+
+    /// The held funds of the account after the order.
+    pub held_after: Decimal,
+
+    /// The lock status of the account after the order.
+    pub locked_after: bool,
+}
+
+impl AuditRecord {
+    /// Create the record of the given order, the given account being the one
+    /// concerned before and after the order.
+    pub fn new(
+        order: &TransactionOrder,
+        before: &Account,
+        after: &Account,
+        error: Option<String>,
+    ) -> Self {
+        let (kind, amount) = match order.kind {
+            TransactionKind::Deposit(amount) => ("deposit", Some(amount)),
+            TransactionKind::Withdrawal(amount) => ("withdrawal", Some(amount)),
+            TransactionKind::Dispute(_) => ("dispute", None),
+            TransactionKind::Resolve(_) => ("resolve", None),
+            TransactionKind::ChargeBack(_) => ("chargeback", None),
+            TransactionKind::Unlock => ("unlock", None),
+            TransactionKind::Open => ("open", None),
+            TransactionKind::Close => ("close", None),
+            TransactionKind::Adjustment(amount) => ("adjustment", Some(amount)),
+        };
+
+        Self {
+            kind,
+            client_id: order.client_id,
+            tx_id: Some(order.tx_id),
+            amount,
+            account: before.client_id,
+            error,
+            available_before: before.available,
+            held_before: before.held,
+            locked_before: before.locked,
+            available_after: after.available,
+            held_after: after.held,
+            locked_after: after.locked,
+        }
+    }
+
+    /// Create the record of the merge of the account of the client `from`
+    /// into the given account, see
+    /// [AccountManager::merge_clients](super::AccountManager::merge_clients).
+    pub fn merge(from: ClientId, before: &Account, after: &Account, error: Option<String>) -> Self {
+        Self {
+            kind: "merge",
+            client_id: from,
+            tx_id: None,
+            amount: None,
+            account: before.client_id,
+            error,
+            available_before: before.available,
+            held_before: before.held,
+            locked_before: before.locked,
+            available_after: after.available,
+            held_after: after.held,
+            locked_after: after.locked,
+        }
+    }
+
+    /// Tell if the order was applied.
+    pub fn is_applied(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The audit trail of the decisions of the
+/// [AccountManager](super::AccountManager), given through its options: one
+/// CSV record per processed order, see [AuditRecord], written to a file or
+/// any writer. A record failing to be written is logged and does not change
+/// the outcome of the order, the first such failure is returned by
+/// [AuditLog::flush].
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::InMemoryAccountStorage;
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{AccountManager, AccountManagerOptions, AuditLog};
+///
+/// #[derive(Clone, Default)]
+/// struct Buffer(Arc<Mutex<Vec<u8>>>);
+///
+/// impl std::io::Write for Buffer {
+///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///
+///     fn flush(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let buffer = Buffer::default();
+/// let options = AccountManagerOptions {
+///     audit_log: Some(Arc::new(AuditLog::new(Box::new(buffer.clone())))),
+///     ..Default::default()
+/// };
+/// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options.clone());
+/// let order = |tx_id, kind| TransactionOrder { tx_id, client_id: 1, kind, timestamp: None, currency: None };
+/// let _tx = manager.process_order(order(1, TransactionKind::Deposit(Decimal::TEN)));
+/// let _error = manager.process_order(order(2, TransactionKind::Withdrawal(Decimal::ONE_HUNDRED)));
+/// options.audit_log.unwrap().flush().unwrap();
+///
+/// let audit = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+/// let lines: Vec<_> = audit.lines().collect();
+/// assert_eq!(lines.len(), 3);
+/// assert_eq!(lines[1], "deposit,1,1,10,1,,0,0,false,10,0,false");
+/// assert!(lines[2].starts_with("withdrawal,1,2,100,1,\"Insufficient available funds"));
+/// assert!(lines[2].ends_with(",10,0,false,10,0,false"));
+/// ```
+pub struct AuditLog {
+    /// The CSV writer of the records.
+    writer: Mutex<csv::Writer<Box<dyn Write + Send + Sync>>>,
+
+    /// The first failure to write a record.
+    failure: Mutex<Option<String>>,
+}
+
+impl Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    /// Create an audit log writing its records to the given writer.
+    pub fn new(writer: Box<dyn Write + Send + Sync>) -> Self {
+        Self {
+            writer: Mutex::new(csv::Writer::from_writer(writer)),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// Create an audit log writing its records to the given file, truncated
+    /// if it exists.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| anyhow!("Cannot create the audit log '{}': {e}", path.display()))?;
+
+        Ok(Self::new(Box::new(BufWriter::new(file))))
+    }
+
+    /// Write the given record.
+    pub fn record(&self, record: &AuditRecord) {
+        if let Err(error) = self.writer.lock().unwrap().serialize(record) {
+            log::error!(
+                "Cannot write the audit record of the {} of client {}: {error}",
+                record.kind,
+                record.client_id
+            );
+            self.failure
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| error.to_string());
+        }
+    }
+
+    /// Flush the records written, failing if a record could not be written.
+    pub fn flush(&self) -> Result<()> {
+        self.writer.lock().unwrap().flush()?;
+
+        match self.failure.lock().unwrap().as_ref() {
+            Some(failure) => Err(anyhow!("The audit log is incomplete: {failure}")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::adapter::InMemoryAccountStorage;
+    use crate::service::{AccountManager, AccountManagerOptions};
+
+    #[test]
+    fn dispute_orders_audit_the_disputed_account() {
+        let file = std::env::temp_dir().join(format!("audit-{}.csv", std::process::id()));
+        let audit_log = Arc::new(AuditLog::create(&file).unwrap());
+        let options = AccountManagerOptions {
+            audit_log: Some(audit_log.clone()),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for (client_id, kind) in [
+            (1, TransactionKind::Deposit(Decimal::TEN)),
+            (2, TransactionKind::Dispute(1)),
+        ] {
+            let order = TransactionOrder {
+                tx_id: 1,
+                client_id,
+                kind,
+                timestamp: None,
+                currency: None,
+            };
+            manager.process_order(order).unwrap();
+        }
+        audit_log.flush().unwrap();
+        let audit = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            audit.lines().last(),
+            Some("dispute,2,1,,1,,10,0,false,0,10,false")
+        );
+    }
+
+    #[test]
+    fn merges_are_audited() {
+        let file = std::env::temp_dir().join(format!("audit-merge-{}.csv", std::process::id()));
+        let audit_log = Arc::new(AuditLog::create(&file).unwrap());
+        let options = AccountManagerOptions {
+            audit_log: Some(audit_log.clone()),
+            ..Default::default()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for (client_id, amount) in [(1, Decimal::TEN), (2, Decimal::ONE)] {
+            let order = TransactionOrder {
+                tx_id: client_id,
+                client_id,
+                kind: TransactionKind::Deposit(amount),
+                timestamp: None,
+                currency: None,
+            };
+            manager.process_order(order).unwrap();
+        }
+        manager.merge_clients(2, 1).unwrap();
+        assert!(manager.merge_clients(2, 1).is_err());
+        audit_log.flush().unwrap();
+        let audit = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let lines: Vec<_> = audit.lines().skip(3).collect();
+
+        assert_eq!(lines[0], "merge,2,,,1,,10,0,false,11,0,false");
+        assert!(lines[1].starts_with("merge,2,,,1,Client id='2' has no account to merge."));
+        assert!(lines[1].ends_with(",11,0,false,11,0,false"));
+    }
+}
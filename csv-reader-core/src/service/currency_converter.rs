@@ -0,0 +1,366 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path, sync::Arc};
+
+use anyhow::{anyhow, bail};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+
+use crate::model::{
+    ClientId, Currency, Transaction, TransactionKind, TransactionOrder, TxId, MAX_DECIMALS,
+};
+use crate::Result;
+
+use super::AccountManager;
+
+/// The exchange rates between currencies: an amount in the source currency
+/// times the rate is the amount in the target currency. The inverse rates are
+/// derived when only one direction is given.
+///
+/// The rate files are CSV files with a header:
+///
+/// ```text
+/// from,to,rate
+/// EUR,USD,1.0850
+/// GBP,EUR,1.1700
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RateTable {
+    /// The rates by source and target currency.
+    rates: HashMap<(Currency, Currency), Decimal>,
+}
+
+/// A record of a rate file.
+#[derive(Debug, Deserialize)]
+struct RateRecord {
+    from: Currency,
+    to: Currency,
+    rate: Decimal,
+}
+
+impl RateTable {
+    /// Set the rate from a currency to another one.
+    pub fn with_rate(mut self, from: Currency, to: Currency, rate: Decimal) -> Self {
+        self.rates.insert((from, to), rate);
+
+        self
+    }
+
+    /// Load the rates of the given file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow!("Cannot read the rate file '{}': {e}", path.display()))?;
+
+        Self::from_reader(file)
+    }
+
+    /// Read the rates of a CSV input. Fails on an invalid record or on a rate
+    /// that is not strictly positive.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    ///
+    /// use csv_reader_core::service::RateTable;
+    ///
+    /// let rates = RateTable::from_reader("from,to,rate\nEUR,USD,1.25\n".as_bytes()).unwrap();
+    /// let (eur, usd) = ("EUR".parse().unwrap(), "USD".parse().unwrap());
+    ///
+    /// assert_eq!(rates.rate(eur, usd), Some(dec!(1.25)));
+    /// assert_eq!(rates.rate(usd, eur), Some(dec!(0.8)));
+    /// assert_eq!(rates.convert(dec!(10), usd, eur).unwrap(), dec!(8));
+    /// assert!(rates.convert(dec!(10), usd, "GBP".parse().unwrap()).is_err());
+    /// assert!(RateTable::from_reader("from,to,rate\nEUR,USD,0\n".as_bytes()).is_err());
+    /// ```
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        let mut table = Self::default();
+        for record in csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+        {
+            let RateRecord { from, to, rate } = record?;
+            if rate <= Decimal::ZERO {
+                bail!("The rate from {from} to {to} must be strictly positive ({rate} given).");
+            }
+            table = table.with_rate(from, to, rate);
+        }
+
+        Ok(table)
+    }
+
+    /// Get the rate from a currency to another one, if known.
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        self.rates
+            .get(&(from, to))
+            .copied()
+            .or_else(|| self.rates.get(&(to, from)).map(|rate| Decimal::ONE / rate))
+    }
+
+    /// Convert the given amount, rounded down to the precision of the
+    /// amounts so a conversion never credits more than its rate gives.
+    pub fn convert(&self, amount: Decimal, from: Currency, to: Currency) -> Result<Decimal> {
+        let rate = self
+            .rate(from, to)
+            .ok_or_else(|| anyhow!("No rate from {from} to {to}."))?;
+
+        Ok((amount * rate)
+            .round_dp_with_strategy(MAX_DECIMALS, RoundingStrategy::ToZero)
+            .normalize())
+    }
+}
+
+/// A transfer of funds between two accounts in different currencies.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    /// The client whose account is debited, in its currency.
+    pub from: ClientId,
+
+    /// The client whose account is credited.
+    pub to: ClientId,
+
+    /// The currency of the credited account.
+    pub to_currency: Currency,
+
+    /// The amount debited, in the currency of the debited account.
+    pub amount: Decimal,
+
+    /// The identifier of the withdrawal debiting the amount.
+    pub debit_tx_id: TxId,
+
+    /// The identifier of the deposit crediting the converted amount.
+    pub credit_tx_id: TxId,
+}
+
+/// The transactions recording a transfer, see [CurrencyConverter::transfer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion {
+    /// The withdrawal from the debited account.
+    pub debit: Transaction,
+
+    /// The deposit of the converted amount on the credited account.
+    pub credit: Transaction,
+
+    /// The rate applied.
+    pub rate: Decimal,
+}
+
+/// Currency conversions over the accounts of a manager: the transfers between
+/// accounts in different currencies and the totals in a base currency for the
+/// reports.
+pub struct CurrencyConverter {
+    manager: Arc<AccountManager>,
+    rates: RateTable,
+}
+
+impl CurrencyConverter {
+    /// Create a converter of the accounts of the given manager.
+    pub fn new(manager: Arc<AccountManager>, rates: RateTable) -> Self {
+        Self { manager, rates }
+    }
+
+    /// Transfer funds between two accounts in different currencies. The
+    /// conversion is recorded as explicit transactions in the currency of
+    /// each account: a withdrawal of the amount from the debited account and
+    /// a deposit of the converted amount on the credited one. Both orders are
+    /// processed as a bundle, see [AccountManager::process_bundle]: either
+    /// both are applied or none is.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use rust_decimal_macros::dec;
+    ///
+    /// use csv_reader_core::adapter::InMemoryAccountStorage;
+    /// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+    /// use csv_reader_core::service::{AccountManager, CurrencyConverter, RateTable, Transfer};
+    ///
+    /// let (eur, usd) = ("EUR".parse().unwrap(), "USD".parse().unwrap());
+    /// let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+    /// let order = TransactionOrder {
+    ///     tx_id: 1,
+    ///     client_id: 1,
+    ///     kind: TransactionKind::Deposit(dec!(100)),
+    ///     timestamp: None,
+    ///     currency: Some(eur),
+    /// };
+    /// let _tx = manager.process_order(order).unwrap();
+    /// let converter =
+    ///     CurrencyConverter::new(manager.clone(), RateTable::default().with_rate(eur, usd, dec!(1.1)));
+    ///
+    /// let transfer = Transfer { from: 1, to: 2, to_currency: usd, amount: dec!(40), debit_tx_id: 2, credit_tx_id: 3 };
+    /// let conversion = converter.transfer(&transfer).unwrap();
+    ///
+    /// assert_eq!(conversion.credit.kind, TransactionKind::Deposit(dec!(44)));
+    /// assert_eq!(manager.get_account(1).unwrap().total, dec!(60));
+    /// assert_eq!(manager.get_account(2).unwrap().total, dec!(44));
+    /// assert_eq!(converter.total_in(eur).unwrap(), dec!(100));
+    /// ```
+    pub fn transfer(&self, transfer: &Transfer) -> Result<Conversion> {
+        let from_currency = self
+            .manager
+            .get_account(transfer.from)
+            .and_then(|account| account.currency)
+            .ok_or_else(|| anyhow!("Client id='{}' has no account currency.", transfer.from))?;
+        let rate = self
+            .rates
+            .rate(from_currency, transfer.to_currency)
+            .ok_or_else(|| anyhow!("No rate from {from_currency} to {}.", transfer.to_currency))?;
+        let converted = self
+            .rates
+            .convert(transfer.amount, from_currency, transfer.to_currency)?;
+        let debit = TransactionOrder {
+            tx_id: transfer.debit_tx_id,
+            client_id: transfer.from,
+            kind: TransactionKind::withdrawal(transfer.amount)?,
+            timestamp: None,
+            currency: Some(from_currency),
+        };
+        let credit = TransactionOrder {
+            tx_id: transfer.credit_tx_id,
+            client_id: transfer.to,
+            kind: TransactionKind::deposit(converted)?,
+            timestamp: None,
+            currency: Some(transfer.to_currency),
+        };
+        if debit.tx_id == credit.tx_id {
+            bail!("The debit and the credit of a transfer need distinct identifiers.");
+        }
+
+        let mut transactions = self
+            .manager
+            .process_bundle(vec![debit, credit])?
+            .into_iter();
+        let (debit, credit) = (transactions.next().unwrap(), transactions.next().unwrap());
+        log::info!(
+            "Transfer of {} {from_currency} from client {} to client {} at {rate}: tx={} and tx={}.",
+            transfer.amount,
+            transfer.from,
+            transfer.to,
+            debit.tx_id,
+            credit.tx_id
+        );
+
+        Ok(Conversion {
+            debit,
+            credit,
+            rate,
+        })
+    }
+
+    /// Get the total funds of all the accounts in the given base currency.
+    /// The accounts without currency are counted as in the base currency.
+    pub fn total_in(&self, base: Currency) -> Result<Decimal> {
+        self.manager
+            .get_accounts()
+            .into_iter()
+            .map(|account| {
+                let currency = account.currency.unwrap_or(base);
+                self.rates.convert(account.total, currency, base)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::adapter::InMemoryAccountStorage;
+    use crate::model::AccountError;
+    use crate::service::TransactionError;
+
+    #[test]
+    fn transfers_are_applied_all_or_nothing() {
+        let (eur, usd) = ("EUR".parse().unwrap(), "USD".parse().unwrap());
+        let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        for (tx_id, client_id, currency) in [(1, 1, eur), (2, 2, eur)] {
+            let order = TransactionOrder {
+                tx_id,
+                client_id,
+                kind: TransactionKind::Deposit(dec!(10)),
+                timestamp: None,
+                currency: Some(currency),
+            };
+            manager.process_order(order).unwrap();
+        }
+        let converter = CurrencyConverter::new(
+            manager.clone(),
+            RateTable::default().with_rate(usd, eur, dec!(0.9)),
+        );
+        let transfer = Transfer {
+            from: 1,
+            to: 2,
+            to_currency: usd,
+            amount: dec!(5),
+            debit_tx_id: 3,
+            credit_tx_id: 4,
+        };
+
+        // the account of client 2 is in EUR
+        let error = converter.transfer(&transfer).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::CurrencyMismatch(2, ..))
+        ));
+        assert_eq!(manager.get_account(1).unwrap().total, dec!(10));
+        assert!(manager.get_transaction(3).is_none());
+
+        let transfer = Transfer { to: 3, ..transfer };
+        let conversion = converter.transfer(&transfer).unwrap();
+        assert_eq!(
+            conversion.credit.kind,
+            TransactionKind::Deposit(dec!(5.5555))
+        );
+        assert_eq!(conversion.credit.currency, Some(usd));
+        assert_eq!(manager.get_account(3).unwrap().currency, Some(usd));
+        assert_eq!(converter.total_in(eur).unwrap(), dec!(19.9999));
+    }
+
+    #[test]
+    fn rejected_credits_leave_the_debited_account_unchanged() {
+        let (eur, usd) = ("EUR".parse().unwrap(), "USD".parse().unwrap());
+        let manager = Arc::new(AccountManager::new(InMemoryAccountStorage::default()));
+        let order = |tx_id, client_id, kind, currency| TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency,
+        };
+        for order in [
+            order(1, 1, TransactionKind::Deposit(dec!(10)), Some(eur)),
+            order(2, 2, TransactionKind::Deposit(dec!(10)), Some(usd)),
+            order(2, 2, TransactionKind::Dispute(2), None),
+            order(2, 2, TransactionKind::ChargeBack(2), None),
+        ] {
+            manager.process_order(order).unwrap();
+        }
+        let converter = CurrencyConverter::new(
+            manager.clone(),
+            RateTable::default().with_rate(eur, usd, dec!(1.1)),
+        );
+        let transfer = Transfer {
+            from: 1,
+            to: 2,
+            to_currency: usd,
+            amount: dec!(5),
+            debit_tx_id: 3,
+            credit_tx_id: 4,
+        };
+
+        // the account of client 2 is locked, the deposit is rejected
+        let error = converter.transfer(&transfer).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransactionError>(),
+            Some(TransactionError::Account(AccountError::AccountLocked))
+        ));
+        let account = manager.get_account(1).unwrap();
+        assert_eq!(account.total, dec!(10));
+        assert_eq!(account.available, dec!(10));
+        assert!(manager.get_transaction(3).is_none());
+        assert!(manager.get_transaction(4).is_none());
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Debug;
+
+use crate::model::{ClientId, Transaction, TransactionKind};
+
+/// The rules of the disputes of a payment scheme, injected into the
+/// [AccountManager](super::AccountManager) through its options. The schemes
+/// processed do not agree on which transactions can be disputed, by whom and
+/// whether a dispute may hold funds the client already spent. Every rule has
+/// a default, the behavior of the [DefaultDisputePolicy].
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::InMemoryAccountStorage;
+/// use csv_reader_core::model::{ClientId, Transaction, TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{AccountManager, AccountManagerOptions, DisputePolicy};
+///
+/// /// Only the deposits are disputed, by the client who made them.
+/// #[derive(Debug)]
+/// struct CardScheme;
+///
+/// impl DisputePolicy for CardScheme {
+///     fn is_disputable(&self, transaction: &Transaction) -> bool {
+///         matches!(transaction.kind, TransactionKind::Deposit(_))
+///     }
+///
+///     fn may_dispute(&self, client_id: ClientId, transaction: &Transaction) -> bool {
+///         client_id == transaction.client_id
+///     }
+/// }
+///
+/// let options = AccountManagerOptions {
+///     dispute_policy: Arc::new(CardScheme),
+///     ..Default::default()
+/// };
+/// let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+/// let order = |tx_id, client_id, kind| TransactionOrder { tx_id, client_id, kind, timestamp: None, currency: None };
+/// let _tx = manager.process_order(order(1, 1, TransactionKind::Deposit(Decimal::TEN))).unwrap();
+/// let _tx = manager.process_order(order(2, 1, TransactionKind::Withdrawal(Decimal::ONE))).unwrap();
+///
+/// assert!(manager.process_order(order(2, 1, TransactionKind::Dispute(2))).is_err());
+/// assert!(manager.process_order(order(1, 2, TransactionKind::Dispute(1))).is_err());
+/// assert!(manager.process_order(order(1, 1, TransactionKind::Dispute(1))).is_ok());
+/// ```
+pub trait DisputePolicy: Debug + Send + Sync {
+    /// Tell if the given transaction can be disputed. By default, the deposits
+    /// and the withdrawals can.
+    fn is_disputable(&self, transaction: &Transaction) -> bool {
+        matches!(
+            transaction.kind,
+            TransactionKind::Deposit(_) | TransactionKind::Withdrawal(_)
+        )
+    }
+
+    /// Tell if the given client may dispute the given transaction, resolve or
+    /// charge back its dispute. By default, any client may.
+    fn may_dispute(&self, _client_id: ClientId, _transaction: &Transaction) -> bool {
+        true
+    }
+
+    /// Tell if the dispute of a deposit may hold more funds than available,
+    /// taking the available funds negative. By default, it may.
+    fn allows_negative_available(&self) -> bool {
+        true
+    }
+}
+
+/// The dispute rules applied when none are given: the deposits and the
+/// withdrawals can be disputed by any client, even when the funds of a
+/// disputed deposit were already spent.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultDisputePolicy;
+
+impl DisputePolicy for DefaultDisputePolicy {}
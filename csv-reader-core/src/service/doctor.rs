@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use rust_decimal::Decimal;
+
+use crate::adapter::{AccountStorage, StorageStats};
+use crate::model::{Account, ClientId, TransactionKind};
+
+/// Health report of a storage backend, see [check_storage].
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    /// The error of the schema check, if any.
+    pub schema_error: Option<String>,
+
+    /// The statistics of the storage.
+    pub stats: StorageStats,
+
+    /// The number of accounts whose invariants were verified.
+    pub accounts_checked: usize,
+
+    /// The invariants found broken.
+    pub violations: Vec<String>,
+
+    /// The time to read the sampled accounts, on average.
+    pub read_latency: Option<Duration>,
+
+    /// The time to write an account, on average.
+    pub write_latency: Option<Duration>,
+}
+
+impl HealthReport {
+    /// Tell if the storage passed all the checks.
+    pub fn is_healthy(&self) -> bool {
+        self.schema_error.is_none() && self.violations.is_empty()
+    }
+}
+
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.schema_error {
+            None => writeln!(f, "Schema: ok")?,
+            Some(error) => writeln!(f, "Schema: {error}")?,
+        }
+        writeln!(
+            f,
+            "Storage: {} accounts, {} transactions, {} open disputes.",
+            self.stats.accounts, self.stats.transactions, self.stats.open_disputes
+        )?;
+        writeln!(
+            f,
+            "Invariants: {} broken on {} accounts checked.",
+            self.violations.len(),
+            self.accounts_checked
+        )?;
+        for violation in &self.violations {
+            writeln!(f, "  {violation}")?;
+        }
+        for (operation, latency) in [("Read", self.read_latency), ("Write", self.write_latency)] {
+            match latency {
+                Some(latency) => writeln!(f, "{operation} latency: {latency:?}")?,
+                None => writeln!(f, "{operation} latency: no account to measure it")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the health of a storage backend: its schema, the invariants of a
+/// sample of at most `sample` accounts and the latency of reading and writing
+/// them. The sampled accounts are written back unchanged and the session of
+/// the storage, if any, is not committed: the storage state is not modified.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::{AccountStorage, InMemoryAccountStorage};
+/// use csv_reader_core::model::Account;
+/// use csv_reader_core::service::check_storage;
+///
+/// let mut storage = InMemoryAccountStorage::default();
+/// let mut account = Account::new(1);
+/// account.total = Decimal::ONE;
+/// storage.store_account(account).unwrap();
+/// let report = check_storage(&mut storage, 100).unwrap();
+///
+/// assert!(!report.is_healthy());
+/// assert_eq!(report.accounts_checked, 1);
+/// assert!(report.write_latency.is_some());
+/// ```
+pub fn check_storage(
+    storage: &mut dyn AccountStorage,
+    sample: usize,
+) -> crate::Result<HealthReport> {
+    let schema_error = storage.check_schema().err().map(|error| error.to_string());
+    let stats = storage.stats();
+    let mut accounts = storage.get_accounts();
+    accounts.sort_unstable_by_key(|account| account.client_id);
+    accounts.truncate(sample);
+    let mut violations = Vec::new();
+
+    // the amounts under dispute must be held by the accounts
+    let mut disputed: HashMap<ClientId, Decimal> = HashMap::new();
+    for transaction in storage.get_disputed_transactions() {
+        match transaction.kind {
+            TransactionKind::Deposit(amount) | TransactionKind::Withdrawal(amount) => {
+                *disputed.entry(transaction.client_id).or_default() += amount
+            }
+            _ => violations.push(format!(
+                "Transaction tx={} is disputed but is neither a deposit nor a withdrawal.",
+                transaction.tx_id
+            )),
+        }
+    }
+    for account in &accounts {
+        let client_id = account.client_id;
+        violations.extend(check_account(account));
+        let disputed = disputed.get(&client_id).copied().unwrap_or_default();
+        if account.held < disputed {
+            violations.push(format!(
+                "Client {client_id}: held funds {} lower than the disputed amount {disputed}.",
+                account.held
+            ));
+        }
+    }
+
+    let (read_latency, write_latency) = match u32::try_from(accounts.len()) {
+        Ok(count) if count > 0 => {
+            let start = Instant::now();
+            for account in &accounts {
+                let _ = storage.get_account(&account.client_id);
+            }
+            let read_latency = start.elapsed() / count;
+            let start = Instant::now();
+            for account in &accounts {
+                storage.store_account(account.clone())?;
+            }
+
+            (Some(read_latency), Some(start.elapsed() / count))
+        }
+        _ => (None, None),
+    };
+
+    Ok(HealthReport {
+        schema_error,
+        stats,
+        accounts_checked: accounts.len(),
+        violations,
+        read_latency,
+        write_latency,
+    })
+}
+
+/// Check the invariants of an account on its own: its total funds are its
+/// available and held funds, the held funds are not negative. Returns the
+/// invariants found broken.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::model::Account;
+/// use csv_reader_core::service::check_account;
+///
+/// let mut account = Account::new(1);
+/// assert!(check_account(&account).is_empty());
+///
+/// account.held = Decimal::NEGATIVE_ONE;
+/// assert_eq!(check_account(&account).len(), 2);
+/// ```
+pub fn check_account(account: &Account) -> Vec<String> {
+    let client_id = account.client_id;
+    let mut violations = Vec::new();
+    if account.available + account.held != account.total {
+        violations.push(format!(
+            "Client {client_id}: total {} is not available {} + held {}.",
+            account.total, account.available, account.held
+        ));
+    }
+    if account.held < Decimal::ZERO {
+        violations.push(format!(
+            "Client {client_id}: negative held funds {}.",
+            account.held
+        ));
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::adapter::{InMemoryAccountStorage, OverlayStorage};
+    use crate::model::TransactionOrder;
+
+    #[test]
+    fn test_healthy_storage() {
+        let mut storage = InMemoryAccountStorage::default();
+        let mut account = Account::new(1);
+        account.deposit(dec!(10)).unwrap();
+        account.dispute(dec!(4)).unwrap();
+        storage.store_account(account.clone()).unwrap();
+        let transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(4)),
+            timestamp: None,
+            currency: None,
+        };
+        storage.store_transaction(transaction.into()).unwrap();
+        storage.set_disputed(1, true).unwrap();
+        let report = check_storage(&mut storage, 10).unwrap();
+
+        assert!(report.is_healthy(), "{report}");
+        assert_eq!(report.stats.open_disputes, 1);
+        assert_eq!(storage.get_account(&1), Some(account));
+    }
+
+    #[test]
+    fn test_disputed_funds_not_held() {
+        let mut storage = InMemoryAccountStorage::default();
+        let mut account = Account::new(1);
+        account.deposit(dec!(10)).unwrap();
+        storage.store_account(account).unwrap();
+        let transaction = TransactionOrder {
+            tx_id: 1,
+            client_id: 1,
+            kind: TransactionKind::Deposit(dec!(10)),
+            timestamp: None,
+            currency: None,
+        };
+        storage.store_transaction(transaction.into()).unwrap();
+        storage.set_disputed(1, true).unwrap();
+        let report = check_storage(&mut storage, 10).unwrap();
+
+        assert_eq!(report.violations.len(), 1);
+        assert!(report
+            .to_string()
+            .contains("lower than the disputed amount"));
+
+        // accounts out of the sample are not checked
+        let report = check_storage(&mut storage, 0).unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(report.read_latency, None);
+    }
+
+    #[test]
+    fn test_session_is_not_committed() {
+        let mut storage = OverlayStorage::new(InMemoryAccountStorage::default());
+        storage.store_account(Account::new(1)).unwrap();
+        let report = check_storage(&mut storage, 10).unwrap();
+
+        assert!(report.write_latency.is_some());
+        assert!(storage.has_changes());
+        assert!(storage.base().get_account(&1).is_none());
+    }
+}
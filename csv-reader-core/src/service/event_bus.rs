@@ -0,0 +1,73 @@
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Mutex,
+};
+
+use crate::model::DomainEvent;
+
+/// The broadcast channel of the [DomainEvent]s: every event published is
+/// sent to all the current subscribers. The subscribers dropping their
+/// receiver are forgotten at the next event.
+///
+/// ```
+/// use csv_reader_core::model::DomainEvent;
+/// use csv_reader_core::service::EventBus;
+///
+/// let bus = EventBus::default();
+/// let alerts = bus.subscribe();
+/// let metrics = bus.subscribe();
+/// bus.publish(DomainEvent::AccountLocked { client_id: 1 });
+///
+/// assert_eq!(alerts.try_recv().unwrap(), DomainEvent::AccountLocked { client_id: 1 });
+/// assert_eq!(metrics.try_recv().unwrap(), DomainEvent::AccountLocked { client_id: 1 });
+/// ```
+#[derive(Debug, Default)]
+pub struct EventBus {
+    /// The channels of the subscribers.
+    subscribers: Mutex<Vec<Sender<DomainEvent>>>,
+}
+
+impl EventBus {
+    /// Subscribe to the events published from now on. The channel is closed
+    /// once the bus is dropped.
+    pub fn subscribe(&self) -> Receiver<DomainEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().unwrap().push(sender);
+
+        receiver
+    }
+
+    /// Send the given event to every subscriber.
+    pub fn publish(&self, event: DomainEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Get the number of subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_subscribers() {
+        let bus = EventBus::default();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+
+        assert_eq!(bus.subscriber_count(), 2);
+        bus.publish(DomainEvent::ClientSuspended { client_id: 3 });
+
+        assert_eq!(bus.subscriber_count(), 1);
+        assert_eq!(
+            kept.try_iter().collect::<Vec<_>>(),
+            vec![DomainEvent::ClientSuspended { client_id: 3 }]
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use rust_decimal::Decimal;
+
+use crate::model::{
+    ClientId, FraudFlag, FraudRule, Timestamp, Transaction, TransactionKind, TransactionOrder,
+};
+
+/// The rules of the [FraudDetector], every rule is disabled by default.
+#[derive(Debug, Default, Clone)]
+pub struct FraudRules {
+    /// Flag the withdrawals of a client beyond the given number within the
+    /// given duration, measured on the timestamps of the orders or on their
+    /// arrival when they have none.
+    pub withdrawal_velocity: Option<(usize, Duration)>,
+
+    /// Flag the disputes ordered by a client beyond this share of its
+    /// deposits and withdrawals, `0.1` for one dispute every ten transactions.
+    pub max_dispute_rate: Option<Decimal>,
+
+    /// Flag the deposits and withdrawals of a client above this multiple of
+    /// the mean amount of its previous ones.
+    pub outlier_factor: Option<Decimal>,
+
+    /// Reject the flagged orders instead of only reporting them.
+    pub block: bool,
+}
+
+/// What the detector knows of the applied transactions of a client.
+#[derive(Debug, Default, Clone)]
+struct ClientActivity {
+    /// The time of the latest withdrawals, for the velocity.
+    withdrawals: VecDeque<Timestamp>,
+
+    /// The number of deposits and withdrawals.
+    transactions: usize,
+
+    /// The sum of the amounts of the deposits and withdrawals.
+    amounts: Decimal,
+
+    /// The number of disputes ordered.
+    disputes: usize,
+}
+
+/// Scores the orders against the [FraudRules] to surface the anomalies. The
+/// [AccountManager](super::AccountManager) screens every order with the
+/// detector of its options before applying it and rejects the flagged orders
+/// when the rules block them. The flags are noted and published on its event
+/// bus once the order is applied or blocked.
+///
+/// ```
+/// use rust_decimal_macros::dec;
+///
+/// use csv_reader_core::model::{FraudFlag, FraudRule, Transaction, TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{FraudDetector, FraudRules};
+///
+/// let detector = FraudDetector::new(FraudRules {
+///     outlier_factor: Some(dec!(10)),
+///     ..Default::default()
+/// });
+/// let order = |tx_id, amount| TransactionOrder { tx_id, client_id: 1, kind: TransactionKind::Deposit(amount), timestamp: None, currency: None };
+/// detector.record(&Transaction::from(order(1, dec!(20))));
+///
+/// assert!(detector.score(&order(2, dec!(150))).is_empty());
+/// assert_eq!(detector.score(&order(3, dec!(250))), vec![FraudRule::AmountOutlier]);
+/// assert!(detector.flags().is_empty());
+///
+/// detector.flag(FraudFlag { client_id: 1, tx_id: 3, rule: FraudRule::AmountOutlier, blocked: false });
+/// assert_eq!(detector.flags().len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct FraudDetector {
+    /// The rules scoring the orders.
+    rules: FraudRules,
+
+    /// The applied transactions of the clients.
+    activity: Mutex<HashMap<ClientId, ClientActivity>>,
+
+    /// The flags raised, in order.
+    flags: Mutex<Vec<FraudFlag>>,
+}
+
+impl Clone for FraudDetector {
+    fn clone(&self) -> Self {
+        Self {
+            rules: self.rules.clone(),
+            activity: Mutex::new(self.activity.lock().unwrap().clone()),
+            flags: Mutex::new(self.flags.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl FraudDetector {
+    /// Create a detector applying the given rules.
+    pub fn new(rules: FraudRules) -> Self {
+        Self {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    /// Tell if the flagged orders must be rejected.
+    pub fn blocks(&self) -> bool {
+        self.rules.block
+    }
+
+    /// Get the rules matched by the given order, scored against the
+    /// transactions recorded so far. Nothing is recorded, see
+    /// [FraudDetector::flag] to note the flags of the order.
+    pub fn score(&self, order: &TransactionOrder) -> Vec<FraudRule> {
+        let activities = self.activity.lock().unwrap();
+        let none = ClientActivity::default();
+        let activity = activities.get(&order.client_id).unwrap_or(&none);
+        let mut rules = Vec::new();
+
+        match order.kind {
+            TransactionKind::Deposit(amount) | TransactionKind::Withdrawal(amount) => {
+                if let TransactionKind::Withdrawal(_) = order.kind {
+                    if self.is_too_fast(activity, order.timestamp) {
+                        rules.push(FraudRule::WithdrawalVelocity);
+                    }
+                }
+                if let Some(factor) = self.rules.outlier_factor {
+                    if activity.transactions > 0
+                        && amount > factor * activity.amounts / Decimal::from(activity.transactions)
+                    {
+                        rules.push(FraudRule::AmountOutlier);
+                    }
+                }
+            }
+            TransactionKind::Dispute(_) => {
+                if let Some(rate) = self.rules.max_dispute_rate {
+                    if Decimal::from(activity.disputes + 1)
+                        > rate * Decimal::from(activity.transactions)
+                    {
+                        rules.push(FraudRule::DisputeRate);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        rules
+    }
+
+    /// Note a flag raised by an order, applied or blocked, in the report.
+    pub fn flag(&self, flag: FraudFlag) {
+        self.flags.lock().unwrap().push(flag);
+    }
+
+    /// Record an applied transaction in the activity of its client.
+    pub fn record(&self, transaction: &Transaction) {
+        let mut activity = self.activity.lock().unwrap();
+        let activity = activity.entry(transaction.client_id).or_default();
+
+        match transaction.kind {
+            TransactionKind::Deposit(amount) | TransactionKind::Withdrawal(amount) => {
+                if let TransactionKind::Withdrawal(_) = transaction.kind {
+                    activity
+                        .withdrawals
+                        .push_back(now_or(transaction.timestamp));
+                    // the times beyond the limit are not needed
+                    let count = self.rules.withdrawal_velocity.map_or(0, |(count, _)| count);
+                    while activity.withdrawals.len() > count {
+                        activity.withdrawals.pop_front();
+                    }
+                }
+                activity.transactions += 1;
+                activity.amounts += amount;
+            }
+            TransactionKind::Dispute(_) => activity.disputes += 1,
+            _ => {}
+        }
+    }
+
+    /// Get the flags raised so far.
+    pub fn flags(&self) -> Vec<FraudFlag> {
+        self.flags.lock().unwrap().clone()
+    }
+
+    /// Tell if a withdrawal at the given time exceeds the velocity of the
+    /// client.
+    fn is_too_fast(&self, activity: &ClientActivity, timestamp: Option<Timestamp>) -> bool {
+        let Some((count, window)) = self.rules.withdrawal_velocity else {
+            return false;
+        };
+        let now = now_or(timestamp);
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+
+        activity
+            .withdrawals
+            .iter()
+            .filter(|time| now - **time < window)
+            .count()
+            >= count
+    }
+}
+
+/// Get the given time or the current one.
+fn now_or(timestamp: Option<Timestamp>) -> Timestamp {
+    timestamp.unwrap_or_else(|| Timestamp::from(SystemTime::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn order(tx_id: u64, kind: TransactionKind, second: u32) -> TransactionOrder {
+        TransactionOrder {
+            tx_id,
+            client_id: 1,
+            kind,
+            timestamp: Some(
+                chrono::Utc
+                    .with_ymd_and_hms(2024, 1, 1, 0, 0, second)
+                    .unwrap(),
+            ),
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn withdrawal_velocity_and_dispute_rate() {
+        let detector = FraudDetector::new(FraudRules {
+            withdrawal_velocity: Some((2, Duration::from_secs(10))),
+            max_dispute_rate: Some(dec!(0.5)),
+            ..Default::default()
+        });
+        let mut flagged = Vec::new();
+        for order in [
+            order(1, TransactionKind::Deposit(dec!(100)), 0),
+            order(2, TransactionKind::Withdrawal(dec!(1)), 1),
+            order(3, TransactionKind::Withdrawal(dec!(1)), 2),
+            order(4, TransactionKind::Withdrawal(dec!(1)), 3),
+            // the withdrawals 2 and 3 are out of the window
+            order(5, TransactionKind::Withdrawal(dec!(1)), 13),
+            order(1, TransactionKind::Dispute(1), 14),
+            order(2, TransactionKind::Dispute(2), 15),
+            order(3, TransactionKind::Dispute(3), 16),
+        ] {
+            let rules = detector.score(&order);
+            if rules.is_empty() {
+                detector.record(&order.into());
+            } else {
+                flagged.extend(rules.into_iter().map(|rule| (order.tx_id, rule)));
+            }
+        }
+
+        assert_eq!(
+            flagged,
+            vec![
+                (4, FraudRule::WithdrawalVelocity),
+                (3, FraudRule::DisputeRate)
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Service module
+//!
+//! Services can be considered as the business logic of the application. They are like
+//! applications used by the actors to perform operations on the data. They are
+//! responsible for managing the data and the operations that can be performed
+//! on it. They must ensure that the data is consistent and that the operations
+//! are performed correctly.
+
+mod account_locks;
+mod account_manager;
+mod account_service;
+mod audit_log;
+mod currency_converter;
+mod dispute_policy;
+#[cfg(feature = "unstable")]
+mod doctor;
+mod event_bus;
+mod fraud_detector;
+mod order_limits;
+#[cfg(feature = "unstable")]
+mod recompute;
+mod reconciliation;
+
+pub use account_manager::*;
+pub use account_service::*;
+pub use audit_log::*;
+pub use currency_converter::*;
+pub use dispute_policy::*;
+#[cfg(feature = "unstable")]
+pub use doctor::*;
+pub use event_bus::*;
+pub use fraud_detector::*;
+pub use order_limits::*;
+#[cfg(feature = "unstable")]
+pub use recompute::*;
+pub use reconciliation::*;
@@ -0,0 +1,98 @@
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::model::{Timestamp, TransactionKind, TransactionOrder};
+
+use super::TransactionError;
+
+/// The caps on the amounts of the deposits and withdrawals enforced by the
+/// [AccountManager](super::AccountManager) before applying an order, every
+/// cap is disabled by default.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::model::{TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{OrderLimits, TransactionError};
+///
+/// let limits = OrderLimits {
+///     max_deposit: Some(Decimal::ONE_HUNDRED),
+///     max_daily_total: Some(Decimal::from(150)),
+///     ..Default::default()
+/// };
+/// let order = |kind| TransactionOrder { tx_id: 1, client_id: 1, kind, timestamp: None, currency: None };
+///
+/// assert!(limits.check(&order(TransactionKind::Deposit(Decimal::ONE_HUNDRED)), Decimal::ZERO).is_ok());
+/// assert!(matches!(
+///     limits.check(&order(TransactionKind::Deposit(Decimal::from(101))), Decimal::ZERO),
+///     Err(TransactionError::LimitExceeded(1, "deposit", _))
+/// ));
+/// assert!(matches!(
+///     limits.check(&order(TransactionKind::Withdrawal(Decimal::TEN)), Decimal::from(145)),
+///     Err(TransactionError::LimitExceeded(1, "daily total", _))
+/// ));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct OrderLimits {
+    /// The maximum amount of a deposit.
+    pub max_deposit: Option<Decimal>,
+
+    /// The maximum amount of a withdrawal.
+    pub max_withdrawal: Option<Decimal>,
+
+    /// The maximum sum of the amounts deposited and withdrawn by a client in
+    /// a day, the day of the timestamp of the orders in UTC or the current
+    /// one when they have none.
+    pub max_daily_total: Option<Decimal>,
+}
+
+impl OrderLimits {
+    /// Tell if no cap is enforced.
+    pub fn is_empty(&self) -> bool {
+        self.max_deposit.is_none()
+            && self.max_withdrawal.is_none()
+            && self.max_daily_total.is_none()
+    }
+
+    /// Check the given order against the caps, the client having already
+    /// moved the given amount on the day of the order.
+    pub fn check(
+        &self,
+        order: &TransactionOrder,
+        daily_total: Decimal,
+    ) -> std::result::Result<(), TransactionError> {
+        let (amount, max) = match order.kind {
+            TransactionKind::Deposit(amount) => {
+                (amount, self.max_deposit.map(|max| ("deposit", max)))
+            }
+            TransactionKind::Withdrawal(amount) => {
+                (amount, self.max_withdrawal.map(|max| ("withdrawal", max)))
+            }
+            _ => return Ok(()),
+        };
+        if let Some((limit, max)) = max.filter(|(_, max)| amount > *max) {
+            return Err(TransactionError::LimitExceeded(order.client_id, limit, max));
+        }
+        if let Some(max) = self
+            .max_daily_total
+            .filter(|max| daily_total + amount > *max)
+        {
+            return Err(TransactionError::LimitExceeded(
+                order.client_id,
+                "daily total",
+                max,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the day the given time counts towards.
+    pub(crate) fn day_of(timestamp: Option<Timestamp>) -> NaiveDate {
+        timestamp
+            .unwrap_or_else(|| Timestamp::from(SystemTime::now()))
+            .date_naive()
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+
+use super::{account_drifts, AccountDrift, AccountManager, AccountManagerOptions};
+use crate::adapter::AccountStorage;
+use crate::model::Account;
+
+/// Report of the recomputation of the accounts, see [recompute_accounts].
+#[derive(Debug, Clone, Default)]
+pub struct RecomputeReport {
+    /// The number of accounts stored or recomputed.
+    pub accounts_checked: usize,
+
+    /// The number of entries of the log replayed, including the ones which
+    /// could not be.
+    pub transactions_replayed: usize,
+
+    /// The accounts differing from their recomputed state.
+    pub drifts: Vec<AccountDrift>,
+
+    /// The entries of the log that could not be replayed.
+    pub errors: Vec<String>,
+
+    /// Tell if the differing accounts were replaced by their recomputed state.
+    pub repaired: bool,
+}
+
+impl RecomputeReport {
+    /// Tell if the stored accounts match their transactions.
+    pub fn is_consistent(&self) -> bool {
+        self.drifts.is_empty() && self.errors.is_empty()
+    }
+}
+
+impl Display for RecomputeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn balances(account: Option<&Account>) -> String {
+            account.map_or_else(
+                || "none".to_string(),
+                |account| format!("{}/{}/{}", account.available, account.held, account.total),
+            )
+        }
+
+        writeln!(
+            f,
+            "Recomputed: {} accounts from {} log entries, {} differing.",
+            self.accounts_checked,
+            self.transactions_replayed,
+            self.drifts.len()
+        )?;
+        for drift in &self.drifts {
+            writeln!(
+                f,
+                "  Client {}: stored {}, recomputed {} (available/held/total).",
+                drift.client_id,
+                balances(drift.stored.as_ref()),
+                balances(drift.rebuilt.as_ref())
+            )?;
+        }
+        for error in &self.errors {
+            writeln!(f, "  {error}")?;
+        }
+        if self.repaired {
+            writeln!(f, "The differing accounts were repaired.")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rebuild the accounts by replaying the transaction log of the storage in
+/// the order it was applied, with the accounting rules of the given options,
+/// and compare them to the stored accounts, useful after fixing a logic bug of
+/// a prior release. The log is replayed as [AccountManager::rebuild] does,
+/// except the entries which cannot be replayed are reported and skipped. With
+/// `repair`, the differing accounts are replaced by their recomputed state,
+/// the ones missing from the log are removed, and the storage changes are
+/// committed.
+///
+/// Fails if the storage keeps no transaction log.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::adapter::{AccountStorage, InMemoryAccountStorage};
+/// use csv_reader_core::model::{Account, LogEntry, TransactionKind, TransactionOrder};
+/// use csv_reader_core::service::{recompute_accounts, AccountManagerOptions};
+///
+/// let mut storage = InMemoryAccountStorage::default();
+/// let order = TransactionOrder {
+///     tx_id: 1,
+///     client_id: 1,
+///     kind: TransactionKind::Deposit(Decimal::TEN),
+///     timestamp: None,
+///     currency: None,
+/// };
+/// storage.append_log(LogEntry::Transaction(order.into())).unwrap();
+/// storage.store_account(Account::new(1)).unwrap();
+/// let options = AccountManagerOptions::default();
+///
+/// let report = recompute_accounts(&mut storage, &options, true).unwrap();
+///
+/// assert_eq!(report.drifts.len(), 1);
+/// assert_eq!(storage.get_account(&1).unwrap().total, Decimal::TEN);
+/// assert!(recompute_accounts(&mut storage, &options, false).unwrap().is_consistent());
+/// ```
+pub fn recompute_accounts(
+    storage: &mut dyn AccountStorage,
+    options: &AccountManagerOptions,
+    repair: bool,
+) -> crate::Result<RecomputeReport> {
+    let history = storage
+        .get_log()
+        .ok_or_else(|| anyhow!("The storage keeps no transaction log."))?;
+    let mut errors = Vec::new();
+    let recomputed = AccountManager::replay_log(&history, options, |error| {
+        errors.push(format!("{error:#}"));
+        Ok(())
+    })?;
+    let (accounts_checked, drifts) =
+        account_drifts(storage.get_accounts(), recomputed.get_accounts());
+    let mut report = RecomputeReport {
+        accounts_checked,
+        transactions_replayed: history.len(),
+        drifts,
+        errors,
+        repaired: false,
+    };
+
+    if repair && !report.drifts.is_empty() {
+        for drift in &report.drifts {
+            match &drift.rebuilt {
+                Some(account) => storage.store_account(account.clone())?,
+                None => {
+                    storage.remove_account(&drift.client_id)?;
+                    continue;
+                }
+            };
+        }
+        storage.commit()?;
+        report.repaired = true;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Days;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::adapter::InMemoryAccountStorage;
+    use crate::model::{ClientId, LogEntry, Timestamp, TransactionKind, TransactionOrder, TxId};
+    use crate::service::CreditLimits;
+
+    fn order(tx_id: TxId, client_id: ClientId, kind: TransactionKind) -> TransactionOrder {
+        TransactionOrder {
+            tx_id,
+            client_id,
+            kind,
+            timestamp: None,
+            currency: None,
+        }
+    }
+
+    /// Get the storage of a manager having processed the given orders with
+    /// the given options and its history recorded.
+    fn processed(
+        orders: Vec<TransactionOrder>,
+        options: &AccountManagerOptions,
+    ) -> InMemoryAccountStorage {
+        let options = AccountManagerOptions {
+            history: true,
+            ..options.clone()
+        };
+        let manager = AccountManager::with_options(InMemoryAccountStorage::default(), options);
+        for order in orders {
+            manager.process_order(order).unwrap();
+        }
+        let mut storage = InMemoryAccountStorage::default();
+        for entry in manager.get_history().unwrap() {
+            storage.append_log(entry).unwrap();
+        }
+        for account in manager.get_accounts() {
+            storage.store_account(account).unwrap();
+        }
+
+        storage
+    }
+
+    #[test]
+    fn test_consistent_accounts() {
+        let mut storage = processed(
+            vec![
+                order(1, 1, TransactionKind::Deposit(dec!(10))),
+                order(2, 1, TransactionKind::Withdrawal(dec!(3))),
+                order(3, 1, TransactionKind::Dispute(1)),
+                order(4, 2, TransactionKind::Deposit(dec!(5))),
+                order(5, 2, TransactionKind::Dispute(4)),
+                order(6, 2, TransactionKind::ChargeBack(4)),
+                order(7, 3, TransactionKind::Deposit(dec!(5))),
+                order(8, 3, TransactionKind::Dispute(7)),
+                order(9, 3, TransactionKind::ChargeBack(7)),
+                order(10, 3, TransactionKind::Unlock),
+            ],
+            &AccountManagerOptions::default(),
+        );
+        let report =
+            recompute_accounts(&mut storage, &AccountManagerOptions::default(), false).unwrap();
+
+        assert!(report.is_consistent(), "{report}");
+        assert_eq!(report.accounts_checked, 3);
+        assert_eq!(report.transactions_replayed, 10);
+    }
+
+    #[test]
+    fn test_report_without_repair() {
+        let mut storage = InMemoryAccountStorage::default();
+        let mut account = Account::new(1);
+        account.deposit(dec!(2)).unwrap();
+        storage.store_account(account.clone()).unwrap();
+        let withdrawal = order(1, 1, TransactionKind::Withdrawal(dec!(1)));
+        storage
+            .append_log(LogEntry::Transaction(withdrawal.into()))
+            .unwrap();
+        let report =
+            recompute_accounts(&mut storage, &AccountManagerOptions::default(), false).unwrap();
+
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.drifts[0].rebuilt, None);
+        assert!(!report.repaired);
+        assert_eq!(storage.get_account(&1), Some(account));
+        assert!(report.to_string().contains("stored 2/0/2, recomputed none"));
+    }
+
+    #[test]
+    fn test_replay_in_applied_order() {
+        let at = |day| "2024-03-01T00:00:00Z".parse::<Timestamp>().unwrap() + Days::new(day);
+        let dated = |tx_id, kind, day| TransactionOrder {
+            timestamp: Some(at(day)),
+            ..order(tx_id, 1, kind)
+        };
+        // the withdrawal is dated before the deposit funding it, and the
+        // funds of the disputed deposit are held before the withdrawal
+        let mut storage = processed(
+            vec![
+                dated(1, TransactionKind::Deposit(dec!(10)), 2),
+                dated(2, TransactionKind::Deposit(dec!(5)), 2),
+                dated(1, TransactionKind::Dispute(1), 2),
+                dated(3, TransactionKind::Withdrawal(dec!(5)), 1),
+            ],
+            &AccountManagerOptions::default(),
+        );
+        let report =
+            recompute_accounts(&mut storage, &AccountManagerOptions::default(), true).unwrap();
+
+        assert!(report.is_consistent(), "{report}");
+        assert!(!report.repaired);
+        let account = storage.get_account(&1).unwrap();
+        assert_eq!((account.available, account.held), (dec!(0), dec!(10)));
+    }
+
+    #[test]
+    fn test_replay_with_credit_limits() {
+        let options = AccountManagerOptions {
+            credit_limits: CreditLimits::new(dec!(5)),
+            ..Default::default()
+        };
+        let mut storage = processed(
+            vec![
+                order(1, 1, TransactionKind::Deposit(dec!(1))),
+                order(2, 1, TransactionKind::Withdrawal(dec!(4))),
+            ],
+            &options,
+        );
+        let report = recompute_accounts(&mut storage, &options, true).unwrap();
+
+        assert!(report.is_consistent(), "{report}");
+        assert_eq!(storage.get_account(&1).unwrap().available, dec!(-3));
+    }
+}
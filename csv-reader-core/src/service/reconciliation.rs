@@ -0,0 +1,277 @@
+use std::{collections::BTreeMap, fmt::Display, io::Read};
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::model::{Account, ClientId, MAX_DECIMALS};
+
+/// The balance of a client expected by an external statement. The fields
+/// left empty are not compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ExpectedBalance {
+    /// The client of the account.
+    pub client: ClientId,
+
+    /// The expected available funds.
+    pub available: Option<Decimal>,
+
+    /// The expected held funds.
+    pub held: Option<Decimal>,
+
+    /// The expected total funds.
+    pub total: Option<Decimal>,
+
+    /// The expected lock status.
+    pub locked: Option<bool>,
+}
+
+/// The balances of the clients expected by an external statement, read from
+/// a CSV file in the format of the accounts export: a header and the
+/// `client,available,held,total,locked` columns, every column but the client
+/// being optional.
+#[derive(Debug, Clone, Default)]
+pub struct Statement {
+    /// The expected balances by client.
+    balances: BTreeMap<ClientId, ExpectedBalance>,
+}
+
+impl Statement {
+    /// Read a statement. Fails on an invalid record or on a client listed
+    /// twice.
+    pub fn from_reader(reader: impl Read) -> crate::Result<Self> {
+        let mut statement = Self::default();
+        for record in csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+        {
+            let balance: ExpectedBalance = record?;
+            let client_id = balance.client;
+            if statement.balances.insert(client_id, balance).is_some() {
+                anyhow::bail!("The client {client_id} is listed twice in the statement.");
+            }
+        }
+
+        Ok(statement)
+    }
+}
+
+impl FromIterator<ExpectedBalance> for Statement {
+    fn from_iter<T: IntoIterator<Item = ExpectedBalance>>(iter: T) -> Self {
+        Self {
+            balances: iter
+                .into_iter()
+                .map(|balance| (balance.client, balance))
+                .collect(),
+        }
+    }
+}
+
+/// A difference between the statement and the computed accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// The client of the statement has no computed account.
+    MissingClient(ClientId),
+
+    /// The computed account of the client is not in the statement.
+    UnexpectedClient(ClientId),
+
+    /// The computed funds of the client are not the expected ones.
+    BalanceDelta {
+        /// The client of the account.
+        client_id: ClientId,
+
+        /// The funds compared: `available`, `held` or `total`.
+        field: &'static str,
+
+        /// The funds of the statement.
+        expected: Decimal,
+
+        /// The computed funds.
+        computed: Decimal,
+    },
+
+    /// The computed lock status of the client is not the expected one.
+    LockedMismatch {
+        /// The client of the account.
+        client_id: ClientId,
+
+        /// The lock status of the statement.
+        expected: bool,
+    },
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingClient(client_id) => {
+                write!(f, "Client {client_id}: in the statement, no account.")
+            }
+            Self::UnexpectedClient(client_id) => {
+                write!(f, "Client {client_id}: account not in the statement.")
+            }
+            Self::BalanceDelta {
+                client_id,
+                field,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "Client {client_id}: {field} {computed}, expected {expected} (delta {}).",
+                computed - expected
+            ),
+            Self::LockedMismatch {
+                client_id,
+                expected,
+            } => write!(
+                f,
+                "Client {client_id}: locked {}, expected {expected}.",
+                !expected
+            ),
+        }
+    }
+}
+
+/// Report of the reconciliation of the accounts, see [reconcile].
+#[derive(Debug, Clone, Default)]
+pub struct ReconciliationReport {
+    /// The number of clients both in the statement and in the accounts.
+    pub clients_compared: usize,
+
+    /// The differences found, by client.
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    /// Tell if the accounts match the statement.
+    pub fn is_reconciled(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+impl Display for ReconciliationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Reconciled: {} clients compared, {} discrepancies.",
+            self.clients_compared,
+            self.discrepancies.len()
+        )?;
+        for discrepancy in &self.discrepancies {
+            writeln!(f, "  {discrepancy}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare the given accounts to an external statement. The amounts are
+/// compared at the precision of the accounts export.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use csv_reader_core::model::Account;
+/// use csv_reader_core::service::{reconcile, Discrepancy, Statement};
+///
+/// let statement = "client,available,held,total,locked\n1,10,0,10,false\n2,5,0,5,false\n";
+/// let statement = Statement::from_reader(statement.as_bytes()).unwrap();
+/// let mut account = Account::new(1);
+/// account.deposit(Decimal::from(12)).unwrap();
+/// let report = reconcile(&[account, Account::new(3)], &statement);
+///
+/// assert_eq!(report.clients_compared, 1);
+/// assert_eq!(
+///     report.discrepancies,
+///     vec![
+///         Discrepancy::BalanceDelta { client_id: 1, field: "available", expected: Decimal::TEN, computed: Decimal::from(12) },
+///         Discrepancy::BalanceDelta { client_id: 1, field: "total", expected: Decimal::TEN, computed: Decimal::from(12) },
+///         Discrepancy::MissingClient(2),
+///         Discrepancy::UnexpectedClient(3),
+///     ]
+/// );
+/// ```
+pub fn reconcile(accounts: &[Account], statement: &Statement) -> ReconciliationReport {
+    let mut accounts: BTreeMap<ClientId, &Account> = accounts
+        .iter()
+        .map(|account| (account.client_id, account))
+        .collect();
+    let mut report = ReconciliationReport::default();
+
+    for (client_id, expected) in &statement.balances {
+        let Some(account) = accounts.remove(client_id) else {
+            report
+                .discrepancies
+                .push(Discrepancy::MissingClient(*client_id));
+            continue;
+        };
+        report.clients_compared += 1;
+        for (field, expected, computed) in [
+            ("available", expected.available, account.available),
+            ("held", expected.held, account.held),
+            ("total", expected.total, account.total),
+        ] {
+            let computed = computed.round_dp(MAX_DECIMALS);
+            match expected {
+                Some(expected) if expected.round_dp(MAX_DECIMALS) != computed => {
+                    report.discrepancies.push(Discrepancy::BalanceDelta {
+                        client_id: *client_id,
+                        field,
+                        expected,
+                        computed,
+                    })
+                }
+                _ => {}
+            }
+        }
+        if let Some(expected) = expected.locked.filter(|locked| *locked != account.locked) {
+            report.discrepancies.push(Discrepancy::LockedMismatch {
+                client_id: *client_id,
+                expected,
+            });
+        }
+    }
+    report
+        .discrepancies
+        .extend(accounts.into_keys().map(Discrepancy::UnexpectedClient));
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn partial_statements() {
+        let statement = "client,total\n1,2.00001\n2,\n";
+        let statement = Statement::from_reader(statement.as_bytes()).unwrap();
+        let mut account = Account::new(1);
+        account.deposit(dec!(2)).unwrap();
+        let mut locked = Account::new(2);
+        locked.locked = true;
+        let report = reconcile(&[account, locked.clone()], &statement);
+
+        assert!(report.is_reconciled(), "{report}");
+        assert_eq!(report.clients_compared, 2);
+
+        let statement: Statement = [ExpectedBalance {
+            client: 2,
+            locked: Some(false),
+            ..Default::default()
+        }]
+        .into_iter()
+        .collect();
+        let report = reconcile(&[locked], &statement);
+        assert_eq!(
+            report.to_string(),
+            "Reconciled: 1 clients compared, 1 discrepancies.\n  \
+             Client 2: locked true, expected false.\n"
+        );
+
+        let statement = "client,total\n1,2\n1,3\n";
+        assert!(Statement::from_reader(statement.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! Helpers shared by the tests of the crate.
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// A writer keeping the written bytes reachable after being boxed.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
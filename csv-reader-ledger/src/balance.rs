@@ -0,0 +1,352 @@
+use core::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use rust_decimal::Decimal;
+
+/// The error type for account operations.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AccountError {
+    /// Insufficient available funds to perform the operation.
+    InsufficientAvailableFunds {
+        /// The available funds in the account.
+        available: Decimal,
+
+        /// The withdraw amount requested
+        requested: Decimal,
+    },
+    /// Insufficient held funds to perform the operation.
+    InsufficientHeldFunds {
+        /// The held funds in the account.
+        held: Decimal,
+
+        /// The resolve amount requested
+        requested: Decimal,
+    },
+    /// Operation cannot be performed because the account is locked.
+    AccountLocked,
+}
+
+impl Display for AccountError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InsufficientAvailableFunds {
+                available,
+                requested,
+            } => write!(
+                f,
+                "Insufficient available funds: available {available}, requested {requested}."
+            ),
+            Self::InsufficientHeldFunds { held, requested } => write!(
+                f,
+                "Insufficient held funds: held {held}, requested {requested}."
+            ),
+            Self::AccountLocked => write!(f, "Account is locked."),
+        }
+    }
+}
+
+impl Error for AccountError {}
+
+/// The funds of an account and its lock status. This is where the accounting
+/// arithmetic happens, the invariant `total = available + held` is kept by
+/// every operation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Balance {
+    /// The available funds.
+    pub available: Decimal,
+
+    /// The held funds.
+    pub held: Decimal,
+
+    /// The total funds.
+    pub total: Decimal,
+
+    /// The lock status.
+    pub locked: bool,
+}
+
+impl Balance {
+    fn check_locked(&self) -> Result<(), AccountError> {
+        if self.locked {
+            Err(AccountError::AccountLocked)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_held(&self, amount: Decimal) -> Result<(), AccountError> {
+        if amount > self.held {
+            Err(AccountError::InsufficientHeldFunds {
+                held: self.held,
+                requested: amount,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update_total(&mut self) {
+        self.total = self.available + self.held;
+    }
+
+    /// Add the given amount to the available funds. Fails if the balance is
+    /// locked.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{AccountError, Balance};
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// assert_eq!(balance.total, dec!(10));
+    ///
+    /// balance.locked = true;
+    /// assert!(matches!(balance.deposit(dec!(10)), Err(AccountError::AccountLocked)));
+    /// ```
+    pub fn deposit(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.check_locked()?;
+        self.available += amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Subtract the given amount from the available funds. Fails if the balance
+    /// is locked or if the available funds are insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{AccountError, Balance};
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.withdraw(dec!(4)).unwrap();
+    /// assert_eq!(balance.total, dec!(6));
+    ///
+    /// assert!(matches!(
+    ///     balance.withdraw(dec!(7)),
+    ///     Err(AccountError::InsufficientAvailableFunds { .. })
+    /// ));
+    /// ```
+    pub fn withdraw(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.withdraw_on_credit(amount, Decimal::ZERO)
+    }
+
+    /// Subtract the given amount from the available funds, which may become
+    /// negative down to minus the given credit limit. Fails if the balance is
+    /// locked or if the available funds and the credit are insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{AccountError, Balance};
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.withdraw_on_credit(dec!(15), dec!(5)).unwrap();
+    /// assert_eq!(balance.available, dec!(-5));
+    ///
+    /// assert!(matches!(
+    ///     balance.withdraw_on_credit(dec!(1), dec!(5)),
+    ///     Err(AccountError::InsufficientAvailableFunds { .. })
+    /// ));
+    /// ```
+    pub fn withdraw_on_credit(
+        &mut self,
+        amount: Decimal,
+        credit_limit: Decimal,
+    ) -> Result<(), AccountError> {
+        self.check_locked()?;
+
+        if self.available + credit_limit < amount {
+            return Err(AccountError::InsufficientAvailableFunds {
+                available: self.available,
+                requested: amount,
+            });
+        }
+        self.available -= amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Move the given amount from the available funds to the held funds. The
+    /// available funds may become negative and locked balances can be
+    /// disputed.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::Balance;
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.dispute(dec!(15)).unwrap();
+    /// assert_eq!(balance.available, dec!(-5));
+    /// assert_eq!(balance.held, dec!(15));
+    /// assert_eq!(balance.total, dec!(10));
+    /// ```
+    pub fn dispute(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.available -= amount;
+        self.held += amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Move the given amount back from the held funds to the available funds.
+    /// Fails if the held funds are insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{AccountError, Balance};
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.dispute(dec!(10)).unwrap();
+    /// balance.resolve(dec!(10)).unwrap();
+    /// assert_eq!(balance.available, dec!(10));
+    ///
+    /// assert!(matches!(
+    ///     balance.resolve(dec!(1)),
+    ///     Err(AccountError::InsufficientHeldFunds { .. })
+    /// ));
+    /// ```
+    pub fn resolve(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.check_held(amount)?;
+        self.available += amount;
+        self.held -= amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Remove the given amount from the held funds and lock the balance. Fails
+    /// if the held funds are insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::Balance;
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.dispute(dec!(4)).unwrap();
+    /// balance.chargeback(dec!(4)).unwrap();
+    /// assert_eq!(balance.total, dec!(6));
+    /// assert!(balance.locked);
+    /// ```
+    pub fn chargeback(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.check_held(amount)?;
+        self.held -= amount;
+        self.locked = true;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Add the given signed amount to the available funds, a manual
+    /// correction. Locked balances can be adjusted. Fails if a debit exceeds
+    /// the available funds.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{AccountError, Balance};
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.locked = true;
+    /// balance.adjust(dec!(10)).unwrap();
+    /// balance.adjust(dec!(-4)).unwrap();
+    /// assert_eq!(balance.total, dec!(6));
+    ///
+    /// assert!(matches!(
+    ///     balance.adjust(dec!(-7)),
+    ///     Err(AccountError::InsufficientAvailableFunds { .. })
+    /// ));
+    /// ```
+    pub fn adjust(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        if self.available + amount < Decimal::ZERO {
+            return Err(AccountError::InsufficientAvailableFunds {
+                available: self.available,
+                requested: -amount,
+            });
+        }
+        self.available += amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Hold the given amount of a disputed withdrawal for a potential
+    /// re-credit: the held and total funds grow while the available funds
+    /// remain the same. Locked balances can be disputed.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::Balance;
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.withdraw(dec!(4)).unwrap();
+    /// balance.dispute_withdrawal(dec!(4)).unwrap();
+    /// assert_eq!(balance.available, dec!(6));
+    /// assert_eq!(balance.held, dec!(4));
+    /// assert_eq!(balance.total, dec!(10));
+    /// ```
+    pub fn dispute_withdrawal(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.held += amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Release the held amount of a disputed withdrawal that stands: the held
+    /// and total funds are lowered back. Fails if the held funds are
+    /// insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::Balance;
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.withdraw(dec!(4)).unwrap();
+    /// balance.dispute_withdrawal(dec!(4)).unwrap();
+    /// balance.resolve_withdrawal(dec!(4)).unwrap();
+    /// assert_eq!(balance.held, dec!(0));
+    /// assert_eq!(balance.total, dec!(6));
+    /// ```
+    pub fn resolve_withdrawal(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.check_held(amount)?;
+        self.held -= amount;
+        self.update_total();
+
+        Ok(())
+    }
+
+    /// Re-credit the held amount of a disputed withdrawal to the available
+    /// funds and lock the balance. Fails if the held funds are insufficient.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::Balance;
+    ///
+    /// let mut balance = Balance::default();
+    /// balance.deposit(dec!(10)).unwrap();
+    /// balance.withdraw(dec!(4)).unwrap();
+    /// balance.dispute_withdrawal(dec!(4)).unwrap();
+    /// balance.chargeback_withdrawal(dec!(4)).unwrap();
+    /// assert_eq!(balance.available, dec!(10));
+    /// assert_eq!(balance.total, dec!(10));
+    /// assert!(balance.locked);
+    /// ```
+    pub fn chargeback_withdrawal(&mut self, amount: Decimal) -> Result<(), AccountError> {
+        self.check_held(amount)?;
+        self.held -= amount;
+        self.available += amount;
+        self.locked = true;
+        self.update_total();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use core::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::TxId;
+
+/// Error raised by an invalid dispute state transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DisputeError {
+    /// The transaction is already under dispute.
+    AlreadyDisputed(TxId),
+
+    /// The transaction is not under dispute.
+    NotDisputed(TxId),
+}
+
+impl Display for DisputeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyDisputed(tx_id) => {
+                write!(f, "Transaction id='{tx_id}' is already disputed")
+            }
+            Self::NotDisputed(tx_id) => write!(f, "Transaction id='{tx_id}' is not disputed."),
+        }
+    }
+}
+
+impl Error for DisputeError {}
+
+/// The dispute state of a disputable transaction.
+///
+/// A transaction starts undisputed. A dispute opens a case that is closed
+/// either by a resolve or by a chargeback, both bringing the transaction back
+/// to the undisputed state.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisputeState {
+    /// No dispute is open on the transaction.
+    #[default]
+    Undisputed,
+
+    /// The transaction is under dispute.
+    Disputed,
+}
+
+impl From<bool> for DisputeState {
+    fn from(disputed: bool) -> Self {
+        if disputed {
+            Self::Disputed
+        } else {
+            Self::Undisputed
+        }
+    }
+}
+
+impl DisputeState {
+    /// Open a dispute on the given transaction.
+    ///
+    /// ```
+    /// use csv_reader_ledger::{DisputeError, DisputeState};
+    ///
+    /// let state = DisputeState::Undisputed.dispute(1).unwrap();
+    /// assert_eq!(state, DisputeState::Disputed);
+    /// assert_eq!(state.dispute(1), Err(DisputeError::AlreadyDisputed(1)));
+    /// ```
+    pub fn dispute(self, tx_id: TxId) -> Result<Self, DisputeError> {
+        match self {
+            Self::Undisputed => Ok(Self::Disputed),
+            Self::Disputed => Err(DisputeError::AlreadyDisputed(tx_id)),
+        }
+    }
+
+    /// Close the dispute on the given transaction by resolving it.
+    ///
+    /// ```
+    /// use csv_reader_ledger::{DisputeError, DisputeState};
+    ///
+    /// let state = DisputeState::Disputed.resolve(1).unwrap();
+    /// assert_eq!(state, DisputeState::Undisputed);
+    /// assert_eq!(state.resolve(1), Err(DisputeError::NotDisputed(1)));
+    /// ```
+    pub fn resolve(self, tx_id: TxId) -> Result<Self, DisputeError> {
+        self.close(tx_id)
+    }
+
+    /// Close the dispute on the given transaction by charging it back.
+    ///
+    /// ```
+    /// use csv_reader_ledger::{DisputeError, DisputeState};
+    ///
+    /// let state = DisputeState::Disputed.chargeback(1).unwrap();
+    /// assert_eq!(state, DisputeState::Undisputed);
+    /// assert_eq!(state.chargeback(1), Err(DisputeError::NotDisputed(1)));
+    /// ```
+    pub fn chargeback(self, tx_id: TxId) -> Result<Self, DisputeError> {
+        self.close(tx_id)
+    }
+
+    /// Tell if a dispute is open.
+    pub fn is_disputed(&self) -> bool {
+        matches!(self, Self::Disputed)
+    }
+
+    fn close(self, tx_id: TxId) -> Result<Self, DisputeError> {
+        match self {
+            Self::Disputed => Ok(Self::Undisputed),
+            Self::Undisputed => Err(DisputeError::NotDisputed(tx_id)),
+        }
+    }
+}
@@ -1,11 +1,12 @@
-use rust_decimal::Decimal;
-use serde::Deserialize;
-use thiserror::Error;
+use alloc::string::String;
+use core::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
 
-use super::ClientId;
+use rust_decimal::Decimal;
 
-/// Type alias for transaction identifiers.
-pub type TxId = u32;
+use crate::TxId;
 
 /// Represents the kind of a transaction.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,31 +27,68 @@ pub enum TransactionKind {
     /// Chargeback a transaction. The identifier refers to a transaction that was
     /// under dispute by ID.
     ChargeBack(TxId),
+
+    /// Unlock the account of the client after a manual review.
+    Unlock,
+
+    /// Open the account of the client, or reopen it once closed.
+    Open,
+
+    /// Close the account of the client, whose funds must all be withdrawn.
+    Close,
+
+    /// Correct the available funds of the client by the given signed amount,
+    /// a credit when positive and a debit when negative.
+    Adjustment(Decimal),
 }
 
 /// Error type for transaction kind creation.
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum TransactionKindError {
     /// Amounts for transactions must be positive.
-    #[error("Transaction amount must be strictily positive ({0} given)")]
     NegativeOrZeroAmount(Decimal),
 
     /// The transaction kind is unknown.
-    #[error("Unknown transaction kind: '{0}'")]
     UnknownKind(String),
 
     /// The transaction must have an amount.
-    #[error("Transaction amount is missing")]
     MissingAmount,
+
+    /// The amount has more decimal places than allowed.
+    TooManyDecimals(Decimal, u32),
+
+    /// Amounts of adjustments must not be zero.
+    ZeroAmount,
 }
 
+impl Display for TransactionKindError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NegativeOrZeroAmount(amount) => write!(
+                f,
+                "Transaction amount must be strictily positive ({amount} given)"
+            ),
+            Self::UnknownKind(kind) => write!(f, "Unknown transaction kind: '{kind}'"),
+            Self::MissingAmount => write!(f, "Transaction amount is missing"),
+            Self::TooManyDecimals(amount, max_decimals) => write!(
+                f,
+                "Transaction amount must have at most {max_decimals} decimal places ({amount} given)"
+            ),
+            Self::ZeroAmount => write!(f, "Transaction amount must not be zero"),
+        }
+    }
+}
+
+impl Error for TransactionKindError {}
+
 impl TransactionKind {
     /// Create a new deposit transaction.
     ///
     /// ```
     /// use rust_decimal::Decimal;
     /// use rust_decimal_macros::dec;
-    /// use csv_reader::model::{TransactionKind, TransactionKindError};
+    /// use csv_reader_ledger::{TransactionKind, TransactionKindError};
     ///
     /// // create a deposit transaction
     /// let deposit = TransactionKind::deposit(dec!(0.0001)).unwrap();
@@ -71,7 +109,7 @@ impl TransactionKind {
     /// ```
     /// use rust_decimal::Decimal;
     /// use rust_decimal_macros::dec;
-    /// use csv_reader::model::{TransactionKind, TransactionKindError};
+    /// use csv_reader_ledger::{TransactionKind, TransactionKindError};
     ///
     /// // create a withdrawal transaction
     /// let withdrawal = TransactionKind::withdrawal(dec!(0.0001)).unwrap();
@@ -90,7 +128,7 @@ impl TransactionKind {
     /// Create a new dispute transaction.
     ///
     /// ```
-    /// use csv_reader::model::TransactionKind;
+    /// use csv_reader_ledger::TransactionKind;
     ///
     /// // create a dispute transaction
     /// let dispute = TransactionKind::dispute(1);
@@ -100,6 +138,30 @@ impl TransactionKind {
         Self::Dispute(tx_id)
     }
 
+    /// Create a new adjustment transaction. Its amount is signed, only zero is
+    /// not allowed.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{TransactionKind, TransactionKindError};
+    ///
+    /// // credits and debits are allowed
+    /// let credit = TransactionKind::adjustment(dec!(0.0001)).unwrap();
+    /// let debit = TransactionKind::adjustment(dec!(-0.0001)).unwrap();
+    /// assert_eq!(debit, TransactionKind::Adjustment(dec!(-0.0001)));
+    ///
+    /// let error = TransactionKind::adjustment(Decimal::ZERO).unwrap_err();
+    /// assert!(matches!(error, TransactionKindError::ZeroAmount));
+    /// ```
+    pub fn adjustment(amount: Decimal) -> Result<Self, TransactionKindError> {
+        if amount.is_zero() {
+            return Err(TransactionKindError::ZeroAmount);
+        }
+
+        Ok(Self::Adjustment(amount))
+    }
+
     /// Check if the given amount is strictly positive.
     fn check_positive_amount(amount: Decimal) -> Result<Decimal, TransactionKindError> {
         if amount <= Decimal::ZERO {
@@ -109,10 +171,34 @@ impl TransactionKind {
         Ok(amount)
     }
 
+    /// Check the given amount has at most the given number of decimal places.
+    /// Trailing zeros do not count.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use csv_reader_ledger::{TransactionKind, TransactionKindError};
+    ///
+    /// let amount = TransactionKind::check_precision(dec!(1.50000), 4).unwrap();
+    /// assert_eq!(amount, dec!(1.50000));
+    ///
+    /// let error = TransactionKind::check_precision(dec!(0.00001), 4).unwrap_err();
+    /// assert!(matches!(error, TransactionKindError::TooManyDecimals(value, 4) if value == dec!(0.00001)));
+    /// ```
+    pub fn check_precision(
+        amount: Decimal,
+        max_decimals: u32,
+    ) -> Result<Decimal, TransactionKindError> {
+        if amount.normalize().scale() > max_decimals {
+            return Err(TransactionKindError::TooManyDecimals(amount, max_decimals));
+        }
+
+        Ok(amount)
+    }
+
     /// Create a new resolve transaction.
     ///
     /// ```
-    /// use csv_reader::model::TransactionKind;
+    /// use csv_reader_ledger::TransactionKind;
     ///
     /// // create a resolve transaction
     /// let resolve = TransactionKind::resolve(1);
@@ -125,7 +211,7 @@ impl TransactionKind {
     /// Create a new chargeback transaction.
     ///
     /// ```
-    /// use csv_reader::model::TransactionKind;
+    /// use csv_reader_ledger::TransactionKind;
     ///
     /// // create a chargeback transaction
     /// let chargeback = TransactionKind::chargeback(1);
@@ -135,95 +221,3 @@ impl TransactionKind {
         Self::ChargeBack(tx_id)
     }
 }
-
-/// A Transaction represents a single transaction that happened on the exchange.
-/// A Transaction has already modified the ledgers and it cannot be modified or
-/// deleted. The transaction identifier is unique. Unexpected behavior can
-/// happen if two different transactions have the same identifier.
-/// If a transaction relates to another transaction, the identifier is valid and
-/// the related transaction can be found.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Transaction {
-    /// The unique identifier of the transaction.
-    pub tx_id: TxId,
-
-    /// The client identifier that made the transaction.
-    pub client_id: ClientId,
-
-    /// The transaction kind.
-    pub kind: TransactionKind,
-}
-
-/// TransactionOrder represents the order of a transaction in the CSV file. It
-/// is a wish emitted by a client that Transaction should be processed in the
-/// given order. This transaction has not yet been validated against the account.
-#[derive(Debug, Clone)]
-pub struct TransactionOrder {
-    /// The unique identifier of the transaction.
-    pub tx_id: TxId,
-
-    /// The client identifier that made the order.
-    pub client_id: ClientId,
-
-    /// The transaction kind.
-    pub kind: TransactionKind,
-}
-
-impl From<TransactionOrder> for Transaction {
-    fn from(order: TransactionOrder) -> Self {
-        Self {
-            tx_id: order.tx_id,
-            client_id: order.client_id,
-            kind: order.kind,
-        }
-    }
-}
-
-/// Transaction entity read from CSV file.
-#[derive(Debug, Clone, Deserialize)]
-pub struct CSVTransactionEntity {
-    /// The transaction kind.
-    pub r#type: String,
-
-    /// The client identifier that made the transaction.
-    pub client: ClientId,
-
-    /// The unique identifier of the transaction.
-    pub tx: TxId,
-
-    /// The amount of the transaction.
-    pub amount: Option<Decimal>,
-}
-
-impl TryFrom<CSVTransactionEntity> for TransactionOrder {
-    type Error = TransactionKindError;
-
-    fn try_from(entity: CSVTransactionEntity) -> Result<Self, Self::Error> {
-        let kind = match entity.r#type.as_str().to_lowercase().as_str() {
-            "deposit" => {
-                if let Some(amount) = entity.amount {
-                    TransactionKind::deposit(amount)?
-                } else {
-                    return Err(TransactionKindError::MissingAmount);
-                }
-            }
-            "withdrawal" => {
-                if let Some(amount) = entity.amount {
-                    TransactionKind::withdrawal(amount)?
-                } else {
-                    return Err(TransactionKindError::MissingAmount);
-                }
-            }
-            "dispute" => TransactionKind::dispute(entity.tx),
-            "resolve" => TransactionKind::resolve(entity.tx),
-            "chargeback" => TransactionKind::chargeback(entity.tx),
-            val => return Err(TransactionKindError::UnknownKind(val.to_owned())),
-        };
-
-        Ok(Self {
-            tx_id: entity.tx,
-            client_id: entity.client,
-            kind,
-        })
-    }
-}
@@ -0,0 +1,25 @@
+#![no_std]
+#![warn(missing_docs)]
+//! LEDGER CORE
+//!
+//! This crate holds the pure accounting logic of the CSV reader: the account
+//! balance arithmetic, the transaction kinds and the dispute state machine. It
+//! does not perform any IO and does not depend on the standard library so it
+//! can be embedded in constrained environments.
+
+extern crate alloc;
+
+mod balance;
+mod dispute;
+mod kind;
+
+pub use balance::*;
+pub use dispute::*;
+pub use kind::*;
+
+/// The client ID type alias, wide enough for any number of clients.
+pub type ClientId = u64;
+
+/// Type alias for transaction identifiers, wide enough for any number of
+/// transactions.
+pub type TxId = u64;
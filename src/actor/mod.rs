@@ -1,12 +0,0 @@
-//! Actor module contains the controlers of the application.
-//!
-//! The actors are controlers. They use services to perform their tasks.
-//! They communicate with other actors through messages.
-
-mod accountant;
-mod exporter;
-mod reader;
-
-pub use accountant::*;
-pub use exporter::*;
-pub use reader::*;